@@ -1,6 +1,8 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+#[cfg(feature = "legacy-compat")]
+pub mod legacy;
 pub mod v10;
 pub mod v11;
 pub mod v12;
@@ -10,3 +12,4 @@ pub mod v15;
 pub mod v16;
 pub mod v8;
 pub mod v9;
+pub mod versioned;
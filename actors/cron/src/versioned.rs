@@ -0,0 +1,154 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fil_actors_shared::versioned::normalize_address;
+
+/// The cron entry type of all versions, the actor and method called at every epoch. It has not
+/// changed since v8.
+pub use crate::v16::Entry;
+
+fil_actors_shared::versioned_state! {
+    actor: "cron",
+    type: Cron,
+    /// The fields of the cron state, with version-agnostic types. The layout has not changed since
+    /// v8.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        #[cfg_attr(feature = "json", serde(with = "entries_json"))]
+        pub entries: Vec<Entry>,
+    }
+    versions {
+        V8 => 8, crate::v8::State;
+        V9 => 9, crate::v9::State;
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        entries: st
+            .entries
+            .iter()
+            .map(|entry| {
+                Ok(Entry {
+                    receiver: normalize_address(&entry.receiver.to_bytes())?,
+                    method_num: entry.method_num,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+    }
+}
+
+/// Lotus JSON of the cron entries, whose state type is tuple-encoded.
+#[cfg(feature = "json")]
+mod entries_json {
+    use fil_actors_shared::lotus_json;
+    use fvm_shared4::address::Address;
+    use fvm_shared4::MethodNum;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Entry;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct EntryJson {
+        #[serde(with = "lotus_json::address")]
+        receiver: Address,
+        method_num: MethodNum,
+    }
+
+    pub fn serialize<S: Serializer>(entries: &[Entry], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(entries.iter().map(|entry| EntryJson {
+            receiver: entry.receiver,
+            method_num: entry.method_num,
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Entry>, D::Error> {
+        Ok(Vec::<EntryJson>::deserialize(deserializer)?
+            .into_iter()
+            .map(|entry| Entry {
+                receiver: entry.receiver,
+                method_num: entry.method_num,
+            })
+            .collect())
+    }
+}
+
+impl VersionedState {
+    /// Returns the actors and methods called by the cron actor at every epoch, in call order.
+    pub fn entries(&self) -> anyhow::Result<Vec<Entry>> {
+        Ok(self.normalize()?.entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_shared4::address::Address;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_entries() {
+        let store = MemoryBlockstore::new();
+        let entry = |receiver, method_num| crate::v8::Entry {
+            receiver: fvm_shared::address::Address::new_id(receiver),
+            method_num,
+        };
+        let st = crate::v8::State {
+            entries: vec![entry(4, 5), entry(6, 7)],
+        };
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let pre = VersionedState::load(&store, 8, &root).unwrap();
+        let post = VersionedState::load(&store, 16, &root).unwrap();
+        assert_eq!(
+            pre.entries().unwrap(),
+            [
+                Entry {
+                    receiver: Address::new_id(4),
+                    method_num: 5,
+                },
+                Entry {
+                    receiver: Address::new_id(6),
+                    method_num: 7,
+                },
+            ]
+        );
+        assert!(pre.normalize().unwrap().changes(&post.normalize().unwrap()).is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_lotus_json() {
+        let state = NormalizedState {
+            entries: vec![
+                Entry {
+                    receiver: Address::new_id(4),
+                    method_num: 5,
+                },
+                Entry {
+                    receiver: Address::new_id(5),
+                    method_num: 9,
+                },
+            ],
+        };
+        let value = serde_json::json!({
+            "Entries": [
+                { "Receiver": "f04", "MethodNum": 5 },
+                { "Receiver": "f05", "MethodNum": 9 },
+            ]
+        });
+        assert_eq!(serde_json::to_value(&state).unwrap(), value);
+        assert_eq!(serde_json::from_value::<NormalizedState>(value).unwrap(), state);
+    }
+}
@@ -1,13 +1,64 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use frc46_token::token;
+use frc46_token::token::state::decode_actor_id;
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared3::address::Address;
+use fvm_shared3::error::ExitCode;
+use fvm_shared4::econ::TokenAmount;
+use fvm_shared4::ActorID;
 
-use frc46_token::token;
+use fil_actors_shared::v10::{ActorError, AsActorError};
 
 #[derive(Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct State {
     pub governor: Address,
     pub token: token::state::TokenState,
 }
+
+impl State {
+    pub fn balance<BS: Blockstore>(
+        &self,
+        bs: &BS,
+        owner: ActorID,
+    ) -> Result<TokenAmount, ActorError> {
+        self.token
+            .get_balance(bs, owner)
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to get balance")
+    }
+
+    pub fn total_supply(&self) -> TokenAmount {
+        self.token.supply.clone()
+    }
+
+    pub fn allowance<BS: Blockstore>(
+        &self,
+        bs: &BS,
+        owner: ActorID,
+        operator: ActorID,
+    ) -> Result<TokenAmount, ActorError> {
+        self.token
+            .get_allowance_between(bs, owner, operator)
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to get allowance")
+    }
+
+    /// Runs a function over the balances of the token holders. Zero balances are not stored.
+    pub fn for_each_balance<BS, F>(&self, bs: &BS, mut f: F) -> Result<(), ActorError>
+    where
+        BS: Blockstore,
+        F: FnMut(ActorID, &TokenAmount) -> Result<(), ActorError>,
+    {
+        self.token
+            .get_balance_map(bs)
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to load balances")?
+            .for_each(|key, balance| {
+                let owner = decode_actor_id(key).ok_or_else(|| {
+                    ActorError::illegal_state(format!("invalid balance key {key:?}"))
+                })?;
+                Ok(f(owner, balance)?)
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate balances")
+    }
+}
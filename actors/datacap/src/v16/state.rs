@@ -1,4 +1,5 @@
 use frc46_token::token;
+use frc46_token::token::state::decode_actor_id;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared4::address::Address;
@@ -34,4 +35,67 @@ impl State {
             .get_balance(bs, owner)
             .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to get balance")
     }
+
+    pub fn total_supply(&self) -> TokenAmount {
+        self.token.supply.clone()
+    }
+
+    pub fn allowance<BS: Blockstore>(
+        &self,
+        bs: &BS,
+        owner: ActorID,
+        operator: ActorID,
+    ) -> Result<TokenAmount, ActorError> {
+        self.token
+            .get_allowance_between(bs, owner, operator)
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to get allowance")
+    }
+
+    /// Runs a function over the balances of the token holders. Zero balances are not stored.
+    pub fn for_each_balance<BS, F>(&self, bs: &BS, mut f: F) -> Result<(), ActorError>
+    where
+        BS: Blockstore,
+        F: FnMut(ActorID, &TokenAmount) -> Result<(), ActorError>,
+    {
+        self.token
+            .get_balance_map(bs)
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to load balances")?
+            .for_each(|key, balance| {
+                let owner = decode_actor_id(key).ok_or_else(|| {
+                    ActorError::illegal_state(format!("invalid balance key {key:?}"))
+                })?;
+                Ok(f(owner, balance)?)
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate balances")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_token_accessors() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store, Address::new_id(1)).unwrap();
+        let amount = TokenAmount::from_whole(5);
+        for owner in [100, 101] {
+            st.token.change_balance_by(&store, owner, &amount).unwrap();
+            st.token.change_supply_by(&amount).unwrap();
+        }
+        st.token.set_allowance(&store, 100, 200, &amount).unwrap();
+
+        assert_eq!(st.total_supply(), TokenAmount::from_whole(10));
+        assert_eq!(st.allowance(&store, 100, 200).unwrap(), amount);
+        assert!(st.allowance(&store, 101, 200).unwrap().is_zero());
+        let mut balances = Vec::new();
+        st.for_each_balance(&store, |owner, balance| {
+            balances.push((owner, balance.clone()));
+            Ok(())
+        })
+        .unwrap();
+        balances.sort();
+        assert_eq!(balances, [(100, amount.clone()), (101, amount)]);
+    }
 }
@@ -0,0 +1,11 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+// The per-version crates replaced by this one, as deprecated aliases of its version modules, so
+// that their users can import them under their old names and migrate one version at a time, e.g.
+// `use fil_actor_eam_state::legacy::fil_actor_eam_v10;`.
+
+#[deprecated(note = "use `fil_actor_eam_state::v10` instead")]
+pub mod fil_actor_eam_v10 {
+    pub use crate::v10::*;
+}
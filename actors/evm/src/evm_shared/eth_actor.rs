@@ -0,0 +1,127 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+use fil_actors_shared::actor_type::ActorType;
+use fil_actors_shared::address_owner::{classify_address_owner, AddressOwner};
+use fil_actors_shared::v16::EAM_ACTOR_ID;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared4::address::{Address, Payload};
+use fvm_shared4::ActorID;
+
+use super::EthAddress;
+
+/// Kind of an actor with an Ethereum identity, as seen by the Ethereum APIs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EthActorKind {
+    /// An externally owned account.
+    EthAccount,
+    /// An EVM smart contract.
+    Contract,
+    /// A delegated address that received funds before an actor was deployed at it.
+    Placeholder,
+}
+
+impl EthActorKind {
+    /// Returns the kind of the actors of a type, or `None` for actors without an Ethereum
+    /// identity of their own. The type of a code CID is given by
+    /// [`fil_actors_shared::bundle::Manifest::actor_type`].
+    pub fn of(actor_type: ActorType) -> Option<Self> {
+        match actor_type {
+            ActorType::EthAccount => Some(Self::EthAccount),
+            ActorType::EVM => Some(Self::Contract),
+            ActorType::Placeholder => Some(Self::Placeholder),
+            _ => None,
+        }
+    }
+}
+
+/// The Ethereum identity of an actor of the state tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthActor {
+    pub id: ActorID,
+    /// `None` for actors without an Ethereum identity of their own, which can still be addressed
+    /// by their masked ID address.
+    pub kind: Option<EthActorKind>,
+    pub eth_address: EthAddress,
+    /// The f410 address of the actor, if it was created through the EAM.
+    pub delegated_address: Option<Address>,
+}
+
+/// Returns the Ethereum address of an actor: the subaddress of its f410 delegated address if it
+/// has one, and its masked ID address, `0xff` followed by zeros and the ID, otherwise.
+pub fn eth_address(id: ActorID, delegated_address: Option<&Address>) -> EthAddress {
+    delegated_address
+        .and_then(eam_subaddress)
+        .unwrap_or_else(|| EthAddress::from_id(id))
+}
+
+/// Returns the f410 address of an Ethereum address, or `None` for masked ID addresses, which
+/// designate actors by ID rather than by delegated address.
+pub fn delegated_address(eth_address: &EthAddress) -> Option<Address> {
+    match eth_address.is_id() {
+        true => None,
+        false => Some(Address::from(eth_address)),
+    }
+}
+
+fn eam_subaddress(address: &Address) -> Option<EthAddress> {
+    match address.payload() {
+        Payload::Delegated(delegated) if delegated.namespace() == EAM_ACTOR_ID => {
+            Some(EthAddress(delegated.subaddress().try_into().ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Resolves an address in the state tree with the given root and returns the Ethereum identity of
+/// its actor, or `None` if the address is not assigned to an actor. Masked ID addresses can be
+/// resolved by converting them to Filecoin addresses first, see [`EthAddress::as_id`].
+pub fn resolve_eth_actor<BS: Blockstore>(
+    store: &BS,
+    state_root: &Cid,
+    address: &Address,
+) -> anyhow::Result<Option<EthActor>> {
+    let Some(classification) = classify_address_owner(store, state_root, address)? else {
+        return Ok(None);
+    };
+    let actor_type = match classification.owner {
+        AddressOwner::Account => Some(ActorType::Account),
+        AddressOwner::EthAccount => Some(ActorType::EthAccount),
+        AddressOwner::Placeholder => Some(ActorType::Placeholder),
+        AddressOwner::Miner => Some(ActorType::Miner),
+        AddressOwner::Other(actor_type) => actor_type,
+    };
+    let delegated_address = classification.actor.delegated_address;
+    Ok(Some(EthActor {
+        id: classification.id,
+        kind: actor_type.and_then(EthActorKind::of),
+        eth_address: eth_address(classification.id, delegated_address.as_ref()),
+        delegated_address,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eth_address() {
+        let f410 = Address::new_delegated(EAM_ACTOR_ID, &[0xab; 20]).unwrap();
+        assert_eq!(eth_address(1000, Some(&f410)), EthAddress([0xab; 20]));
+        assert_eq!(delegated_address(&EthAddress([0xab; 20])), Some(f410));
+
+        // Actors without an f410 address are addressed by their masked ID.
+        let masked = eth_address(1000, None);
+        assert_eq!(masked.as_id(), Some(1000));
+        assert_eq!(delegated_address(&masked), None);
+        let other = Address::new_delegated(11, &[0xab; 20]).unwrap();
+        assert_eq!(eth_address(1000, Some(&other)), masked);
+
+        assert_eq!(
+            EthActorKind::of(ActorType::EVM),
+            Some(EthActorKind::Contract)
+        );
+        assert_eq!(EthActorKind::of(ActorType::Account), None);
+    }
+}
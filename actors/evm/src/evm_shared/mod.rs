@@ -1,6 +1,8 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+pub mod eth_actor;
+pub mod storage;
 pub mod v10;
 pub mod v11;
 pub mod v12;
@@ -8,3 +10,34 @@ pub mod v13;
 pub mod v14;
 pub mod v15;
 pub mod v16;
+
+/// The EVM word of the latest version. Words have been 32 bytes big-endian integers since v10, so
+/// the words of other versions convert through their bytes, e.g. `U256::from(word.to_bytes())`.
+pub use v16::uints::U256;
+
+/// The EVM-form address of the latest version, which converts to and from its 20 bytes.
+pub use v16::address::EthAddress;
+
+impl From<[u8; 32]> for U256 {
+    fn from(bytes: [u8; 32]) -> Self {
+        U256::from_big_endian(&bytes)
+    }
+}
+
+impl From<U256> for [u8; 32] {
+    fn from(word: U256) -> Self {
+        word.to_big_endian()
+    }
+}
+
+impl From<[u8; 20]> for EthAddress {
+    fn from(bytes: [u8; 20]) -> Self {
+        EthAddress(bytes)
+    }
+}
+
+impl From<EthAddress> for [u8; 20] {
+    fn from(address: EthAddress) -> Self {
+        address.0
+    }
+}
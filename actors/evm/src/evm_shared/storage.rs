@@ -0,0 +1,64 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use multihash_codetable::{Code, MultihashDigest};
+
+use super::U256;
+
+/// Computes the Keccak-256 digest of some data, the hash function of the EVM.
+pub fn keccak256(data: impl AsRef<[u8]>) -> [u8; 32] {
+    let digest = Code::Keccak256.digest(data.as_ref());
+    let mut hash = [0; 32];
+    hash.copy_from_slice(digest.digest());
+    hash
+}
+
+/// Returns the storage slot of the value of `key` in a Solidity mapping declared at `slot`, that
+/// is `keccak256(key . slot)`. Keys shorter than a word, such as integers and addresses, are
+/// left-padded with zeros, see `EthAddress::as_evm_word`.
+///
+/// Slots are the keys of the contract storage of the EVM actor state, so the value can then be
+/// looked up in the storage of any version.
+pub fn mapping_slot(key: U256, slot: U256) -> U256 {
+    let mut preimage = [0; 64];
+    preimage[..32].copy_from_slice(&key.to_bytes());
+    preimage[32..].copy_from_slice(&slot.to_bytes());
+    U256::from(keccak256(preimage))
+}
+
+/// Returns the storage slot of the first element of a Solidity dynamic array declared at `slot`,
+/// that is `keccak256(slot)`. The length of the array is stored at `slot` itself.
+pub fn dynamic_array_slot(slot: U256) -> U256 {
+    U256::from(keccak256(slot.to_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm_shared::EthAddress;
+
+    #[test]
+    fn test_storage_slots() {
+        assert_eq!(
+            hex::encode(keccak256([])),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex::encode(<[u8; 32]>::from(dynamic_array_slot(U256::ZERO))),
+            "290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+        );
+        assert_eq!(
+            hex::encode(mapping_slot(U256::ZERO, U256::ZERO).to_bytes()),
+            "ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+        );
+
+        let address = EthAddress::from([0xab; 20]);
+        assert_eq!(<[u8; 20]>::from(address), [0xab; 20]);
+        let word = address.as_evm_word();
+        assert_eq!(U256::from(<[u8; 32]>::from(word)), word);
+        assert_ne!(
+            mapping_slot(word, U256::ONE),
+            mapping_slot(word, U256::ZERO)
+        );
+    }
+}
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 pub mod evm_shared;
+#[cfg(feature = "legacy-compat")]
+pub mod legacy;
 pub mod v10;
 pub mod v11;
 pub mod v12;
@@ -9,3 +11,4 @@ pub mod v13;
 pub mod v14;
 pub mod v15;
 pub mod v16;
+pub mod versioned;
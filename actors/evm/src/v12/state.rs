@@ -136,6 +136,45 @@ pub struct State {
     pub tombstone: Option<Tombstone>,
 }
 
+/// Lifecycle stage of an EVM contract, see [`State::lifecycle`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ContractLifecycle {
+    /// The contract has not self-destructed.
+    Alive,
+    /// The contract self-destructed during the top-level message being executed, and behaves
+    /// normally until that message ends.
+    SelfDestructing(Tombstone),
+    /// The contract self-destructed during an earlier message. It behaves as an empty contract
+    /// and can be resurrected by deploying a new contract at its address with CREATE/CREATE2.
+    Dead(Tombstone),
+}
+
+impl State {
+    /// Returns the lifecycle stage of the contract. `current_message` is the `(origin, nonce)`
+    /// of the top-level message being executed, or `None` when inspecting the state between
+    /// messages, e.g. at a tipset boundary.
+    ///
+    /// The delegated (`f410`) address of a contract is not part of its state but of its actor
+    /// entry in the state tree, and is kept across self-destruction and resurrection.
+    pub fn lifecycle(&self, current_message: Option<(ActorID, u64)>) -> ContractLifecycle {
+        match self.tombstone {
+            None => ContractLifecycle::Alive,
+            Some(tombstone) if current_message == Some((tombstone.origin, tombstone.nonce)) => {
+                ContractLifecycle::SelfDestructing(tombstone)
+            }
+            Some(tombstone) => ContractLifecycle::Dead(tombstone),
+        }
+    }
+
+    /// Whether the contract can be resurrected, which is the case once it is dead. A `Resurrect`
+    /// re-runs the constructor, replacing the whole state: the bytecode and its hash, the
+    /// contract storage and the nonce are reset to those of the new contract, and the tombstone is
+    /// cleared. The actor ID, balance and delegated address are kept.
+    pub fn can_resurrect(&self, current_message: Option<(ActorID, u64)>) -> bool {
+        matches!(self.lifecycle(current_message), ContractLifecycle::Dead(_))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use fvm_ipld_encoding::{from_slice, to_vec, BytesDe};
@@ -0,0 +1,206 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use cid::Cid;
+#[cfg(feature = "json")]
+use fil_actors_shared::lotus_json;
+use fvm_ipld_blockstore::Blockstore;
+
+/// The bytecode hash and tombstone types of all versions. They have not changed since v10.
+pub use crate::v16::{BytecodeHash, Tombstone};
+
+fil_actors_shared::versioned_state! {
+    actor: "EVM",
+    type: EVM,
+    /// The fields of the EVM actor state, with version-agnostic types. The layout has not changed
+    /// since v10.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        /// Raw block of the contract bytecode.
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub bytecode: Cid,
+        /// Keccak-256 hash of the bytecode.
+        #[cfg_attr(feature = "json", serde(with = "json::bytecode_hash"))]
+        pub bytecode_hash: BytecodeHash,
+        /// Root of the contract storage.
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub contract_state: Cid,
+        pub nonce: u64,
+        /// Set if the contract has self-destructed.
+        #[cfg_attr(feature = "json", serde(with = "json::tombstone"))]
+        pub tombstone: Option<Tombstone>,
+    }
+    versions {
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        bytecode: st.bytecode,
+        bytecode_hash: <[u8; 32]>::from(st.bytecode_hash).into(),
+        contract_state: st.contract_state,
+        nonce: st.nonce,
+        tombstone: st.tombstone.map(|t| Tombstone {
+            origin: t.origin,
+            nonce: t.nonce,
+        }),
+    }
+}
+
+/// Lotus JSON of the EVM types. Lotus renders them as a byte array and an object, while their state
+/// encodings are a byte string and a tuple.
+#[cfg(feature = "json")]
+mod json {
+    pub mod bytecode_hash {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        use super::super::BytecodeHash;
+
+        pub fn serialize<S: Serializer>(
+            value: &BytecodeHash,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            <[u8; 32]>::from(*value).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<BytecodeHash, D::Error> {
+            <[u8; 32]>::deserialize(deserializer).map(BytecodeHash::from)
+        }
+    }
+
+    pub mod tombstone {
+        use fvm_shared4::ActorID;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        use super::super::Tombstone;
+
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct TombstoneJson {
+            origin: ActorID,
+            nonce: u64,
+        }
+
+        pub fn serialize<S: Serializer>(
+            value: &Option<Tombstone>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            value
+                .map(|t| TombstoneJson {
+                    origin: t.origin,
+                    nonce: t.nonce,
+                })
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Tombstone>, D::Error> {
+            Ok(Option::<TombstoneJson>::deserialize(deserializer)?.map(|t| Tombstone {
+                origin: t.origin,
+                nonce: t.nonce,
+            }))
+        }
+    }
+}
+
+impl VersionedState {
+    pub fn bytecode_cid(&self) -> anyhow::Result<Cid> {
+        Ok(self.normalize()?.bytecode)
+    }
+
+    pub fn bytecode_hash(&self) -> anyhow::Result<BytecodeHash> {
+        Ok(self.normalize()?.bytecode_hash)
+    }
+
+    pub fn nonce(&self) -> anyhow::Result<u64> {
+        Ok(self.normalize()?.nonce)
+    }
+
+    /// Loads the bytecode of the contract, as returned by `eth_getCode`.
+    pub fn bytecode<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<Vec<u8>> {
+        let cid = self.bytecode_cid()?;
+        store
+            .get(&cid)?
+            .with_context(|| format!("EVM bytecode {cid} not found"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm_shared::storage::keccak256;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_ipld_encoding::IPLD_RAW;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    #[test]
+    fn test_versioned_state() {
+        let store = MemoryBlockstore::new();
+        let code = vec![0x60, 0x00, 0x60, 0x00, 0xf3];
+        let bytecode = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(&code));
+        store.put_keyed(&bytecode, &code).unwrap();
+        let st = crate::v10::State {
+            bytecode,
+            bytecode_hash: keccak256(&code).into(),
+            contract_state: bytecode,
+            nonce: 3,
+            tombstone: Some(crate::v10::Tombstone {
+                origin: 100,
+                nonce: 7,
+            }),
+        };
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let pre = VersionedState::load(&store, 10, &root).unwrap();
+        let post = VersionedState::load(&store, 16, &root).unwrap();
+        assert_eq!(pre.bytecode(&store).unwrap(), code);
+        assert_eq!(pre.bytecode_hash().unwrap(), BytecodeHash::from(keccak256(&code)));
+        assert_eq!(pre.nonce().unwrap(), 3);
+        assert_eq!(
+            pre.normalize().unwrap().tombstone,
+            Some(Tombstone {
+                origin: 100,
+                nonce: 7
+            })
+        );
+        assert!(pre.normalize().unwrap().changes(&post.normalize().unwrap()).is_empty());
+        assert!(VersionedState::load(&store, 9, &root).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_lotus_json() {
+        let bytecode = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(&[0xfe]));
+        let state = NormalizedState {
+            bytecode,
+            bytecode_hash: BytecodeHash::NATIVE_ACTOR,
+            contract_state: bytecode,
+            nonce: 3,
+            tombstone: Some(Tombstone {
+                origin: 100,
+                nonce: 7,
+            }),
+        };
+        let value = serde_json::to_value(&state).unwrap();
+        assert_eq!(
+            value["BytecodeHash"],
+            serde_json::json!(<[u8; 32]>::from(BytecodeHash::NATIVE_ACTOR))
+        );
+        assert_eq!(value["Tombstone"], serde_json::json!({ "Origin": 100, "Nonce": 7 }));
+        assert_eq!(value["Bytecode"], serde_json::json!({ "/": bytecode.to_string() }));
+        assert_eq!(serde_json::from_value::<NormalizedState>(value).unwrap(), state);
+    }
+}
@@ -4,7 +4,10 @@
 use fvm_shared4::METHOD_CONSTRUCTOR;
 use num_derive::FromPrimitive;
 
-pub use self::state::State;
+pub use self::state::{
+    new_actor_address, predict_actor_address, predict_exec4_actor_address, PredictedActorAddress,
+    State,
+};
 pub use self::types::*;
 
 mod state;
@@ -3,6 +3,7 @@
 
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::to_vec;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared4::address::{Address, Protocol};
 use fvm_shared4::ActorID;
@@ -97,3 +98,82 @@ impl State {
         Ok(found.copied().map(Address::new_id))
     }
 }
+
+/// Addresses the init actor assigns to an actor it creates, see [`predict_actor_address`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PredictedActorAddress {
+    /// The robust address, which does not depend on the order of execution of the messages.
+    pub robust_address: Address,
+    /// The delegated address of an actor created through Exec4.
+    pub delegated_address: Option<Address>,
+    /// The ID address assigned to the actor, provided no other actor is created before it.
+    pub id_address: Address,
+    /// Whether the ID is the one of an actor already at the delegated address, such as a
+    /// placeholder that received funds, which the new actor is deployed over.
+    pub existing: bool,
+}
+
+/// Returns the robust address of an actor created by a message, as the runtime derives it from
+/// the key address of the message origin, the message nonce and the number of actors created
+/// earlier while executing the message.
+pub fn new_actor_address(
+    origin: &Address,
+    nonce: u64,
+    actors_created: u64,
+) -> Result<Address, ActorError> {
+    let mut bytes = to_vec(origin)?;
+    bytes.extend_from_slice(&nonce.to_be_bytes());
+    bytes.extend_from_slice(&actors_created.to_be_bytes());
+    Ok(Address::new_actor(&bytes))
+}
+
+/// Predicts the addresses of the actor created by an Exec message with the given nonce, sent by
+/// the account with the given key address, against the init actor state it executes on. The
+/// actor is assumed to be the first one created by the message.
+pub fn predict_actor_address<BS: Blockstore>(
+    store: &BS,
+    init_state: &State,
+    creator: &Address,
+    nonce: u64,
+) -> Result<PredictedActorAddress, ActorError> {
+    let robust_address = new_actor_address(creator, nonce, 0)?;
+    if init_state
+        .resolve_address(store, &robust_address)?
+        .is_some()
+    {
+        return Err(actor_error_v15!(
+            forbidden,
+            "robust address {} is already allocated in the address map",
+            robust_address
+        ));
+    }
+    Ok(PredictedActorAddress {
+        robust_address,
+        delegated_address: None,
+        id_address: Address::new_id(init_state.next_id),
+        existing: false,
+    })
+}
+
+/// Predicts the addresses of the actor created by an Exec4 message, see
+/// [`predict_actor_address`]. The delegated address is made of the ID of the calling actor, which
+/// is the Ethereum Address Manager, as namespace and of the given subaddress. A delegated address
+/// already in the address map keeps its ID.
+pub fn predict_exec4_actor_address<BS: Blockstore>(
+    store: &BS,
+    init_state: &State,
+    creator: &Address,
+    nonce: u64,
+    namespace: ActorID,
+    subaddress: &[u8],
+) -> Result<PredictedActorAddress, ActorError> {
+    let delegated_address = Address::new_delegated(namespace, subaddress)
+        .map_err(|e| actor_error_v15!(illegal_argument, "invalid delegated address: {}", e))?;
+    let mut predicted = predict_actor_address(store, init_state, creator, nonce)?;
+    predicted.delegated_address = Some(delegated_address);
+    if let Some(id_address) = init_state.resolve_address(store, &delegated_address)? {
+        predicted.id_address = id_address;
+        predicted.existing = true;
+    }
+    Ok(predicted)
+}
@@ -0,0 +1,148 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use cid::Cid;
+#[cfg(feature = "json")]
+use fil_actors_shared::lotus_json;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_hamt::{BytesKey, Hamt, Hamtv0};
+use fvm_shared4::address::{Address, Protocol};
+use fvm_shared4::{ActorID, HAMT_BIT_WIDTH};
+
+fil_actors_shared::versioned_state! {
+    actor: "init",
+    type: Init,
+    /// The fields of the init actor state. The layout has not changed since v0.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        /// Root of the address map, a HAMT of actor IDs keyed by robust address.
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub address_map: Cid,
+        #[cfg_attr(feature = "json", serde(rename = "NextID"))]
+        pub next_id: ActorID,
+        pub network_name: String,
+    }
+    versions {
+        V0 => 0, crate::v0::State;
+        V8 => 8, crate::v8::State;
+        V9 => 9, crate::v9::State;
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        address_map: st.address_map,
+        next_id: st.next_id,
+        network_name: st.network_name.clone(),
+    }
+}
+
+impl VersionedState {
+    /// Resolves an address to its ID address, or `None` if it is not in the address map. ID
+    /// addresses are returned as-is, whether or not they are assigned to an actor.
+    ///
+    /// The address map of v0 uses the HAMT format of specs-actors v0 to v2.
+    pub fn resolve_address<BS: Blockstore>(
+        &self,
+        store: &BS,
+        address: &Address,
+    ) -> anyhow::Result<Option<Address>> {
+        if address.protocol() == Protocol::ID {
+            return Ok(Some(*address));
+        }
+        let root = self.normalize()?.address_map;
+        let key = address.to_bytes();
+        let id = match self {
+            Self::V0(_) => {
+                Hamtv0::<_, ActorID, BytesKey>::load_with_bit_width(&root, store, HAMT_BIT_WIDTH)?
+                    .get(&key)?
+                    .copied()
+            }
+            _ => Hamt::<_, ActorID, BytesKey>::load_with_bit_width(&root, store, HAMT_BIT_WIDTH)?
+                .get(&key)?
+                .copied(),
+        };
+        Ok(id.map(Address::new_id))
+    }
+
+    /// Runs a function over the entries of the address map, the robust addresses and the IDs
+    /// they resolve to, in no particular order. Singleton actors have no entry.
+    pub fn for_each_id_mapping<BS, F>(&self, store: &BS, mut f: F) -> anyhow::Result<()>
+    where
+        BS: Blockstore,
+        F: FnMut(&Address, ActorID) -> anyhow::Result<()>,
+    {
+        let root = self.normalize()?.address_map;
+        let mut visit = |key: &BytesKey, id: &ActorID| {
+            let address = Address::from_bytes(key).context("invalid address map key")?;
+            f(&address, *id)
+        };
+        match self {
+            Self::V0(_) => {
+                Hamtv0::<_, ActorID, BytesKey>::load_with_bit_width(&root, store, HAMT_BIT_WIDTH)?
+                    .for_each(&mut visit)?
+            }
+            _ => Hamt::<_, ActorID, BytesKey>::load_with_bit_width(&root, store, HAMT_BIT_WIDTH)?
+                .for_each(&mut visit)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_resolve_address() {
+        let store = MemoryBlockstore::new();
+        let secp = Address::new_secp256k1(&[1; 65]).unwrap();
+        let actor = Address::new_actor(b"actor");
+
+        let mut v0_map = Hamtv0::<_, ActorID>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        let mut v8_map = Hamt::<_, ActorID>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        for (address, id) in [(secp, 100), (actor, 101)] {
+            v0_map.set(address.to_bytes().into(), id).unwrap();
+            v8_map.set(address.to_bytes().into(), id).unwrap();
+        }
+        let v0 = crate::v0::State::new(v0_map.flush().unwrap(), "test".to_string());
+        let v8 = crate::v8::State {
+            address_map: v8_map.flush().unwrap(),
+            next_id: 102,
+            network_name: "test".to_string(),
+        };
+
+        for (version, root) in [
+            (0, store.put_cbor(&v0, Code::Blake2b256).unwrap()),
+            (8, store.put_cbor(&v8, Code::Blake2b256).unwrap()),
+        ] {
+            let state = VersionedState::load(&store, version, &root).unwrap();
+            let resolve = |address| state.resolve_address(&store, &address).unwrap();
+            assert_eq!(resolve(secp), Some(Address::new_id(100)));
+            assert_eq!(resolve(Address::new_id(5)), Some(Address::new_id(5)));
+            assert_eq!(resolve(Address::new_actor(b"other")), None);
+
+            let mut mappings = Vec::new();
+            state
+                .for_each_id_mapping(&store, |address, id| {
+                    mappings.push((*address, id));
+                    Ok(())
+                })
+                .unwrap();
+            mappings.sort_by_key(|(_, id)| *id);
+            assert_eq!(mappings, [(secp, 100), (actor, 101)]);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+// Compares reads through the versioned market state with the version-agnostic paths, which
+// decode blocks through IPLD. Run with `cargo bench -p fil_actor_market_state`.
+
+use cid::Cid;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fil_actor_market_state::v16::{DealProposal, Label, State};
+use fil_actors_shared::diff::diff_amt;
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared4::address::Address;
+use fvm_shared4::econ::TokenAmount;
+use fvm_shared4::piece::PaddedPieceSize;
+use ipld_core::ipld::Ipld;
+
+const DEALS: u64 = 10_000;
+
+fn market_state(store: &MemoryBlockstore) -> State {
+    let mut st = State::new(store).unwrap();
+    let proposals: Vec<_> = (0..DEALS)
+        .map(|deal_id| {
+            let proposal = DealProposal {
+                piece_cid: Cid::default(),
+                piece_size: PaddedPieceSize(2048),
+                verified_deal: false,
+                client: Address::new_id(100 + deal_id % 10),
+                provider: Address::new_id(1000 + deal_id % 100),
+                label: Label::String(format!("deal {deal_id}")),
+                start_epoch: 0,
+                end_epoch: 1_000_000,
+                storage_price_per_epoch: TokenAmount::from_atto(deal_id),
+                provider_collateral: TokenAmount::from_atto(1),
+                client_collateral: TokenAmount::from_atto(1),
+            };
+            (deal_id, proposal)
+        })
+        .collect();
+    st.put_deal_proposals(store, &proposals).unwrap();
+    st
+}
+
+fn deal_scan(c: &mut Criterion) {
+    let store = MemoryBlockstore::new();
+    let st = market_state(&store);
+    let empty = State::new(&store).unwrap();
+    let mut group = c.benchmark_group("deal_scan");
+    group.sample_size(20);
+    group.bench_function("versioned", |b| {
+        b.iter(|| {
+            let mut end_epochs = 0;
+            st.load_proposals(black_box(&store))
+                .unwrap()
+                .for_each(|_, proposal| {
+                    end_epochs += proposal.end_epoch;
+                    Ok(())
+                })
+                .unwrap();
+            end_epochs
+        })
+    });
+    group.bench_function("ipld", |b| {
+        b.iter(|| {
+            let mut end_epochs = 0;
+            Amt::<Ipld, _>::load(&st.proposals, black_box(&store))
+                .unwrap()
+                .for_each(|_, proposal| {
+                    let proposal: DealProposal = ipld_core::serde::from_ipld(proposal.clone())?;
+                    end_epochs += proposal.end_epoch;
+                    Ok(())
+                })
+                .unwrap();
+            end_epochs
+        })
+    });
+    group.bench_function("diff_from_empty", |b| {
+        b.iter(|| {
+            diff_amt(black_box(&store), &empty.proposals, &st.proposals)
+                .unwrap()
+                .len()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, deal_scan);
+criterion_main!(benches);
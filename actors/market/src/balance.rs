@@ -0,0 +1,111 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::{ensure, Context};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::address::{Address, Protocol};
+use fvm_shared4::econ::TokenAmount;
+
+use crate::v16::balance_table::BALANCE_TABLE_BITWIDTH;
+use crate::versioned::VersionedState;
+
+/// The funds of an address in the market actor, as reported by `StateMarketBalance`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarketBalance {
+    /// Funds deposited in escrow, including the locked ones.
+    pub escrow: TokenAmount,
+    /// Funds locked as deal collateral and storage fees.
+    pub locked: TokenAmount,
+}
+
+impl MarketBalance {
+    /// Returns the funds which can be withdrawn.
+    pub fn available(&self) -> TokenAmount {
+        &self.escrow - &self.locked
+    }
+}
+
+impl VersionedState {
+    /// Returns the market balance of an address, zero if it never deposited funds. The tables are
+    /// keyed by ID address, so other addresses must be resolved with the init actor first.
+    ///
+    /// The escrow and locked tables have had the same layout since v8, HAMTs of token amounts by
+    /// address with a bit width of 6, so they are read the same way whatever the version.
+    pub fn market_balance<BS: Blockstore>(
+        &self,
+        store: &BS,
+        address: &Address,
+    ) -> anyhow::Result<MarketBalance> {
+        ensure!(
+            address.protocol() == Protocol::ID,
+            "balance tables are keyed by ID address, got {address}"
+        );
+        let state = self.normalize()?;
+        Ok(MarketBalance {
+            escrow: table_balance(store, &state.escrow_table, address)
+                .context("failed to read the escrow table")?,
+            locked: table_balance(store, &state.locked_table, address)
+                .context("failed to read the locked table")?,
+        })
+    }
+}
+
+fn table_balance<BS: Blockstore>(
+    store: &BS,
+    root: &Cid,
+    address: &Address,
+) -> anyhow::Result<TokenAmount> {
+    let table =
+        Hamt::<_, TokenAmount, BytesKey>::load_with_bit_width(root, store, BALANCE_TABLE_BITWIDTH)?;
+    Ok(table
+        .get(&BytesKey(address.to_bytes()))?
+        .cloned()
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v8::balance_table::BalanceTable;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_market_balance() {
+        let store = MemoryBlockstore::new();
+        let client = Address::new_id(100);
+        let mut st = crate::v8::State::new(&store).unwrap();
+        let client_v2 = fvm_shared::address::Address::new_id(100);
+        let atto = fvm_shared::econ::TokenAmount::from_atto;
+        let mut escrow = BalanceTable::from_root(&store, &st.escrow_table).unwrap();
+        escrow.add(&client_v2, &atto(30)).unwrap();
+        st.escrow_table = escrow.root().unwrap();
+        let mut locked = BalanceTable::from_root(&store, &st.locked_table).unwrap();
+        locked.add(&client_v2, &atto(10)).unwrap();
+        st.locked_table = locked.root().unwrap();
+        let v8 = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let mut st = crate::v16::State::new(&store).unwrap();
+        st.add_balance_to_escrow_table(&store, &client, &TokenAmount::from_atto(5))
+            .unwrap();
+        let v16 = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let state = VersionedState::load(&store, 8, &v8).unwrap();
+        let balance = state.market_balance(&store, &client).unwrap();
+        assert_eq!(balance.escrow, TokenAmount::from_atto(30));
+        assert_eq!(balance.available(), TokenAmount::from_atto(20));
+        let unknown = state.market_balance(&store, &Address::new_id(101)).unwrap();
+        assert_eq!(unknown, MarketBalance::default());
+
+        let state = VersionedState::load(&store, 16, &v16).unwrap();
+        let balance = state.market_balance(&store, &client).unwrap();
+        assert_eq!(balance.escrow, TokenAmount::from_atto(5));
+        assert_eq!(balance.locked, TokenAmount::from_atto(0));
+
+        let robust = Address::new_secp256k1(&[0; 65]).unwrap();
+        assert!(state.market_balance(&store, &robust).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fil_actors_shared::conversion_tests::{arbitrary_cid, cbor_convert, Conversion};
+use quickcheck::{Arbitrary, Gen};
+
+macro_rules! deal_proposal_conversion {
+    ($($name:ident: $old:ident => $new:ident, $shared:ident;)*) => {
+        $(
+            #[doc = concat!(
+                "[`Conversion`] of `DealProposal` from ", stringify!($old), " to ", stringify!($new), "."
+            )]
+            pub struct $name;
+
+            impl Conversion for $name {
+                type Old = crate::$old::DealProposal;
+                type New = crate::$new::DealProposal;
+
+                fn arbitrary(g: &mut Gen) -> Self::Old {
+                    use $shared::address::Address;
+                    use $shared::econ::TokenAmount;
+                    use $shared::piece::PaddedPieceSize;
+
+                    crate::$old::DealProposal {
+                        piece_cid: arbitrary_cid(g),
+                        piece_size: PaddedPieceSize(u64::arbitrary(g)),
+                        verified_deal: bool::arbitrary(g),
+                        client: Address::new_id(u64::arbitrary(g)),
+                        provider: Address::new_id(u64::arbitrary(g)),
+                        label: crate::$old::Label::String(String::arbitrary(g)),
+                        start_epoch: i64::arbitrary(g),
+                        end_epoch: i64::arbitrary(g),
+                        storage_price_per_epoch: TokenAmount::from_atto(u64::arbitrary(g)),
+                        provider_collateral: TokenAmount::from_atto(u64::arbitrary(g)),
+                        client_collateral: TokenAmount::from_atto(u64::arbitrary(g)),
+                    }
+                }
+
+                fn upgrade(old: &Self::Old) -> anyhow::Result<Self::New> {
+                    cbor_convert(old)
+                }
+
+                fn downgrade(new: &Self::New) -> anyhow::Result<Self::Old> {
+                    cbor_convert(new)
+                }
+            }
+        )*
+    };
+}
+
+deal_proposal_conversion! {
+    DealProposalV8ToV9: v8 => v9, fvm_shared;
+    DealProposalV9ToV10: v9 => v10, fvm_shared;
+    DealProposalV10ToV11: v10 => v11, fvm_shared3;
+    DealProposalV11ToV12: v11 => v12, fvm_shared3;
+    DealProposalV12ToV13: v12 => v13, fvm_shared4;
+    DealProposalV13ToV14: v13 => v14, fvm_shared4;
+    DealProposalV14ToV15: v14 => v15, fvm_shared4;
+    DealProposalV15ToV16: v15 => v16, fvm_shared4;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_actors_shared::conversion_tests::check_conversion;
+
+    #[test]
+    fn test_deal_proposal_conversions() {
+        check_conversion::<DealProposalV8ToV9>(100);
+        check_conversion::<DealProposalV9ToV10>(100);
+        check_conversion::<DealProposalV10ToV11>(100);
+        check_conversion::<DealProposalV11ToV12>(100);
+        check_conversion::<DealProposalV12ToV13>(100);
+        check_conversion::<DealProposalV13ToV14>(100);
+        check_conversion::<DealProposalV14ToV15>(100);
+        check_conversion::<DealProposalV15ToV16>(100);
+    }
+}
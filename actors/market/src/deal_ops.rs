@@ -0,0 +1,117 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use fil_actors_shared::ids::DealID;
+use fil_actors_shared::v16::MapKey;
+use fil_actors_shared::v8::parse_uint_key;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::HAMT_BIT_WIDTH;
+
+use crate::versioned::VersionedState;
+
+impl VersionedState {
+    /// Returns the CIDs of the proposals of the deals which are published but not activated yet,
+    /// in no particular order.
+    ///
+    /// The pending proposals have been a set of proposal CIDs since v8, so they are read the same
+    /// way whatever the version.
+    pub fn pending_deal_proposals<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<Vec<Cid>> {
+        let root = self.normalize()?.pending_proposals;
+        let mut proposals = Vec::new();
+        load_set(store, &root)
+            .context("failed to load pending proposals")?
+            .for_each(|key, _| {
+                proposals.push(Cid::try_from(key.0.as_slice())?);
+                Ok(())
+            })?;
+        Ok(proposals)
+    }
+
+    /// Returns the deals scheduled for processing by the market cron at the epochs of the given
+    /// range, by epoch. Deals are sorted by ID.
+    ///
+    /// Deal operations are a HAMT of deal ID sets keyed by epoch in all versions, but the epochs
+    /// are encoded as unsigned varints up to v13 and as zigzag varints from v14 on.
+    pub fn deal_ops_by_epoch<BS: Blockstore>(
+        &self,
+        store: &BS,
+        epochs: Range<ChainEpoch>,
+    ) -> anyhow::Result<BTreeMap<ChainEpoch, Vec<DealID>>> {
+        let root = self.normalize()?.deal_ops_by_epoch;
+        let zigzag = self.version() >= 14;
+        let mut ops = BTreeMap::new();
+        Hamt::<_, Cid, BytesKey>::load_with_bit_width(&root, store, HAMT_BIT_WIDTH)
+            .context("failed to load deal ops")?
+            .for_each(|key, set| {
+                let epoch = match zigzag {
+                    true => ChainEpoch::from_bytes(key).map_err(|e| anyhow!(e))?,
+                    false => parse_uint_key(key)? as ChainEpoch,
+                };
+                if !epochs.contains(&epoch) {
+                    return Ok(());
+                }
+                let mut deals = Vec::new();
+                load_set(store, set)
+                    .with_context(|| format!("failed to load the deal ops of epoch {epoch}"))?
+                    .for_each(|key, _| {
+                        deals.push(DealID(parse_uint_key(key)?));
+                        Ok(())
+                    })?;
+                deals.sort_unstable();
+                ops.insert(epoch, deals);
+                Ok(())
+            })?;
+        Ok(ops)
+    }
+}
+
+fn load_set<'bs, BS: Blockstore>(
+    store: &'bs BS,
+    root: &Cid,
+) -> anyhow::Result<Hamt<&'bs BS, (), BytesKey>> {
+    Ok(Hamt::load_with_bit_width(root, store, HAMT_BIT_WIDTH)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_deal_pipeline() {
+        let store = MemoryBlockstore::new();
+        let proposal = store.put_cbor(&"proposal", Code::Blake2b256).unwrap();
+        let ops = [(10, 3), (10, 1), (20, 2), (30, 4)];
+
+        let mut v13 = crate::v13::State::new(&store).unwrap();
+        v13.put_pending_deals(&store, &[proposal]).unwrap();
+        v13.put_deals_by_epoch(&store, &ops).unwrap();
+        let mut v16 = crate::v16::State::new(&store).unwrap();
+        v16.put_pending_deals(&store, &[proposal]).unwrap();
+        v16.put_deals_by_epoch(&store, &ops).unwrap();
+
+        for (version, root) in [
+            (13, store.put_cbor(&v13, Code::Blake2b256).unwrap()),
+            (16, store.put_cbor(&v16, Code::Blake2b256).unwrap()),
+        ] {
+            let state = VersionedState::load(&store, version, &root).unwrap();
+            assert_eq!(state.pending_deal_proposals(&store).unwrap(), [proposal]);
+            assert_eq!(
+                state.deal_ops_by_epoch(&store, 10..30).unwrap(),
+                BTreeMap::from([
+                    (10, vec![DealID(1), DealID(3)]),
+                    (20, vec![DealID(2)])
+                ])
+            );
+        }
+    }
+}
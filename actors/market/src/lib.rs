@@ -1,6 +1,13 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+pub mod balance;
+/// Property checks of the `DealProposal` conversions between adjacent versions.
+#[cfg(feature = "arb")]
+pub mod conversions;
+pub mod deal_ops;
+#[cfg(feature = "legacy-compat")]
+pub mod legacy;
 pub mod v10;
 pub mod v11;
 pub mod v12;
@@ -10,3 +17,4 @@ pub mod v15;
 pub mod v16;
 pub mod v8;
 pub mod v9;
+pub mod versioned;
@@ -3,6 +3,8 @@
 
 use cid::{Cid, Version};
 use fil_actor_verifreg_state::v10::AllocationID;
+use fil_actors_shared::actor_error_v10;
+use fil_actors_shared::v10::ActorError;
 use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_encoding::BytesSer;
 use fvm_shared3::address::Address;
@@ -15,6 +17,8 @@ use ipld_core::ipld::Ipld;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::convert::{TryFrom, TryInto};
 
+use super::policy::detail::DEAL_MAX_LABEL_SIZE;
+
 /// Cid prefix for piece `Cid`s
 pub fn is_piece_cid(c: &Cid) -> bool {
     // TODO: Move FIL_COMMITMENT etc, into a better place
@@ -82,6 +86,28 @@ impl Label {
             Label::Bytes(b) => b.is_empty(),
         }
     }
+
+    /// Returns whether the label is valid UTF-8. String labels always are, byte labels may be.
+    pub fn is_utf8(&self) -> bool {
+        match self {
+            Label::String(_) => true,
+            Label::Bytes(b) => std::str::from_utf8(b).is_ok(),
+        }
+    }
+
+    /// Checks that the label fits within [`DEAL_MAX_LABEL_SIZE`], as the market actor does when
+    /// publishing the deal.
+    pub fn validate_size(&self) -> Result<(), ActorError> {
+        if self.len() > DEAL_MAX_LABEL_SIZE {
+            return Err(actor_error_v10!(
+                illegal_argument,
+                "deal label can be at most {} bytes, is {}",
+                DEAL_MAX_LABEL_SIZE,
+                self.len()
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Note: Deal Collateral is only released and returned to clients and miners
@@ -3,6 +3,11 @@
 
 use fvm_shared3::econ::TokenAmount;
 
+pub mod detail {
+    /// Maximum length of a deal label.
+    pub const DEAL_MAX_LABEL_SIZE: usize = 256;
+}
+
 /// Penalty to provider deal collateral if the deadline expires before sector commitment.
 pub(super) fn collateral_penalty_for_deal_activation_missed(
     provider_collateral: TokenAmount,
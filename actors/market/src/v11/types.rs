@@ -90,6 +90,32 @@ pub struct VerifyDealsForActivationReturn {
     pub sectors: Vec<SectorDealData>,
 }
 
+/// A deal of a batch of sectors to pre-commit that would fail activation, see
+/// [`State::check_deals_for_activation`](super::State::check_deals_for_activation).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DealActivationIssue {
+    /// Index of the sector in the batch.
+    pub sector_index: usize,
+    pub deal_id: DealID,
+    pub kind: DealActivationIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DealActivationIssueKind {
+    /// The deal was never published, or has expired and been removed.
+    NotFound,
+    /// The deal is already in an earlier sector of the batch.
+    Duplicate,
+    /// The deal is already activated in a sector.
+    AlreadyActivated,
+    /// The deal was made with another provider.
+    WrongProvider(Address),
+    /// The start epoch of the deal has passed.
+    StartEpochElapsed(ChainEpoch),
+    /// The deal ends after the sector expires.
+    ExceedsSectorExpiry(ChainEpoch),
+}
+
 #[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, Eq, PartialEq, Default)]
 pub struct SectorDealData {
     /// Option::None signifies commitment to empty sector, meaning no deals.
@@ -246,3 +272,45 @@ pub struct MarketNotifyDealParams {
     pub proposal: Vec<u8>,
     pub deal_id: u64,
 }
+
+/// What the cron tick of the market actor does to a scheduled deal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CronDealOutcome {
+    /// The deal proposal is no longer in the state, e.g. after an earlier settlement.
+    Missing,
+    /// The deal has not reached its start epoch.
+    NotStarted,
+    /// The deal was not activated before its start epoch: the provider collateral is slashed and
+    /// the deal is removed.
+    TimedOut,
+    /// The elapsed epochs are paid to the provider and the deal stays scheduled.
+    Updated,
+    /// The deal has reached its end epoch: it is paid in full and the collaterals are unlocked.
+    Completed,
+    /// The deal was terminated: it is paid until its slash epoch and the provider collateral is
+    /// slashed.
+    Slashed,
+}
+
+/// Simulated processing of a scheduled deal by the cron tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronDealUpdate {
+    pub deal_id: DealID,
+    /// Epoch the deal was scheduled at.
+    pub scheduled_epoch: ChainEpoch,
+    pub outcome: CronDealOutcome,
+    /// Storage fee transferred from the client to the provider.
+    pub payment: TokenAmount,
+    /// Provider collateral burnt.
+    pub slashed: TokenAmount,
+}
+
+/// Outcome of [`simulate_cron_tick`](super::simulate_cron_tick).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CronTickSimulation {
+    pub deals: Vec<CronDealUpdate>,
+    pub total_payment: TokenAmount,
+    pub total_slashed: TokenAmount,
+    /// Number of scheduled deals beyond `max_deals`, which were not simulated.
+    pub deferred: u64,
+}
@@ -15,8 +15,10 @@ use fil_actors_shared::{
 /// Balance table which handles getting and updating token balances specifically
 pub struct BalanceTable<BS: Blockstore>(pub Map2<BS, Address, TokenAmount>);
 
+pub const BALANCE_TABLE_BITWIDTH: u32 = 6;
+
 const CONF: Config = Config {
-    bit_width: 6,
+    bit_width: BALANCE_TABLE_BITWIDTH,
     ..DEFAULT_HAMT_CONFIG
 };
 
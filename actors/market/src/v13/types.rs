@@ -1,6 +1,8 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::collections::BTreeMap;
+
 use super::ext::verifreg::AllocationID;
 use cid::Cid;
 use fil_actors_shared::v13::Array;
@@ -92,6 +94,65 @@ pub struct VerifyDealsForActivationReturn {
     pub unsealed_cids: Vec<Option<Cid>>,
 }
 
+/// A deal of a batch of sectors to pre-commit that would fail activation, see
+/// [`State::check_deals_for_activation`](super::State::check_deals_for_activation).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DealActivationIssue {
+    /// Index of the sector in the batch.
+    pub sector_index: usize,
+    pub deal_id: DealID,
+    pub kind: DealActivationIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DealActivationIssueKind {
+    /// The deal was never published, or has expired and been removed.
+    NotFound,
+    /// The deal is already in an earlier sector of the batch.
+    Duplicate,
+    /// The deal is already activated in a sector.
+    AlreadyActivated,
+    /// The deal was made with another provider.
+    WrongProvider(Address),
+    /// The start epoch of the deal has passed.
+    StartEpochElapsed(ChainEpoch),
+    /// The deal ends after the sector expires.
+    ExceedsSectorExpiry(ChainEpoch),
+}
+
+/// The deals recorded against the sectors of a provider, see
+/// [`State::provider_sector_deal_ids`](super::State::provider_sector_deal_ids).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderSectorDealIds {
+    /// Deal IDs of each sector with deals.
+    pub sectors: BTreeMap<SectorNumber, Vec<DealID>>,
+    pub deal_count: u64,
+    /// Total padded size of the pieces of the deals whose proposals are still in the state.
+    pub total_piece_size: u64,
+}
+
+/// An inconsistency between the sector deals of a provider and its miner sectors, see
+/// [`State::check_provider_sector_deals`](super::State::check_provider_sector_deals).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectorDealIssue {
+    pub sector_number: SectorNumber,
+    pub kind: SectorDealIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SectorDealIssueKind {
+    /// The sector is not a sector of the miner, so its deals are orphaned.
+    SectorNotFound,
+    /// The deal proposal is not in the state.
+    ProposalNotFound(DealID),
+    /// The deal was made with another provider.
+    WrongProvider(DealID, Address),
+    /// The deal is not activated, or was activated in another sector.
+    SectorMismatch(DealID, Option<SectorNumber>),
+    /// The pieces of the deals of the sector, with their total size, exceed the sector size.
+    ExceedsSectorSize(u64),
+}
+
 #[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, Eq, PartialEq)]
 pub struct BatchActivateDealsParams {
     /// Deals to activate, grouped by sector.
@@ -279,3 +340,76 @@ pub struct DealSettlementSummary {
     /// Whether the deal has settled for the final time
     pub completed: bool,
 }
+
+/// What happens to a deal when the sector holding it is terminated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DealTerminationOutcome {
+    /// The deal is active and gets slashed, burning the provider collateral.
+    Slashed,
+    /// The deal has reached its end epoch and is settled without penalty.
+    Expired,
+    /// The deal has already been slashed.
+    AlreadySlashed,
+    /// The deal has already been settled and cleaned up.
+    Settled,
+}
+
+/// Impact on a single deal of a sector termination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DealTerminationImpact {
+    pub deal_id: DealID,
+    pub outcome: DealTerminationOutcome,
+    /// Provider collateral of the deal, zero if it has already been settled.
+    pub provider_collateral: TokenAmount,
+}
+
+/// Impact on the market of terminating a set of sectors, as processed by
+/// `OnMinerSectorsTerminate`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TerminationImpact {
+    pub deals: Vec<DealTerminationImpact>,
+    /// Total provider collateral of the deals that get slashed.
+    pub collateral_at_risk: TokenAmount,
+}
+
+/// What the cron tick of the market actor does to a scheduled deal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CronDealOutcome {
+    /// The deal proposal is no longer in the state, e.g. after an earlier settlement.
+    Missing,
+    /// The deal has not reached its start epoch.
+    NotStarted,
+    /// The deal was not activated before its start epoch: the provider collateral is slashed and
+    /// the deal is removed.
+    TimedOut,
+    /// The elapsed epochs are paid to the provider and the deal stays scheduled.
+    Updated,
+    /// The deal has reached its end epoch: it is paid in full and the collaterals are unlocked.
+    Completed,
+    /// The deal was terminated: it is paid until its slash epoch and the provider collateral is
+    /// slashed.
+    Slashed,
+}
+
+/// Simulated processing of a scheduled deal by the cron tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronDealUpdate {
+    pub deal_id: DealID,
+    /// Epoch the deal was scheduled at.
+    pub scheduled_epoch: ChainEpoch,
+    pub outcome: CronDealOutcome,
+    /// Storage fee transferred from the client to the provider.
+    pub payment: TokenAmount,
+    /// Provider collateral burnt.
+    pub slashed: TokenAmount,
+}
+
+/// Outcome of [`simulate_cron_tick`](super::simulate_cron_tick).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CronTickSimulation {
+    pub deals: Vec<CronDealUpdate>,
+    pub total_payment: TokenAmount,
+    pub total_slashed: TokenAmount,
+    /// Number of scheduled deals beyond `max_deals`, which were not simulated.
+    pub deferred: u64,
+}
@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use cid::Cid;
+#[cfg(feature = "proofs")]
+use fil_actors_shared::abi::commp::{verify_unsealed_sector_cid_v2, CommDVerification};
 use fil_actors_shared::actor_error_v15;
+use fil_actors_shared::hamt_proof::{prove_hamt_key, HamtProof};
+use fil_actors_shared::page::{list_amt_page, list_hamt_page, Page, PageCursor};
 use fil_actors_shared::v15::DealWeight;
 use fil_actors_shared::v15::{
     ActorContext, ActorError, Array, AsActorError, Config, Map2, Set, SetMultimap,
@@ -10,20 +14,27 @@ use fil_actors_shared::v15::{
 };
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
+#[cfg(feature = "proofs")]
+use fvm_shared::piece::{PaddedPieceSize as PaddedPieceSizeV2, PieceInfo as PieceInfoV2};
+#[cfg(feature = "proofs")]
+use fvm_shared::sector::RegisteredSealProof as RegisteredSealProofV2;
 use fvm_shared4::address::Address;
 use fvm_shared4::bigint::BigInt;
 use fvm_shared4::clock::{ChainEpoch, EPOCH_UNDEFINED};
 use fvm_shared4::deal::DealID;
 use fvm_shared4::econ::TokenAmount;
 use fvm_shared4::error::ExitCode;
-use fvm_shared4::sector::SectorNumber;
+use fvm_shared4::piece::PaddedPieceSize;
+#[cfg(feature = "proofs")]
+use fvm_shared4::sector::RegisteredSealProof;
+use fvm_shared4::sector::{SectorNumber, SectorSize};
 use fvm_shared4::{ActorID, HAMT_BIT_WIDTH};
 use num_traits::Zero;
 use std::cmp::{max, min};
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 
-use crate::v15::balance_table::BalanceTable;
+use crate::v15::balance_table::{BalanceTable, BALANCE_TABLE_BITWIDTH};
 use crate::v15::ext::verifreg::AllocationID;
 
 use super::policy::*;
@@ -230,6 +241,43 @@ impl State {
             + &self.total_client_storage_fee
     }
 
+    /// Lists up to `limit` deal proposals, in deal ID order, starting from `cursor` or from the
+    /// first one. See [`list_amt_page`] for resuming a listing on a later state.
+    pub fn list_deals<BS: Blockstore>(
+        &self,
+        store: &BS,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<(DealID, DealProposal)>> {
+        list_amt_page(store, &self.proposals, cursor, limit)
+    }
+
+    /// Lists up to `limit` escrow balances, starting from `cursor` or from the first one. They are
+    /// listed in HAMT order rather than by address, see [`list_hamt_page`].
+    pub fn list_escrow_balances<BS: Blockstore>(
+        &self,
+        store: &BS,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<(Address, TokenAmount)>> {
+        let page = list_hamt_page::<_, TokenAmount>(
+            store,
+            &self.escrow_table,
+            BALANCE_TABLE_BITWIDTH,
+            cursor,
+            limit,
+        )?;
+        let entries = page
+            .entries
+            .into_iter()
+            .map(|(key, balance)| Ok((Address::from_bytes(&key)?, balance)))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Page {
+            entries,
+            next_cursor: page.next_cursor,
+        })
+    }
+
     pub fn load_deal_states<'bs, BS>(
         &self,
         store: &'bs BS,
@@ -629,6 +677,34 @@ impl State {
         pending_deals.has(key)
     }
 
+    /// Returns the CIDs of the pending deal proposals, which have been published and not yet
+    /// activated or timed out.
+    pub fn pending_deals<BS>(&self, store: &BS) -> Result<Vec<Cid>, ActorError>
+    where
+        BS: Blockstore,
+    {
+        self.load_pending_deals(store)?.collect_keys()
+    }
+
+    /// Returns the HAMT blocks showing whether a deal proposal is pending, which a client holding
+    /// a trusted state root can check without trusting the node serving the state, see
+    /// [`HamtProof`].
+    pub fn prove_pending_deal<BS>(
+        &self,
+        store: &BS,
+        proposal_cid: &Cid,
+    ) -> anyhow::Result<HamtProof>
+    where
+        BS: Blockstore,
+    {
+        prove_hamt_key(
+            store,
+            &self.pending_proposals,
+            &proposal_cid.to_bytes(),
+            HAMT_BIT_WIDTH,
+        )
+    }
+
     pub fn put_pending_deals<BS>(
         &mut self,
         store: &BS,
@@ -696,6 +772,114 @@ impl State {
         Ok((total_w, total_vw))
     }
 
+    /// Computes the `(deal_weight, verified_deal_weight)` that activating the given deals in a
+    /// sector at `activation_epoch` would contribute, as [`Self::verify_deals_for_activation`]
+    /// does, but without checking that the deals can be activated by a particular provider.
+    pub fn deal_weights_for_sector<BS>(
+        &self,
+        store: &BS,
+        deal_ids: &[DealID],
+        activation_epoch: ChainEpoch,
+        sector_expiry: ChainEpoch,
+    ) -> Result<(DealWeight, DealWeight), ActorError>
+    where
+        BS: Blockstore,
+    {
+        let proposal_array = self.load_proposals(store)?;
+        let mut total_w = BigInt::zero();
+        let mut total_vw = BigInt::zero();
+        let deal_duration = sector_expiry - activation_epoch;
+        for (_, proposal) in get_proposals(&proposal_array, deal_ids, self.next_id)? {
+            let deal_spacetime = DealWeight::from(deal_duration as u64 * proposal.piece_size.0);
+            if proposal.verified_deal {
+                total_vw += deal_spacetime;
+            } else {
+                total_w += deal_spacetime;
+            };
+        }
+
+        Ok((total_w, total_vw))
+    }
+
+    /// Checks the deals of a batch of sectors to be pre-committed by `provider` at `curr_epoch`
+    /// as [`Self::verify_deals_for_activation`] does, and that they are not activated yet. All
+    /// the issues are reported rather than the first one, so the deals at fault can be dropped
+    /// from the batch. A deal repeated in several sectors of the batch is reported too, since
+    /// only the first of them can activate it.
+    pub fn check_deals_for_activation<BS>(
+        &self,
+        store: &BS,
+        provider: &Address,
+        sectors: &[SectorDeals],
+        curr_epoch: ChainEpoch,
+    ) -> Result<Vec<DealActivationIssue>, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let proposals = self.load_proposals(store)?;
+        let mut seen_deal_ids = BTreeSet::new();
+        let mut issues = Vec::new();
+        for (sector_index, sector) in sectors.iter().enumerate() {
+            for &deal_id in &sector.deal_ids {
+                let kind = if !seen_deal_ids.insert(deal_id) {
+                    Some(DealActivationIssueKind::Duplicate)
+                } else {
+                    match find_proposal(&proposals, deal_id)? {
+                        None => Some(DealActivationIssueKind::NotFound),
+                        Some(proposal) if &proposal.provider != provider => {
+                            Some(DealActivationIssueKind::WrongProvider(proposal.provider))
+                        }
+                        Some(proposal) if curr_epoch > proposal.start_epoch => Some(
+                            DealActivationIssueKind::StartEpochElapsed(proposal.start_epoch),
+                        ),
+                        Some(proposal) if proposal.end_epoch > sector.sector_expiry => Some(
+                            DealActivationIssueKind::ExceedsSectorExpiry(proposal.end_epoch),
+                        ),
+                        Some(_) => self
+                            .find_deal_state(store, deal_id)?
+                            .map(|_| DealActivationIssueKind::AlreadyActivated),
+                    }
+                };
+                if let Some(kind) = kind {
+                    issues.push(DealActivationIssue {
+                        sector_index,
+                        deal_id,
+                        kind,
+                    });
+                }
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Recomputes the unsealed CID (`CommD`) of a sector from the pieces of its deals and compares
+    /// it with `expected`, typically the `unsealed_cid` of the sector pre-commitment. The deal
+    /// proposals must still be in the state. Data onboarded without deals is not known to the
+    /// market actor, so such sectors can only be verified from their pieces, with
+    /// [`verify_unsealed_sector_cid_v2`].
+    #[cfg(feature = "proofs")]
+    pub fn verify_sector_commd<BS>(
+        &self,
+        store: &BS,
+        seal_proof: RegisteredSealProof,
+        deal_ids: &[DealID],
+        expected: &Cid,
+    ) -> anyhow::Result<CommDVerification>
+    where
+        BS: Blockstore,
+    {
+        let proposal_array = self.load_proposals(store)?;
+        let pieces: Vec<_> = get_proposals(&proposal_array, deal_ids, self.next_id)?
+            .into_iter()
+            .map(|(_, proposal)| PieceInfoV2 {
+                size: PaddedPieceSizeV2(proposal.piece_size.0),
+                cid: proposal.piece_cid,
+            })
+            .collect();
+        let seal_proof = RegisteredSealProofV2::from(i64::from(seal_proof));
+        verify_unsealed_sector_cid_v2(seal_proof, &pieces, expected)
+    }
+
     ////////////////////////////////////////////////////////////////////////////////
     // Provider sector/deal operations
     ////////////////////////////////////////////////////////////////////////////////
@@ -838,6 +1022,171 @@ impl State {
         )
     }
 
+    /// Returns `(piece_cid, piece_size, verified)` for the deals recorded against a provider's
+    /// sector. Deals whose proposals have already been cleaned up are skipped.
+    pub fn sector_pieces<BS>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        sector_number: SectorNumber,
+    ) -> Result<Vec<(Cid, PaddedPieceSize, bool)>, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let provider_sectors = self.load_provider_sectors(store)?;
+        let sector_deals = load_provider_sector_deals(store, &provider_sectors, provider)?;
+        let Some(deal_ids) = sector_deals.get(&sector_number)? else {
+            return Ok(vec![]);
+        };
+
+        let proposals = self.load_proposals(store)?;
+        let mut pieces = Vec::with_capacity(deal_ids.len());
+        for deal_id in deal_ids {
+            if let Some(proposal) = find_proposal(&proposals, *deal_id)? {
+                pieces.push((
+                    proposal.piece_cid,
+                    proposal.piece_size,
+                    proposal.verified_deal,
+                ));
+            }
+        }
+        Ok(pieces)
+    }
+
+    /// Aggregates the deals recorded against the sectors of a provider, which the market uses to
+    /// settle and terminate deals by sector. Deals whose proposals have already been cleaned up
+    /// are counted but have no size.
+    pub fn provider_sector_deal_ids<BS>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+    ) -> Result<ProviderSectorDealIds, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let provider_sectors = self.load_provider_sectors(store)?;
+        let sector_deals = load_provider_sector_deals(store, &provider_sectors, provider)?;
+        let proposals = self.load_proposals(store)?;
+        let mut result = ProviderSectorDealIds::default();
+        sector_deals.for_each(|sector_number, deal_ids| {
+            for deal_id in deal_ids {
+                if let Some(proposal) = find_proposal(&proposals, *deal_id)? {
+                    result.total_piece_size += proposal.piece_size.0;
+                }
+            }
+            result.deal_count += deal_ids.len() as u64;
+            result.sectors.insert(sector_number, deal_ids.clone());
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    /// Checks the deals recorded against the sectors of a provider against the sectors of its
+    /// miner, given with their sizes, e.g. collected with the `for_each_sector` of the miner
+    /// state. Sectors unknown to the miner, deals missing, made with another provider or not
+    /// activated in the sector, and sectors whose pieces exceed their size are reported.
+    pub fn check_provider_sector_deals<BS>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        miner_sectors: &BTreeMap<SectorNumber, SectorSize>,
+    ) -> Result<Vec<SectorDealIssue>, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let proposals = self.load_proposals(store)?;
+        let states = self.load_deal_states(store)?;
+        let provider_address = Address::new_id(provider);
+        let mut issues = Vec::new();
+        for (sector_number, deal_ids) in self.provider_sector_deal_ids(store, provider)?.sectors {
+            let mut issue = |kind| {
+                issues.push(SectorDealIssue {
+                    sector_number,
+                    kind,
+                })
+            };
+            let sector_size = miner_sectors.get(&sector_number);
+            if sector_size.is_none() {
+                issue(SectorDealIssueKind::SectorNotFound);
+            }
+            let mut piece_size = 0;
+            for deal_id in deal_ids {
+                let Some(proposal) = find_proposal(&proposals, deal_id)? else {
+                    issue(SectorDealIssueKind::ProposalNotFound(deal_id));
+                    continue;
+                };
+                piece_size += proposal.piece_size.0;
+                if proposal.provider != provider_address {
+                    issue(SectorDealIssueKind::WrongProvider(
+                        deal_id,
+                        proposal.provider,
+                    ));
+                }
+                let activated_in = find_deal_state(&states, deal_id)?
+                    .filter(|state| state.sector_start_epoch != EPOCH_UNDEFINED)
+                    .map(|state| state.sector_number);
+                if activated_in != Some(sector_number) {
+                    issue(SectorDealIssueKind::SectorMismatch(deal_id, activated_in));
+                }
+            }
+            if sector_size.is_some_and(|&size| piece_size > size as u64) {
+                issue(SectorDealIssueKind::ExceedsSectorSize(piece_size));
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Reports the deals affected by the termination of a provider's sectors at the given epoch,
+    /// mirroring the processing of `OnMinerSectorsTerminate` without modifying the state.
+    pub fn termination_impact<BS>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        sector_numbers: &[SectorNumber],
+        epoch: ChainEpoch,
+    ) -> Result<TerminationImpact, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let provider_sectors = self.load_provider_sectors(store)?;
+        let sector_deals = load_provider_sector_deals(store, &provider_sectors, provider)?;
+        let proposals = self.load_proposals(store)?;
+        let states = self.load_deal_states(store)?;
+
+        let mut impact = TerminationImpact::default();
+        for sector_number in sector_numbers {
+            let Some(sector_deal_ids) = sector_deals.get(sector_number)? else {
+                continue;
+            };
+            for &deal_id in sector_deal_ids {
+                let Some(proposal) = find_proposal(&proposals, deal_id)? else {
+                    impact.deals.push(DealTerminationImpact {
+                        deal_id,
+                        outcome: DealTerminationOutcome::Settled,
+                        provider_collateral: TokenAmount::zero(),
+                    });
+                    continue;
+                };
+                let slashed = find_deal_state(&states, deal_id)?
+                    .is_some_and(|state| state.slash_epoch != EPOCH_UNDEFINED);
+                let outcome = if proposal.end_epoch <= epoch {
+                    DealTerminationOutcome::Expired
+                } else if slashed {
+                    DealTerminationOutcome::AlreadySlashed
+                } else {
+                    impact.collateral_at_risk += &proposal.provider_collateral;
+                    DealTerminationOutcome::Slashed
+                };
+                impact.deals.push(DealTerminationImpact {
+                    deal_id,
+                    outcome,
+                    provider_collateral: proposal.provider_collateral,
+                });
+            }
+        }
+        Ok(impact)
+    }
+
     fn save_provider_sectors<BS>(
         &mut self,
         provider_sectors: &mut ProviderSectorsMap<BS>,
@@ -1497,3 +1846,85 @@ where
     }
     Ok(())
 }
+
+/// Reports what the cron tick of the market actor at `epoch` would do to the deals scheduled since
+/// the last cron tick, without modifying the state. At most `max_deals` deals are simulated, in
+/// the order of the cron tick, the others are only counted.
+///
+/// Payments and slashing follow the processing of the deal updates of this version, while the
+/// resulting updates of the balance table and the rescheduling of the deals are not simulated.
+pub fn simulate_cron_tick<BS>(
+    store: &BS,
+    state: &State,
+    epoch: ChainEpoch,
+    max_deals: usize,
+) -> Result<CronTickSimulation, ActorError>
+where
+    BS: Blockstore,
+{
+    let mut simulation = CronTickSimulation::default();
+    for scheduled_epoch in (state.last_cron + 1)..=epoch {
+        for deal_id in state.get_deals_for_epoch(store, scheduled_epoch)? {
+            if simulation.deals.len() >= max_deals {
+                simulation.deferred += 1;
+                continue;
+            }
+            let update = simulate_deal_update(store, state, deal_id, scheduled_epoch, epoch)?;
+            simulation.total_payment += &update.payment;
+            simulation.total_slashed += &update.slashed;
+            simulation.deals.push(update);
+        }
+    }
+    Ok(simulation)
+}
+
+fn simulate_deal_update<BS>(
+    store: &BS,
+    state: &State,
+    deal_id: DealID,
+    scheduled_epoch: ChainEpoch,
+    epoch: ChainEpoch,
+) -> Result<CronDealUpdate, ActorError>
+where
+    BS: Blockstore,
+{
+    let mut update = CronDealUpdate {
+        deal_id,
+        scheduled_epoch,
+        outcome: CronDealOutcome::Missing,
+        payment: TokenAmount::zero(),
+        slashed: TokenAmount::zero(),
+    };
+    let Some(proposal) = state.find_proposal(store, deal_id)? else {
+        return Ok(update);
+    };
+    if epoch < proposal.start_epoch {
+        update.outcome = CronDealOutcome::NotStarted;
+        return Ok(update);
+    }
+    let Some(deal_state) = state.find_deal_state(store, deal_id)? else {
+        update.outcome = CronDealOutcome::TimedOut;
+        update.slashed =
+            collateral_penalty_for_deal_activation_missed(proposal.provider_collateral);
+        return Ok(update);
+    };
+
+    let ever_slashed = deal_state.slash_epoch != EPOCH_UNDEFINED;
+    let payment_start_epoch = max(proposal.start_epoch, deal_state.last_updated_epoch);
+    let payment_end_epoch = if ever_slashed {
+        min(deal_state.slash_epoch, proposal.end_epoch)
+    } else {
+        min(proposal.end_epoch, epoch)
+    };
+    update.payment =
+        &proposal.storage_price_per_epoch * max(0, payment_end_epoch - payment_start_epoch);
+    update.outcome = if ever_slashed {
+        update.slashed = proposal.provider_collateral;
+        CronDealOutcome::Slashed
+    } else if epoch >= proposal.end_epoch {
+        CronDealOutcome::Completed
+    } else {
+        CronDealOutcome::Updated
+    };
+    Ok(update)
+}
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use cid::{Cid, Version};
+use fil_actors_shared::actor_error_v16;
+use fil_actors_shared::v16::ActorError;
 use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_encoding::BytesSer;
 use fvm_shared4::address::Address;
@@ -15,6 +17,8 @@ use ipld_core::ipld::Ipld;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::convert::{TryFrom, TryInto};
 
+use super::policy::detail::DEAL_MAX_LABEL_SIZE;
+
 /// Cid prefix for piece Cids
 pub fn is_piece_cid(c: &Cid) -> bool {
     // TODO: Move FIL_COMMITMENT etc, into a better place
@@ -82,6 +86,28 @@ impl Label {
             Label::Bytes(b) => b.is_empty(),
         }
     }
+
+    /// Returns whether the label is valid UTF-8. String labels always are, byte labels may be.
+    pub fn is_utf8(&self) -> bool {
+        match self {
+            Label::String(_) => true,
+            Label::Bytes(b) => std::str::from_utf8(b).is_ok(),
+        }
+    }
+
+    /// Checks that the label fits within [`DEAL_MAX_LABEL_SIZE`], as the market actor does when
+    /// publishing the deal.
+    pub fn validate_size(&self) -> Result<(), ActorError> {
+        if self.len() > DEAL_MAX_LABEL_SIZE {
+            return Err(actor_error_v16!(
+                illegal_argument,
+                "deal label can be at most {} bytes, is {}",
+                DEAL_MAX_LABEL_SIZE,
+                self.len()
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Note: Deal Collateral is only released and returned to clients and miners
@@ -148,3 +174,24 @@ pub struct DealState {
     // -1 if deal never slashed
     pub slash_epoch: ChainEpoch,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_shared4::error::ExitCode;
+
+    #[test]
+    fn test_label_validation() {
+        let label = Label::String("a".repeat(DEAL_MAX_LABEL_SIZE));
+        assert!(label.is_utf8());
+        assert!(label.validate_size().is_ok());
+
+        let label = Label::Bytes(vec![0xff; DEAL_MAX_LABEL_SIZE + 1]);
+        assert!(!label.is_utf8());
+        assert_eq!(
+            label.validate_size().unwrap_err().exit_code(),
+            ExitCode::USR_ILLEGAL_ARGUMENT
+        );
+        assert!(Label::Bytes(b"utf8".to_vec()).is_utf8());
+    }
+}
@@ -7,24 +7,35 @@ use std::collections::BTreeMap;
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
+#[cfg(feature = "proofs")]
+use fvm_shared::piece::{PaddedPieceSize as PaddedPieceSizeV2, PieceInfo as PieceInfoV2};
+#[cfg(feature = "proofs")]
+use fvm_shared::sector::RegisteredSealProof as RegisteredSealProofV2;
 use fvm_shared4::address::Address;
 use fvm_shared4::bigint::BigInt;
 use fvm_shared4::clock::{ChainEpoch, EPOCH_UNDEFINED};
 use fvm_shared4::deal::DealID;
 use fvm_shared4::econ::TokenAmount;
 use fvm_shared4::error::ExitCode;
-use fvm_shared4::sector::SectorNumber;
+use fvm_shared4::piece::PaddedPieceSize;
+#[cfg(feature = "proofs")]
+use fvm_shared4::sector::RegisteredSealProof;
+use fvm_shared4::sector::{SectorNumber, SectorSize};
 use fvm_shared4::{ActorID, HAMT_BIT_WIDTH};
 use num_traits::Zero;
 use std::collections::BTreeSet;
 
+#[cfg(feature = "proofs")]
+use fil_actors_shared::abi::commp::{verify_unsealed_sector_cid_v2, CommDVerification};
 use fil_actors_shared::actor_error_v16;
+use fil_actors_shared::hamt_proof::{prove_hamt_key, HamtProof};
+use fil_actors_shared::page::{list_amt_page, list_hamt_page, Page, PageCursor};
 use fil_actors_shared::v16::{
     ActorContext, ActorError, Array, AsActorError, Config, DealWeight, Map2, Set, SetMultimap,
     SetMultimapConfig, DEFAULT_HAMT_CONFIG,
 };
 
-use crate::v16::balance_table::BalanceTable;
+use crate::v16::balance_table::{BalanceTable, BALANCE_TABLE_BITWIDTH};
 use crate::v16::ext::verifreg::AllocationID;
 
 use super::policy::*;
@@ -231,6 +242,43 @@ impl State {
             + &self.total_client_storage_fee
     }
 
+    /// Lists up to `limit` deal proposals, in deal ID order, starting from `cursor` or from the
+    /// first one. See [`list_amt_page`] for resuming a listing on a later state.
+    pub fn list_deals<BS: Blockstore>(
+        &self,
+        store: &BS,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<(DealID, DealProposal)>> {
+        list_amt_page(store, &self.proposals, cursor, limit)
+    }
+
+    /// Lists up to `limit` escrow balances, starting from `cursor` or from the first one. They are
+    /// listed in HAMT order rather than by address, see [`list_hamt_page`].
+    pub fn list_escrow_balances<BS: Blockstore>(
+        &self,
+        store: &BS,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<(Address, TokenAmount)>> {
+        let page = list_hamt_page::<_, TokenAmount>(
+            store,
+            &self.escrow_table,
+            BALANCE_TABLE_BITWIDTH,
+            cursor,
+            limit,
+        )?;
+        let entries = page
+            .entries
+            .into_iter()
+            .map(|(key, balance)| Ok((Address::from_bytes(&key)?, balance)))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Page {
+            entries,
+            next_cursor: page.next_cursor,
+        })
+    }
+
     pub fn load_deal_states<'bs, BS>(
         &self,
         store: &'bs BS,
@@ -630,6 +678,34 @@ impl State {
         pending_deals.has(key)
     }
 
+    /// Returns the CIDs of the pending deal proposals, which have been published and not yet
+    /// activated or timed out.
+    pub fn pending_deals<BS>(&self, store: &BS) -> Result<Vec<Cid>, ActorError>
+    where
+        BS: Blockstore,
+    {
+        self.load_pending_deals(store)?.collect_keys()
+    }
+
+    /// Returns the HAMT blocks showing whether a deal proposal is pending, which a client holding
+    /// a trusted state root can check without trusting the node serving the state, see
+    /// [`HamtProof`].
+    pub fn prove_pending_deal<BS>(
+        &self,
+        store: &BS,
+        proposal_cid: &Cid,
+    ) -> anyhow::Result<HamtProof>
+    where
+        BS: Blockstore,
+    {
+        prove_hamt_key(
+            store,
+            &self.pending_proposals,
+            &proposal_cid.to_bytes(),
+            HAMT_BIT_WIDTH,
+        )
+    }
+
     pub fn put_pending_deals<BS>(
         &mut self,
         store: &BS,
@@ -697,6 +773,114 @@ impl State {
         Ok((total_w, total_vw))
     }
 
+    /// Computes the `(deal_weight, verified_deal_weight)` that activating the given deals in a
+    /// sector at `activation_epoch` would contribute, as [`Self::verify_deals_for_activation`]
+    /// does, but without checking that the deals can be activated by a particular provider.
+    pub fn deal_weights_for_sector<BS>(
+        &self,
+        store: &BS,
+        deal_ids: &[DealID],
+        activation_epoch: ChainEpoch,
+        sector_expiry: ChainEpoch,
+    ) -> Result<(DealWeight, DealWeight), ActorError>
+    where
+        BS: Blockstore,
+    {
+        let proposal_array = self.load_proposals(store)?;
+        let mut total_w = BigInt::zero();
+        let mut total_vw = BigInt::zero();
+        let deal_duration = sector_expiry - activation_epoch;
+        for (_, proposal) in get_proposals(&proposal_array, deal_ids, self.next_id)? {
+            let deal_spacetime = DealWeight::from(deal_duration as u64 * proposal.piece_size.0);
+            if proposal.verified_deal {
+                total_vw += deal_spacetime;
+            } else {
+                total_w += deal_spacetime;
+            };
+        }
+
+        Ok((total_w, total_vw))
+    }
+
+    /// Checks the deals of a batch of sectors to be pre-committed by `provider` at `curr_epoch`
+    /// as [`Self::verify_deals_for_activation`] does, and that they are not activated yet. All
+    /// the issues are reported rather than the first one, so the deals at fault can be dropped
+    /// from the batch. A deal repeated in several sectors of the batch is reported too, since
+    /// only the first of them can activate it.
+    pub fn check_deals_for_activation<BS>(
+        &self,
+        store: &BS,
+        provider: &Address,
+        sectors: &[SectorDeals],
+        curr_epoch: ChainEpoch,
+    ) -> Result<Vec<DealActivationIssue>, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let proposals = self.load_proposals(store)?;
+        let mut seen_deal_ids = BTreeSet::new();
+        let mut issues = Vec::new();
+        for (sector_index, sector) in sectors.iter().enumerate() {
+            for &deal_id in &sector.deal_ids {
+                let kind = if !seen_deal_ids.insert(deal_id) {
+                    Some(DealActivationIssueKind::Duplicate)
+                } else {
+                    match find_proposal(&proposals, deal_id)? {
+                        None => Some(DealActivationIssueKind::NotFound),
+                        Some(proposal) if &proposal.provider != provider => {
+                            Some(DealActivationIssueKind::WrongProvider(proposal.provider))
+                        }
+                        Some(proposal) if curr_epoch > proposal.start_epoch => Some(
+                            DealActivationIssueKind::StartEpochElapsed(proposal.start_epoch),
+                        ),
+                        Some(proposal) if proposal.end_epoch > sector.sector_expiry => Some(
+                            DealActivationIssueKind::ExceedsSectorExpiry(proposal.end_epoch),
+                        ),
+                        Some(_) => self
+                            .find_deal_state(store, deal_id)?
+                            .map(|_| DealActivationIssueKind::AlreadyActivated),
+                    }
+                };
+                if let Some(kind) = kind {
+                    issues.push(DealActivationIssue {
+                        sector_index,
+                        deal_id,
+                        kind,
+                    });
+                }
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Recomputes the unsealed CID (`CommD`) of a sector from the pieces of its deals and compares
+    /// it with `expected`, typically the `unsealed_cid` of the sector pre-commitment. The deal
+    /// proposals must still be in the state. Data onboarded without deals is not known to the
+    /// market actor, so such sectors can only be verified from their pieces, with
+    /// [`verify_unsealed_sector_cid_v2`].
+    #[cfg(feature = "proofs")]
+    pub fn verify_sector_commd<BS>(
+        &self,
+        store: &BS,
+        seal_proof: RegisteredSealProof,
+        deal_ids: &[DealID],
+        expected: &Cid,
+    ) -> anyhow::Result<CommDVerification>
+    where
+        BS: Blockstore,
+    {
+        let proposal_array = self.load_proposals(store)?;
+        let pieces: Vec<_> = get_proposals(&proposal_array, deal_ids, self.next_id)?
+            .into_iter()
+            .map(|(_, proposal)| PieceInfoV2 {
+                size: PaddedPieceSizeV2(proposal.piece_size.0),
+                cid: proposal.piece_cid,
+            })
+            .collect();
+        let seal_proof = RegisteredSealProofV2::from(i64::from(seal_proof));
+        verify_unsealed_sector_cid_v2(seal_proof, &pieces, expected)
+    }
+
     ////////////////////////////////////////////////////////////////////////////////
     // Provider sector/deal operations
     ////////////////////////////////////////////////////////////////////////////////
@@ -839,6 +1023,171 @@ impl State {
         )
     }
 
+    /// Returns `(piece_cid, piece_size, verified)` for the deals recorded against a provider's
+    /// sector. Deals whose proposals have already been cleaned up are skipped.
+    pub fn sector_pieces<BS>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        sector_number: SectorNumber,
+    ) -> Result<Vec<(Cid, PaddedPieceSize, bool)>, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let provider_sectors = self.load_provider_sectors(store)?;
+        let sector_deals = load_provider_sector_deals(store, &provider_sectors, provider)?;
+        let Some(deal_ids) = sector_deals.get(&sector_number)? else {
+            return Ok(vec![]);
+        };
+
+        let proposals = self.load_proposals(store)?;
+        let mut pieces = Vec::with_capacity(deal_ids.len());
+        for deal_id in deal_ids {
+            if let Some(proposal) = find_proposal(&proposals, *deal_id)? {
+                pieces.push((
+                    proposal.piece_cid,
+                    proposal.piece_size,
+                    proposal.verified_deal,
+                ));
+            }
+        }
+        Ok(pieces)
+    }
+
+    /// Aggregates the deals recorded against the sectors of a provider, which the market uses to
+    /// settle and terminate deals by sector. Deals whose proposals have already been cleaned up
+    /// are counted but have no size.
+    pub fn provider_sector_deal_ids<BS>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+    ) -> Result<ProviderSectorDealIds, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let provider_sectors = self.load_provider_sectors(store)?;
+        let sector_deals = load_provider_sector_deals(store, &provider_sectors, provider)?;
+        let proposals = self.load_proposals(store)?;
+        let mut result = ProviderSectorDealIds::default();
+        sector_deals.for_each(|sector_number, deal_ids| {
+            for deal_id in deal_ids {
+                if let Some(proposal) = find_proposal(&proposals, *deal_id)? {
+                    result.total_piece_size += proposal.piece_size.0;
+                }
+            }
+            result.deal_count += deal_ids.len() as u64;
+            result.sectors.insert(sector_number, deal_ids.clone());
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    /// Checks the deals recorded against the sectors of a provider against the sectors of its
+    /// miner, given with their sizes, e.g. collected with the `for_each_sector` of the miner
+    /// state. Sectors unknown to the miner, deals missing, made with another provider or not
+    /// activated in the sector, and sectors whose pieces exceed their size are reported.
+    pub fn check_provider_sector_deals<BS>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        miner_sectors: &BTreeMap<SectorNumber, SectorSize>,
+    ) -> Result<Vec<SectorDealIssue>, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let proposals = self.load_proposals(store)?;
+        let states = self.load_deal_states(store)?;
+        let provider_address = Address::new_id(provider);
+        let mut issues = Vec::new();
+        for (sector_number, deal_ids) in self.provider_sector_deal_ids(store, provider)?.sectors {
+            let mut issue = |kind| {
+                issues.push(SectorDealIssue {
+                    sector_number,
+                    kind,
+                })
+            };
+            let sector_size = miner_sectors.get(&sector_number);
+            if sector_size.is_none() {
+                issue(SectorDealIssueKind::SectorNotFound);
+            }
+            let mut piece_size = 0;
+            for deal_id in deal_ids {
+                let Some(proposal) = find_proposal(&proposals, deal_id)? else {
+                    issue(SectorDealIssueKind::ProposalNotFound(deal_id));
+                    continue;
+                };
+                piece_size += proposal.piece_size.0;
+                if proposal.provider != provider_address {
+                    issue(SectorDealIssueKind::WrongProvider(
+                        deal_id,
+                        proposal.provider,
+                    ));
+                }
+                let activated_in = find_deal_state(&states, deal_id)?
+                    .filter(|state| state.sector_start_epoch != EPOCH_UNDEFINED)
+                    .map(|state| state.sector_number);
+                if activated_in != Some(sector_number) {
+                    issue(SectorDealIssueKind::SectorMismatch(deal_id, activated_in));
+                }
+            }
+            if sector_size.is_some_and(|&size| piece_size > size as u64) {
+                issue(SectorDealIssueKind::ExceedsSectorSize(piece_size));
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Reports the deals affected by the termination of a provider's sectors at the given epoch,
+    /// mirroring the processing of `OnMinerSectorsTerminate` without modifying the state.
+    pub fn termination_impact<BS>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        sector_numbers: &[SectorNumber],
+        epoch: ChainEpoch,
+    ) -> Result<TerminationImpact, ActorError>
+    where
+        BS: Blockstore,
+    {
+        let provider_sectors = self.load_provider_sectors(store)?;
+        let sector_deals = load_provider_sector_deals(store, &provider_sectors, provider)?;
+        let proposals = self.load_proposals(store)?;
+        let states = self.load_deal_states(store)?;
+
+        let mut impact = TerminationImpact::default();
+        for sector_number in sector_numbers {
+            let Some(sector_deal_ids) = sector_deals.get(sector_number)? else {
+                continue;
+            };
+            for &deal_id in sector_deal_ids {
+                let Some(proposal) = find_proposal(&proposals, deal_id)? else {
+                    impact.deals.push(DealTerminationImpact {
+                        deal_id,
+                        outcome: DealTerminationOutcome::Settled,
+                        provider_collateral: TokenAmount::zero(),
+                    });
+                    continue;
+                };
+                let slashed = find_deal_state(&states, deal_id)?
+                    .is_some_and(|state| state.slash_epoch != EPOCH_UNDEFINED);
+                let outcome = if proposal.end_epoch <= epoch {
+                    DealTerminationOutcome::Expired
+                } else if slashed {
+                    DealTerminationOutcome::AlreadySlashed
+                } else {
+                    impact.collateral_at_risk += &proposal.provider_collateral;
+                    DealTerminationOutcome::Slashed
+                };
+                impact.deals.push(DealTerminationImpact {
+                    deal_id,
+                    outcome,
+                    provider_collateral: proposal.provider_collateral,
+                });
+            }
+        }
+        Ok(impact)
+    }
+
     fn save_provider_sectors<BS>(
         &mut self,
         provider_sectors: &mut ProviderSectorsMap<BS>,
@@ -1498,3 +1847,487 @@ where
     }
     Ok(())
 }
+
+/// Reports what the cron tick of the market actor at `epoch` would do to the deals scheduled since
+/// the last cron tick, without modifying the state. At most `max_deals` deals are simulated, in
+/// the order of the cron tick, the others are only counted.
+///
+/// Payments and slashing follow the processing of the deal updates of this version, while the
+/// resulting updates of the balance table and the rescheduling of the deals are not simulated.
+pub fn simulate_cron_tick<BS>(
+    store: &BS,
+    state: &State,
+    epoch: ChainEpoch,
+    max_deals: usize,
+) -> Result<CronTickSimulation, ActorError>
+where
+    BS: Blockstore,
+{
+    let mut simulation = CronTickSimulation::default();
+    for scheduled_epoch in (state.last_cron + 1)..=epoch {
+        for deal_id in state.get_deals_for_epoch(store, scheduled_epoch)? {
+            if simulation.deals.len() >= max_deals {
+                simulation.deferred += 1;
+                continue;
+            }
+            let update = simulate_deal_update(store, state, deal_id, scheduled_epoch, epoch)?;
+            simulation.total_payment += &update.payment;
+            simulation.total_slashed += &update.slashed;
+            simulation.deals.push(update);
+        }
+    }
+    Ok(simulation)
+}
+
+fn simulate_deal_update<BS>(
+    store: &BS,
+    state: &State,
+    deal_id: DealID,
+    scheduled_epoch: ChainEpoch,
+    epoch: ChainEpoch,
+) -> Result<CronDealUpdate, ActorError>
+where
+    BS: Blockstore,
+{
+    let mut update = CronDealUpdate {
+        deal_id,
+        scheduled_epoch,
+        outcome: CronDealOutcome::Missing,
+        payment: TokenAmount::zero(),
+        slashed: TokenAmount::zero(),
+    };
+    let Some(proposal) = state.find_proposal(store, deal_id)? else {
+        return Ok(update);
+    };
+    if epoch < proposal.start_epoch {
+        update.outcome = CronDealOutcome::NotStarted;
+        return Ok(update);
+    }
+    let Some(deal_state) = state.find_deal_state(store, deal_id)? else {
+        update.outcome = CronDealOutcome::TimedOut;
+        update.slashed =
+            collateral_penalty_for_deal_activation_missed(proposal.provider_collateral);
+        return Ok(update);
+    };
+
+    let ever_slashed = deal_state.slash_epoch != EPOCH_UNDEFINED;
+    let payment_start_epoch = max(proposal.start_epoch, deal_state.last_updated_epoch);
+    let payment_end_epoch = if ever_slashed {
+        min(deal_state.slash_epoch, proposal.end_epoch)
+    } else {
+        min(proposal.end_epoch, epoch)
+    };
+    update.payment =
+        &proposal.storage_price_per_epoch * max(0, payment_end_epoch - payment_start_epoch);
+    update.outcome = if ever_slashed {
+        update.slashed = proposal.provider_collateral;
+        CronDealOutcome::Slashed
+    } else if epoch >= proposal.end_epoch {
+        CronDealOutcome::Completed
+    } else {
+        CronDealOutcome::Updated
+    };
+    Ok(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::Label;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared4::sector::RegisteredSealProof;
+
+    fn proposal(end_epoch: ChainEpoch, provider_collateral: u64) -> DealProposal {
+        DealProposal {
+            piece_cid: Cid::default(),
+            piece_size: PaddedPieceSize(2048),
+            verified_deal: false,
+            client: Address::new_id(100),
+            provider: Address::new_id(101),
+            label: Label::String(String::new()),
+            start_epoch: 0,
+            end_epoch,
+            storage_price_per_epoch: TokenAmount::zero(),
+            provider_collateral: TokenAmount::from_atto(provider_collateral),
+            client_collateral: TokenAmount::zero(),
+        }
+    }
+
+    fn deal_state(slash_epoch: ChainEpoch) -> DealState {
+        DealState {
+            sector_number: 1,
+            sector_start_epoch: 0,
+            last_updated_epoch: EPOCH_UNDEFINED,
+            slash_epoch,
+        }
+    }
+
+    #[test]
+    fn test_termination_impact() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store).unwrap();
+        st.put_deal_proposals(
+            &store,
+            &[
+                (1, proposal(1000, 10)),
+                (2, proposal(50, 20)),
+                (3, proposal(1000, 30)),
+            ],
+        )
+        .unwrap();
+        st.put_deal_states(
+            &store,
+            &[
+                (1, deal_state(EPOCH_UNDEFINED)),
+                (2, deal_state(EPOCH_UNDEFINED)),
+                (3, deal_state(80)),
+            ],
+        )
+        .unwrap();
+        st.put_sector_deal_ids(&store, 101, &[(1, vec![1, 2, 3, 4])])
+            .unwrap();
+
+        let impact = st.termination_impact(&store, 101, &[1, 2], 100).unwrap();
+        let outcomes: Vec<_> = impact.deals.iter().map(|d| d.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                DealTerminationOutcome::Slashed,
+                DealTerminationOutcome::Expired,
+                DealTerminationOutcome::AlreadySlashed,
+                DealTerminationOutcome::Settled,
+            ]
+        );
+        assert_eq!(impact.collateral_at_risk, TokenAmount::from_atto(10));
+
+        let impact = st.termination_impact(&store, 102, &[1], 100).unwrap();
+        assert_eq!(impact, TerminationImpact::default());
+    }
+
+    #[test]
+    fn test_provider_sector_deals() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store).unwrap();
+        st.put_deal_proposals(
+            &store,
+            &[
+                (1, proposal(1000, 10)),
+                (2, proposal(1000, 20)),
+                (
+                    3,
+                    DealProposal {
+                        provider: Address::new_id(102),
+                        ..proposal(1000, 30)
+                    },
+                ),
+            ],
+        )
+        .unwrap();
+        st.put_deal_states(
+            &store,
+            &[
+                (1, deal_state(EPOCH_UNDEFINED)),
+                (3, deal_state(EPOCH_UNDEFINED)),
+            ],
+        )
+        .unwrap();
+        st.put_sector_deal_ids(&store, 101, &[(1, vec![1, 2, 3, 4]), (7, vec![1])])
+            .unwrap();
+
+        let deals = st.provider_sector_deal_ids(&store, 101).unwrap();
+        assert_eq!(
+            deals.sectors,
+            BTreeMap::from([(1, vec![1, 2, 3, 4]), (7, vec![1])])
+        );
+        assert_eq!(deals.deal_count, 5);
+        assert_eq!(deals.total_piece_size, 4 * 2048);
+        assert_eq!(
+            st.provider_sector_deal_ids(&store, 102).unwrap(),
+            ProviderSectorDealIds::default()
+        );
+
+        let miner_sectors = BTreeMap::from([(1, SectorSize::_2KiB)]);
+        let issues = st
+            .check_provider_sector_deals(&store, 101, &miner_sectors)
+            .unwrap();
+        let issues: Vec<_> = issues
+            .into_iter()
+            .map(|i| (i.sector_number, i.kind))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (1, SectorDealIssueKind::SectorMismatch(2, None)),
+                (
+                    1,
+                    SectorDealIssueKind::WrongProvider(3, Address::new_id(102))
+                ),
+                (1, SectorDealIssueKind::ProposalNotFound(4)),
+                (1, SectorDealIssueKind::ExceedsSectorSize(3 * 2048)),
+                (7, SectorDealIssueKind::SectorNotFound),
+                (7, SectorDealIssueKind::SectorMismatch(1, Some(1))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_deals_and_balances() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store).unwrap();
+        st.put_deal_proposals(
+            &store,
+            &[
+                (3, proposal(1000, 30)),
+                (1, proposal(1000, 10)),
+                (2, proposal(1000, 20)),
+            ],
+        )
+        .unwrap();
+        for id in 100..105 {
+            st.add_balance_to_escrow_table(
+                &store,
+                &Address::new_id(id),
+                &TokenAmount::from_atto(id),
+            )
+            .unwrap();
+        }
+
+        let page = st.list_deals(&store, None, 2).unwrap();
+        assert_eq!(
+            page.entries.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [1, 2]
+        );
+        let page = st.list_deals(&store, page.next_cursor.as_ref(), 2).unwrap();
+        assert_eq!(page.entries, [(3, proposal(1000, 30))]);
+        assert_eq!(page.next_cursor, None);
+
+        let mut balances = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = st.list_escrow_balances(&store, cursor.as_ref(), 2).unwrap();
+            balances.extend(page.entries);
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        balances.sort_by_key(|(address, _)| address.id().unwrap());
+        let expected: Vec<_> = (100..105)
+            .map(|id| (Address::new_id(id), TokenAmount::from_atto(id)))
+            .collect();
+        assert_eq!(balances, expected);
+    }
+
+    #[cfg(feature = "proofs")]
+    #[test]
+    fn test_verify_sector_commd() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store).unwrap();
+        let piece_cid: Cid = "baga6ea4seaqknzm22isnhsxt2s4dnw45kfywmhenngqq3nc7jvecakoca6ksyhy"
+            .parse()
+            .unwrap();
+        st.put_deal_proposals(
+            &store,
+            &[(
+                1,
+                DealProposal {
+                    piece_cid,
+                    ..proposal(1000, 10)
+                },
+            )],
+        )
+        .unwrap();
+        st.next_id = 2;
+
+        let seal_proof = RegisteredSealProof::StackedDRG2KiBV1P1;
+        let verification = st
+            .verify_sector_commd(&store, seal_proof, &[1], &piece_cid)
+            .unwrap();
+        assert!(verification.is_valid());
+
+        // Without deals, the sector is expected to hold no data.
+        let verification = st
+            .verify_sector_commd(&store, seal_proof, &[], &piece_cid)
+            .unwrap();
+        assert!(!verification.is_valid());
+        assert!(st
+            .verify_sector_commd(&store, seal_proof, &[2], &piece_cid)
+            .is_err());
+    }
+
+    #[test]
+    fn test_simulate_cron_tick() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store).unwrap();
+        let paid = |end_epoch| DealProposal {
+            storage_price_per_epoch: TokenAmount::from_atto(2),
+            ..proposal(end_epoch, 10)
+        };
+        st.put_deal_proposals(
+            &store,
+            &[
+                (1, paid(1000)),
+                (2, paid(50)),
+                (3, paid(1000)),
+                (4, paid(1000)),
+                (
+                    5,
+                    DealProposal {
+                        start_epoch: 200,
+                        ..paid(1000)
+                    },
+                ),
+            ],
+        )
+        .unwrap();
+        st.put_deal_states(
+            &store,
+            &[
+                (1, deal_state(EPOCH_UNDEFINED)),
+                (2, deal_state(EPOCH_UNDEFINED)),
+                (3, deal_state(80)),
+            ],
+        )
+        .unwrap();
+        st.put_deals_by_epoch(
+            &store,
+            &[
+                (10, 1),
+                (10, 2),
+                (20, 3),
+                (20, 4),
+                (30, 5),
+                (30, 6),
+                (200, 1),
+            ],
+        )
+        .unwrap();
+        st.last_cron = 5;
+
+        let simulation = simulate_cron_tick(&store, &st, 100, 10).unwrap();
+        let mut outcomes: Vec<_> = simulation
+            .deals
+            .iter()
+            .map(|d| (d.deal_id, d.outcome, d.payment.atto().clone()))
+            .collect();
+        outcomes.sort_by_key(|(deal_id, ..)| *deal_id);
+        assert_eq!(
+            outcomes,
+            vec![
+                (1, CronDealOutcome::Updated, 200.into()),
+                (2, CronDealOutcome::Completed, 100.into()),
+                (3, CronDealOutcome::Slashed, 160.into()),
+                (4, CronDealOutcome::TimedOut, 0.into()),
+                (5, CronDealOutcome::NotStarted, 0.into()),
+                (6, CronDealOutcome::Missing, 0.into()),
+            ]
+        );
+        assert_eq!(simulation.total_payment, TokenAmount::from_atto(460));
+        assert_eq!(simulation.total_slashed, TokenAmount::from_atto(20));
+        assert_eq!(simulation.deferred, 0);
+
+        let simulation = simulate_cron_tick(&store, &st, 100, 2).unwrap();
+        assert_eq!(simulation.deals.len(), 2);
+        assert_eq!(simulation.deferred, 4);
+    }
+
+    #[test]
+    fn test_check_deals_for_activation() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store).unwrap();
+        let starting = |start_epoch, end_epoch| DealProposal {
+            start_epoch,
+            ..proposal(end_epoch, 0)
+        };
+        let other_provider = DealProposal {
+            provider: Address::new_id(102),
+            ..starting(20, 1000)
+        };
+        st.put_deal_proposals(
+            &store,
+            &[
+                (1, starting(20, 1000)),
+                (2, starting(20, 2000)),
+                (3, starting(20, 1000)),
+                (4, other_provider),
+                (5, starting(5, 1000)),
+            ],
+        )
+        .unwrap();
+        st.put_deal_states(&store, &[(3, deal_state(EPOCH_UNDEFINED))])
+            .unwrap();
+        let sector = |deal_ids| SectorDeals {
+            sector_number: 0,
+            sector_type: RegisteredSealProof::StackedDRG32GiBV1P1,
+            sector_expiry: 1500,
+            deal_ids,
+        };
+
+        let issues = st
+            .check_deals_for_activation(
+                &store,
+                &Address::new_id(101),
+                &[sector(vec![1, 2, 3]), sector(vec![4, 5, 1, 6])],
+                10,
+            )
+            .unwrap();
+        let issues: Vec<_> = issues
+            .into_iter()
+            .map(|issue| (issue.sector_index, issue.deal_id, issue.kind))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (0, 2, DealActivationIssueKind::ExceedsSectorExpiry(2000)),
+                (0, 3, DealActivationIssueKind::AlreadyActivated),
+                (
+                    1,
+                    4,
+                    DealActivationIssueKind::WrongProvider(Address::new_id(102))
+                ),
+                (1, 5, DealActivationIssueKind::StartEpochElapsed(5)),
+                (1, 1, DealActivationIssueKind::Duplicate),
+                (1, 6, DealActivationIssueKind::NotFound),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pending_deals() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store).unwrap();
+        let cid = |end_epoch| {
+            fvm_ipld_encoding::CborStore::put_cbor(
+                &store,
+                &proposal(end_epoch, 1),
+                multihash_codetable::Code::Blake2b256,
+            )
+            .unwrap()
+        };
+        let pending = [cid(100), cid(200)];
+        st.put_pending_deals(&store, &pending).unwrap();
+
+        let mut listed = st.pending_deals(&store).unwrap();
+        listed.sort();
+        let mut expected = pending.to_vec();
+        expected.sort();
+        assert_eq!(listed, expected);
+
+        let proof = st.prove_pending_deal(&store, &pending[0]).unwrap();
+        assert_eq!(proof.root, st.pending_proposals);
+        assert!(proof
+            .verify(
+                &st.pending_proposals,
+                &pending[0].to_bytes(),
+                HAMT_BIT_WIDTH
+            )
+            .unwrap()
+            .is_some());
+        let other = cid(300);
+        let proof = st.prove_pending_deal(&store, &other).unwrap();
+        assert!(proof
+            .verify(&st.pending_proposals, &other.to_bytes(), HAMT_BIT_WIDTH)
+            .unwrap()
+            .is_none());
+    }
+}
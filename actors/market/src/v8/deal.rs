@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use cid::Cid;
+use fil_actors_shared::actor_error_v8;
+use fil_actors_shared::v8::ActorError;
 use fil_actors_shared::v8::DealWeight;
 use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_encoding::BytesSer;
@@ -15,6 +17,9 @@ use multihash_codetable::{Code, MultihashDigest};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::convert::{TryFrom, TryInto};
 
+/// Maximum length of a deal label.
+pub const DEAL_MAX_LABEL_SIZE: usize = 256;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Label {
     String(String),
@@ -73,6 +78,28 @@ impl Label {
             Label::Bytes(b) => b.is_empty(),
         }
     }
+
+    /// Returns whether the label is valid UTF-8. String labels always are, byte labels may be.
+    pub fn is_utf8(&self) -> bool {
+        match self {
+            Label::String(_) => true,
+            Label::Bytes(b) => std::str::from_utf8(b).is_ok(),
+        }
+    }
+
+    /// Checks that the label fits within [`DEAL_MAX_LABEL_SIZE`], as the market actor does when
+    /// publishing the deal.
+    pub fn validate_size(&self) -> Result<(), ActorError> {
+        if self.len() > DEAL_MAX_LABEL_SIZE {
+            return Err(actor_error_v8!(
+                illegal_argument,
+                "deal label can be at most {} bytes, is {}",
+                DEAL_MAX_LABEL_SIZE,
+                self.len()
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Note: Deal Collateral is only released and returned to clients and miners
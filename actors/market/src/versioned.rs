@@ -0,0 +1,193 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+use fil_actors_shared::ids::DealID;
+#[cfg(feature = "json")]
+use fil_actors_shared::lotus_json;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
+
+/// The fields added after v8, `None` for the versions without them.
+trait LaterFields {
+    fn pending_deal_allocation_ids(&self) -> Option<Cid> {
+        None
+    }
+
+    fn provider_sectors(&self) -> Option<Cid> {
+        None
+    }
+}
+
+impl LaterFields for crate::v8::State {}
+
+macro_rules! impl_later_fields {
+    ($($module:ident),*; $($with_sectors:ident),*) => {
+        $(impl LaterFields for crate::$module::State {
+            fn pending_deal_allocation_ids(&self) -> Option<Cid> {
+                Some(self.pending_deal_allocation_ids)
+            }
+        })*
+        $(impl LaterFields for crate::$with_sectors::State {
+            fn pending_deal_allocation_ids(&self) -> Option<Cid> {
+                Some(self.pending_deal_allocation_ids)
+            }
+
+            fn provider_sectors(&self) -> Option<Cid> {
+                Some(self.provider_sectors)
+            }
+        })*
+    };
+}
+
+impl_later_fields!(v9, v10, v11, v12; v13, v14, v15, v16);
+
+fil_actors_shared::versioned_state! {
+    actor: "market",
+    type: Market,
+    /// The fields of the market state, with version-agnostic types, to compare states across an
+    /// upgrade. Fields introduced after v8 are `None` for the versions without them.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub proposals: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub states: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub pending_proposals: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub escrow_table: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub locked_table: Cid,
+        #[cfg_attr(feature = "json", serde(rename = "NextID"))]
+        pub next_id: DealID,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub deal_ops_by_epoch: Cid,
+        pub last_cron: ChainEpoch,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+        pub total_client_locked_collateral: TokenAmount,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+        pub total_provider_locked_collateral: TokenAmount,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+        pub total_client_storage_fee: TokenAmount,
+        /// Since v9.
+        #[cfg_attr(
+            feature = "json",
+            serde(default, skip_serializing_if = "Option::is_none", with = "lotus_json::cid::opt")
+        )]
+        pub pending_deal_allocation_ids: Option<Cid>,
+        /// Since v13.
+        #[cfg_attr(
+            feature = "json",
+            serde(default, skip_serializing_if = "Option::is_none", with = "lotus_json::cid::opt")
+        )]
+        pub provider_sectors: Option<Cid>,
+    }
+    versions {
+        V8 => 8, crate::v8::State;
+        V9 => 9, crate::v9::State;
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        proposals: st.proposals,
+        states: st.states,
+        pending_proposals: st.pending_proposals,
+        escrow_table: st.escrow_table,
+        locked_table: st.locked_table,
+        next_id: DealID(st.next_id),
+        deal_ops_by_epoch: st.deal_ops_by_epoch,
+        last_cron: st.last_cron,
+        total_client_locked_collateral: TokenAmount::from_atto(
+            st.total_client_locked_collateral.atto().clone(),
+        ),
+        total_provider_locked_collateral: TokenAmount::from_atto(
+            st.total_provider_locked_collateral.atto().clone(),
+        ),
+        total_client_storage_fee: TokenAmount::from_atto(
+            st.total_client_storage_fee.atto().clone(),
+        ),
+        pending_deal_allocation_ids: st.pending_deal_allocation_ids(),
+        provider_sectors: st.provider_sectors(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_actors_shared::actor_type::ActorType;
+    use fil_actors_shared::bundle::KnownCids;
+    use fil_actors_shared::versioned::UpgradeVersions;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::{CborStore, IPLD_RAW};
+    use fvm_shared4::version::NetworkVersion;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    #[test]
+    fn test_load_dual() {
+        let store = MemoryBlockstore::new();
+        let pre = crate::v12::State::new(&store).unwrap();
+        let pre = store.put_cbor(&pre, Code::Blake2b256).unwrap();
+        let post = crate::v13::State::new(&store).unwrap();
+        let post = store.put_cbor(&post, Code::Blake2b256).unwrap();
+
+        // The code CIDs of the market actor on both sides of the upgrade to network version 22.
+        let versions = UpgradeVersions::at(NetworkVersion::V22).unwrap();
+        let code = |version: u64| Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(&[version as u8]));
+        let mut known = KnownCids::default();
+        for version in [versions.pre, versions.post] {
+            known.insert("testnet", version, ActorType::Market, code(version));
+        }
+        let (pre, post) = load_dual(
+            &store,
+            &known,
+            &code(versions.pre),
+            &pre,
+            &code(versions.post),
+            &post,
+        )
+        .unwrap();
+        let changes = pre.normalize().unwrap().changes(&post.normalize().unwrap());
+        assert_eq!(
+            changes.iter().map(|c| c.field).collect::<Vec<_>>(),
+            ["provider_sectors"]
+        );
+        assert_eq!(changes[0].pre, "None");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_lotus_json() {
+        let fixture = include_str!("../testdata/lotus_state_v16.json");
+        let state: NormalizedState = serde_json::from_str(fixture).unwrap();
+        assert_eq!(state.next_id, DealID(148213));
+        assert_eq!(
+            state.total_provider_locked_collateral,
+            TokenAmount::from_atto(123456789000000000000u128)
+        );
+        assert!(state.provider_sectors.is_some());
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::from_str::<serde_json::Value>(fixture).unwrap()
+        );
+
+        // The fields missing from older versions are missing from their Lotus JSON as well.
+        let v8 = NormalizedState {
+            pending_deal_allocation_ids: None,
+            provider_sectors: None,
+            ..state
+        };
+        let value = serde_json::to_value(&v8).unwrap();
+        assert!(value.get("ProviderSectors").is_none());
+        assert_eq!(serde_json::from_value::<NormalizedState>(value).unwrap(), v8);
+    }
+}
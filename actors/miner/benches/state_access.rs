@@ -0,0 +1,100 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+// Compares reads through the versioned miner state with the version-agnostic paths, which decode
+// blocks through IPLD or best-effort CBOR. Run with `cargo bench -p fil_actor_miner_state`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fil_actor_miner_state::sector_history::SectorHistoryEncoder;
+use fil_actor_miner_state::v16::{MinerInfo, SectorOnChainInfo, State};
+use fil_actors_shared::forward_compat::get_cbor_best_effort;
+use fil_actors_shared::v16::runtime::Policy;
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared4::sector::RegisteredPoStProof;
+use ipld_core::ipld::Ipld;
+use multihash_codetable::Code;
+
+const SECTORS: u64 = 10_000;
+
+fn miner_state(store: &MemoryBlockstore) -> State {
+    let info = MinerInfo::new(
+        100,
+        101,
+        vec![],
+        vec![],
+        vec![],
+        RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+    )
+    .unwrap();
+    let info = store.put_cbor(&info, Code::Blake2b256).unwrap();
+    let mut st = State::new(&Policy::default(), store, info, 0, 0).unwrap();
+    let sectors = (0..SECTORS)
+        .map(|sector_number| SectorOnChainInfo {
+            sector_number,
+            expiration: 1_000_000,
+            ..Default::default()
+        })
+        .collect();
+    st.put_sectors(store, sectors).unwrap();
+    st
+}
+
+fn miner_info(c: &mut Criterion) {
+    let store = MemoryBlockstore::new();
+    let st = miner_state(&store);
+    let mut group = c.benchmark_group("miner_info");
+    group.bench_function("versioned", |b| {
+        b.iter(|| st.get_info(black_box(&store)).unwrap())
+    });
+    group.bench_function("best_effort", |b| {
+        b.iter(|| get_cbor_best_effort::<_, MinerInfo>(black_box(&store), &st.info).unwrap())
+    });
+    group.finish();
+}
+
+fn sector_scan(c: &mut Criterion) {
+    let store = MemoryBlockstore::new();
+    let st = miner_state(&store);
+    let mut group = c.benchmark_group("sector_scan");
+    group.sample_size(20);
+    group.bench_function("versioned", |b| {
+        b.iter(|| {
+            let mut expirations = 0;
+            st.for_each_sector(black_box(&store), |sector| {
+                expirations += sector.expiration;
+                Ok(())
+            })
+            .unwrap();
+            expirations
+        })
+    });
+    group.bench_function("ipld", |b| {
+        b.iter(|| {
+            let mut expirations = 0;
+            Amt::<Ipld, _>::load(&st.sectors, black_box(&store))
+                .unwrap()
+                .for_each(|_, sector| {
+                    let sector: SectorOnChainInfo = ipld_core::serde::from_ipld(sector.clone())?;
+                    expirations += sector.expiration;
+                    Ok(())
+                })
+                .unwrap();
+            expirations
+        })
+    });
+    group.bench_function("history_dump", |b| {
+        b.iter(|| {
+            SectorHistoryEncoder::new()
+                .record(black_box(&store), 0, &st.sectors)
+                .unwrap()
+                .upserted
+                .len()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, miner_info, sector_scan);
+criterion_main!(benches);
@@ -0,0 +1,274 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::{anyhow, bail};
+use fil_actors_shared::version::actors_version;
+use fvm_shared4::bigint::BigInt;
+use fvm_shared4::econ::TokenAmount;
+use fvm_shared4::version::NetworkVersion;
+
+/// Cost of proving a set of sectors, either one `ProveCommitSector` message per sector or a single
+/// `ProveCommitAggregate` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProveCommitCost {
+    /// Number of sectors proven by the aggregate, or 1 for individual prove-commits.
+    pub aggregate_size: u64,
+    /// Network fee burnt by the miner actor, only charged on aggregates.
+    pub network_fee: TokenAmount,
+    /// Gas fee of the message, at the given base fee.
+    pub gas_fee: TokenAmount,
+    /// Total cost divided by the number of sectors.
+    pub per_sector: TokenAmount,
+}
+
+/// Batch size recommendation of [`plan_prove_commit_aggregate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchPlan {
+    /// Cost of proving the sectors one by one.
+    pub individual: ProveCommitCost,
+    /// Cheapest option per sector, which is `individual` when aggregating does not pay off.
+    pub recommended: ProveCommitCost,
+}
+
+macro_rules! for_version {
+    ($version:expr, $m:ident!($($args:tt)*)) => {
+        match $version {
+            8 => $m!(v8, fvm_shared::econ::TokenAmount, $($args)*),
+            9 => $m!(v9, fvm_shared::econ::TokenAmount, $($args)*),
+            10 => $m!(v10, fvm_shared3::econ::TokenAmount, $($args)*),
+            11 => $m!(v11, fvm_shared3::econ::TokenAmount, $($args)*),
+            12 => $m!(v12, fvm_shared4::econ::TokenAmount, $($args)*),
+            13 => $m!(v13, fvm_shared4::econ::TokenAmount, $($args)*),
+            14 => $m!(v14, fvm_shared4::econ::TokenAmount, $($args)*),
+            15 => $m!(v15, fvm_shared4::econ::TokenAmount, $($args)*),
+            16 => $m!(v16, fvm_shared4::econ::TokenAmount, $($args)*),
+            version => unreachable!("actors version {version} is not handled"),
+        }
+    };
+}
+
+macro_rules! network_fee {
+    ($version:ident, $token:path, $fee:ident, $size:expr, $base_fee:expr) => {{
+        use $token as VersionedTokenAmount;
+        let base_fee = VersionedTokenAmount::from_atto($base_fee.atto().clone());
+        let fee = crate::$version::$fee($size.try_into()?, &base_fee);
+        TokenAmount::from_atto(fee.atto().clone())
+    }};
+}
+
+macro_rules! batch_balancer {
+    (v8, $token:path,) => {
+        crate::v8::BATCH_BALANCER.atto().clone()
+    };
+    (v9, $token:path,) => {
+        crate::v9::BATCH_BALANCER.atto().clone()
+    };
+    (v10, $token:path,) => {
+        crate::v10::BATCH_BALANCER.atto().clone()
+    };
+    ($version:ident, $token:path,) => {
+        crate::$version::detail::BATCH_BALANCER.atto().clone()
+    };
+}
+
+macro_rules! single_prove_commit_gas {
+    ($version:ident, $token:path,) => {
+        crate::$version::ESTIMATED_SINGLE_PROVE_COMMIT_GAS_USAGE.clone()
+    };
+}
+
+macro_rules! aggregated_sectors {
+    ($version:ident, $token:path,) => {{
+        let policy = fil_actors_shared::$version::runtime::Policy::default();
+        (policy.min_aggregated_sectors, policy.max_aggregated_sectors)
+    }};
+}
+
+fn supported_actors_version(network_version: NetworkVersion) -> anyhow::Result<u64> {
+    actors_version(network_version)
+        .ok_or_else(|| anyhow!("unsupported network version {network_version}"))
+}
+
+/// Returns the network fee burnt for a `ProveCommitAggregate` of `aggregate_size` sectors at the
+/// given network version.
+pub fn aggregate_prove_commit_network_fee(
+    network_version: NetworkVersion,
+    aggregate_size: u64,
+    base_fee: &TokenAmount,
+) -> anyhow::Result<TokenAmount> {
+    let version = supported_actors_version(network_version)?;
+    Ok(for_version!(
+        version,
+        network_fee!(aggregate_prove_commit_network_fee, aggregate_size, base_fee)
+    ))
+}
+
+/// Returns the network fee burnt for a `PreCommitSectorBatch` of `batch_size` sectors at the given
+/// network version.
+pub fn aggregate_pre_commit_network_fee(
+    network_version: NetworkVersion,
+    batch_size: u64,
+    base_fee: &TokenAmount,
+) -> anyhow::Result<TokenAmount> {
+    let version = supported_actors_version(network_version)?;
+    Ok(for_version!(
+        version,
+        network_fee!(aggregate_pre_commit_network_fee, batch_size, base_fee)
+    ))
+}
+
+/// Returns the batch balancer, the floor of the base fee used to compute the network fees of
+/// aggregates and batches.
+pub fn batch_balancer(network_version: NetworkVersion) -> anyhow::Result<TokenAmount> {
+    let version = supported_actors_version(network_version)?;
+    Ok(TokenAmount::from_atto(for_version!(
+        version,
+        batch_balancer!()
+    )))
+}
+
+/// Recommends how to prove `pending_sectors` sectors at the given network version and base fee,
+/// by comparing the per-sector cost of individual prove-commits with that of every aggregate size
+/// allowed by the policy.
+///
+/// The gas used by individual prove-commits is the estimate the network fee is derived from. The
+/// gas used by an aggregate depends on the proof verification prices of the FVM, so it must be
+/// provided by `aggregate_gas`, e.g. from gas estimations of previous messages. Ties are broken in
+/// favor of larger aggregates, which need fewer messages.
+pub fn plan_prove_commit_aggregate(
+    network_version: NetworkVersion,
+    pending_sectors: u64,
+    base_fee: &TokenAmount,
+    aggregate_gas: impl Fn(u64) -> u64,
+) -> anyhow::Result<BatchPlan> {
+    if pending_sectors == 0 {
+        bail!("no sectors to prove");
+    }
+    let version = supported_actors_version(network_version)?;
+    let single_gas: BigInt = for_version!(version, single_prove_commit_gas!());
+    let gas_fee = base_fee * &single_gas;
+    let individual = ProveCommitCost {
+        aggregate_size: 1,
+        network_fee: TokenAmount::default(),
+        per_sector: gas_fee.clone(),
+        gas_fee,
+    };
+
+    let (min_size, max_size) = for_version!(version, aggregated_sectors!());
+    let mut recommended = individual.clone();
+    for aggregate_size in min_size..=max_size.min(pending_sectors) {
+        let network_fee =
+            aggregate_prove_commit_network_fee(network_version, aggregate_size, base_fee)?;
+        let gas_fee = base_fee * aggregate_gas(aggregate_size);
+        let per_sector = (&network_fee + &gas_fee).div_floor(aggregate_size);
+        if per_sector <= recommended.per_sector {
+            recommended = ProveCommitCost {
+                aggregate_size,
+                network_fee,
+                gas_fee,
+                per_sector,
+            };
+        }
+    }
+    Ok(BatchPlan {
+        individual,
+        recommended,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregate_gas(aggregate_size: u64) -> u64 {
+        100_000_000 + 5_000_000 * aggregate_size
+    }
+
+    #[test]
+    fn test_network_fees_match_versioned_formulas() {
+        let base_fee = TokenAmount::from_nano(100);
+        let fee = aggregate_prove_commit_network_fee(NetworkVersion::V25, 10, &base_fee).unwrap();
+        assert_eq!(
+            fee,
+            crate::v16::aggregate_prove_commit_network_fee(10, &base_fee)
+        );
+        assert_eq!(
+            aggregate_prove_commit_network_fee(NetworkVersion::V16, 10, &base_fee).unwrap(),
+            fee
+        );
+        assert_eq!(
+            aggregate_pre_commit_network_fee(NetworkVersion::V18, 10, &base_fee).unwrap(),
+            TokenAmount::from_atto(
+                crate::v10::aggregate_pre_commit_network_fee(
+                    10,
+                    &fvm_shared3::econ::TokenAmount::from_nano(100)
+                )
+                .atto()
+                .clone()
+            )
+        );
+        assert_eq!(
+            aggregate_pre_commit_network_fee(NetworkVersion::V19, 10, &base_fee).unwrap(),
+            TokenAmount::from_atto(
+                crate::v11::aggregate_pre_commit_network_fee(
+                    10,
+                    &fvm_shared3::econ::TokenAmount::from_nano(100)
+                )
+                .atto()
+                .clone()
+            )
+        );
+
+        // Below the batch balancer, the fee does not depend on the base fee.
+        let balancer = batch_balancer(NetworkVersion::V21).unwrap();
+        assert_eq!(balancer, TokenAmount::from_nano(5));
+        assert_eq!(
+            aggregate_prove_commit_network_fee(NetworkVersion::V21, 4, &TokenAmount::from_atto(1))
+                .unwrap(),
+            aggregate_prove_commit_network_fee(NetworkVersion::V21, 4, &balancer).unwrap()
+        );
+        assert!(aggregate_prove_commit_network_fee(NetworkVersion::V15, 4, &balancer).is_err());
+    }
+
+    #[test]
+    fn test_plan_prove_commit_aggregate() {
+        // The network fee makes aggregation more expensive when the base fee is low.
+        let plan = plan_prove_commit_aggregate(
+            NetworkVersion::V25,
+            100,
+            &TokenAmount::from_atto(100),
+            aggregate_gas,
+        )
+        .unwrap();
+        assert_eq!(plan.recommended, plan.individual);
+
+        // The fixed verification cost is amortized when the base fee is high.
+        let plan = plan_prove_commit_aggregate(
+            NetworkVersion::V25,
+            100,
+            &TokenAmount::from_nano(100),
+            aggregate_gas,
+        )
+        .unwrap();
+        assert_eq!(plan.recommended.aggregate_size, 100);
+        assert!(plan.recommended.per_sector < plan.individual.per_sector);
+        assert!(plan.recommended.network_fee.is_positive());
+
+        // Too few sectors to aggregate.
+        let plan = plan_prove_commit_aggregate(
+            NetworkVersion::V25,
+            3,
+            &TokenAmount::from_nano(100),
+            aggregate_gas,
+        )
+        .unwrap();
+        assert_eq!(plan.recommended.aggregate_size, 1);
+        assert!(plan_prove_commit_aggregate(
+            NetworkVersion::V25,
+            0,
+            &TokenAmount::from_nano(100),
+            aggregate_gas
+        )
+        .is_err());
+    }
+}
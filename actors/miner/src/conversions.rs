@@ -0,0 +1,229 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::bail;
+use fil_actors_shared::conversion_tests::{arbitrary_cid, Conversion};
+use fvm_ipld_encoding::{from_slice, to_vec, BytesDe};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::sector::{RegisteredPoStProof, RegisteredSealProof, SectorSize};
+use ipld_core::ipld::Ipld;
+use quickcheck::{Arbitrary, Gen};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{v8, v9};
+
+/// Converts a tuple-encoded value to the type of another version after editing its fields.
+fn convert_fields<A, B>(value: &A, edit: impl FnOnce(&mut Vec<Ipld>)) -> anyhow::Result<B>
+where
+    A: Serialize,
+    B: DeserializeOwned,
+{
+    let Ipld::List(mut fields) = from_slice(&to_vec(value)?)? else {
+        bail!("expected a tuple-encoded structure");
+    };
+    edit(&mut fields);
+    Ok(from_slice(&to_vec(&Ipld::List(fields))?)?)
+}
+
+/// Returns the fields of an arbitrary sector in the layout of the given version. The
+/// `replaced_sector_age` of the versions before v12 is zero, since the v12 migration drops it.
+fn arbitrary_sector_fields(g: &mut Gen, version: u64) -> Vec<Ipld> {
+    let sector = v8::SectorOnChainInfo {
+        sector_number: u64::arbitrary(g),
+        seal_proof: *g
+            .choose(&[
+                RegisteredSealProof::StackedDRG32GiBV1P1,
+                RegisteredSealProof::StackedDRG64GiBV1P1,
+            ])
+            .expect("non-empty choice"),
+        sealed_cid: arbitrary_cid(g),
+        deal_ids: Vec::arbitrary(g),
+        activation: i64::arbitrary(g),
+        expiration: i64::arbitrary(g),
+        deal_weight: BigInt::from(u64::arbitrary(g)),
+        verified_deal_weight: BigInt::from(u64::arbitrary(g)),
+        initial_pledge: TokenAmount::from_atto(u64::arbitrary(g)),
+        expected_day_reward: TokenAmount::from_atto(u64::arbitrary(g)),
+        expected_storage_pledge: TokenAmount::from_atto(u64::arbitrary(g)),
+        replaced_sector_age: 0,
+        replaced_day_reward: TokenAmount::from_atto(u64::arbitrary(g)),
+        sector_key_cid: bool::arbitrary(g).then(|| arbitrary_cid(g)),
+    };
+    let mut fields = encoded_fields(&sector);
+    if version >= 9 {
+        fields.push(Ipld::Bool(bool::arbitrary(g)));
+    }
+    if version >= 12 {
+        fields[11] = Ipld::Integer(i64::arbitrary(g).into());
+        upgrade_sector_flags(&mut fields);
+    }
+    fields
+}
+
+/// Replaces the `simple_qa_power` field with the `flags` of v12.
+fn upgrade_sector_flags(fields: &mut [Ipld]) {
+    fields[14] = Ipld::Integer((fields[14] == Ipld::Bool(true)).into());
+}
+
+/// Returns the fields of arbitrary miner info in the layout of the given version. From v9 on, the
+/// owner is the beneficiary, as set by the v9 migration.
+fn arbitrary_miner_info_fields(g: &mut Gen, version: u64) -> Vec<Ipld> {
+    let id = |g: &mut Gen| Address::new_id(u64::arbitrary(g));
+    let info = v8::MinerInfo {
+        owner: id(g),
+        worker: id(g),
+        control_addresses: (0..u8::arbitrary(g) % 4).map(|_| id(g)).collect(),
+        pending_worker_key: None,
+        peer_id: Vec::arbitrary(g),
+        multi_address: Vec::<Vec<u8>>::arbitrary(g)
+            .into_iter()
+            .map(BytesDe)
+            .collect(),
+        window_post_proof_type: RegisteredPoStProof::StackedDRGWindow32GiBV1,
+        sector_size: SectorSize::_32GiB,
+        window_post_partition_sectors: u64::arbitrary(g),
+        consensus_fault_elapsed: i64::arbitrary(g),
+        pending_owner_address: bool::arbitrary(g).then(|| id(g)),
+    };
+    let mut fields = encoded_fields(&info);
+    if version >= 9 {
+        add_default_beneficiary(&mut fields);
+    }
+    fields
+}
+
+/// Appends the beneficiary fields of v9, with the owner as beneficiary.
+fn add_default_beneficiary(fields: &mut Vec<Ipld>) {
+    let owner = fields[0].clone();
+    fields.push(owner);
+    fields.push(encoded(&v9::BeneficiaryTerm::default_value()));
+    fields.push(Ipld::Null);
+}
+
+fn encoded<T: Serialize>(value: &T) -> Ipld {
+    from_slice(&to_vec(value).expect("encodable value")).expect("decodable value")
+}
+
+fn encoded_fields<T: Serialize>(value: &T) -> Vec<Ipld> {
+    match encoded(value) {
+        Ipld::List(fields) => fields,
+        _ => unreachable!("tuple-encoded structure"),
+    }
+}
+
+fn decode_fields<T: DeserializeOwned>(fields: Vec<Ipld>) -> T {
+    from_slice(&to_vec(&Ipld::List(fields)).expect("encodable fields"))
+        .expect("fields of the version layout")
+}
+
+macro_rules! conversion {
+    ($(
+        $name:ident: $ty:ident $old:ident => $new:ident, $version:literal,
+        $arbitrary:ident, $upgrade:expr, $downgrade:expr, $changed:expr;
+    )*) => {
+        $(
+            #[doc = concat!(
+                "[`Conversion`] of `", stringify!($ty), "` from ", stringify!($old),
+                " to ", stringify!($new), "."
+            )]
+            pub struct $name;
+
+            impl Conversion for $name {
+                type Old = crate::$old::$ty;
+                type New = crate::$new::$ty;
+                const CHANGED_FIELDS: &'static [usize] = $changed;
+
+                fn arbitrary(g: &mut Gen) -> Self::Old {
+                    decode_fields($arbitrary(g, $version))
+                }
+
+                fn upgrade(old: &Self::Old) -> anyhow::Result<Self::New> {
+                    convert_fields(old, $upgrade)
+                }
+
+                fn downgrade(new: &Self::New) -> anyhow::Result<Self::Old> {
+                    convert_fields(new, $downgrade)
+                }
+            }
+        )*
+    };
+}
+
+conversion! {
+    SectorOnChainInfoV8ToV9: SectorOnChainInfo v8 => v9, 8, arbitrary_sector_fields,
+        |f| f.push(Ipld::Bool(false)), |f| { f.pop(); }, &[];
+    SectorOnChainInfoV9ToV10: SectorOnChainInfo v9 => v10, 9, arbitrary_sector_fields,
+        |_| {}, |_| {}, &[];
+    SectorOnChainInfoV10ToV11: SectorOnChainInfo v10 => v11, 10, arbitrary_sector_fields,
+        |_| {}, |_| {}, &[];
+    // The v12 migration sets the power base epoch to the activation epoch, and replaces
+    // simple_qa_power with the SIMPLE_QA_POWER flag.
+    SectorOnChainInfoV11ToV12: SectorOnChainInfo v11 => v12, 11, arbitrary_sector_fields,
+        |f| {
+            f[11] = f[4].clone();
+            upgrade_sector_flags(f);
+        },
+        |f| {
+            f[11] = Ipld::Integer(0);
+            f[14] = Ipld::Bool(matches!(f[14], Ipld::Integer(flags) if flags & 1 != 0));
+        },
+        &[11, 14];
+    SectorOnChainInfoV12ToV13: SectorOnChainInfo v12 => v13, 12, arbitrary_sector_fields,
+        |_| {}, |_| {}, &[];
+    SectorOnChainInfoV13ToV14: SectorOnChainInfo v13 => v14, 13, arbitrary_sector_fields,
+        |_| {}, |_| {}, &[];
+    SectorOnChainInfoV14ToV15: SectorOnChainInfo v14 => v15, 14, arbitrary_sector_fields,
+        |_| {}, |_| {}, &[];
+    SectorOnChainInfoV15ToV16: SectorOnChainInfo v15 => v16, 15, arbitrary_sector_fields,
+        |_| {}, |_| {}, &[];
+
+    MinerInfoV8ToV9: MinerInfo v8 => v9, 8, arbitrary_miner_info_fields,
+        add_default_beneficiary, |f| f.truncate(11), &[];
+    MinerInfoV9ToV10: MinerInfo v9 => v10, 9, arbitrary_miner_info_fields,
+        |_| {}, |_| {}, &[];
+    MinerInfoV10ToV11: MinerInfo v10 => v11, 10, arbitrary_miner_info_fields,
+        |_| {}, |_| {}, &[];
+    MinerInfoV11ToV12: MinerInfo v11 => v12, 11, arbitrary_miner_info_fields,
+        |_| {}, |_| {}, &[];
+    MinerInfoV12ToV13: MinerInfo v12 => v13, 12, arbitrary_miner_info_fields,
+        |_| {}, |_| {}, &[];
+    MinerInfoV13ToV14: MinerInfo v13 => v14, 13, arbitrary_miner_info_fields,
+        |_| {}, |_| {}, &[];
+    MinerInfoV14ToV15: MinerInfo v14 => v15, 14, arbitrary_miner_info_fields,
+        |_| {}, |_| {}, &[];
+    MinerInfoV15ToV16: MinerInfo v15 => v16, 15, arbitrary_miner_info_fields,
+        |_| {}, |_| {}, &[];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_actors_shared::conversion_tests::check_conversion;
+
+    #[test]
+    fn test_sector_on_chain_info_conversions() {
+        check_conversion::<SectorOnChainInfoV8ToV9>(100);
+        check_conversion::<SectorOnChainInfoV9ToV10>(100);
+        check_conversion::<SectorOnChainInfoV10ToV11>(100);
+        check_conversion::<SectorOnChainInfoV11ToV12>(100);
+        check_conversion::<SectorOnChainInfoV12ToV13>(100);
+        check_conversion::<SectorOnChainInfoV13ToV14>(100);
+        check_conversion::<SectorOnChainInfoV14ToV15>(100);
+        check_conversion::<SectorOnChainInfoV15ToV16>(100);
+    }
+
+    #[test]
+    fn test_miner_info_conversions() {
+        check_conversion::<MinerInfoV8ToV9>(100);
+        check_conversion::<MinerInfoV9ToV10>(100);
+        check_conversion::<MinerInfoV10ToV11>(100);
+        check_conversion::<MinerInfoV11ToV12>(100);
+        check_conversion::<MinerInfoV12ToV13>(100);
+        check_conversion::<MinerInfoV13ToV14>(100);
+        check_conversion::<MinerInfoV14ToV15>(100);
+        check_conversion::<MinerInfoV15ToV16>(100);
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::{anyhow, bail, Context};
+use fil_actors_shared::v16::DealWeight;
+use fil_actors_shared::version::actors_version;
+use fvm_shared4::bigint::Zero;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
+use fvm_shared4::sector::{SectorSize, StoragePower};
+use fvm_shared4::smooth::FilterEstimate;
+use fvm_shared4::version::NetworkVersion;
+use num_traits::FromPrimitive;
+
+/// Network-wide inputs to the pre-commit deposit and initial pledge formulas, normalized to the
+/// newest shared types regardless of the actors version they were read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PledgeInputs {
+    /// Reward actor `this_epoch_reward_smoothed`.
+    pub reward_estimate: FilterEstimate,
+    /// Power actor `this_epoch_qa_power_smoothed`.
+    pub network_qa_power_estimate: FilterEstimate,
+    /// Reward actor `this_epoch_baseline_power`.
+    pub baseline_power: StoragePower,
+    /// Total FIL circulating supply.
+    pub circulating_supply: TokenAmount,
+    /// Epochs elapsed since the power actor `ramp_start_epoch` (FIP-0081). Ignored before v15.
+    pub epochs_since_ramp_start: i64,
+    /// Power actor `ramp_duration_epochs` (FIP-0081). Ignored before v15.
+    pub ramp_duration_epochs: u64,
+}
+
+/// Sector parameters that determine its quality-adjusted power.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectorEstimate {
+    pub sector_size: SectorSize,
+    /// Epochs from activation to expiration.
+    pub duration: ChainEpoch,
+    /// Spacetime occupied by verified pieces.
+    pub verified_deal_weight: DealWeight,
+}
+
+macro_rules! estimate {
+    ($version:ident, $estimate:path, $inputs:expr, $sector:expr, precommit) => {{
+        use crate::$version::pre_commit_deposit_for_power;
+        let (reward_estimate, network_qa_power_estimate) = estimate!(@filters $estimate, $inputs);
+        let qa_power = estimate!(@qa_power $version, $sector);
+        pre_commit_deposit_for_power(&reward_estimate, &network_qa_power_estimate, &qa_power)
+    }};
+    ($version:ident, $estimate:path, $inputs:expr, $sector:expr, pledge) => {{
+        use crate::$version::initial_pledge_for_power;
+        let (reward_estimate, network_qa_power_estimate) = estimate!(@filters $estimate, $inputs);
+        initial_pledge_for_power(
+            &estimate!(@qa_power $version, $sector),
+            &$inputs.baseline_power,
+            &reward_estimate,
+            &network_qa_power_estimate,
+            &$inputs.circulating_supply,
+        )
+    }};
+    ($version:ident, $estimate:path, $inputs:expr, $sector:expr, pledge_ramp) => {{
+        use crate::$version::initial_pledge_for_power;
+        let (reward_estimate, network_qa_power_estimate) = estimate!(@filters $estimate, $inputs);
+        initial_pledge_for_power(
+            &estimate!(@qa_power $version, $sector),
+            &$inputs.baseline_power,
+            &reward_estimate,
+            &network_qa_power_estimate,
+            &$inputs.circulating_supply,
+            $inputs.epochs_since_ramp_start,
+            $inputs.ramp_duration_epochs,
+        )
+    }};
+    (@filters $estimate:path, $inputs:expr) => {{
+        use $estimate as VersionedFilterEstimate;
+        let convert = |e: &FilterEstimate| VersionedFilterEstimate {
+            position: e.position.clone(),
+            velocity: e.velocity.clone(),
+        };
+        (
+            convert(&$inputs.reward_estimate),
+            convert(&$inputs.network_qa_power_estimate),
+        )
+    }};
+    (@qa_power v12, $sector:expr) => {
+        estimate!(@qa_power_with_deal_weight v12, $sector)
+    };
+    (@qa_power v13, $sector:expr) => {
+        estimate!(@qa_power_with_deal_weight v13, $sector)
+    };
+    (@qa_power v14, $sector:expr) => {
+        estimate!(@qa_power_with_deal_weight v14, $sector)
+    };
+    (@qa_power $version:ident, $sector:expr) => {
+        crate::$version::qa_power_for_weight(
+            $sector.sector_size,
+            $sector.duration,
+            &$sector.verified_deal_weight,
+        )
+    };
+    (@qa_power_with_deal_weight $version:ident, $sector:expr) => {
+        crate::$version::qa_power_for_weight(
+            $sector.sector_size,
+            $sector.duration,
+            &DealWeight::zero(),
+            &$sector.verified_deal_weight,
+        )
+    };
+}
+
+/// Computes the estimates with the v11 formulas, which take the `fvm_shared3` types.
+macro_rules! estimate_v11 {
+    ($inputs:expr, $sector:expr, precommit) => {{
+        let (reward_estimate, network_qa_power_estimate, qa_power) =
+            estimate_v11!(@args $inputs, $sector);
+        let deposit = crate::v11::pre_commit_deposit_for_power(
+            &reward_estimate,
+            &network_qa_power_estimate,
+            &qa_power,
+        );
+        TokenAmount::from_atto(deposit.atto().clone())
+    }};
+    ($inputs:expr, $sector:expr, pledge) => {{
+        let (reward_estimate, network_qa_power_estimate, qa_power) =
+            estimate_v11!(@args $inputs, $sector);
+        let pledge = crate::v11::initial_pledge_for_power(
+            &qa_power,
+            &$inputs.baseline_power,
+            &reward_estimate,
+            &network_qa_power_estimate,
+            &fvm_shared3::econ::TokenAmount::from_atto($inputs.circulating_supply.atto().clone()),
+        );
+        TokenAmount::from_atto(pledge.atto().clone())
+    }};
+    (@args $inputs:expr, $sector:expr) => {{
+        let (reward_estimate, network_qa_power_estimate) =
+            estimate!(@filters fvm_shared3::smooth::FilterEstimate, $inputs);
+        let sector_size = FromPrimitive::from_u64($sector.sector_size as u64)
+            .context("unknown sector size")?;
+        let qa_power = crate::v11::qa_power_for_weight(
+            sector_size,
+            $sector.duration,
+            &DealWeight::zero(),
+            &$sector.verified_deal_weight,
+        );
+        (reward_estimate, network_qa_power_estimate, qa_power)
+    }};
+}
+
+fn supported_actors_version(network_version: NetworkVersion) -> anyhow::Result<u64> {
+    let version = actors_version(network_version)
+        .ok_or_else(|| anyhow!("unsupported network version {network_version}"))?;
+    if version < 11 {
+        bail!("pledge formulas are not available for actors version {version}");
+    }
+    Ok(version)
+}
+
+/// Estimates the deposit required to pre-commit a sector at the given network version.
+pub fn estimate_precommit_deposit(
+    network_version: NetworkVersion,
+    inputs: &PledgeInputs,
+    sector: &SectorEstimate,
+) -> anyhow::Result<TokenAmount> {
+    let version = supported_actors_version(network_version)?;
+    Ok(match version {
+        11 => estimate_v11!(inputs, sector, precommit),
+        12 => estimate!(v12, FilterEstimate, inputs, sector, precommit),
+        13 => estimate!(v13, FilterEstimate, inputs, sector, precommit),
+        14 => estimate!(
+            v14,
+            fil_actors_shared::v14::reward::FilterEstimate,
+            inputs,
+            sector,
+            precommit
+        ),
+        15 => estimate!(
+            v15,
+            fil_actors_shared::v15::reward::FilterEstimate,
+            inputs,
+            sector,
+            precommit
+        ),
+        16 => estimate!(
+            v16,
+            fil_actors_shared::v16::reward::FilterEstimate,
+            inputs,
+            sector,
+            precommit
+        ),
+        _ => unreachable!("actors version {version} is not handled"),
+    })
+}
+
+/// Estimates the initial pledge of a sector activated at the given network version, including
+/// the FIP-0081 pledge ramp from v15 onwards.
+pub fn estimate_initial_pledge(
+    network_version: NetworkVersion,
+    inputs: &PledgeInputs,
+    sector: &SectorEstimate,
+) -> anyhow::Result<TokenAmount> {
+    let version = supported_actors_version(network_version)?;
+    Ok(match version {
+        11 => estimate_v11!(inputs, sector, pledge),
+        12 => estimate!(v12, FilterEstimate, inputs, sector, pledge),
+        13 => estimate!(v13, FilterEstimate, inputs, sector, pledge),
+        14 => estimate!(
+            v14,
+            fil_actors_shared::v14::reward::FilterEstimate,
+            inputs,
+            sector,
+            pledge
+        ),
+        15 => estimate!(
+            v15,
+            fil_actors_shared::v15::reward::FilterEstimate,
+            inputs,
+            sector,
+            pledge_ramp
+        ),
+        16 => estimate!(
+            v16,
+            fil_actors_shared::v16::reward::FilterEstimate,
+            inputs,
+            sector,
+            pledge_ramp
+        ),
+        _ => unreachable!("actors version {version} is not handled"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_actors_shared::v16::network::EPOCHS_IN_DAY;
+    use fvm_shared4::bigint::BigInt;
+
+    fn inputs(epochs_since_ramp_start: i64) -> PledgeInputs {
+        let power = StoragePower::from(1u64 << 60);
+        PledgeInputs {
+            reward_estimate: FilterEstimate::new(BigInt::from(10u64.pow(18)), BigInt::zero()),
+            network_qa_power_estimate: FilterEstimate::new(power.clone(), BigInt::zero()),
+            baseline_power: power * 2,
+            circulating_supply: TokenAmount::from_whole(10_000_000),
+            epochs_since_ramp_start,
+            ramp_duration_epochs: 365 * EPOCHS_IN_DAY as u64,
+        }
+    }
+
+    fn sector() -> SectorEstimate {
+        SectorEstimate {
+            sector_size: SectorSize::_32GiB,
+            duration: 540 * EPOCHS_IN_DAY,
+            verified_deal_weight: DealWeight::zero(),
+        }
+    }
+
+    const SUPPORTED: [NetworkVersion; 7] = [
+        NetworkVersion::V19,
+        NetworkVersion::V20,
+        NetworkVersion::V21,
+        NetworkVersion::V22,
+        NetworkVersion::V23,
+        NetworkVersion::V24,
+        NetworkVersion::V25,
+    ];
+
+    #[test]
+    fn test_estimates_match_known_values() {
+        // With a constant reward of 1 FIL per epoch and 2^60 bytes of network power, a 32 GiB
+        // sector earns 10^18 * 2^35 / 2^60 attoFIL per epoch. The deposit is 20 days of that
+        // reward, and the pledge adds 30% of the circulating supply times the share of the
+        // sector in the baseline power of 2^61 bytes.
+        let deposit = TokenAmount::from_atto(1_716_613_769_531_250u64);
+        let consensus_pledge = TokenAmount::from_atto(44_703_483_581_542_968u64);
+        for nv in SUPPORTED {
+            assert_eq!(
+                estimate_precommit_deposit(nv, &inputs(0), &sector()).unwrap(),
+                deposit,
+                "{nv}"
+            );
+            assert_eq!(
+                estimate_initial_pledge(nv, &inputs(0), &sector()).unwrap(),
+                &deposit + &consensus_pledge,
+                "{nv}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_estimates_are_clamped_at_one_attofil() {
+        // Fixture of the builtin-actors precommit_deposit_and_initial_pledge_positive test: no
+        // reward and no circulating supply.
+        let network_qa_power = StoragePower::from(1u64 << 10);
+        let inputs = PledgeInputs {
+            reward_estimate: FilterEstimate::new(BigInt::zero(), BigInt::zero()),
+            network_qa_power_estimate: FilterEstimate::new(
+                network_qa_power.clone(),
+                network_qa_power.clone(),
+            ),
+            baseline_power: network_qa_power,
+            circulating_supply: TokenAmount::zero(),
+            ..inputs(0)
+        };
+        let sector = SectorEstimate {
+            sector_size: SectorSize::_64GiB,
+            ..sector()
+        };
+        for nv in SUPPORTED {
+            let one = TokenAmount::from_atto(1);
+            assert_eq!(
+                estimate_precommit_deposit(nv, &inputs, &sector).unwrap(),
+                one
+            );
+            assert_eq!(estimate_initial_pledge(nv, &inputs, &sector).unwrap(), one);
+        }
+    }
+
+    #[test]
+    fn test_pledge_ignores_ramp_before_v15() {
+        let pledge = estimate_initial_pledge(NetworkVersion::V21, &inputs(0), &sector()).unwrap();
+        let ramped = estimate_initial_pledge(NetworkVersion::V21, &inputs(i64::MAX), &sector());
+        assert_eq!(pledge, ramped.unwrap());
+    }
+
+    #[test]
+    fn test_initial_pledge_ramp() {
+        let before = estimate_initial_pledge(NetworkVersion::V25, &inputs(-1), &sector()).unwrap();
+        let after =
+            estimate_initial_pledge(NetworkVersion::V25, &inputs(i64::MAX), &sector()).unwrap();
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_unsupported_versions() {
+        assert!(estimate_initial_pledge(NetworkVersion::V18, &inputs(0), &sector()).is_err());
+        assert!(estimate_precommit_deposit(NetworkVersion::V15, &inputs(0), &sector()).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Context};
+use cid::Cid;
+use fil_actors_shared::ids::SectorNumber;
+use fil_actors_shared::state_check::StateTree;
+use fvm_ipld_amt::Amt;
+use fvm_ipld_bitfield::BitField;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::ActorID;
+use ipld_core::ipld::Ipld;
+
+// Positions of the fields of interest in the miner state, deadlines and partitions. They have not
+// moved since v8.
+const MINER_DEADLINES_FIELD: usize = 12;
+const DEADLINE_PARTITIONS_FIELD: usize = 0;
+const PARTITION_FAULTS_FIELD: usize = 2;
+const PARTITION_RECOVERIES_FIELD: usize = 3;
+const PARTITION_TERMINATED_FIELD: usize = 4;
+
+/// A change of the fault status of a sector between two consecutive state roots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultEventKind {
+    /// The sector was declared or detected faulty.
+    Faulted,
+    /// The owner declared the recovery of the faulty sector, to be proven in its next Window PoSt.
+    RecoveryDeclared,
+    /// The sector is no longer faulty.
+    Recovered,
+    /// The sector was terminated or expired.
+    Terminated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultEvent {
+    /// Epoch of the state root in which the change is first seen.
+    pub epoch: ChainEpoch,
+    pub kind: FaultEventKind,
+}
+
+/// Fault timelines of the sectors of a miner, see [`fault_history`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaultHistory {
+    /// Events of each sector with at least one, in epoch order.
+    pub timelines: BTreeMap<SectorNumber, Vec<FaultEvent>>,
+}
+
+impl FaultHistory {
+    /// Returns the events of a sector, empty if it never changed status.
+    pub fn timeline(&self, sector: SectorNumber) -> &[FaultEvent] {
+        self.timelines.get(&sector).map_or(&[], Vec::as_slice)
+    }
+
+    fn push(&mut self, sectors: &BitField, epoch: ChainEpoch, kind: FaultEventKind) {
+        for sector in sectors.iter() {
+            self.timelines
+                .entry(SectorNumber(sector))
+                .or_default()
+                .push(FaultEvent { epoch, kind });
+        }
+    }
+}
+
+/// Faults, declared recoveries and terminations of the partitions of a deadline or miner.
+#[derive(Debug, Clone, Default)]
+struct FaultState {
+    faults: BitField,
+    recoveries: BitField,
+    terminated: BitField,
+}
+
+impl FaultState {
+    fn union(&mut self, other: &Self) {
+        self.faults |= &other.faults;
+        self.recoveries |= &other.recoveries;
+        self.terminated |= &other.terminated;
+    }
+}
+
+/// Reconstructs the fault timelines of the sectors of a miner from its state in a sequence of
+/// state roots, given with their epochs in increasing order, e.g. the parent state roots of
+/// consecutive tipsets.
+///
+/// Each pair of consecutive roots is compared: sectors entering the faults, the declared
+/// recoveries or the terminated sectors of their partitions are reported as faulted, recovering
+/// or terminated, and sectors leaving the faults without being terminated as recovered. Events
+/// are dated with the epoch of the root in which they are first seen, so their precision is the
+/// spacing of the roots. The first root only sets the initial status. Deadlines whose CID did not
+/// change are not reloaded, so a dense sequence of roots costs little more than a sparse one.
+///
+/// The state is decoded by field position, which is the same for all versions since v8. Roots in
+/// which the miner does not exist count as a miner without sectors.
+pub fn fault_history<BS: Blockstore>(
+    store: &BS,
+    roots: impl IntoIterator<Item = (ChainEpoch, Cid)>,
+    miner: ActorID,
+) -> anyhow::Result<FaultHistory> {
+    let mut history = FaultHistory::default();
+    let mut cache = HashMap::new();
+    let mut previous: Option<(ChainEpoch, Option<Cid>, FaultState)> = None;
+    for (epoch, root) in roots {
+        let tree = StateTree::load(store, &root)
+            .with_context(|| format!("failed to load state tree at epoch {epoch}"))?;
+        let miner_state = tree.actor(miner)?.map(|actor| actor.state);
+        let state = match &previous {
+            Some((last, _, _)) if epoch <= *last => {
+                bail!("epoch {epoch} is not after the previous epoch {last}")
+            }
+            Some((_, last_state, state)) if *last_state == miner_state => state.clone(),
+            _ => match &miner_state {
+                Some(miner_state) => load_fault_state(store, miner_state, &mut cache)
+                    .with_context(|| format!("failed to load miner f0{miner} at epoch {epoch}"))?,
+                None => FaultState::default(),
+            },
+        };
+        if let Some((_, _, old)) = &previous {
+            let terminated = &state.terminated - &old.terminated;
+            history.push(
+                &(&state.faults - &old.faults),
+                epoch,
+                FaultEventKind::Faulted,
+            );
+            history.push(
+                &(&state.recoveries - &old.recoveries),
+                epoch,
+                FaultEventKind::RecoveryDeclared,
+            );
+            history.push(
+                &(&(&old.faults - &state.faults) - &terminated),
+                epoch,
+                FaultEventKind::Recovered,
+            );
+            history.push(&terminated, epoch, FaultEventKind::Terminated);
+        }
+        previous = Some((epoch, miner_state, state));
+    }
+    Ok(history)
+}
+
+fn load_fault_state<BS: Blockstore>(
+    store: &BS,
+    miner_state: &Cid,
+    cache: &mut HashMap<Cid, FaultState>,
+) -> anyhow::Result<FaultState> {
+    let miner_state = load(store, miner_state)?;
+    let deadlines = field(&miner_state, MINER_DEADLINES_FIELD)?;
+    let (due,): (Vec<Cid>,) = store
+        .get_cbor(&link(deadlines)?)?
+        .context("deadlines not found")?;
+    let mut state = FaultState::default();
+    let mut loaded = HashMap::new();
+    for deadline in due {
+        let deadline_state = match cache.remove(&deadline) {
+            Some(deadline_state) => deadline_state,
+            None => load_deadline_fault_state(store, &deadline)?,
+        };
+        state.union(&deadline_state);
+        loaded.insert(deadline, deadline_state);
+    }
+    // Keep the deadlines of the latest state only, as those of older states will not come back.
+    *cache = loaded;
+    Ok(state)
+}
+
+fn load_deadline_fault_state<BS: Blockstore>(
+    store: &BS,
+    deadline: &Cid,
+) -> anyhow::Result<FaultState> {
+    let deadline = load(store, deadline)?;
+    let partitions = field(&deadline, DEADLINE_PARTITIONS_FIELD)?;
+    let partitions = Amt::<Ipld, _>::load(&link(partitions)?, store)?;
+    let mut state = FaultState::default();
+    partitions.for_each(|_, partition| {
+        let bitfield = |index| -> anyhow::Result<BitField> {
+            Ok(ipld_core::serde::from_ipld(
+                field(partition, index)?.clone(),
+            )?)
+        };
+        state.union(&FaultState {
+            faults: bitfield(PARTITION_FAULTS_FIELD)?,
+            recoveries: bitfield(PARTITION_RECOVERIES_FIELD)?,
+            terminated: bitfield(PARTITION_TERMINATED_FIELD)?,
+        });
+        Ok(())
+    })?;
+    Ok(state)
+}
+
+fn load<BS: Blockstore>(store: &BS, cid: &Cid) -> anyhow::Result<Ipld> {
+    store
+        .get_cbor(cid)?
+        .with_context(|| format!("block {cid} not found"))
+}
+
+fn field(value: &Ipld, index: usize) -> anyhow::Result<&Ipld> {
+    match value {
+        Ipld::List(fields) => fields
+            .get(index)
+            .with_context(|| format!("missing field {index}")),
+        _ => bail!("expected a tuple-encoded structure"),
+    }
+}
+
+fn link(value: &Ipld) -> anyhow::Result<Cid> {
+    match value {
+        Ipld::Link(cid) => Ok(*cid),
+        _ => bail!("expected a link"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::{Deadline, MinerInfo, Partition, State};
+    use fil_actors_shared::v16::runtime::Policy;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::IPLD_RAW;
+    use fvm_ipld_hamt::{BytesKey, Hamt};
+    use fvm_shared4::address::Address;
+    use fvm_shared4::sector::RegisteredPoStProof;
+    use fvm_shared4::HAMT_BIT_WIDTH;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    const MINER: ActorID = 1000;
+
+    fn code(name: &str) -> Cid {
+        Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(name.as_bytes()))
+    }
+
+    /// Builds a state tree whose miner has one partition with the given faults, recoveries and
+    /// terminated sectors.
+    fn state_tree(
+        store: &MemoryBlockstore,
+        faults: &[u64],
+        recoveries: &[u64],
+        terminated: &[u64],
+    ) -> Cid {
+        let policy = Policy::default();
+        let info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let info = store.put_cbor(&info, Code::Blake2b256).unwrap();
+        let mut miner = State::new(&policy, store, info, 0, 0).unwrap();
+        let mut partition = Partition::new(store).unwrap();
+        partition.sectors = BitField::try_from_bits(0..10).unwrap();
+        partition.faults = BitField::try_from_bits(faults.iter().copied()).unwrap();
+        partition.recoveries = BitField::try_from_bits(recoveries.iter().copied()).unwrap();
+        partition.terminated = BitField::try_from_bits(terminated.iter().copied()).unwrap();
+        let mut deadline = Deadline::new(store).unwrap();
+        let mut partitions = deadline.partitions_amt(store).unwrap();
+        partitions.set(0, partition).unwrap();
+        deadline.partitions = partitions.flush().unwrap();
+        let mut deadlines = miner.load_deadlines(store).unwrap();
+        deadlines
+            .update_deadline(&policy, store, 3, &deadline)
+            .unwrap();
+        miner.save_deadlines(store, deadlines).unwrap();
+        let miner = store.put_cbor(&miner, Code::Blake2b256).unwrap();
+
+        let manifest = vec![
+            ("system".to_string(), code("system")),
+            ("storageminer".to_string(), code("storageminer")),
+        ];
+        let manifest = store.put_cbor(&manifest, Code::Blake2b256).unwrap();
+        let system = store.put_cbor(&(manifest,), Code::Blake2b256).unwrap();
+        let mut actors = Hamt::<_, Ipld, BytesKey>::new_with_bit_width(store, HAMT_BIT_WIDTH);
+        for (id, name, state) in [(0, "system", system), (MINER, "storageminer", miner)] {
+            let actor = Ipld::List(vec![Ipld::Link(code(name)), Ipld::Link(state)]);
+            actors
+                .set(BytesKey(Address::new_id(id).to_bytes()), actor)
+                .unwrap();
+        }
+        let actors = actors.flush().unwrap();
+        let info = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        store
+            .put_cbor(&(5u64, actors, info), Code::Blake2b256)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fault_history() {
+        use FaultEventKind::*;
+        let store = MemoryBlockstore::new();
+        let roots = [
+            (10, state_tree(&store, &[1], &[], &[])),
+            (20, state_tree(&store, &[1, 2, 3], &[], &[])),
+            (30, state_tree(&store, &[1, 2, 3], &[], &[])),
+            (40, state_tree(&store, &[1, 2, 3], &[2], &[])),
+            (50, state_tree(&store, &[1], &[], &[3])),
+        ];
+        let history = fault_history(&store, roots, MINER).unwrap();
+
+        let event = |epoch, kind| FaultEvent { epoch, kind };
+        assert_eq!(history.timeline(SectorNumber(1)), []);
+        assert_eq!(
+            history.timeline(SectorNumber(2)),
+            [
+                event(20, Faulted),
+                event(40, RecoveryDeclared),
+                event(50, Recovered)
+            ]
+        );
+        assert_eq!(
+            history.timeline(SectorNumber(3)),
+            [event(20, Faulted), event(50, Terminated)]
+        );
+        assert_eq!(history.timelines.len(), 2);
+
+        let unordered = [roots[1], roots[0]];
+        assert!(fault_history(&store, unordered, MINER).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use fil_actors_shared::v16::runtime::Policy;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared4::econ::TokenAmount;
+
+use crate::versioned::VersionedState;
+
+macro_rules! check_state_invariants {
+    ($($variant:ident => $module:ident, $shared:ident;)*) => {
+        impl VersionedState {
+            /// Checks the invariants of the state against the balance of the miner actor, with
+            /// the checks of its actors version. Only the deadline count of the policy is used.
+            ///
+            /// Returns the violations found, each prefixed with the deadline and partition it
+            /// was found in, if any. Fails if the deadlines or partitions cannot be loaded.
+            pub fn check_state_invariants<BS: Blockstore>(
+                &self,
+                store: &BS,
+                policy: &Policy,
+                balance: &TokenAmount,
+            ) -> anyhow::Result<Vec<String>> {
+                let mut violations = Vec::new();
+                match self {
+                    $(Self::$variant(st) => {
+                        let balance = $shared::econ::TokenAmount::from_atto(balance.atto().clone());
+                        if let Err(e) = st.check_balance_invariants(&balance) {
+                            violations.push(e.to_string());
+                        }
+                        if st.current_deadline >= policy.wpost_period_deadlines {
+                            violations.push(format!(
+                                "current deadline {} out of range",
+                                st.current_deadline
+                            ));
+                        }
+
+                        let deadlines = st
+                            .load_deadlines(store)
+                            .map_err(|e| anyhow::anyhow!(e))
+                            .context("failed to load deadlines")?;
+                        if deadlines.due.len() as u64 != policy.wpost_period_deadlines {
+                            violations.push(format!(
+                                "expected {} deadlines, found {}",
+                                policy.wpost_period_deadlines,
+                                deadlines.due.len()
+                            ));
+                        }
+                        for (index, root) in deadlines.due.iter().enumerate() {
+                            let deadline: crate::$module::Deadline = store
+                                .get_cbor(root)?
+                                .with_context(|| format!("deadline {index} not found"))?;
+                            if let Err(e) = deadline.validate_state() {
+                                violations.push(format!("deadline {index}: {e}"));
+                            }
+                            deadline
+                                .for_each(store, |partition_index, partition| {
+                                    if let Err(e) = partition.validate_state() {
+                                        violations.push(format!(
+                                            "deadline {index} partition {partition_index}: {e}"
+                                        ));
+                                    }
+                                    Ok(())
+                                })
+                                .with_context(|| {
+                                    format!("failed to load the partitions of deadline {index}")
+                                })?;
+                        }
+                    })*
+                }
+                Ok(violations)
+            }
+        }
+    };
+}
+
+check_state_invariants! {
+    V8 => v8, fvm_shared;
+    V9 => v9, fvm_shared;
+    V10 => v10, fvm_shared3;
+    V11 => v11, fvm_shared3;
+    V12 => v12, fvm_shared4;
+    V13 => v13, fvm_shared4;
+    V14 => v14, fvm_shared4;
+    V15 => v15, fvm_shared4;
+    V16 => v16, fvm_shared4;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::{MinerInfo, State};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared4::sector::RegisteredPoStProof;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_check_state_invariants() {
+        let store = MemoryBlockstore::new();
+        let policy = Policy::default();
+        let info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let info = store.put_cbor(&info, Code::Blake2b256).unwrap();
+        let mut st = State::new(&policy, &store, info, 0, 0).unwrap();
+        st.initial_pledge = TokenAmount::from_whole(10);
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let state = VersionedState::load(&store, 16, &root).unwrap();
+        let balance = TokenAmount::from_whole(10);
+        assert!(state
+            .check_state_invariants(&store, &policy, &balance)
+            .unwrap()
+            .is_empty());
+
+        // The balance no longer covers the initial pledge.
+        let violations = state
+            .check_state_invariants(&store, &policy, &TokenAmount::from_whole(9))
+            .unwrap();
+        assert_eq!(violations.len(), 1);
+    }
+}
@@ -1,6 +1,21 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+pub mod batching;
+/// Property checks of the `SectorOnChainInfo` and `MinerInfo` conversions between adjacent
+/// versions.
+#[cfg(feature = "arb")]
+pub mod conversions;
+pub mod economics;
+pub mod fault_history;
+pub mod invariants;
+#[cfg(feature = "legacy-compat")]
+pub mod legacy;
+pub mod policy_lookup;
+pub mod power;
+pub mod power_continuity;
+pub mod precommit;
+pub mod sector_history;
 pub mod v10;
 pub mod v11;
 pub mod v12;
@@ -10,3 +25,6 @@ pub mod v15;
 pub mod v16;
 pub mod v8;
 pub mod v9;
+pub mod versioned;
+pub mod vesting;
+pub mod window_post;
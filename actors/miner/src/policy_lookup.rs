@@ -0,0 +1,255 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::{anyhow, bail};
+use fil_actors_shared::version::actors_version;
+use fvm_shared4::bigint::BigInt;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::sector::RegisteredSealProof;
+use fvm_shared4::version::NetworkVersion;
+
+/// Seal-proof dependent policy values derived by the miner actor, as computed with the default
+/// (mainnet) policy of an actors version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SealProofPolicy {
+    /// Actors version the values were derived from.
+    pub actors_version: u64,
+    /// Whether new sectors may be pre-committed with this proof.
+    pub can_pre_commit: bool,
+    /// Whether sectors may be proven with non-interactive PoRep using this proof.
+    pub can_prove_commit_ni: bool,
+    /// Maximum number of epochs between pre-commit and prove-commit.
+    pub max_prove_commit_duration: Option<ChainEpoch>,
+    /// Maximum lifetime of a sector sealed with this proof.
+    pub sector_maximum_lifetime: Option<ChainEpoch>,
+    /// Number of epochs between pre-commit and the interactive PoRep challenge.
+    pub pre_commit_challenge_delay: ChainEpoch,
+    /// Maximum age of the seal randomness of a non-interactive PoRep proof. Only set for
+    /// non-interactive proofs on versions that support them.
+    pub max_prove_commit_ni_randomness_lookback: Option<ChainEpoch>,
+}
+
+macro_rules! seal_proof_policy {
+    ($version:ident, $actors_version:expr, $proof:expr) => {{
+        use crate::$version::{
+            can_pre_commit_seal_proof, max_prove_commit_duration,
+            seal_proof_sector_maximum_lifetime,
+        };
+        let policy = fil_actors_shared::$version::runtime::Policy::default();
+        let proof = From::from(i64::from($proof));
+        SealProofPolicy {
+            actors_version: $actors_version,
+            can_pre_commit: can_pre_commit_seal_proof(&policy, proof),
+            can_prove_commit_ni: false,
+            max_prove_commit_duration: max_prove_commit_duration(&policy, proof),
+            sector_maximum_lifetime: seal_proof_sector_maximum_lifetime(proof),
+            pre_commit_challenge_delay: policy.pre_commit_challenge_delay,
+            max_prove_commit_ni_randomness_lookback: None,
+        }
+    }};
+    ($version:ident, $actors_version:expr, $proof:expr, ni) => {{
+        use crate::$version::can_prove_commit_ni_seal_proof;
+        let policy = fil_actors_shared::$version::runtime::Policy::default();
+        let can_prove_commit_ni = can_prove_commit_ni_seal_proof(&policy, $proof);
+        SealProofPolicy {
+            can_prove_commit_ni,
+            max_prove_commit_ni_randomness_lookback: can_prove_commit_ni
+                .then_some(policy.max_prove_commit_ni_randomness_lookback),
+            ..seal_proof_policy!($version, $actors_version, $proof)
+        }
+    }};
+}
+
+/// Returns the seal-proof dependent policy values of the given actors version, so that schedulers
+/// don't need to hardcode tables that change with network upgrades.
+pub fn seal_proof_policy(
+    version: u64,
+    proof: RegisteredSealProof,
+) -> anyhow::Result<SealProofPolicy> {
+    Ok(match version {
+        8 => seal_proof_policy!(v8, version, proof),
+        9 => seal_proof_policy!(v9, version, proof),
+        10 => seal_proof_policy!(v10, version, proof),
+        11 => seal_proof_policy!(v11, version, proof),
+        12 => seal_proof_policy!(v12, version, proof),
+        13 => seal_proof_policy!(v13, version, proof),
+        14 => seal_proof_policy!(v14, version, proof, ni),
+        15 => seal_proof_policy!(v15, version, proof, ni),
+        16 => seal_proof_policy!(v16, version, proof, ni),
+        _ => bail!("unsupported actors version {version}"),
+    })
+}
+
+/// Returns the seal-proof dependent policy values in effect at the given network version, see
+/// [`seal_proof_policy`].
+pub fn seal_proof_policy_at(
+    network_version: NetworkVersion,
+    proof: RegisteredSealProof,
+) -> anyhow::Result<SealProofPolicy> {
+    seal_proof_policy(network_actors_version(network_version)?, proof)
+}
+
+/// Power quality multipliers and reward smoothing constants of an actors version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EconomicConstants {
+    /// Actors version the values were taken from.
+    pub actors_version: u64,
+    /// Quality multiplier of committed capacity.
+    pub quality_base_multiplier: BigInt,
+    /// Quality multiplier of unverified deal space. Unverified deals no longer carry a weight of
+    /// their own since v15, so this is only set for earlier versions.
+    pub deal_weight_multiplier: Option<BigInt>,
+    /// Quality multiplier of verified deal space.
+    pub verified_deal_weight_multiplier: BigInt,
+    /// Alpha parameter of the alpha-beta filters smoothing the reward and network power, in
+    /// fixed point with 128 fractional bits.
+    pub smoothing_alpha: BigInt,
+    /// Beta parameter of the alpha-beta filters, in the same representation as the alpha.
+    pub smoothing_beta: BigInt,
+}
+
+macro_rules! economic_constants {
+    ($version:ident, $actors_version:expr, $smooth:path) => {{
+        use crate::$version::{QUALITY_BASE_MULTIPLIER, VERIFIED_DEAL_WEIGHT_MULTIPLIER};
+        use $smooth::{DEFAULT_ALPHA, DEFAULT_BETA};
+        EconomicConstants {
+            actors_version: $actors_version,
+            quality_base_multiplier: QUALITY_BASE_MULTIPLIER.clone(),
+            deal_weight_multiplier: None,
+            verified_deal_weight_multiplier: VERIFIED_DEAL_WEIGHT_MULTIPLIER.clone(),
+            smoothing_alpha: DEFAULT_ALPHA.clone(),
+            smoothing_beta: DEFAULT_BETA.clone(),
+        }
+    }};
+    ($version:ident, $actors_version:expr, $smooth:path, deal_weight) => {{
+        EconomicConstants {
+            deal_weight_multiplier: Some(crate::$version::DEAL_WEIGHT_MULTIPLIER.clone()),
+            ..economic_constants!($version, $actors_version, $smooth)
+        }
+    }};
+}
+
+/// Returns the power quality multipliers and smoothing constants of the given actors version, so
+/// that models don't need to hardcode them.
+pub fn economic_constants(version: u64) -> anyhow::Result<EconomicConstants> {
+    Ok(match version {
+        8 => economic_constants!(v8, version, fvm_shared::smooth, deal_weight),
+        9 => economic_constants!(v9, version, fvm_shared::smooth, deal_weight),
+        10 => economic_constants!(v10, version, fvm_shared3::smooth, deal_weight),
+        11 => economic_constants!(v11, version, fvm_shared3::smooth, deal_weight),
+        12 => economic_constants!(v12, version, fvm_shared4::smooth, deal_weight),
+        13 => economic_constants!(v13, version, fvm_shared4::smooth, deal_weight),
+        14 => economic_constants!(
+            v14,
+            version,
+            fil_actors_shared::v14::reward::smooth,
+            deal_weight
+        ),
+        15 => economic_constants!(v15, version, fil_actors_shared::v15::reward::smooth),
+        16 => economic_constants!(v16, version, fil_actors_shared::v16::reward::smooth),
+        _ => bail!("unsupported actors version {version}"),
+    })
+}
+
+/// Returns the power quality multipliers and smoothing constants in effect at the given network
+/// version, see [`economic_constants`].
+pub fn economic_constants_at(network_version: NetworkVersion) -> anyhow::Result<EconomicConstants> {
+    economic_constants(network_actors_version(network_version)?)
+}
+
+fn network_actors_version(network_version: NetworkVersion) -> anyhow::Result<u64> {
+    actors_version(network_version)
+        .ok_or_else(|| anyhow!("unsupported network version {network_version}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_actors_shared::v16::network::EPOCHS_IN_DAY;
+
+    #[test]
+    fn test_seal_proof_policy_interactive() {
+        let p = seal_proof_policy_at(
+            NetworkVersion::V21,
+            RegisteredSealProof::StackedDRG32GiBV1P1,
+        )
+        .unwrap();
+        assert_eq!(p.actors_version, 12);
+        assert!(p.can_pre_commit);
+        assert!(!p.can_prove_commit_ni);
+        assert_eq!(
+            p.max_prove_commit_duration,
+            Some(30 * EPOCHS_IN_DAY + p.pre_commit_challenge_delay)
+        );
+        assert_eq!(p.max_prove_commit_ni_randomness_lookback, None);
+    }
+
+    #[test]
+    fn test_seal_proof_policy_ni() {
+        let proof = RegisteredSealProof::StackedDRG32GiBV1P2_Feat_NiPoRep;
+
+        let before = seal_proof_policy_at(NetworkVersion::V22, proof).unwrap();
+        assert!(!before.can_prove_commit_ni);
+        assert_eq!(before.sector_maximum_lifetime, None);
+
+        let after = seal_proof_policy_at(NetworkVersion::V23, proof).unwrap();
+        assert!(after.can_prove_commit_ni);
+        assert!(after.sector_maximum_lifetime.is_some());
+        assert!(after.max_prove_commit_ni_randomness_lookback.is_some());
+    }
+
+    #[test]
+    fn test_seal_proof_policy_nv19() {
+        let p = seal_proof_policy_at(
+            NetworkVersion::V19,
+            RegisteredSealProof::StackedDRG32GiBV1P1,
+        )
+        .unwrap();
+        assert_eq!(p.actors_version, 11);
+        assert!(p.can_pre_commit);
+    }
+
+    #[test]
+    fn test_seal_proof_policy_unsupported_network_version() {
+        assert!(seal_proof_policy_at(
+            NetworkVersion::V15,
+            RegisteredSealProof::StackedDRG32GiBV1P1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_economic_constants() {
+        let v12 = economic_constants_at(NetworkVersion::V21).unwrap();
+        assert_eq!(v12.actors_version, 12);
+        assert_eq!(v12.quality_base_multiplier, BigInt::from(10));
+        assert_eq!(v12.deal_weight_multiplier, Some(BigInt::from(10)));
+        assert_eq!(v12.verified_deal_weight_multiplier, BigInt::from(100));
+
+        let v16 = economic_constants_at(NetworkVersion::V25).unwrap();
+        assert_eq!(v16.deal_weight_multiplier, None);
+        assert_eq!(v16.smoothing_alpha, v12.smoothing_alpha);
+        assert_eq!(v16.smoothing_beta, v12.smoothing_beta);
+
+        let v11 = economic_constants_at(NetworkVersion::V19).unwrap();
+        assert_eq!(v11.actors_version, 11);
+        assert_eq!(v11.deal_weight_multiplier, Some(BigInt::from(10)));
+
+        assert!(economic_constants_at(NetworkVersion::V15).is_err());
+    }
+
+    #[test]
+    fn test_actors_version_entry_points() {
+        let proof = RegisteredSealProof::StackedDRG32GiBV1P1;
+        assert_eq!(
+            seal_proof_policy(11, proof).unwrap(),
+            seal_proof_policy_at(NetworkVersion::V20, proof).unwrap()
+        );
+        assert_eq!(
+            economic_constants(16).unwrap(),
+            economic_constants_at(NetworkVersion::V25).unwrap()
+        );
+        assert!(seal_proof_policy(7, proof).is_err());
+        assert!(economic_constants(17).is_err());
+    }
+}
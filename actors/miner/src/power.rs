@@ -0,0 +1,151 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::iter::Sum;
+use std::ops;
+
+use fvm_shared4::sector::StoragePower;
+use num_traits::Zero;
+
+/// Raw and quality-adjusted power, independent of the actors version. The `PowerPair`s of all
+/// versions convert into it, so power from states of several versions can be summed and compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PowerPair {
+    pub raw: StoragePower,
+    pub qa: StoragePower,
+}
+
+impl PowerPair {
+    pub fn new(raw: StoragePower, qa: StoragePower) -> Self {
+        Self { raw, qa }
+    }
+
+    pub fn zero() -> Self {
+        Default::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero() && self.qa.is_zero()
+    }
+}
+
+impl ops::Add for &PowerPair {
+    type Output = PowerPair;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        PowerPair {
+            raw: &self.raw + &rhs.raw,
+            qa: &self.qa + &rhs.qa,
+        }
+    }
+}
+
+impl ops::Add for PowerPair {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl ops::AddAssign<&Self> for PowerPair {
+    fn add_assign(&mut self, rhs: &Self) {
+        self.raw += &rhs.raw;
+        self.qa += &rhs.qa;
+    }
+}
+
+impl ops::Sub for &PowerPair {
+    type Output = PowerPair;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        PowerPair {
+            raw: &self.raw - &rhs.raw,
+            qa: &self.qa - &rhs.qa,
+        }
+    }
+}
+
+impl ops::Sub for PowerPair {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        &self - &rhs
+    }
+}
+
+impl ops::SubAssign<&Self> for PowerPair {
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.raw -= &rhs.raw;
+        self.qa -= &rhs.qa;
+    }
+}
+
+impl ops::Neg for PowerPair {
+    type Output = PowerPair;
+
+    fn neg(self) -> Self::Output {
+        PowerPair {
+            raw: -self.raw,
+            qa: -self.qa,
+        }
+    }
+}
+
+impl Sum for PowerPair {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |total, power| total + power)
+    }
+}
+
+impl<'a> Sum<&'a PowerPair> for PowerPair {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |mut total, power| {
+            total += power;
+            total
+        })
+    }
+}
+
+macro_rules! from_versioned {
+    ($($module:ident),*) => {
+        $(
+            impl From<&crate::$module::PowerPair> for PowerPair {
+                fn from(power: &crate::$module::PowerPair) -> Self {
+                    Self {
+                        raw: power.raw.clone(),
+                        qa: power.qa.clone(),
+                    }
+                }
+            }
+
+            impl From<crate::$module::PowerPair> for PowerPair {
+                fn from(power: crate::$module::PowerPair) -> Self {
+                    Self {
+                        raw: power.raw,
+                        qa: power.qa,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_versioned!(v8, v9, v10, v11, v12, v13, v14, v15, v16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_pair() {
+        let pair = |raw: i64, qa: i64| PowerPair::new(raw.into(), qa.into());
+        let v8 = crate::v8::PowerPair::new(1.into(), 10.into());
+        let v16 = crate::v16::PowerPair::new(2.into(), 20.into());
+
+        let total: PowerPair = [PowerPair::from(&v8), v16.into()].iter().sum();
+        assert_eq!(total, pair(3, 30));
+        assert_eq!(&total - &pair(3, 30), PowerPair::zero());
+        assert!((total.clone() + -total).is_zero());
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{bail, Context};
+use cid::Cid;
+use fil_actors_shared::state_check::StateTree;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::address::Address;
+use fvm_shared4::{ActorID, HAMT_BIT_WIDTH};
+
+use crate::power::PowerPair;
+
+const POWER_ACTOR_ID: ActorID = 4;
+
+/// Power of a miner in a state tree, as claimed in the power actor and as tracked by the
+/// partitions of the miner.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MinerPower {
+    /// Claim in the power actor, `None` if the miner has none.
+    pub claim: Option<PowerPair>,
+    pub live: PowerPair,
+    pub faulty: PowerPair,
+    pub unproven: PowerPair,
+}
+
+impl MinerPower {
+    /// Returns the power of the proven, non-faulty sectors, which the claim should match.
+    pub fn active(&self) -> PowerPair {
+        &(&self.live - &self.faulty) - &self.unproven
+    }
+}
+
+/// The power of a miner in two consecutive state trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerChange {
+    pub miner: ActorID,
+    pub old: MinerPower,
+    pub new: MinerPower,
+}
+
+impl PowerChange {
+    pub fn claim_delta(&self) -> PowerPair {
+        let old = self.old.claim.clone().unwrap_or_default();
+        &self.new.claim.clone().unwrap_or_default() - &old
+    }
+
+    /// Change of live power, from activations, terminations, expirations and sector updates.
+    pub fn live_delta(&self) -> PowerPair {
+        &self.new.live - &self.old.live
+    }
+
+    /// Change of faulty power, from declared or detected faults and recoveries.
+    pub fn faulty_delta(&self) -> PowerPair {
+        &self.new.faulty - &self.old.faulty
+    }
+
+    /// Change of unproven power, from new sectors and sectors proven for the first time.
+    pub fn unproven_delta(&self) -> PowerPair {
+        &self.new.unproven - &self.old.unproven
+    }
+
+    /// Returns the part of the claim change which is not explained by the changes of the
+    /// partitions, zero if the claim followed them.
+    pub fn unexplained(&self) -> PowerPair {
+        &self.claim_delta() - &(&self.new.active() - &self.old.active())
+    }
+
+    pub fn is_explained(&self) -> bool {
+        self.unexplained().is_zero()
+    }
+}
+
+/// Outcome of [`verify_power_continuity`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PowerContinuityReport {
+    /// Miners whose claim or partition power changed.
+    pub changes: Vec<PowerChange>,
+}
+
+impl PowerContinuityReport {
+    /// Returns the changes whose claim does not follow the partitions of the miner.
+    pub fn unexplained(&self) -> impl Iterator<Item = &PowerChange> {
+        self.changes.iter().filter(|change| !change.is_explained())
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.unexplained().next().is_none()
+    }
+}
+
+/// Verifies that the power claims of the miners changed along with their partitions between
+/// two consecutive state trees, of the given actors versions.
+///
+/// The claim of a miner in the power actor must move by as much as the active power of its
+/// partitions. The live, faulty and unproven power deltas of each change tell which kind of
+/// operation moved the power; a claim moving on its own, or partitions moving without the
+/// claim, is an unexplained jump. Only miners whose claim or actor state changed are loaded.
+pub fn verify_power_continuity<BS: Blockstore>(
+    store: &BS,
+    old_root: &Cid,
+    old_version: u64,
+    new_root: &Cid,
+    new_version: u64,
+) -> anyhow::Result<PowerContinuityReport> {
+    let old = StateTree::load(store, old_root).context("failed to load old state tree")?;
+    let new = StateTree::load(store, new_root).context("failed to load new state tree")?;
+    let old_claims = load_claims(store, &old, old_version).context("failed to load old claims")?;
+    let new_claims = load_claims(store, &new, new_version).context("failed to load new claims")?;
+
+    let mut report = PowerContinuityReport::default();
+    let miners: BTreeSet<_> = old_claims.keys().chain(new_claims.keys()).collect();
+    for &miner in miners {
+        let (old_actor, new_actor) = (old.actor(miner)?, new.actor(miner)?);
+        let (old_claim, new_claim) = (old_claims.get(&miner), new_claims.get(&miner));
+        if old_claim == new_claim && old_actor == new_actor {
+            continue;
+        }
+        let mut change = PowerChange {
+            miner,
+            old: MinerPower {
+                claim: old_claim.cloned(),
+                ..Default::default()
+            },
+            new: MinerPower {
+                claim: new_claim.cloned(),
+                ..Default::default()
+            },
+        };
+        if let Some(actor) = old_actor.filter(|actor| old.is_miner(actor)) {
+            load_partition_power(store, old_version, &actor.state, &mut change.old)
+                .with_context(|| format!("failed to load old partitions of miner f0{miner}"))?;
+        }
+        if let Some(actor) = new_actor.filter(|actor| new.is_miner(actor)) {
+            load_partition_power(store, new_version, &actor.state, &mut change.new)
+                .with_context(|| format!("failed to load new partitions of miner f0{miner}"))?;
+        }
+        if change.old != change.new {
+            report.changes.push(change);
+        }
+    }
+    Ok(report)
+}
+
+fn load_claims<BS: Blockstore>(
+    store: &BS,
+    tree: &StateTree<BS>,
+    version: u64,
+) -> anyhow::Result<BTreeMap<ActorID, PowerPair>> {
+    let power = tree
+        .actor(POWER_ACTOR_ID)?
+        .context("power actor not found")?;
+    versioned_power_claims(store, version, &power.state)
+}
+
+macro_rules! versioned {
+    ($($version:literal => $module:ident;)*) => {
+        fn versioned_power_claims<BS: Blockstore>(
+            store: &BS,
+            version: u64,
+            state: &Cid,
+        ) -> anyhow::Result<BTreeMap<ActorID, PowerPair>> {
+            match version {
+                $($version => {
+                    let st: fil_actor_power_state::$module::State = store
+                        .get_cbor(state)?
+                        .with_context(|| format!("power state {state} not found"))?;
+                    let claims = Hamt::<_, fil_actor_power_state::$module::Claim, BytesKey>::load_with_bit_width(
+                        &st.claims,
+                        store,
+                        HAMT_BIT_WIDTH,
+                    )?;
+                    let mut result = BTreeMap::new();
+                    for entry in claims.iter() {
+                        let (key, claim) = entry?;
+                        let power = PowerPair {
+                            raw: claim.raw_byte_power.clone(),
+                            qa: claim.quality_adj_power.clone(),
+                        };
+                        result.insert(Address::from_bytes(key)?.id()?, power);
+                    }
+                    Ok(result)
+                })*
+                _ => bail!("unsupported power actors version {version}"),
+            }
+        }
+
+        fn load_partition_power<BS: Blockstore>(
+            store: &BS,
+            version: u64,
+            state: &Cid,
+            power: &mut MinerPower,
+        ) -> anyhow::Result<()> {
+            match version {
+                $($version => {
+                    let st: crate::$module::State = store
+                        .get_cbor(state)?
+                        .with_context(|| format!("miner state {state} not found"))?;
+                    for due in st.load_deadlines(store)?.due {
+                        let deadline: crate::$module::Deadline = store
+                            .get_cbor(&due)?
+                            .with_context(|| format!("deadline {due} not found"))?;
+                        deadline.for_each(store, |_, partition| {
+                            power.live += &PowerPair::from(&partition.live_power);
+                            power.faulty += &PowerPair::from(&partition.faulty_power);
+                            power.unproven += &PowerPair::from(&partition.unproven_power);
+                            Ok(())
+                        })?;
+                    }
+                    Ok(())
+                })*
+                _ => bail!("unsupported miner actors version {version}"),
+            }
+        }
+    };
+}
+
+versioned! {
+    8 => v8;
+    9 => v9;
+    10 => v10;
+    11 => v11;
+    12 => v12;
+    13 => v13;
+    14 => v14;
+    15 => v15;
+    16 => v16;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::{Deadline, MinerInfo, Partition, State};
+    use fil_actor_power_state::v16 as power;
+    use fil_actors_shared::v16::runtime::Policy;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::IPLD_RAW;
+    use fvm_shared4::sector::{RegisteredPoStProof, StoragePower};
+    use ipld_core::ipld::Ipld;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    const MINER: ActorID = 1000;
+
+    fn code(name: &str) -> Cid {
+        Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(name.as_bytes()))
+    }
+
+    fn pair(power: u64) -> PowerPair {
+        PowerPair::new(StoragePower::from(power), StoragePower::from(power))
+    }
+
+    /// Builds a state tree with a miner having the given claim and live power in one partition.
+    fn state_tree(store: &MemoryBlockstore, claim: u64, live: u64) -> Cid {
+        let policy = Policy::default();
+        let info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let info = store.put_cbor(&info, Code::Blake2b256).unwrap();
+        let mut miner = State::new(&policy, store, info, 0, 0).unwrap();
+        let mut partition = Partition::new(store).unwrap();
+        partition.live_power = crate::v16::PowerPair::new(live.into(), live.into());
+        let mut deadline = Deadline::new(store).unwrap();
+        let mut partitions = deadline.partitions_amt(store).unwrap();
+        partitions.set(0, partition).unwrap();
+        deadline.partitions = partitions.flush().unwrap();
+        let mut deadlines = miner.load_deadlines(store).unwrap();
+        deadlines
+            .update_deadline(&policy, store, 0, &deadline)
+            .unwrap();
+        miner.save_deadlines(store, deadlines).unwrap();
+        let miner = store.put_cbor(&miner, Code::Blake2b256).unwrap();
+
+        let mut power = power::State::new(store).unwrap();
+        let mut claims = power.load_claims(store).unwrap();
+        let claim = power::Claim {
+            window_post_proof_type: RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+            raw_byte_power: StoragePower::from(claim),
+            quality_adj_power: StoragePower::from(claim),
+        };
+        power::set_claim(&mut claims, &Address::new_id(MINER), claim).unwrap();
+        power.save_claims(&mut claims).unwrap();
+        let power = store.put_cbor(&power, Code::Blake2b256).unwrap();
+
+        let manifest: Vec<(String, Cid)> = ["system", "storagepower", "storageminer"]
+            .iter()
+            .map(|name| (name.to_string(), code(name)))
+            .collect();
+        let manifest = store.put_cbor(&manifest, Code::Blake2b256).unwrap();
+        let system = store.put_cbor(&(manifest,), Code::Blake2b256).unwrap();
+        let mut actors = Hamt::<_, Ipld, BytesKey>::new_with_bit_width(store, HAMT_BIT_WIDTH);
+        for (id, name, state) in [
+            (0, "system", system),
+            (POWER_ACTOR_ID, "storagepower", power),
+            (MINER, "storageminer", miner),
+        ] {
+            let actor = Ipld::List(vec![
+                Ipld::Link(code(name)),
+                Ipld::Link(state),
+                Ipld::Integer(0),
+                Ipld::Bytes(vec![]),
+            ]);
+            actors
+                .set(BytesKey(Address::new_id(id).to_bytes()), actor)
+                .unwrap();
+        }
+        let actors = actors.flush().unwrap();
+        let info = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        store
+            .put_cbor(&(5u64, actors, info), Code::Blake2b256)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_verify_power_continuity() {
+        let store = MemoryBlockstore::new();
+        let old = state_tree(&store, 10, 10);
+
+        let report = verify_power_continuity(&store, &old, 16, &old, 16).unwrap();
+        assert!(report.changes.is_empty());
+
+        let new = state_tree(&store, 30, 30);
+        let report = verify_power_continuity(&store, &old, 16, &new, 16).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.changes[0].live_delta(), pair(20));
+
+        // The claim moved by 20 while the partitions only gained 5.
+        let new = state_tree(&store, 30, 15);
+        let report = verify_power_continuity(&store, &old, 16, &new, 16).unwrap();
+        let unexplained: Vec<_> = report.unexplained().collect();
+        assert_eq!(unexplained.len(), 1);
+        assert_eq!(unexplained[0].miner, MINER);
+        assert_eq!(unexplained[0].claim_delta(), pair(20));
+        assert_eq!(unexplained[0].unexplained(), pair(15));
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use cid::Cid;
+use fil_actors_shared::ids::{DealID, SectorNumber};
+use fil_actors_shared::multimap::uint_key;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
+use fvm_shared4::sector::RegisteredSealProof;
+use fvm_shared4::HAMT_BIT_WIDTH;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::v16::CompactCommD;
+use crate::versioned::VersionedState;
+
+/// A pre-committed sector, with the fields of `SectorPreCommitOnChainInfo` common to all
+/// versions and version-agnostic types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreCommittedSector {
+    pub seal_proof: RegisteredSealProof,
+    pub sector_number: SectorNumber,
+    /// `CommR`
+    pub sealed_cid: Cid,
+    pub seal_rand_epoch: ChainEpoch,
+    pub deal_ids: Vec<DealID>,
+    pub expiration: ChainEpoch,
+    /// `CommD`, `None` for v8 pre-commits, which did not record it.
+    pub unsealed_cid: Option<CompactCommD>,
+    pub pre_commit_deposit: TokenAmount,
+    pub pre_commit_epoch: ChainEpoch,
+}
+
+impl From<crate::v8::SectorPreCommitOnChainInfo> for PreCommittedSector {
+    fn from(precommit: crate::v8::SectorPreCommitOnChainInfo) -> Self {
+        let info = precommit.info;
+        Self {
+            seal_proof: RegisteredSealProof::from(i64::from(info.seal_proof)),
+            sector_number: info.sector_number.into(),
+            sealed_cid: info.sealed_cid,
+            seal_rand_epoch: info.seal_rand_epoch,
+            deal_ids: info.deal_ids.into_iter().map(DealID).collect(),
+            expiration: info.expiration,
+            unsealed_cid: None,
+            pre_commit_deposit: TokenAmount::from_atto(precommit.pre_commit_deposit.atto().clone()),
+            pre_commit_epoch: precommit.pre_commit_epoch,
+        }
+    }
+}
+
+impl From<crate::v16::SectorPreCommitOnChainInfo> for PreCommittedSector {
+    fn from(precommit: crate::v16::SectorPreCommitOnChainInfo) -> Self {
+        let info = precommit.info;
+        Self {
+            seal_proof: info.seal_proof,
+            sector_number: info.sector_number.into(),
+            sealed_cid: info.sealed_cid,
+            seal_rand_epoch: info.seal_rand_epoch,
+            deal_ids: info.deal_ids.into_iter().map(DealID).collect(),
+            expiration: info.expiration,
+            unsealed_cid: Some(info.unsealed_cid),
+            pre_commit_deposit: precommit.pre_commit_deposit,
+            pre_commit_epoch: precommit.pre_commit_epoch,
+        }
+    }
+}
+
+impl VersionedState {
+    /// Returns a pre-committed sector, if it has not been proven or expired yet.
+    ///
+    /// Pre-commits have had the same layout since v9, when v8's replaced capacity and deal
+    /// weights were dropped and `CommD` was added, so v9 and later pre-commits decode as v16 ones.
+    pub fn get_precommitted_sector<BS: Blockstore>(
+        &self,
+        store: &BS,
+        sector_number: SectorNumber,
+    ) -> anyhow::Result<Option<PreCommittedSector>> {
+        let root = self.normalize()?.pre_committed_sectors;
+        let key = BytesKey(uint_key(sector_number.0));
+        Ok(match self {
+            Self::V8(_) => {
+                load_precommits::<_, crate::v8::SectorPreCommitOnChainInfo>(store, &root)?
+                    .get(&key)?
+                    .cloned()
+                    .map(Into::into)
+            }
+            _ => load_precommits::<_, crate::v16::SectorPreCommitOnChainInfo>(store, &root)?
+                .get(&key)?
+                .cloned()
+                .map(Into::into),
+        })
+    }
+
+    /// Runs a function over the pre-committed sectors, in no particular order.
+    pub fn for_each_precommitted_sector<BS, F>(&self, store: &BS, mut f: F) -> anyhow::Result<()>
+    where
+        BS: Blockstore,
+        F: FnMut(PreCommittedSector) -> anyhow::Result<()>,
+    {
+        let root = self.normalize()?.pre_committed_sectors;
+        match self {
+            Self::V8(_) => {
+                load_precommits::<_, crate::v8::SectorPreCommitOnChainInfo>(store, &root)?
+                    .for_each(|_, precommit| f(precommit.clone().into()))?
+            }
+            _ => load_precommits::<_, crate::v16::SectorPreCommitOnChainInfo>(store, &root)?
+                .for_each(|_, precommit| f(precommit.clone().into()))?,
+        }
+        Ok(())
+    }
+}
+
+fn load_precommits<'bs, BS, V>(
+    store: &'bs BS,
+    root: &Cid,
+) -> anyhow::Result<Hamt<&'bs BS, V, BytesKey>>
+where
+    BS: Blockstore,
+    V: DeserializeOwned + Serialize,
+{
+    Hamt::load_with_bit_width(root, store, HAMT_BIT_WIDTH)
+        .with_context(|| format!("failed to load pre-commits {root}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::{MinerInfo, SectorPreCommitInfo, SectorPreCommitOnChainInfo, State};
+    use fil_actors_shared::v16::runtime::Policy;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_shared4::sector::RegisteredPoStProof;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_precommitted_sectors() {
+        let store = MemoryBlockstore::new();
+        let info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let info = store.put_cbor(&info, Code::Blake2b256).unwrap();
+        let mut st = State::new(&Policy::default(), &store, info, 0, 0).unwrap();
+        let precommit = |sector_number| SectorPreCommitOnChainInfo {
+            info: SectorPreCommitInfo {
+                seal_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+                sector_number,
+                sealed_cid: Cid::default(),
+                seal_rand_epoch: 10,
+                deal_ids: vec![sector_number],
+                expiration: 1000,
+                unsealed_cid: CompactCommD::empty(),
+            },
+            pre_commit_deposit: TokenAmount::from_atto(sector_number),
+            pre_commit_epoch: 20,
+        };
+        st.put_precommitted_sectors(&store, vec![precommit(3), precommit(5)])
+            .unwrap();
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+        let state = VersionedState::load(&store, 16, &root).unwrap();
+
+        assert_eq!(
+            state.get_precommitted_sector(&store, SectorNumber(5)).unwrap(),
+            Some(precommit(5).into())
+        );
+        assert_eq!(
+            state.get_precommitted_sector(&store, SectorNumber(4)).unwrap(),
+            None
+        );
+        let mut sectors = Vec::new();
+        state
+            .for_each_precommitted_sector(&store, |precommit| {
+                sectors.push(precommit.sector_number);
+                Ok(())
+            })
+            .unwrap();
+        sectors.sort();
+        assert_eq!(sectors, [SectorNumber(3), SectorNumber(5)]);
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context};
+use cid::Cid;
+use fil_actors_shared::diff::{diff_amt, Change};
+use fil_actors_shared::ids::SectorNumber;
+use fvm_ipld_amt::Amt;
+use fvm_ipld_bitfield::BitField;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared4::clock::ChainEpoch;
+use ipld_core::ipld::Ipld;
+use serde::de::DeserializeOwned;
+
+/// Changes of the sector table of a miner since the previous recorded epoch.
+///
+/// Sector records are kept as the DAG-CBOR values of the `SectorOnChainInfo` of the actors version
+/// at that epoch, so histories spanning network upgrades can be recorded. The changes of a
+/// typical epoch are a handful of sectors, while a full dump is the whole table.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, PartialEq)]
+pub struct SectorTableDelta {
+    pub epoch: ChainEpoch,
+    /// Records of the sectors added or modified, in sector number order.
+    pub upserted: Vec<(SectorNumber, Ipld)>,
+    pub removed: BitField,
+}
+
+/// Records the sector table of a miner at successive epochs as deltas, see
+/// [`SectorTableDelta`].
+#[derive(Debug, Clone, Default)]
+pub struct SectorHistoryEncoder {
+    last: Option<(ChainEpoch, Cid)>,
+    deltas: Vec<SectorTableDelta>,
+}
+
+impl SectorHistoryEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the sector table whose AMT root is `sectors` at the given epoch, which must be
+    /// after the previously recorded one. An unchanged root is recorded as an empty delta without
+    /// loading the table.
+    pub fn record<BS: Blockstore>(
+        &mut self,
+        store: &BS,
+        epoch: ChainEpoch,
+        sectors: &Cid,
+    ) -> anyhow::Result<&SectorTableDelta> {
+        let mut delta = SectorTableDelta {
+            epoch,
+            upserted: Vec::new(),
+            removed: BitField::new(),
+        };
+        match self.last {
+            Some((last_epoch, _)) if epoch <= last_epoch => {
+                bail!("epoch {epoch} is not after the last recorded epoch {last_epoch}")
+            }
+            Some((_, last)) => {
+                for change in diff_amt(store, &last, sectors)? {
+                    match change {
+                        Change::Added(number, record) | Change::Modified(number, _, record) => {
+                            delta.upserted.push((SectorNumber(number), record))
+                        }
+                        Change::Removed(number, _) => delta.removed.set(number),
+                    }
+                }
+            }
+            None => {
+                let table = Amt::<Ipld, _>::load(sectors, store)
+                    .with_context(|| format!("failed to load sectors {sectors}"))?;
+                table.for_each(|number, record| {
+                    delta.upserted.push((SectorNumber(number), record.clone()));
+                    Ok(())
+                })?;
+            }
+        }
+        self.last = Some((epoch, *sectors));
+        self.deltas.push(delta);
+        Ok(self.deltas.last().expect("delta just pushed"))
+    }
+
+    /// Returns the recorded deltas, to be persisted.
+    pub fn finish(self) -> SectorHistory {
+        SectorHistory {
+            deltas: self.deltas,
+        }
+    }
+}
+
+/// Recorded deltas of a sector table, which reconstructs the table at any recorded epoch.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, Default, PartialEq)]
+pub struct SectorHistory {
+    /// Deltas in epoch order, the first one holding the whole table.
+    pub deltas: Vec<SectorTableDelta>,
+}
+
+impl SectorHistory {
+    /// Returns the undecoded sector records at the given epoch, which is the table of the last
+    /// recorded epoch at or before it.
+    pub fn table_at(&self, epoch: ChainEpoch) -> anyhow::Result<BTreeMap<SectorNumber, Ipld>> {
+        match self.deltas.first() {
+            Some(first) if first.epoch <= epoch => {}
+            _ => bail!("no sector table recorded at or before epoch {epoch}"),
+        }
+        let mut table = BTreeMap::new();
+        for delta in self.deltas.iter().take_while(|delta| delta.epoch <= epoch) {
+            for number in delta.removed.iter() {
+                table.remove(&SectorNumber(number));
+            }
+            table.extend(delta.upserted.iter().cloned());
+        }
+        Ok(table)
+    }
+
+    /// Returns the sectors at the given epoch decoded as the `SectorOnChainInfo` of a version,
+    /// see [`SectorHistory::table_at`].
+    pub fn sectors_at<T: DeserializeOwned>(
+        &self,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<BTreeMap<SectorNumber, T>> {
+        self.table_at(epoch)?
+            .into_iter()
+            .map(|(number, record)| {
+                let info = ipld_core::serde::from_ipld(record)
+                    .with_context(|| format!("failed to decode sector {number}"))?;
+                Ok((number, info))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::{SectorOnChainInfo, SECTORS_AMT_BITWIDTH};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::{from_slice, to_vec};
+
+    fn sector(number: u64, expiration: ChainEpoch) -> SectorOnChainInfo {
+        SectorOnChainInfo {
+            sector_number: number,
+            expiration,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sector_history() {
+        let store = MemoryBlockstore::new();
+        let mut sectors = Amt::new_with_bit_width(&store, SECTORS_AMT_BITWIDTH);
+        for number in 0..100 {
+            sectors.set(number, sector(number, 1000)).unwrap();
+        }
+        let mut encoder = SectorHistoryEncoder::new();
+        let delta = encoder
+            .record(&store, 10, &sectors.flush().unwrap())
+            .unwrap();
+        assert_eq!(delta.upserted.len(), 100);
+
+        // Extend a sector, add one and remove one.
+        sectors.set(5, sector(5, 2000)).unwrap();
+        sectors.set(100, sector(100, 1500)).unwrap();
+        sectors.delete(7).unwrap();
+        let delta = encoder
+            .record(&store, 20, &sectors.flush().unwrap())
+            .unwrap();
+        assert_eq!(delta.upserted.len(), 2);
+        assert_eq!(delta.removed.iter().collect::<Vec<_>>(), vec![7]);
+
+        let root = sectors.flush().unwrap();
+        assert!(encoder.record(&store, 20, &root).is_err());
+        assert!(encoder
+            .record(&store, 30, &root)
+            .unwrap()
+            .upserted
+            .is_empty());
+
+        // The history survives a round trip through its encoding.
+        let history: SectorHistory = from_slice(&to_vec(&encoder.finish()).unwrap()).unwrap();
+        let at_15 = history.sectors_at::<SectorOnChainInfo>(15).unwrap();
+        assert_eq!(at_15.len(), 100);
+        assert_eq!(at_15[&SectorNumber(5)].expiration, 1000);
+        let at_30 = history.sectors_at::<SectorOnChainInfo>(30).unwrap();
+        assert_eq!(at_30.len(), 100);
+        assert_eq!(at_30[&SectorNumber(5)], sector(5, 2000));
+        assert!(!at_30.contains_key(&SectorNumber(7)));
+        assert!(history.table_at(9).is_err());
+    }
+}
@@ -1,9 +1,11 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::cmp;
 use std::collections::BTreeMap;
 use std::ops::AddAssign;
 
+use fil_actors_shared::v11::runtime::Policy;
 use fvm_ipld_bitfield::BitField;
 use fvm_shared3::clock::ChainEpoch;
 
@@ -50,3 +52,25 @@ impl TerminationResult {
         self.sectors.iter().map(|(&epoch, bf)| (epoch, bf))
     }
 }
+
+/// Early terminations recorded in a miner's state whose penalties are yet to be processed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PendingEarlyTerminations {
+    /// Number of deadlines with pending early terminations.
+    pub deadlines: u64,
+    /// Number of partitions with pending early terminations.
+    pub partitions: u64,
+    /// Number of early terminated sectors pending processing.
+    pub sectors: u64,
+}
+
+impl PendingEarlyTerminations {
+    /// Estimates the number of processing passes needed to settle all pending early
+    /// terminations. Each pass processes at most `addressed_partitions_max` partitions and
+    /// `addressed_sectors_max` sectors.
+    pub fn processing_passes(&self, policy: &Policy) -> u64 {
+        let partition_passes = self.partitions.div_ceil(policy.addressed_partitions_max);
+        let sector_passes = self.sectors.div_ceil(policy.addressed_sectors_max);
+        cmp::max(partition_passes, sector_passes)
+    }
+}
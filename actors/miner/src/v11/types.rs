@@ -4,7 +4,7 @@
 use cid::Cid;
 use fvm_ipld_bitfield::BitField;
 use fvm_ipld_encoding::tuple::*;
-use fvm_ipld_encoding::{strict_bytes, BytesDe};
+use fvm_ipld_encoding::{strict_bytes, BytesDe, RawBytes};
 use fvm_shared3::address::Address;
 use fvm_shared3::bigint::bigint_ser;
 use fvm_shared3::clock::ChainEpoch;
@@ -16,6 +16,7 @@ use fvm_shared3::sector::{
     SectorSize, StoragePower,
 };
 use fvm_shared3::smooth::FilterEstimate;
+use fvm_shared3::MethodNum;
 
 use fil_actors_shared::v11::DealWeight;
 
@@ -23,6 +24,7 @@ use super::commd::CompactCommD;
 use fil_actor_verifreg_state::v11::ClaimID;
 
 use super::beneficiary::*;
+use super::Method;
 
 pub type CronEvent = i64;
 
@@ -67,7 +69,7 @@ pub struct ChangeWorkerAddressParams {
     pub new_control_addresses: Vec<Address>,
 }
 
-#[derive(Serialize_tuple, Deserialize_tuple)]
+#[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
 #[serde(transparent)]
 pub struct ChangeOwnerAddressParams {
     pub new_owner: Address,
@@ -258,7 +260,7 @@ pub struct ReportConsensusFaultParams {
     pub header_extra: Vec<u8>,
 }
 
-#[derive(Clone, Serialize_tuple, Deserialize_tuple)]
+#[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
 pub struct WithdrawBalanceParams {
     pub amount_requested: TokenAmount,
 }
@@ -269,6 +271,20 @@ pub struct WithdrawBalanceReturn {
     pub amount_withdrawn: TokenAmount,
 }
 
+/// A `WithdrawBalance` message planned against a miner state, see
+/// [`State::plan_withdrawal`](crate::v11::State::plan_withdrawal).
+#[derive(Debug, Clone)]
+pub struct WithdrawalPlan {
+    /// Address the funds are sent to, the beneficiary.
+    pub to: Address,
+    /// Parameters requesting exactly the amount the message withdraws.
+    pub params: WithdrawBalanceParams,
+    /// The most the message could withdraw.
+    pub withdrawable: TokenAmount,
+    /// Fee debt the message repays and burns before withdrawing.
+    pub fee_debt_repaid: TokenAmount,
+}
+
 #[derive(Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct WorkerKeyChange {
     /// Must be an ID address
@@ -439,6 +455,30 @@ impl ChangeBeneficiaryParams {
     }
 }
 
+/// A validated `ChangeBeneficiary` message, see
+/// [`MinerInfo::propose_beneficiary_change`](crate::v11::MinerInfo::propose_beneficiary_change).
+#[derive(Debug, Clone)]
+pub struct BeneficiaryChangeMessage {
+    /// Address which must send the message.
+    pub from: Address,
+    pub params: ChangeBeneficiaryParams,
+    /// Addresses whose approval is still needed once the message is executed. The change takes
+    /// effect when this is empty.
+    pub remaining_approvals: Vec<Address>,
+}
+
+/// A validated `ChangeOwnerAddress` message, see
+/// [`MinerInfo::propose_owner_change`](crate::v11::MinerInfo::propose_owner_change).
+#[derive(Debug, Clone)]
+pub struct OwnerChangeMessage {
+    /// Address which must send the message.
+    pub from: Address,
+    pub params: ChangeOwnerAddressParams,
+    /// Address which must confirm the change by sending the same message, `None` once the
+    /// change takes effect.
+    pub remaining_confirmation: Option<Address>,
+}
+
 #[derive(Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct ActiveBeneficiary {
     pub beneficiary: Address,
@@ -496,3 +536,92 @@ pub struct GetPeerIDReturn {
 pub struct GetMultiaddrsReturn {
     pub multi_addrs: Vec<BytesDe>,
 }
+
+/// Methods of the miner actor which are removed by later versions, to decode and label historical
+/// messages. The discriminants are the method numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u64)]
+pub enum LegacyMethod {
+    PreCommitSector = Method::PreCommitSector as MethodNum,
+    ProveCommitSector = Method::ProveCommitSector as MethodNum,
+    ConfirmSectorProofsValid = Method::ConfirmSectorProofsValid as MethodNum,
+    ProveReplicaUpdates2 = Method::ProveReplicaUpdates2 as MethodNum,
+}
+
+/// Decoded parameters of a [`LegacyMethod`].
+pub enum LegacyMethodParams {
+    PreCommitSector(PreCommitSectorParams),
+    ProveCommitSector(ProveCommitSectorParams),
+    ConfirmSectorProofsValid(ConfirmSectorProofsParams),
+    ProveReplicaUpdates2(ProveReplicaUpdatesParams2),
+}
+
+impl LegacyMethod {
+    pub fn from_method_num(method: MethodNum) -> Option<Self> {
+        match method {
+            m if m == Self::PreCommitSector as MethodNum => Some(Self::PreCommitSector),
+            m if m == Self::ProveCommitSector as MethodNum => Some(Self::ProveCommitSector),
+            m if m == Self::ConfirmSectorProofsValid as MethodNum => {
+                Some(Self::ConfirmSectorProofsValid)
+            }
+            m if m == Self::ProveReplicaUpdates2 as MethodNum => Some(Self::ProveReplicaUpdates2),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PreCommitSector => "PreCommitSector",
+            Self::ProveCommitSector => "ProveCommitSector",
+            Self::ConfirmSectorProofsValid => "ConfirmSectorProofsValid",
+            Self::ProveReplicaUpdates2 => "ProveReplicaUpdates2",
+        }
+    }
+
+    /// Describes the effects of a successful call on the state.
+    pub fn state_effect(self) -> &'static str {
+        match self {
+            Self::PreCommitSector => {
+                "Records a sector pre-commitment and locks its pre-commit deposit from the \
+                 available balance."
+            }
+            Self::ProveCommitSector => {
+                "Submits the proof of replication of a pre-committed sector to the power actor for \
+                 batch verification. The sector is only activated by the `ConfirmSectorProofsValid` \
+                 callback at the end of the epoch."
+            }
+            Self::ConfirmSectorProofsValid => {
+                "Callback of the power actor with the pre-committed sectors whose proofs were \
+                 verified. It activates them: the pre-commit deposits are released, the initial \
+                 pledges are locked and the power of the sectors is added."
+            }
+            Self::ProveReplicaUpdates2 => {
+                "Upgrades committed capacity sectors with data (SnapDeals): activates the deals, \
+                 replaces the sealed CID of the sectors and updates their power. Returns the \
+                 sectors which were updated."
+            }
+        }
+    }
+
+    pub fn decode_params(self, params: &RawBytes) -> anyhow::Result<LegacyMethodParams> {
+        Ok(match self {
+            Self::PreCommitSector => LegacyMethodParams::PreCommitSector(params.deserialize()?),
+            Self::ProveCommitSector => LegacyMethodParams::ProveCommitSector(params.deserialize()?),
+            Self::ConfirmSectorProofsValid => {
+                LegacyMethodParams::ConfirmSectorProofsValid(params.deserialize()?)
+            }
+            Self::ProveReplicaUpdates2 => {
+                LegacyMethodParams::ProveReplicaUpdates2(params.deserialize()?)
+            }
+        })
+    }
+
+    /// Decodes the return value of a call, for the methods returning one. `ProveReplicaUpdates2`
+    /// returns the sectors which were updated.
+    pub fn decode_return(self, ret: &RawBytes) -> anyhow::Result<Option<BitField>> {
+        Ok(match self {
+            Self::ProveReplicaUpdates2 => Some(ret.deserialize()?),
+            _ => None,
+        })
+    }
+}
@@ -7,6 +7,7 @@ use std::ops::{self, Neg};
 use anyhow::{anyhow, Context};
 use cid::Cid;
 use fil_actors_shared::actor_error_v12;
+use fil_actors_shared::overlay::OverlayStore;
 use fil_actors_shared::v12::runtime::Policy;
 use fil_actors_shared::v12::{ActorDowncast, Array};
 use fvm_ipld_bitfield::BitField;
@@ -450,6 +451,26 @@ impl Partition {
         Ok((power_delta, pledge_delta))
     }
 
+    /// Computes the power and pledge deltas [`Partition::replace_sectors`] would produce, without
+    /// modifying the partition nor the store: the nodes of the updated expiration queue are
+    /// written to an [`OverlayStore`], which is discarded.
+    pub fn simulate_replace_sectors<BS: Blockstore>(
+        &self,
+        store: &BS,
+        old_sectors: &[SectorOnChainInfo],
+        new_sectors: &[SectorOnChainInfo],
+        sector_size: SectorSize,
+        quant: QuantSpec,
+    ) -> anyhow::Result<(PowerPair, TokenAmount)> {
+        self.clone().replace_sectors(
+            &OverlayStore::new(store),
+            old_sectors,
+            new_sectors,
+            sector_size,
+            quant,
+        )
+    }
+
     /// Record the epoch of any sectors expiring early, for termination fee calculation later.
     pub fn record_early_termination<BS: Blockstore>(
         &mut self,
@@ -473,6 +494,18 @@ impl Partition {
         Ok(())
     }
 
+    /// Returns the number of early terminated sectors whose termination is yet to be processed.
+    pub fn early_terminated_sector_count<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<u64> {
+        let early_termination_queue =
+            BitFieldQueue::new(store, &self.early_terminated, NO_QUANTIZATION)?;
+        let mut count = 0;
+        early_termination_queue.amt.for_each(|_, sectors| {
+            count += sectors.len();
+            Ok(())
+        })?;
+        Ok(count)
+    }
+
     /// Marks a collection of sectors as terminated.
     /// The sectors are removed from Faults and Recoveries.
     /// The epoch of termination is recorded for future termination fee calculation.
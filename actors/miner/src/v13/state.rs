@@ -3,15 +3,17 @@
 
 use std::borrow::Borrow;
 use std::cmp;
-use std::ops::Neg;
+use std::collections::BTreeMap;
+use std::ops::{ControlFlow, Neg, RangeBounds};
 
 use anyhow::{anyhow, Error};
 use cid::Cid;
 use fil_actors_shared::actor_error_v13;
+use fil_actors_shared::page::{for_each_amt_in_range, list_amt_page, Page, PageCursor};
 use fil_actors_shared::v13::runtime::Policy;
 use fil_actors_shared::v13::{
-    make_empty_map, make_map_with_root_and_bitwidth, u64_key, ActorDowncast, ActorError, Array,
-    AsActorError,
+    make_empty_map, make_map_with_root_and_bitwidth, parse_uint_key, u64_key, ActorDowncast,
+    ActorError, Array, AsActorError,
 };
 use fvm_ipld_amt::Error as AmtError;
 use fvm_ipld_bitfield::BitField;
@@ -34,10 +36,11 @@ use super::beneficiary::*;
 use super::deadlines::new_deadline_info;
 use super::policy::*;
 use super::types::*;
+use super::Method;
 use super::{
     assign_deadlines, deadline_is_mutable, new_deadline_info_from_offset_and_epoch,
     quant_spec_for_deadline, BitFieldQueue, Deadline, DeadlineInfo, DeadlineSectorMap, Deadlines,
-    PowerPair, Sectors, TerminationResult, VestingFunds,
+    PendingEarlyTerminations, PowerPair, Sectors, TerminationResult, VestingFunds,
 };
 
 const PRECOMMIT_EXPIRY_AMT_BITWIDTH: u32 = 6;
@@ -218,6 +221,23 @@ impl State {
         }
     }
 
+    /// Returns the active beneficiary and the pending beneficiary change, if any, as returned by
+    /// the `GetBeneficiary` method. The approval flags of the pending change tell which of the
+    /// current beneficiary and the nominee have yet to approve it.
+    pub fn active_beneficiary<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<GetBeneficiaryReturn> {
+        let info = self.get_info(store)?;
+        Ok(GetBeneficiaryReturn {
+            active: ActiveBeneficiary {
+                beneficiary: info.beneficiary,
+                term: info.beneficiary_term,
+            },
+            proposed: info.pending_beneficiary_term,
+        })
+    }
+
     pub fn save_info<BS: Blockstore>(
         &mut self,
         store: &BS,
@@ -374,6 +394,24 @@ impl State {
         Ok(result)
     }
 
+    /// Returns the deposit of each pre-committed sector, which add up to `pre_commit_deposits`.
+    pub fn pre_commit_deposit_breakdown<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<BTreeMap<SectorNumber, TokenAmount>> {
+        let precommitted = make_map_with_root_and_bitwidth::<_, SectorPreCommitOnChainInfo>(
+            &self.pre_committed_sectors,
+            store,
+            HAMT_BIT_WIDTH,
+        )?;
+        let mut deposits = BTreeMap::new();
+        precommitted.for_each(|key, precommit| {
+            deposits.insert(parse_uint_key(key)?, precommit.pre_commit_deposit.clone());
+            Ok(())
+        })?;
+        Ok(deposits)
+    }
+
     pub fn delete_precommitted_sectors<BS: Blockstore>(
         &mut self,
         store: &BS,
@@ -457,15 +495,71 @@ impl State {
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn for_each_sector<BS: Blockstore, F>(&self, store: &BS, mut f: F) -> anyhow::Result<()>
     where
         F: FnMut(&SectorOnChainInfo) -> anyhow::Result<()>,
     {
         let sectors = Sectors::load(store, &self.sectors)?;
         sectors.amt.for_each(|_, v| f(v))?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(count = sectors.amt.count(), "scanned sectors");
         Ok(())
     }
 
+    /// Lists up to `limit` sectors, in sector number order, starting from `cursor` or from the
+    /// first sector. See [`list_amt_page`] for resuming a listing on a later state.
+    pub fn list_sectors<BS: Blockstore>(
+        &self,
+        store: &BS,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<SectorOnChainInfo>> {
+        Ok(list_amt_page(store, &self.sectors, cursor, limit)?.map(|(_, sector)| sector))
+    }
+
+    /// Runs a function over the sectors whose numbers are in `range`, in sector number order,
+    /// until it returns [`ControlFlow::Break`]. Unlike [`State::for_each_sector`], sectors outside
+    /// the range are not loaded.
+    pub fn for_each_sector_in_range<BS: Blockstore, F>(
+        &self,
+        store: &BS,
+        range: impl RangeBounds<SectorNumber>,
+        mut f: F,
+    ) -> anyhow::Result<ControlFlow<()>>
+    where
+        F: FnMut(&SectorOnChainInfo) -> anyhow::Result<ControlFlow<()>>,
+    {
+        let sectors = Sectors::load(store, &self.sectors)?;
+        for_each_amt_in_range(&sectors.amt, range, |_, sector| f(sector))
+    }
+
+    /// Returns the number of sectors, read from the root of the sectors AMT without loading them.
+    pub fn count_sectors<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<u64> {
+        Ok(Sectors::load(store, &self.sectors)?.amt.count())
+    }
+
+    /// Sums the initial pledge of all sectors, grouped by QAP class and by vintage, i.e. ranges
+    /// of `vintage_length` epochs of activation. Since FIP-0081 the pledge of a sector depends on
+    /// when it was onboarded relative to the pledge ramp, so vintages let valuations apply the
+    /// right terms to each group. Sectors are streamed from the sectors AMT.
+    pub fn total_initial_pledge_breakdown<BS: Blockstore>(
+        &self,
+        store: &BS,
+        vintage_length: ChainEpoch,
+    ) -> anyhow::Result<PledgeBreakdown> {
+        if vintage_length <= 0 {
+            return Err(anyhow!("invalid vintage length {}", vintage_length));
+        }
+        let mut breakdown = PledgeBreakdown::default();
+        self.for_each_sector(store, |sector| {
+            let vintage = sector.activation - sector.activation.rem_euclid(vintage_length);
+            breakdown.add(vintage, SectorQapClass::of(sector), &sector.initial_pledge);
+            Ok(())
+        })?;
+        Ok(breakdown)
+    }
+
     /// Returns the deadline and partition index for a sector number.
     pub fn find_sector<BS: Blockstore>(
         &self,
@@ -661,6 +755,31 @@ impl State {
         Ok((result, !no_early_terminations))
     }
 
+    /// Returns the early terminations that are yet to be processed. These are processed in
+    /// batches bounded by the policy, see [`PendingEarlyTerminations::processing_passes`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn pending_early_terminations<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<PendingEarlyTerminations> {
+        let mut pending = PendingEarlyTerminations::default();
+        if self.early_terminations.is_empty() {
+            return Ok(pending);
+        }
+
+        let deadlines = self.load_deadlines(store)?;
+        for deadline_idx in self.early_terminations.iter() {
+            let deadline = deadlines.load_deadline(store, deadline_idx)?;
+            pending.deadlines += 1;
+            for partition_idx in deadline.early_terminations.iter() {
+                let partition = deadline.load_partition(store, partition_idx)?;
+                pending.partitions += 1;
+                pending.sectors += partition.early_terminated_sector_count(store)?;
+            }
+        }
+        Ok(pending)
+    }
+
     /// Returns an error if the target sector cannot be found, or some other bad state is reached.
     /// Returns Ok(false) if the target sector is faulty, terminated, or unproven
     /// Returns Ok(true) otherwise
@@ -803,6 +922,15 @@ impl State {
             || !self.locked_funds.is_zero()
     }
 
+    /// Returns the status of the deadline cron of the miner.
+    pub fn cron_status(&self) -> CronStatus {
+        CronStatus {
+            active: self.deadline_cron_active,
+            continues: self.continue_deadline_cron(),
+            reactivated_by: &[Method::PreCommitSectorBatch2 as u64],
+        }
+    }
+
     //
     // Funds and vesting
     //
@@ -1017,6 +1145,85 @@ impl State {
         Ok(self.get_unlocked_balance(actor_balance)? - &self.fee_debt)
     }
 
+    /// Returns the amount the beneficiary can withdraw at the given epoch, as WithdrawBalance
+    /// computes it: the available balance once vested funds are unlocked, capped by the remaining
+    /// quota when the beneficiary is not the owner. Zero when the available balance is negative,
+    /// or when the beneficiary term has expired or is used up. Fails when terminated sectors have
+    /// outstanding fees, as WithdrawBalance then aborts.
+    pub fn get_withdrawable_balance<BS: Blockstore>(
+        &self,
+        store: &BS,
+        info: &MinerInfo,
+        actor_balance: &TokenAmount,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<TokenAmount> {
+        anyhow::ensure!(
+            self.early_terminations.is_empty(),
+            "cannot withdraw funds while {} deadlines have terminated sectors with outstanding fees",
+            self.early_terminations.len()
+        );
+        let vested = if self.locked_funds.is_zero() {
+            TokenAmount::zero()
+        } else {
+            self.check_vested_funds(store, current_epoch)?
+        };
+        let mut withdrawable = self.get_available_balance(actor_balance)? + vested;
+        if info.beneficiary != info.owner {
+            withdrawable = cmp::min(withdrawable, info.beneficiary_term.available(current_epoch));
+        }
+        Ok(cmp::max(withdrawable, TokenAmount::zero()))
+    }
+
+    /// Plans a `WithdrawBalance` message for the requested amount at the given epoch, as the
+    /// actor executes it: the amount withdrawn is capped by
+    /// [`get_withdrawable_balance`](Self::get_withdrawable_balance), and the fee debt is repaid
+    /// from the unlocked balance.
+    pub fn plan_withdrawal<BS: Blockstore>(
+        &self,
+        store: &BS,
+        actor_balance: &TokenAmount,
+        requested: &TokenAmount,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<WithdrawalPlan> {
+        anyhow::ensure!(
+            !requested.is_negative(),
+            "negative fund requested for withdrawal: {}",
+            requested
+        );
+        let info = self.get_info(store)?;
+        let withdrawable =
+            self.get_withdrawable_balance(store, &info, actor_balance, current_epoch)?;
+        if withdrawable.is_zero() {
+            // Nothing can be withdrawn, which makes the message abort when the fee debt cannot be
+            // repaid or the beneficiary has no quota left.
+            let vested = if self.locked_funds.is_zero() {
+                TokenAmount::zero()
+            } else {
+                self.check_vested_funds(store, current_epoch)?
+            };
+            anyhow::ensure!(
+                !(self.get_available_balance(actor_balance)? + vested).is_negative(),
+                "unlocked balance can not repay fee debt ({})",
+                self.fee_debt
+            );
+            anyhow::ensure!(
+                info.beneficiary == info.owner
+                    || info.beneficiary_term.available(current_epoch).is_positive(),
+                "beneficiary expiration of epoch {} passed or quota of {} used up",
+                info.beneficiary_term.expiration,
+                info.beneficiary_term.quota
+            );
+        }
+        Ok(WithdrawalPlan {
+            to: info.beneficiary,
+            params: WithdrawBalanceParams {
+                amount_requested: cmp::min(withdrawable.clone(), requested.clone()),
+            },
+            withdrawable,
+            fee_debt_repaid: self.fee_debt.clone(),
+        })
+    }
+
     pub fn check_balance_invariants(&self, balance: &TokenAmount) -> anyhow::Result<()> {
         if self.pre_commit_deposits.is_negative() {
             return Err(anyhow!(
@@ -1242,6 +1449,20 @@ impl State {
     }
 }
 
+/// Status of the deadline cron of a miner. While it is inactive, the deadlines of the miner are
+/// not processed: missed Window PoSts are not detected as faults, expired pre-commits are not
+/// cleaned up, early terminations are not processed and vested funds are only unlocked on
+/// withdrawal. The cron stops at the end of a deadline once the miner holds no pre-commit
+/// deposits, initial pledge or locked funds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronStatus {
+    pub active: bool,
+    /// Whether an active cron will be scheduled again at the end of the current deadline.
+    pub continues: bool,
+    /// Numbers of the methods which enroll the cron again when it is inactive.
+    pub reactivated_by: &'static [u64],
+}
+
 pub struct AdvanceDeadlineResult {
     pub pledge_delta: TokenAmount,
     pub power_delta: PowerPair,
@@ -1255,6 +1476,54 @@ pub struct AdvanceDeadlineResult {
     pub total_faulty_power: PowerPair,
 }
 
+/// Class of a sector in terms of quality-adjusted power, from its deal weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SectorQapClass {
+    /// Committed capacity, without deals.
+    CommittedCapacity,
+    /// Unverified deals only.
+    Deals,
+    /// At least some verified deals.
+    VerifiedDeals,
+}
+
+impl SectorQapClass {
+    pub fn of(sector: &SectorOnChainInfo) -> Self {
+        if !sector.verified_deal_weight.is_zero() {
+            Self::VerifiedDeals
+        } else if !sector.deal_weight.is_zero() {
+            Self::Deals
+        } else {
+            Self::CommittedCapacity
+        }
+    }
+}
+
+/// Initial pledge locked by a group of sectors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PledgeBucket {
+    pub sector_count: u64,
+    pub initial_pledge: TokenAmount,
+}
+
+/// Initial pledge of a miner, see [`State::total_initial_pledge_breakdown`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PledgeBreakdown {
+    pub total: PledgeBucket,
+    /// Pledge keyed by the first epoch of the vintage and the QAP class of the sectors.
+    pub by_vintage: BTreeMap<(ChainEpoch, SectorQapClass), PledgeBucket>,
+}
+
+impl PledgeBreakdown {
+    fn add(&mut self, vintage: ChainEpoch, class: SectorQapClass, pledge: &TokenAmount) {
+        let bucket = self.by_vintage.entry((vintage, class)).or_default();
+        for bucket in [bucket, &mut self.total] {
+            bucket.sector_count += 1;
+            bucket.initial_pledge += pledge;
+        }
+    }
+}
+
 /// Static information about miner
 #[derive(Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct MinerInfo {
@@ -1350,4 +1619,139 @@ impl MinerInfo {
             pending_owner_address: None,
         })
     }
+
+    /// Builds the `ChangeBeneficiary` message by which the owner proposes a new beneficiary.
+    ///
+    /// When the owner is nominated, the quota and expiration must be zero and the nominee
+    /// approval is implicit. Otherwise, the quota is the total amount the beneficiary may withdraw
+    /// until the expiration epoch. The current beneficiary must approve the change unless its term
+    /// has no quota available anymore, and the nominee must approve it unless it is the owner.
+    pub fn propose_beneficiary_change(
+        &self,
+        new_beneficiary: Address,
+        new_quota: TokenAmount,
+        new_expiration: ChainEpoch,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<BeneficiaryChangeMessage> {
+        anyhow::ensure!(
+            new_beneficiary.id().is_ok(),
+            "new beneficiary {} must be an ID address",
+            new_beneficiary
+        );
+        if new_beneficiary == self.owner {
+            anyhow::ensure!(
+                new_quota.is_zero() && new_expiration == 0,
+                "quota and expiration must be zero when nominating the owner"
+            );
+        } else {
+            anyhow::ensure!(
+                new_quota.is_positive(),
+                "beneficiary quota {} must be positive",
+                new_quota
+            );
+            anyhow::ensure!(
+                new_expiration > current_epoch,
+                "beneficiary expiration {} must be after the current epoch {}",
+                new_expiration,
+                current_epoch
+            );
+        }
+
+        let mut remaining_approvals = Vec::new();
+        if !self.beneficiary_term.available(current_epoch).is_zero() {
+            remaining_approvals.push(self.beneficiary);
+        }
+        if new_beneficiary != self.owner {
+            remaining_approvals.push(new_beneficiary);
+        }
+        Ok(BeneficiaryChangeMessage {
+            from: self.owner,
+            params: ChangeBeneficiaryParams::new(new_beneficiary, new_quota, new_expiration),
+            remaining_approvals,
+        })
+    }
+
+    /// Builds the `ChangeBeneficiary` message by which `approver`, the current beneficiary or the
+    /// nominee, approves the pending beneficiary change.
+    pub fn approve_beneficiary_change(
+        &self,
+        approver: Address,
+    ) -> anyhow::Result<BeneficiaryChangeMessage> {
+        let pending = self
+            .pending_beneficiary_term
+            .as_ref()
+            .ok_or_else(|| anyhow!("no beneficiary change is pending"))?;
+        anyhow::ensure!(
+            approver == self.beneficiary || approver == pending.new_beneficiary,
+            "{} is neither the current beneficiary {} nor the nominee {}",
+            approver,
+            self.beneficiary,
+            pending.new_beneficiary
+        );
+        let mut approved_by_beneficiary = pending.approved_by_beneficiary;
+        let mut approved_by_nominee = pending.approved_by_nominee;
+        if approver == self.beneficiary {
+            approved_by_beneficiary = true;
+        }
+        if approver == pending.new_beneficiary {
+            approved_by_nominee = true;
+        }
+        anyhow::ensure!(
+            approved_by_beneficiary != pending.approved_by_beneficiary
+                || approved_by_nominee != pending.approved_by_nominee,
+            "{} has already approved the beneficiary change",
+            approver
+        );
+
+        let mut remaining_approvals = Vec::new();
+        if !approved_by_beneficiary {
+            remaining_approvals.push(self.beneficiary);
+        }
+        if !approved_by_nominee {
+            remaining_approvals.push(pending.new_beneficiary);
+        }
+        Ok(BeneficiaryChangeMessage {
+            from: approver,
+            params: ChangeBeneficiaryParams::new(
+                pending.new_beneficiary,
+                pending.new_quota.clone(),
+                pending.new_expiration,
+            ),
+            remaining_approvals,
+        })
+    }
+
+    /// Builds the `ChangeOwnerAddress` message by which the owner proposes a new owner. The new
+    /// owner must then confirm the change, see [`Self::confirm_owner_change`]. If the owner is also
+    /// the beneficiary, the new owner becomes the beneficiary too.
+    pub fn propose_owner_change(&self, new_owner: Address) -> anyhow::Result<OwnerChangeMessage> {
+        anyhow::ensure!(
+            new_owner.id().is_ok(),
+            "new owner {} must be an ID address",
+            new_owner
+        );
+        anyhow::ensure!(
+            new_owner != self.owner,
+            "{} is already the owner",
+            new_owner
+        );
+        Ok(OwnerChangeMessage {
+            from: self.owner,
+            params: ChangeOwnerAddressParams { new_owner },
+            remaining_confirmation: Some(new_owner),
+        })
+    }
+
+    /// Builds the `ChangeOwnerAddress` message by which the proposed owner confirms the pending
+    /// owner change.
+    pub fn confirm_owner_change(&self) -> anyhow::Result<OwnerChangeMessage> {
+        let new_owner = self
+            .pending_owner_address
+            .ok_or_else(|| anyhow!("no owner change is pending"))?;
+        Ok(OwnerChangeMessage {
+            from: new_owner,
+            params: ChangeOwnerAddressParams { new_owner },
+            remaining_confirmation: None,
+        })
+    }
 }
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::cmp;
+use std::ops::RangeInclusive;
 
 use cid::{Cid, Version};
 use fil_actors_shared::v14::network::*;
@@ -15,6 +16,7 @@ use fvm_shared4::sector::{RegisteredPoStProof, RegisteredSealProof, SectorSize,
 use lazy_static::lazy_static;
 
 use super::types::SectorOnChainInfo;
+use super::MinerInfo;
 use super::{PowerPair, BASE_REWARD_FOR_DISPUTED_WINDOW_POST};
 
 /// Precision used for making QA power calculations
@@ -223,3 +225,32 @@ pub fn reward_for_disputed_window_post(
     // This is currently just the base. In the future, the fee may scale based on the disputed power.
     BASE_REWARD_FOR_DISPUTED_WINDOW_POST.clone()
 }
+
+/// Returns whether the miner is still serving a consensus fault penalty at `curr_epoch`, during
+/// which it is ineligible for block production and for permissioned methods.
+pub fn consensus_fault_active(info: &MinerInfo, curr_epoch: ChainEpoch) -> bool {
+    // For penalization period to last for exactly finality epochs
+    // consensus faults are active until currEpoch exceeds ConsensusFaultElapsed
+    curr_epoch <= info.consensus_fault_elapsed
+}
+
+/// Returns the epochs at which a consensus fault that happened at `fault_epoch` may be reported:
+/// strictly after the fault, and no later than chain finality after it.
+pub fn report_consensus_fault_window(
+    policy: &Policy,
+    fault_epoch: ChainEpoch,
+) -> RangeInclusive<ChainEpoch> {
+    (fault_epoch + 1)..=(fault_epoch + policy.chain_finality)
+}
+
+/// Returns whether a consensus fault that happened at `fault_epoch` can be reported against the
+/// miner at `curr_epoch`. Faults preceding the end of the last penalty period are rejected.
+pub fn can_report_consensus_fault(
+    policy: &Policy,
+    info: &MinerInfo,
+    fault_epoch: ChainEpoch,
+    curr_epoch: ChainEpoch,
+) -> bool {
+    fault_epoch >= info.consensus_fault_elapsed
+        && report_consensus_fault_window(policy, fault_epoch).contains(&curr_epoch)
+}
@@ -17,6 +17,7 @@ use fvm_shared4::sector::{
     RegisteredUpdateProof, SectorNumber, SectorSize, StoragePower,
 };
 use fvm_shared4::ActorID;
+use fvm_shared4::MethodNum;
 use serde::{Deserialize, Serialize};
 
 use fil_actors_shared::v14::reward::FilterEstimate;
@@ -27,6 +28,7 @@ use crate::v14::ext::verifreg::AllocationID;
 use crate::v14::ext::verifreg::ClaimID;
 
 use super::beneficiary::*;
+use super::Method;
 
 pub type CronEvent = i64;
 
@@ -71,7 +73,7 @@ pub struct ChangeWorkerAddressParams {
     pub new_control_addresses: Vec<Address>,
 }
 
-#[derive(Serialize_tuple, Deserialize_tuple)]
+#[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
 #[serde(transparent)]
 pub struct ChangeOwnerAddressParams {
     pub new_owner: Address,
@@ -354,7 +356,7 @@ pub struct ReportConsensusFaultParams {
     pub header_extra: Vec<u8>,
 }
 
-#[derive(Clone, Serialize_tuple, Deserialize_tuple)]
+#[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
 pub struct WithdrawBalanceParams {
     pub amount_requested: TokenAmount,
 }
@@ -365,6 +367,20 @@ pub struct WithdrawBalanceReturn {
     pub amount_withdrawn: TokenAmount,
 }
 
+/// A `WithdrawBalance` message planned against a miner state, see
+/// [`State::plan_withdrawal`](crate::v14::State::plan_withdrawal).
+#[derive(Debug, Clone)]
+pub struct WithdrawalPlan {
+    /// Address the funds are sent to, the beneficiary.
+    pub to: Address,
+    /// Parameters requesting exactly the amount the message withdraws.
+    pub params: WithdrawBalanceParams,
+    /// The most the message could withdraw.
+    pub withdrawable: TokenAmount,
+    /// Fee debt the message repays and burns before withdrawing.
+    pub fee_debt_repaid: TokenAmount,
+}
+
 #[derive(Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct WorkerKeyChange {
     /// Must be an ID address
@@ -562,6 +578,30 @@ impl ChangeBeneficiaryParams {
     }
 }
 
+/// A validated `ChangeBeneficiary` message, see
+/// [`MinerInfo::propose_beneficiary_change`](crate::v14::MinerInfo::propose_beneficiary_change).
+#[derive(Debug, Clone)]
+pub struct BeneficiaryChangeMessage {
+    /// Address which must send the message.
+    pub from: Address,
+    pub params: ChangeBeneficiaryParams,
+    /// Addresses whose approval is still needed once the message is executed. The change takes
+    /// effect when this is empty.
+    pub remaining_approvals: Vec<Address>,
+}
+
+/// A validated `ChangeOwnerAddress` message, see
+/// [`MinerInfo::propose_owner_change`](crate::v14::MinerInfo::propose_owner_change).
+#[derive(Debug, Clone)]
+pub struct OwnerChangeMessage {
+    /// Address which must send the message.
+    pub from: Address,
+    pub params: ChangeOwnerAddressParams,
+    /// Address which must confirm the change by sending the same message, `None` once the
+    /// change takes effect.
+    pub remaining_confirmation: Option<Address>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct ActiveBeneficiary {
     pub beneficiary: Address,
@@ -679,3 +719,52 @@ pub struct PieceReturn {
     // The caller is free to ignore this, but may chose to abort and roll back.
     pub accepted: bool,
 }
+
+/// Methods of the miner actor which are removed by later versions, to decode and label historical
+/// messages. The discriminants are the method numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u64)]
+pub enum LegacyMethod {
+    ConfirmSectorProofsValid = Method::ConfirmSectorProofsValid as MethodNum,
+}
+
+/// Decoded parameters of a [`LegacyMethod`].
+pub enum LegacyMethodParams {
+    ConfirmSectorProofsValid(ConfirmSectorProofsParams),
+}
+
+impl LegacyMethod {
+    pub fn from_method_num(method: MethodNum) -> Option<Self> {
+        match method {
+            m if m == Self::ConfirmSectorProofsValid as MethodNum => {
+                Some(Self::ConfirmSectorProofsValid)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ConfirmSectorProofsValid => "ConfirmSectorProofsValid",
+        }
+    }
+
+    /// Describes the effects of a successful call on the state.
+    pub fn state_effect(self) -> &'static str {
+        match self {
+            Self::ConfirmSectorProofsValid => {
+                "Callback of the power actor with the pre-committed sectors whose proofs were \
+                 verified. It activates them: the pre-commit deposits are released, the initial \
+                 pledges are locked and the power of the sectors is added."
+            }
+        }
+    }
+
+    pub fn decode_params(self, params: &RawBytes) -> anyhow::Result<LegacyMethodParams> {
+        Ok(match self {
+            Self::ConfirmSectorProofsValid => {
+                LegacyMethodParams::ConfirmSectorProofsValid(params.deserialize()?)
+            }
+        })
+    }
+}
@@ -44,12 +44,23 @@ impl BeneficiaryTerm {
     /// return 0 when the usedQuota >= Quota for safe
     /// otherwise Return quota-used_quota
     pub fn available(&self, cur: ChainEpoch) -> TokenAmount {
-        if self.expiration > cur {
-            (&self.quota).sub(&self.used_quota).max(TokenAmount::zero())
-        } else {
+        if self.is_expired(cur) {
             TokenAmount::zero()
+        } else {
+            self.remaining_quota()
         }
     }
+
+    /// Get the amount of the quota that has not been withdrawn yet, regardless of expiration
+    /// return 0 when the used_quota >= quota
+    pub fn remaining_quota(&self) -> TokenAmount {
+        (&self.quota).sub(&self.used_quota).max(TokenAmount::zero())
+    }
+
+    /// Whether the beneficiary's rights have expired at the given epoch, reverting to the owner
+    pub fn is_expired(&self, cur: ChainEpoch) -> bool {
+        self.expiration <= cur
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
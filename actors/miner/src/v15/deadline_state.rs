@@ -188,6 +188,18 @@ pub struct DisputeInfo {
     pub disputed_power: PowerPair,
 }
 
+/// A WindowPoSt of the proofs snapshot of a deadline, with the infos of the sectors it proves
+/// loaded from the sectors snapshot, as needed to re-run its verification off-chain.
+#[derive(Clone)]
+pub struct PoStSnapshotEntry {
+    /// Index of the proof in the proofs snapshot, as passed to `DisputeWindowedPoSt`.
+    pub index: u64,
+    pub post: WindowedPoSt,
+    /// Sector infos in the order they were challenged. Faulty, terminated and unproven sectors of
+    /// the partition snapshots are substituted with the first provable sector, as on-chain.
+    pub sector_infos: Vec<SectorOnChainInfo>,
+}
+
 impl Deadline {
     pub fn new<BS: Blockstore>(store: &BS) -> Result<Self, ActorError> {
         let empty_partitions_array =
@@ -249,6 +261,30 @@ impl Deadline {
         Ok(Array::load(&self.partitions, store)?)
     }
 
+    /// Returns the power of all live sectors in the deadline, faulty or not. Unlike
+    /// `faulty_power`, this is not tracked on the deadline and is summed over its partitions.
+    pub fn live_power<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<PowerPair> {
+        let mut live_power = PowerPair::zero();
+        self.partitions_amt(store)?
+            .for_each(|_, partition: &Partition| {
+                live_power += &partition.live_power;
+                Ok(())
+            })?;
+        Ok(live_power)
+    }
+
+    /// Returns the power of live sectors in the deadline that are neither faulty nor unproven,
+    /// summed over its partitions.
+    pub fn active_power<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<PowerPair> {
+        let mut active_power = PowerPair::zero();
+        self.partitions_amt(store)?
+            .for_each(|_, partition: &Partition| {
+                active_power += &partition.active_power();
+                Ok(())
+            })?;
+        Ok(active_power)
+    }
+
     pub fn optimistic_proofs_amt<'db, BS: Blockstore>(
         &self,
         store: &'db BS,
@@ -1112,6 +1148,32 @@ impl Deadline {
         })
     }
 
+    /// Calls `f` on each proof of the previous challenge window of this deadline with the sectors
+    /// it proves, see [`PoStSnapshotEntry`]. Proofs disputed successfully have been removed from
+    /// the snapshot, and proofs verified on-chain were never recorded.
+    pub fn for_each_post_snapshot<BS: Blockstore>(
+        &self,
+        store: &BS,
+        mut f: impl FnMut(PoStSnapshotEntry) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let proofs = self
+            .optimistic_proofs_snapshot_amt(store)
+            .map_err(|e| e.downcast_wrap("failed to load post proofs snapshot amt"))?;
+        let sectors = Sectors::load(store, &self.sectors_snapshot)
+            .map_err(|e| anyhow!("failed to load sectors snapshot: {e}"))?;
+        proofs.for_each(|index, post| {
+            let dispute = self.load_partitions_for_dispute(store, post.partitions.clone())?;
+            let sector_infos =
+                sectors.load_for_proof(&dispute.all_sector_nos, &dispute.ignored_sector_nos)?;
+            f(PoStSnapshotEntry {
+                index,
+                post: post.clone(),
+                sector_infos,
+            })
+        })?;
+        Ok(())
+    }
+
     pub fn is_live(&self) -> bool {
         if self.live_sectors > 0 {
             return true;
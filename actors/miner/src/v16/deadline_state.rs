@@ -188,6 +188,18 @@ pub struct DisputeInfo {
     pub disputed_power: PowerPair,
 }
 
+/// A WindowPoSt of the proofs snapshot of a deadline, with the infos of the sectors it proves
+/// loaded from the sectors snapshot, as needed to re-run its verification off-chain.
+#[derive(Clone)]
+pub struct PoStSnapshotEntry {
+    /// Index of the proof in the proofs snapshot, as passed to `DisputeWindowedPoSt`.
+    pub index: u64,
+    pub post: WindowedPoSt,
+    /// Sector infos in the order they were challenged. Faulty, terminated and unproven sectors of
+    /// the partition snapshots are substituted with the first provable sector, as on-chain.
+    pub sector_infos: Vec<SectorOnChainInfo>,
+}
+
 impl Deadline {
     pub fn new<BS: Blockstore>(store: &BS) -> Result<Self, ActorError> {
         let empty_partitions_array =
@@ -249,6 +261,30 @@ impl Deadline {
         Ok(Array::load(&self.partitions, store)?)
     }
 
+    /// Returns the power of all live sectors in the deadline, faulty or not. Unlike
+    /// `faulty_power`, this is not tracked on the deadline and is summed over its partitions.
+    pub fn live_power<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<PowerPair> {
+        let mut live_power = PowerPair::zero();
+        self.partitions_amt(store)?
+            .for_each(|_, partition: &Partition| {
+                live_power += &partition.live_power;
+                Ok(())
+            })?;
+        Ok(live_power)
+    }
+
+    /// Returns the power of live sectors in the deadline that are neither faulty nor unproven,
+    /// summed over its partitions.
+    pub fn active_power<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<PowerPair> {
+        let mut active_power = PowerPair::zero();
+        self.partitions_amt(store)?
+            .for_each(|_, partition: &Partition| {
+                active_power += &partition.active_power();
+                Ok(())
+            })?;
+        Ok(active_power)
+    }
+
     pub fn optimistic_proofs_amt<'db, BS: Blockstore>(
         &self,
         store: &'db BS,
@@ -1112,6 +1148,32 @@ impl Deadline {
         })
     }
 
+    /// Calls `f` on each proof of the previous challenge window of this deadline with the sectors
+    /// it proves, see [`PoStSnapshotEntry`]. Proofs disputed successfully have been removed from
+    /// the snapshot, and proofs verified on-chain were never recorded.
+    pub fn for_each_post_snapshot<BS: Blockstore>(
+        &self,
+        store: &BS,
+        mut f: impl FnMut(PoStSnapshotEntry) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let proofs = self
+            .optimistic_proofs_snapshot_amt(store)
+            .map_err(|e| e.downcast_wrap("failed to load post proofs snapshot amt"))?;
+        let sectors = Sectors::load(store, &self.sectors_snapshot)
+            .map_err(|e| anyhow!("failed to load sectors snapshot: {e}"))?;
+        proofs.for_each(|index, post| {
+            let dispute = self.load_partitions_for_dispute(store, post.partitions.clone())?;
+            let sector_infos =
+                sectors.load_for_proof(&dispute.all_sector_nos, &dispute.ignored_sector_nos)?;
+            f(PoStSnapshotEntry {
+                index,
+                post: post.clone(),
+                sector_infos,
+            })
+        })?;
+        Ok(())
+    }
+
     pub fn is_live(&self) -> bool {
         if self.live_sectors > 0 {
             return true;
@@ -1437,3 +1499,104 @@ impl Deadline {
         Ok(all_replaced)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::{power_for_sectors, NO_QUANTIZATION};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_for_each_post_snapshot() {
+        let store = MemoryBlockstore::new();
+        let sectors: Vec<_> = (0..3)
+            .map(|sector_number| SectorOnChainInfo {
+                sector_number,
+                expiration: 1000,
+                ..Default::default()
+            })
+            .collect();
+        let mut sectors_amt = Array::new_with_bit_width(&store, SECTORS_AMT_BITWIDTH);
+        for sector in &sectors {
+            sectors_amt
+                .set(sector.sector_number, sector.clone())
+                .unwrap();
+        }
+        let sectors_root = sectors_amt.flush().unwrap();
+
+        // Partition 0 holds sectors 0 and 1, partition 1 sector 2, which misses its PoSt.
+        let mut dl = Deadline::new(&store).unwrap();
+        dl.add_sectors(
+            &store,
+            2,
+            true,
+            &sectors,
+            SectorSize::_32GiB,
+            NO_QUANTIZATION,
+        )
+        .unwrap();
+        let proofs = [PoStProof {
+            post_proof: fvm_shared4::sector::RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+            proof_bytes: vec![1],
+        }];
+        dl.record_post_proofs(&store, &BitField::try_from_bits([0]).unwrap(), &proofs)
+            .unwrap();
+        dl.record_post_proofs(&store, &BitField::try_from_bits([0, 1]).unwrap(), &proofs)
+            .unwrap();
+        dl.partitions_posted = BitField::try_from_bits([0]).unwrap();
+        dl.process_deadline_end(&store, NO_QUANTIZATION, 100, sectors_root)
+            .unwrap();
+
+        let mut entries = Vec::new();
+        dl.for_each_post_snapshot(&store, |entry| {
+            entries.push(entry);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].index, 0);
+        assert_eq!(entries[0].post.proofs, proofs);
+        assert_eq!(entries[0].sector_infos, sectors[..2]);
+        // The faulty sector 2 is substituted with sector 0.
+        assert_eq!(
+            entries[1].sector_infos,
+            [sectors[0].clone(), sectors[1].clone(), sectors[0].clone()]
+        );
+    }
+
+    #[test]
+    fn test_active_power_excludes_unproven_sectors() {
+        let store = MemoryBlockstore::new();
+        let sectors: Vec<_> = (0..3)
+            .map(|sector_number| SectorOnChainInfo {
+                sector_number,
+                expiration: 1000,
+                ..Default::default()
+            })
+            .collect();
+        let sector_size = SectorSize::_32GiB;
+
+        // Sectors 0 and 1 are proven, sector 2 is added to the same partition unproven.
+        let mut dl = Deadline::new(&store).unwrap();
+        dl.add_sectors(&store, 4, true, &sectors[..2], sector_size, NO_QUANTIZATION)
+            .unwrap();
+        dl.add_sectors(
+            &store,
+            4,
+            false,
+            &sectors[2..],
+            sector_size,
+            NO_QUANTIZATION,
+        )
+        .unwrap();
+
+        assert_eq!(
+            dl.live_power(&store).unwrap(),
+            power_for_sectors(sector_size, &sectors)
+        );
+        assert_eq!(
+            dl.active_power(&store).unwrap(),
+            power_for_sectors(sector_size, &sectors[..2])
+        );
+    }
+}
@@ -7,6 +7,7 @@ use std::ops::{self, Neg};
 use anyhow::{anyhow, Context};
 use cid::Cid;
 use fil_actors_shared::actor_error_v16;
+use fil_actors_shared::overlay::OverlayStore;
 use fil_actors_shared::v16::runtime::Policy;
 use fil_actors_shared::v16::{ActorDowncast, Array};
 use fvm_ipld_bitfield::BitField;
@@ -451,6 +452,26 @@ impl Partition {
         Ok((power_delta, pledge_delta))
     }
 
+    /// Computes the power and pledge deltas [`Partition::replace_sectors`] would produce, without
+    /// modifying the partition nor the store: the nodes of the updated expiration queue are
+    /// written to an [`OverlayStore`], which is discarded.
+    pub fn simulate_replace_sectors<BS: Blockstore>(
+        &self,
+        store: &BS,
+        old_sectors: &[SectorOnChainInfo],
+        new_sectors: &[SectorOnChainInfo],
+        sector_size: SectorSize,
+        quant: QuantSpec,
+    ) -> anyhow::Result<(PowerPair, TokenAmount)> {
+        self.clone().replace_sectors(
+            &OverlayStore::new(store),
+            old_sectors,
+            new_sectors,
+            sector_size,
+            quant,
+        )
+    }
+
     /// Record the epoch of any sectors expiring early, for termination fee calculation later.
     pub fn record_early_termination<BS: Blockstore>(
         &mut self,
@@ -474,6 +495,18 @@ impl Partition {
         Ok(())
     }
 
+    /// Returns the number of early terminated sectors whose termination is yet to be processed.
+    pub fn early_terminated_sector_count<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<u64> {
+        let early_termination_queue =
+            BitFieldQueue::new(store, &self.early_terminated, NO_QUANTIZATION)?;
+        let mut count = 0;
+        early_termination_queue.amt.for_each(|_, sectors| {
+            count += sectors.len();
+            Ok(())
+        })?;
+        Ok(count)
+    }
+
     /// Marks a collection of sectors as terminated.
     /// The sectors are removed from Faults and Recoveries.
     /// The epoch of termination is recorded for future termination fee calculation.
@@ -949,3 +982,64 @@ impl ops::Neg for &PowerPair {
         -self.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_actors_shared::v16::DealWeight;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared4::sector::RegisteredSealProof;
+
+    fn sector(sector_number: u64, verified_deal_weight: DealWeight) -> SectorOnChainInfo {
+        SectorOnChainInfo {
+            sector_number,
+            seal_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+            activation: 0,
+            expiration: 1000,
+            verified_deal_weight,
+            initial_pledge: TokenAmount::from_atto(100),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_simulate_replace_sectors() {
+        let store = MemoryBlockstore::new();
+        let sector_size = SectorSize::_32GiB;
+        let mut partition = Partition::new(&store).unwrap();
+        let old = vec![sector(1, DealWeight::zero())];
+        partition
+            .add_sectors(&store, true, &old, sector_size, NO_QUANTIZATION)
+            .unwrap();
+        let before = partition.clone();
+
+        let mut new = sector(1, DealWeight::from(1000 * sector_size as u64));
+        new.initial_pledge = TokenAmount::from_atto(150);
+        let (power_delta, pledge_delta) = partition
+            .simulate_replace_sectors(&store, &old, &[new.clone()], sector_size, NO_QUANTIZATION)
+            .unwrap();
+        assert_eq!(power_delta.raw, StoragePower::zero());
+        assert!(power_delta.qa.is_positive());
+        assert_eq!(pledge_delta, TokenAmount::from_atto(50));
+        assert_eq!(partition.expirations_epochs, before.expirations_epochs);
+        assert_eq!(partition.live_power, before.live_power);
+        // The updated expiration queue is not written to the store.
+        let mut replaced = partition.clone();
+        replaced
+            .replace_sectors(
+                &OverlayStore::new(&store),
+                &old,
+                &[new.clone()],
+                sector_size,
+                NO_QUANTIZATION,
+            )
+            .unwrap();
+        assert!(!store.has(&replaced.expirations_epochs).unwrap());
+
+        let applied = partition
+            .replace_sectors(&store, &old, &[new], sector_size, NO_QUANTIZATION)
+            .unwrap();
+        assert!(store.has(&partition.expirations_epochs).unwrap());
+        assert_eq!(applied, (power_delta, pledge_delta));
+    }
+}
@@ -3,7 +3,8 @@
 
 use std::borrow::Borrow;
 use std::cmp;
-use std::ops::Neg;
+use std::collections::BTreeMap;
+use std::ops::{ControlFlow, Neg, RangeBounds};
 
 use anyhow::{anyhow, Error};
 use cid::Cid;
@@ -23,6 +24,7 @@ use multihash_codetable::Code;
 use num_traits::Zero;
 
 use fil_actors_shared::actor_error_v16;
+use fil_actors_shared::page::{for_each_amt_in_range, list_amt_page, Page, PageCursor};
 use fil_actors_shared::v16::runtime::policy_constants::MAX_SECTOR_NUMBER;
 use fil_actors_shared::v16::runtime::Policy;
 use fil_actors_shared::v16::{
@@ -33,10 +35,11 @@ use super::beneficiary::*;
 use super::deadlines::new_deadline_info;
 use super::policy::*;
 use super::types::*;
+use super::Method;
 use super::{
     assign_deadlines, deadline_is_mutable, new_deadline_info_from_offset_and_epoch,
     quant_spec_for_deadline, BitFieldQueue, Deadline, DeadlineInfo, DeadlineSectorMap, Deadlines,
-    PowerPair, QuantSpec, Sectors, TerminationResult, VestingFunds,
+    PendingEarlyTerminations, PowerPair, QuantSpec, Sectors, TerminationResult, VestingFunds,
 };
 
 pub type PreCommitMap<BS> = Map2<BS, SectorNumber, SectorPreCommitOnChainInfo>;
@@ -218,6 +221,23 @@ impl State {
         }
     }
 
+    /// Returns the active beneficiary and the pending beneficiary change, if any, as returned by
+    /// the `GetBeneficiary` method. The approval flags of the pending change tell which of the
+    /// current beneficiary and the nominee have yet to approve it.
+    pub fn active_beneficiary<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<GetBeneficiaryReturn> {
+        let info = self.get_info(store)?;
+        Ok(GetBeneficiaryReturn {
+            active: ActiveBeneficiary {
+                beneficiary: info.beneficiary,
+                term: info.beneficiary_term,
+            },
+            proposed: info.pending_beneficiary_term,
+        })
+    }
+
     pub fn save_info<BS: Blockstore>(
         &mut self,
         store: &BS,
@@ -379,6 +399,25 @@ impl State {
         Ok(result)
     }
 
+    /// Returns the deposit of each pre-committed sector, which add up to `pre_commit_deposits`.
+    pub fn pre_commit_deposit_breakdown<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> Result<BTreeMap<SectorNumber, TokenAmount>, ActorError> {
+        let precommitted = PreCommitMap::load(
+            store,
+            &self.pre_committed_sectors,
+            PRECOMMIT_CONFIG,
+            "precommits",
+        )?;
+        let mut deposits = BTreeMap::new();
+        precommitted.for_each(|sector_number, precommit| {
+            deposits.insert(sector_number, precommit.pre_commit_deposit.clone());
+            Ok(())
+        })?;
+        Ok(deposits)
+    }
+
     pub fn delete_precommitted_sectors<BS: Blockstore>(
         &mut self,
         store: &BS,
@@ -466,15 +505,71 @@ impl State {
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn for_each_sector<BS: Blockstore, F>(&self, store: &BS, mut f: F) -> anyhow::Result<()>
     where
         F: FnMut(&SectorOnChainInfo) -> anyhow::Result<()>,
     {
         let sectors = Sectors::load(store, &self.sectors)?;
         sectors.amt.for_each(|_, v| f(v))?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(count = sectors.amt.count(), "scanned sectors");
         Ok(())
     }
 
+    /// Lists up to `limit` sectors, in sector number order, starting from `cursor` or from the
+    /// first sector. See [`list_amt_page`] for resuming a listing on a later state.
+    pub fn list_sectors<BS: Blockstore>(
+        &self,
+        store: &BS,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<SectorOnChainInfo>> {
+        Ok(list_amt_page(store, &self.sectors, cursor, limit)?.map(|(_, sector)| sector))
+    }
+
+    /// Runs a function over the sectors whose numbers are in `range`, in sector number order,
+    /// until it returns [`ControlFlow::Break`]. Unlike [`State::for_each_sector`], sectors outside
+    /// the range are not loaded.
+    pub fn for_each_sector_in_range<BS: Blockstore, F>(
+        &self,
+        store: &BS,
+        range: impl RangeBounds<SectorNumber>,
+        mut f: F,
+    ) -> anyhow::Result<ControlFlow<()>>
+    where
+        F: FnMut(&SectorOnChainInfo) -> anyhow::Result<ControlFlow<()>>,
+    {
+        let sectors = Sectors::load(store, &self.sectors)?;
+        for_each_amt_in_range(&sectors.amt, range, |_, sector| f(sector))
+    }
+
+    /// Returns the number of sectors, read from the root of the sectors AMT without loading them.
+    pub fn count_sectors<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<u64> {
+        Ok(Sectors::load(store, &self.sectors)?.amt.count())
+    }
+
+    /// Sums the initial pledge of all sectors, grouped by QAP class and by vintage, i.e. ranges
+    /// of `vintage_length` epochs of activation. Since FIP-0081 the pledge of a sector depends on
+    /// when it was onboarded relative to the pledge ramp, so vintages let valuations apply the
+    /// right terms to each group. Sectors are streamed from the sectors AMT.
+    pub fn total_initial_pledge_breakdown<BS: Blockstore>(
+        &self,
+        store: &BS,
+        vintage_length: ChainEpoch,
+    ) -> anyhow::Result<PledgeBreakdown> {
+        if vintage_length <= 0 {
+            return Err(anyhow!("invalid vintage length {}", vintage_length));
+        }
+        let mut breakdown = PledgeBreakdown::default();
+        self.for_each_sector(store, |sector| {
+            let vintage = sector.activation - sector.activation.rem_euclid(vintage_length);
+            breakdown.add(vintage, SectorQapClass::of(sector), &sector.initial_pledge);
+            Ok(())
+        })?;
+        Ok(breakdown)
+    }
+
     /// Returns the deadline and partition index for a sector number.
     pub fn find_sector<BS: Blockstore>(
         &self,
@@ -719,6 +814,31 @@ impl State {
         Ok((result, !no_early_terminations))
     }
 
+    /// Returns the early terminations that are yet to be processed. These are processed in
+    /// batches bounded by the policy, see [`PendingEarlyTerminations::processing_passes`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn pending_early_terminations<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<PendingEarlyTerminations> {
+        let mut pending = PendingEarlyTerminations::default();
+        if self.early_terminations.is_empty() {
+            return Ok(pending);
+        }
+
+        let deadlines = self.load_deadlines(store)?;
+        for deadline_idx in self.early_terminations.iter() {
+            let deadline = deadlines.load_deadline(store, deadline_idx)?;
+            pending.deadlines += 1;
+            for partition_idx in deadline.early_terminations.iter() {
+                let partition = deadline.load_partition(store, partition_idx)?;
+                pending.partitions += 1;
+                pending.sectors += partition.early_terminated_sector_count(store)?;
+            }
+        }
+        Ok(pending)
+    }
+
     /// Returns an error if the target sector cannot be found, or some other bad state is reached.
     /// Returns Ok(false) if the target sector is faulty, terminated, or unproven
     /// Returns Ok(true) otherwise
@@ -861,6 +981,18 @@ impl State {
             || !self.locked_funds.is_zero()
     }
 
+    /// Returns the status of the deadline cron of the miner.
+    pub fn cron_status(&self) -> CronStatus {
+        CronStatus {
+            active: self.deadline_cron_active,
+            continues: self.continue_deadline_cron(),
+            reactivated_by: &[
+                Method::PreCommitSectorBatch2 as u64,
+                Method::ProveCommitSectorsNI as u64,
+            ],
+        }
+    }
+
     //
     // Funds and vesting
     //
@@ -1075,6 +1207,85 @@ impl State {
         Ok(self.get_unlocked_balance(actor_balance)? - &self.fee_debt)
     }
 
+    /// Returns the amount the beneficiary can withdraw at the given epoch, as WithdrawBalance
+    /// computes it: the available balance once vested funds are unlocked, capped by the remaining
+    /// quota when the beneficiary is not the owner. Zero when the available balance is negative,
+    /// or when the beneficiary term has expired or is used up. Fails when terminated sectors have
+    /// outstanding fees, as WithdrawBalance then aborts.
+    pub fn get_withdrawable_balance<BS: Blockstore>(
+        &self,
+        store: &BS,
+        info: &MinerInfo,
+        actor_balance: &TokenAmount,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<TokenAmount> {
+        anyhow::ensure!(
+            self.early_terminations.is_empty(),
+            "cannot withdraw funds while {} deadlines have terminated sectors with outstanding fees",
+            self.early_terminations.len()
+        );
+        let vested = if self.locked_funds.is_zero() {
+            TokenAmount::zero()
+        } else {
+            self.check_vested_funds(store, current_epoch)?
+        };
+        let mut withdrawable = self.get_available_balance(actor_balance)? + vested;
+        if info.beneficiary != info.owner {
+            withdrawable = cmp::min(withdrawable, info.beneficiary_term.available(current_epoch));
+        }
+        Ok(cmp::max(withdrawable, TokenAmount::zero()))
+    }
+
+    /// Plans a `WithdrawBalance` message for the requested amount at the given epoch, as the
+    /// actor executes it: the amount withdrawn is capped by
+    /// [`get_withdrawable_balance`](Self::get_withdrawable_balance), and the fee debt is repaid
+    /// from the unlocked balance.
+    pub fn plan_withdrawal<BS: Blockstore>(
+        &self,
+        store: &BS,
+        actor_balance: &TokenAmount,
+        requested: &TokenAmount,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<WithdrawalPlan> {
+        anyhow::ensure!(
+            !requested.is_negative(),
+            "negative fund requested for withdrawal: {}",
+            requested
+        );
+        let info = self.get_info(store)?;
+        let withdrawable =
+            self.get_withdrawable_balance(store, &info, actor_balance, current_epoch)?;
+        if withdrawable.is_zero() {
+            // Nothing can be withdrawn, which makes the message abort when the fee debt cannot be
+            // repaid or the beneficiary has no quota left.
+            let vested = if self.locked_funds.is_zero() {
+                TokenAmount::zero()
+            } else {
+                self.check_vested_funds(store, current_epoch)?
+            };
+            anyhow::ensure!(
+                !(self.get_available_balance(actor_balance)? + vested).is_negative(),
+                "unlocked balance can not repay fee debt ({})",
+                self.fee_debt
+            );
+            anyhow::ensure!(
+                info.beneficiary == info.owner
+                    || info.beneficiary_term.available(current_epoch).is_positive(),
+                "beneficiary expiration of epoch {} passed or quota of {} used up",
+                info.beneficiary_term.expiration,
+                info.beneficiary_term.quota
+            );
+        }
+        Ok(WithdrawalPlan {
+            to: info.beneficiary,
+            params: WithdrawBalanceParams {
+                amount_requested: cmp::min(withdrawable.clone(), requested.clone()),
+            },
+            withdrawable,
+            fee_debt_repaid: self.fee_debt.clone(),
+        })
+    }
+
     pub fn check_balance_invariants(&self, balance: &TokenAmount) -> anyhow::Result<()> {
         if self.pre_commit_deposits.is_negative() {
             return Err(anyhow!(
@@ -1303,6 +1514,20 @@ impl State {
     }
 }
 
+/// Status of the deadline cron of a miner. While it is inactive, the deadlines of the miner are
+/// not processed: missed Window PoSts are not detected as faults, expired pre-commits are not
+/// cleaned up, early terminations are not processed and vested funds are only unlocked on
+/// withdrawal. The cron stops at the end of a deadline once the miner holds no pre-commit
+/// deposits, initial pledge or locked funds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronStatus {
+    pub active: bool,
+    /// Whether an active cron will be scheduled again at the end of the current deadline.
+    pub continues: bool,
+    /// Numbers of the methods which enroll the cron again when it is inactive.
+    pub reactivated_by: &'static [u64],
+}
+
 pub struct AdvanceDeadlineResult {
     pub pledge_delta: TokenAmount,
     pub power_delta: PowerPair,
@@ -1316,6 +1541,54 @@ pub struct AdvanceDeadlineResult {
     pub total_faulty_power: PowerPair,
 }
 
+/// Class of a sector in terms of quality-adjusted power, from its deal weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SectorQapClass {
+    /// Committed capacity, without deals.
+    CommittedCapacity,
+    /// Unverified deals only.
+    Deals,
+    /// At least some verified deals.
+    VerifiedDeals,
+}
+
+impl SectorQapClass {
+    pub fn of(sector: &SectorOnChainInfo) -> Self {
+        if !sector.verified_deal_weight.is_zero() {
+            Self::VerifiedDeals
+        } else if !sector.deal_weight.is_zero() {
+            Self::Deals
+        } else {
+            Self::CommittedCapacity
+        }
+    }
+}
+
+/// Initial pledge locked by a group of sectors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PledgeBucket {
+    pub sector_count: u64,
+    pub initial_pledge: TokenAmount,
+}
+
+/// Initial pledge of a miner, see [`State::total_initial_pledge_breakdown`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PledgeBreakdown {
+    pub total: PledgeBucket,
+    /// Pledge keyed by the first epoch of the vintage and the QAP class of the sectors.
+    pub by_vintage: BTreeMap<(ChainEpoch, SectorQapClass), PledgeBucket>,
+}
+
+impl PledgeBreakdown {
+    fn add(&mut self, vintage: ChainEpoch, class: SectorQapClass, pledge: &TokenAmount) {
+        let bucket = self.by_vintage.entry((vintage, class)).or_default();
+        for bucket in [bucket, &mut self.total] {
+            bucket.sector_count += 1;
+            bucket.initial_pledge += pledge;
+        }
+    }
+}
+
 /// Static information about miner
 #[derive(Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct MinerInfo {
@@ -1411,4 +1684,473 @@ impl MinerInfo {
             pending_owner_address: None,
         })
     }
+
+    /// Builds the `ChangeBeneficiary` message by which the owner proposes a new beneficiary.
+    ///
+    /// When the owner is nominated, the quota and expiration must be zero and the nominee
+    /// approval is implicit. Otherwise, the quota is the total amount the beneficiary may withdraw
+    /// until the expiration epoch. The current beneficiary must approve the change unless its term
+    /// has no quota available anymore, and the nominee must approve it unless it is the owner.
+    pub fn propose_beneficiary_change(
+        &self,
+        new_beneficiary: Address,
+        new_quota: TokenAmount,
+        new_expiration: ChainEpoch,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<BeneficiaryChangeMessage> {
+        anyhow::ensure!(
+            new_beneficiary.id().is_ok(),
+            "new beneficiary {} must be an ID address",
+            new_beneficiary
+        );
+        if new_beneficiary == self.owner {
+            anyhow::ensure!(
+                new_quota.is_zero() && new_expiration == 0,
+                "quota and expiration must be zero when nominating the owner"
+            );
+        } else {
+            anyhow::ensure!(
+                new_quota.is_positive(),
+                "beneficiary quota {} must be positive",
+                new_quota
+            );
+            anyhow::ensure!(
+                new_expiration > current_epoch,
+                "beneficiary expiration {} must be after the current epoch {}",
+                new_expiration,
+                current_epoch
+            );
+        }
+
+        let mut remaining_approvals = Vec::new();
+        if !self.beneficiary_term.available(current_epoch).is_zero() {
+            remaining_approvals.push(self.beneficiary);
+        }
+        if new_beneficiary != self.owner {
+            remaining_approvals.push(new_beneficiary);
+        }
+        Ok(BeneficiaryChangeMessage {
+            from: self.owner,
+            params: ChangeBeneficiaryParams::new(new_beneficiary, new_quota, new_expiration),
+            remaining_approvals,
+        })
+    }
+
+    /// Builds the `ChangeBeneficiary` message by which `approver`, the current beneficiary or the
+    /// nominee, approves the pending beneficiary change.
+    pub fn approve_beneficiary_change(
+        &self,
+        approver: Address,
+    ) -> anyhow::Result<BeneficiaryChangeMessage> {
+        let pending = self
+            .pending_beneficiary_term
+            .as_ref()
+            .ok_or_else(|| anyhow!("no beneficiary change is pending"))?;
+        anyhow::ensure!(
+            approver == self.beneficiary || approver == pending.new_beneficiary,
+            "{} is neither the current beneficiary {} nor the nominee {}",
+            approver,
+            self.beneficiary,
+            pending.new_beneficiary
+        );
+        let mut approved_by_beneficiary = pending.approved_by_beneficiary;
+        let mut approved_by_nominee = pending.approved_by_nominee;
+        if approver == self.beneficiary {
+            approved_by_beneficiary = true;
+        }
+        if approver == pending.new_beneficiary {
+            approved_by_nominee = true;
+        }
+        anyhow::ensure!(
+            approved_by_beneficiary != pending.approved_by_beneficiary
+                || approved_by_nominee != pending.approved_by_nominee,
+            "{} has already approved the beneficiary change",
+            approver
+        );
+
+        let mut remaining_approvals = Vec::new();
+        if !approved_by_beneficiary {
+            remaining_approvals.push(self.beneficiary);
+        }
+        if !approved_by_nominee {
+            remaining_approvals.push(pending.new_beneficiary);
+        }
+        Ok(BeneficiaryChangeMessage {
+            from: approver,
+            params: ChangeBeneficiaryParams::new(
+                pending.new_beneficiary,
+                pending.new_quota.clone(),
+                pending.new_expiration,
+            ),
+            remaining_approvals,
+        })
+    }
+
+    /// Builds the `ChangeOwnerAddress` message by which the owner proposes a new owner. The new
+    /// owner must then confirm the change, see [`Self::confirm_owner_change`]. If the owner is also
+    /// the beneficiary, the new owner becomes the beneficiary too.
+    pub fn propose_owner_change(&self, new_owner: Address) -> anyhow::Result<OwnerChangeMessage> {
+        anyhow::ensure!(
+            new_owner.id().is_ok(),
+            "new owner {} must be an ID address",
+            new_owner
+        );
+        anyhow::ensure!(
+            new_owner != self.owner,
+            "{} is already the owner",
+            new_owner
+        );
+        Ok(OwnerChangeMessage {
+            from: self.owner,
+            params: ChangeOwnerAddressParams { new_owner },
+            remaining_confirmation: Some(new_owner),
+        })
+    }
+
+    /// Builds the `ChangeOwnerAddress` message by which the proposed owner confirms the pending
+    /// owner change.
+    pub fn confirm_owner_change(&self) -> anyhow::Result<OwnerChangeMessage> {
+        let new_owner = self
+            .pending_owner_address
+            .ok_or_else(|| anyhow!("no owner change is pending"))?;
+        Ok(OwnerChangeMessage {
+            from: new_owner,
+            params: ChangeOwnerAddressParams { new_owner },
+            remaining_confirmation: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::VestingFund;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_pre_commit_deposit_breakdown() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&Policy::default(), &store, Cid::default(), 0, 0).unwrap();
+        let precommits: Vec<_> = [(3, 30), (7, 70)]
+            .into_iter()
+            .map(|(sector_number, deposit)| SectorPreCommitOnChainInfo {
+                info: SectorPreCommitInfo {
+                    sector_number,
+                    ..Default::default()
+                },
+                pre_commit_deposit: TokenAmount::from_atto(deposit),
+                pre_commit_epoch: 0,
+            })
+            .collect();
+        for precommit in &precommits {
+            st.add_pre_commit_deposit(&precommit.pre_commit_deposit)
+                .unwrap();
+        }
+        st.put_precommitted_sectors(&store, precommits).unwrap();
+
+        let breakdown = st.pre_commit_deposit_breakdown(&store).unwrap();
+        assert_eq!(
+            breakdown,
+            BTreeMap::from([
+                (3, TokenAmount::from_atto(30)),
+                (7, TokenAmount::from_atto(70))
+            ])
+        );
+        assert_eq!(
+            breakdown.values().sum::<TokenAmount>(),
+            st.pre_commit_deposits
+        );
+    }
+
+    #[test]
+    fn test_total_initial_pledge_breakdown() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&Policy::default(), &store, Cid::default(), 0, 0).unwrap();
+        let sector = |sector_number, activation, deal_weight: u64, verified: u64, pledge| {
+            SectorOnChainInfo {
+                sector_number,
+                activation,
+                deal_weight: deal_weight.into(),
+                verified_deal_weight: verified.into(),
+                initial_pledge: TokenAmount::from_atto(pledge),
+                ..Default::default()
+            }
+        };
+        st.put_sectors(
+            &store,
+            vec![
+                sector(1, 10, 0, 0, 100),
+                sector(2, 99, 0, 0, 200),
+                sector(3, 50, 5, 0, 300),
+                sector(4, 150, 5, 5, 400),
+            ],
+        )
+        .unwrap();
+
+        let breakdown = st.total_initial_pledge_breakdown(&store, 100).unwrap();
+        assert_eq!(breakdown.total.sector_count, 4);
+        assert_eq!(breakdown.total.initial_pledge, TokenAmount::from_atto(1000));
+        let buckets: Vec<_> = breakdown
+            .by_vintage
+            .iter()
+            .map(|(key, bucket)| {
+                (
+                    *key,
+                    bucket.sector_count,
+                    bucket.initial_pledge.atto().clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            buckets,
+            vec![
+                ((0, SectorQapClass::CommittedCapacity), 2, 300.into()),
+                ((0, SectorQapClass::Deals), 1, 300.into()),
+                ((100, SectorQapClass::VerifiedDeals), 1, 400.into()),
+            ]
+        );
+
+        assert!(st.total_initial_pledge_breakdown(&store, 0).is_err());
+    }
+
+    #[test]
+    fn test_active_beneficiary() {
+        let store = MemoryBlockstore::new();
+        let mut info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let info_cid = store.put_cbor(&info, Code::Blake2b256).unwrap();
+        let mut st = State::new(&Policy::default(), &store, info_cid, 0, 0).unwrap();
+
+        // The owner is the beneficiary of a new miner.
+        let beneficiary = st.active_beneficiary(&store).unwrap();
+        assert_eq!(beneficiary.active.beneficiary, Address::new_id(100));
+        assert_eq!(beneficiary.active.term, BeneficiaryTerm::default());
+        assert!(beneficiary.proposed.is_none());
+
+        let mut pending =
+            PendingBeneficiaryChange::new(Address::new_id(200), TokenAmount::from_atto(10), 1000);
+        pending.approved_by_beneficiary = true;
+        info.pending_beneficiary_term = Some(pending.clone());
+        st.save_info(&store, &info).unwrap();
+
+        let beneficiary = st.active_beneficiary(&store).unwrap();
+        assert_eq!(beneficiary.active.beneficiary, Address::new_id(100));
+        assert_eq!(beneficiary.proposed, Some(pending));
+    }
+
+    #[test]
+    fn test_beneficiary_and_owner_change_messages() {
+        let mut info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let owner = Address::new_id(100);
+        let nominee = Address::new_id(200);
+        let quota = TokenAmount::from_whole(10);
+
+        // The owner is the beneficiary and has no quota, so only the nominee must approve.
+        let msg = info
+            .propose_beneficiary_change(nominee, quota.clone(), 1000, 10)
+            .unwrap();
+        assert_eq!(msg.from, owner);
+        assert_eq!(msg.remaining_approvals, vec![nominee]);
+        assert!(info
+            .propose_beneficiary_change(nominee, TokenAmount::zero(), 1000, 10)
+            .is_err());
+        assert!(info
+            .propose_beneficiary_change(owner, quota.clone(), 0, 10)
+            .is_err());
+
+        let mut pending = PendingBeneficiaryChange::new(nominee, quota.clone(), 1000);
+        pending.approved_by_beneficiary = true;
+        info.pending_beneficiary_term = Some(pending);
+        let msg = info.approve_beneficiary_change(nominee).unwrap();
+        assert_eq!(msg.from, nominee);
+        assert_eq!(msg.params.new_quota, quota);
+        assert!(msg.remaining_approvals.is_empty());
+        assert!(info.approve_beneficiary_change(owner).is_err());
+        assert!(info
+            .approve_beneficiary_change(Address::new_id(300))
+            .is_err());
+
+        // Reverting to the owner needs the approval of the active beneficiary.
+        info.beneficiary = nominee;
+        info.beneficiary_term = BeneficiaryTerm::new(quota, TokenAmount::zero(), 1000);
+        info.pending_beneficiary_term = None;
+        let msg = info
+            .propose_beneficiary_change(owner, TokenAmount::zero(), 0, 10)
+            .unwrap();
+        assert_eq!(msg.remaining_approvals, vec![nominee]);
+
+        let new_owner = Address::new_id(300);
+        let msg = info.propose_owner_change(new_owner).unwrap();
+        assert_eq!(msg.from, owner);
+        assert_eq!(msg.remaining_confirmation, Some(new_owner));
+        assert!(info.propose_owner_change(owner).is_err());
+        assert!(info.confirm_owner_change().is_err());
+        info.pending_owner_address = Some(new_owner);
+        let msg = info.confirm_owner_change().unwrap();
+        assert_eq!(msg.from, new_owner);
+        assert_eq!(msg.params.new_owner, new_owner);
+        assert_eq!(msg.remaining_confirmation, None);
+    }
+
+    #[test]
+    fn test_get_withdrawable_balance() {
+        let store = MemoryBlockstore::new();
+        let mut info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let mut st = State::new(&Policy::default(), &store, Cid::default(), 0, 0).unwrap();
+        let vesting = VestingFunds {
+            funds: vec![
+                VestingFund {
+                    epoch: 10,
+                    amount: TokenAmount::from_atto(30),
+                },
+                VestingFund {
+                    epoch: 20,
+                    amount: TokenAmount::from_atto(70),
+                },
+            ],
+        };
+        st.save_vesting_funds(&store, &vesting).unwrap();
+        st.locked_funds = TokenAmount::from_atto(100);
+        st.initial_pledge = TokenAmount::from_atto(50);
+        let balance = TokenAmount::from_atto(200);
+        let withdrawable = |st: &State, info: &MinerInfo, epoch| {
+            st.get_withdrawable_balance(&store, info, &balance, epoch)
+                .unwrap()
+                .atto()
+                .clone()
+        };
+
+        // The owner takes the available balance, including the funds vested by then.
+        assert_eq!(withdrawable(&st, &info, 10), 50.into());
+        assert_eq!(withdrawable(&st, &info, 11), 80.into());
+
+        // Another beneficiary is capped by its remaining quota until the term expires.
+        info.beneficiary = Address::new_id(200);
+        info.beneficiary_term =
+            BeneficiaryTerm::new(TokenAmount::from_atto(100), TokenAmount::from_atto(40), 15);
+        assert_eq!(
+            info.beneficiary_term.remaining_quota(),
+            TokenAmount::from_atto(60)
+        );
+        assert_eq!(withdrawable(&st, &info, 11), 60.into());
+        assert!(info.beneficiary_term.is_expired(15));
+        assert_eq!(withdrawable(&st, &info, 15), 0.into());
+
+        // Fee debt beyond the available balance leaves nothing to withdraw.
+        info.beneficiary = info.owner;
+        st.fee_debt = TokenAmount::from_atto(200);
+        assert_eq!(withdrawable(&st, &info, 11), 0.into());
+
+        // Withdrawals abort while terminated sectors have outstanding fees.
+        st.fee_debt = TokenAmount::zero();
+        st.early_terminations.set(0);
+        assert!(st
+            .get_withdrawable_balance(&store, &info, &balance, 11)
+            .is_err());
+    }
+
+    #[test]
+    fn test_plan_withdrawal() {
+        let store = MemoryBlockstore::new();
+        let mut info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let info_cid = store.put_cbor(&info, Code::Blake2b256).unwrap();
+        let mut st = State::new(&Policy::default(), &store, info_cid, 0, 0).unwrap();
+        st.initial_pledge = TokenAmount::from_atto(50);
+        st.fee_debt = TokenAmount::from_atto(20);
+        let balance = TokenAmount::from_atto(200);
+
+        // Over-requests are planned as the amount the actor would withdraw.
+        let plan = st
+            .plan_withdrawal(&store, &balance, &TokenAmount::from_atto(1000), 10)
+            .unwrap();
+        assert_eq!(plan.to, Address::new_id(100));
+        assert_eq!(plan.withdrawable, TokenAmount::from_atto(130));
+        assert_eq!(plan.params.amount_requested, TokenAmount::from_atto(130));
+        assert_eq!(plan.fee_debt_repaid, TokenAmount::from_atto(20));
+        let plan = st
+            .plan_withdrawal(&store, &balance, &TokenAmount::from_atto(30), 10)
+            .unwrap();
+        assert_eq!(plan.params.amount_requested, TokenAmount::from_atto(30));
+        assert!(st
+            .plan_withdrawal(&store, &balance, &TokenAmount::from_atto(-1), 10)
+            .is_err());
+
+        // A beneficiary is capped by its quota and cannot withdraw once it is used up.
+        info.beneficiary = Address::new_id(200);
+        info.beneficiary_term =
+            BeneficiaryTerm::new(TokenAmount::from_atto(100), TokenAmount::from_atto(40), 15);
+        st.save_info(&store, &info).unwrap();
+        let plan = st
+            .plan_withdrawal(&store, &balance, &TokenAmount::from_atto(1000), 10)
+            .unwrap();
+        assert_eq!(plan.to, Address::new_id(200));
+        assert_eq!(plan.params.amount_requested, TokenAmount::from_atto(60));
+        assert!(st
+            .plan_withdrawal(&store, &balance, &TokenAmount::from_atto(1000), 15)
+            .is_err());
+
+        // The message aborts when the unlocked balance does not cover the fee debt.
+        st.fee_debt = TokenAmount::from_atto(200);
+        assert!(st
+            .plan_withdrawal(&store, &balance, &TokenAmount::from_atto(1), 10)
+            .is_err());
+
+        // Without locked funds, the vesting table is not read when nothing can be withdrawn.
+        info.beneficiary = info.owner;
+        st.save_info(&store, &info).unwrap();
+        st.fee_debt = TokenAmount::from_atto(150);
+        st.vesting_funds = Cid::default();
+        let plan = st
+            .plan_withdrawal(&store, &balance, &TokenAmount::from_atto(1), 10)
+            .unwrap();
+        assert!(plan.withdrawable.is_zero());
+        assert!(plan.params.amount_requested.is_zero());
+    }
+
+    #[test]
+    fn test_cron_status() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&Policy::default(), &store, Cid::default(), 0, 0).unwrap();
+        let status = st.cron_status();
+        assert!(!status.active && !status.continues);
+        assert!(status
+            .reactivated_by
+            .contains(&(Method::ProveCommitSectorsNI as u64)));
+
+        st.deadline_cron_active = true;
+        st.add_initial_pledge(&TokenAmount::from_atto(10)).unwrap();
+        let status = st.cron_status();
+        assert!(status.active && status.continues);
+    }
 }
@@ -5,7 +5,7 @@ use cid::Cid;
 use fil_actors_shared::v8::DealWeight;
 use fvm_ipld_bitfield::UnvalidatedBitField;
 use fvm_ipld_encoding::tuple::*;
-use fvm_ipld_encoding::{serde_bytes, BytesDe};
+use fvm_ipld_encoding::{serde_bytes, BytesDe, RawBytes};
 use fvm_shared::address::Address;
 use fvm_shared::bigint::bigint_ser;
 use fvm_shared::clock::ChainEpoch;
@@ -17,6 +17,9 @@ use fvm_shared::sector::{
     StoragePower,
 };
 use fvm_shared::smooth::FilterEstimate;
+use fvm_shared::MethodNum;
+
+use super::Method;
 
 pub type CronEvent = i64;
 
@@ -211,7 +214,7 @@ pub struct ReportConsensusFaultParams {
     pub header_extra: Vec<u8>,
 }
 
-#[derive(Serialize_tuple, Deserialize_tuple)]
+#[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
 pub struct WithdrawBalanceParams {
     pub amount_requested: TokenAmount,
 }
@@ -222,6 +225,20 @@ pub struct WithdrawBalanceReturn {
     pub amount_withdrawn: TokenAmount,
 }
 
+/// A `WithdrawBalance` message planned against a miner state, see
+/// [`State::plan_withdrawal`](crate::v8::State::plan_withdrawal).
+#[derive(Debug, Clone)]
+pub struct WithdrawalPlan {
+    /// Address the funds are sent to, the owner.
+    pub to: Address,
+    /// Parameters requesting exactly the amount the message withdraws.
+    pub params: WithdrawBalanceParams,
+    /// The most the message could withdraw.
+    pub withdrawable: TokenAmount,
+    /// Fee debt the message repays and burns before withdrawing.
+    pub fee_debt_repaid: TokenAmount,
+}
+
 #[derive(Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
 pub struct WorkerKeyChange {
     /// Must be an ID address
@@ -342,3 +359,71 @@ pub struct ReplicaUpdate {
 pub struct ProveReplicaUpdatesParams {
     pub updates: Vec<ReplicaUpdate>,
 }
+
+/// Methods of the miner actor which are removed by later versions, to decode and label historical
+/// messages. The discriminants are the method numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u64)]
+pub enum LegacyMethod {
+    PreCommitSector = Method::PreCommitSector as MethodNum,
+    ProveCommitSector = Method::ProveCommitSector as MethodNum,
+    ConfirmSectorProofsValid = Method::ConfirmSectorProofsValid as MethodNum,
+}
+
+/// Decoded parameters of a [`LegacyMethod`].
+pub enum LegacyMethodParams {
+    PreCommitSector(PreCommitSectorParams),
+    ProveCommitSector(ProveCommitSectorParams),
+    ConfirmSectorProofsValid(ConfirmSectorProofsParams),
+}
+
+impl LegacyMethod {
+    pub fn from_method_num(method: MethodNum) -> Option<Self> {
+        match method {
+            m if m == Self::PreCommitSector as MethodNum => Some(Self::PreCommitSector),
+            m if m == Self::ProveCommitSector as MethodNum => Some(Self::ProveCommitSector),
+            m if m == Self::ConfirmSectorProofsValid as MethodNum => {
+                Some(Self::ConfirmSectorProofsValid)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PreCommitSector => "PreCommitSector",
+            Self::ProveCommitSector => "ProveCommitSector",
+            Self::ConfirmSectorProofsValid => "ConfirmSectorProofsValid",
+        }
+    }
+
+    /// Describes the effects of a successful call on the state.
+    pub fn state_effect(self) -> &'static str {
+        match self {
+            Self::PreCommitSector => {
+                "Records a sector pre-commitment and locks its pre-commit deposit from the \
+                 available balance."
+            }
+            Self::ProveCommitSector => {
+                "Submits the proof of replication of a pre-committed sector to the power actor for \
+                 batch verification. The sector is only activated by the `ConfirmSectorProofsValid` \
+                 callback at the end of the epoch."
+            }
+            Self::ConfirmSectorProofsValid => {
+                "Callback of the power actor with the pre-committed sectors whose proofs were \
+                 verified. It activates them: the pre-commit deposits are released, the initial \
+                 pledges are locked and the power of the sectors is added."
+            }
+        }
+    }
+
+    pub fn decode_params(self, params: &RawBytes) -> anyhow::Result<LegacyMethodParams> {
+        Ok(match self {
+            Self::PreCommitSector => LegacyMethodParams::PreCommitSector(params.deserialize()?),
+            Self::ProveCommitSector => LegacyMethodParams::ProveCommitSector(params.deserialize()?),
+            Self::ConfirmSectorProofsValid => {
+                LegacyMethodParams::ConfirmSectorProofsValid(params.deserialize()?)
+            }
+        })
+    }
+}
@@ -134,6 +134,52 @@ pub fn deadline_available_for_compaction(
         )
 }
 
+/// Returns the instance of the deadline of `deadline_info` which is current or next at
+/// `current_epoch`, whatever the proving period `deadline_info` was computed for.
+fn deadline_at_epoch(
+    policy: &Policy,
+    deadline_info: &DeadlineInfo,
+    current_epoch: ChainEpoch,
+) -> DeadlineInfo {
+    let period_start = deadline_info.period_start
+        + (current_epoch - deadline_info.period_start).div_euclid(policy.wpost_proving_period)
+            * policy.wpost_proving_period;
+    new_deadline_info(policy, period_start, deadline_info.index, current_epoch).next_not_elapsed()
+}
+
+/// Whether a `SubmitWindowedPoSt` message for the deadline of `deadline_info` is accepted if
+/// executed at `current_epoch`, i.e. whether that deadline is open at that epoch.
+pub fn can_submit_post_now(
+    policy: &Policy,
+    deadline_info: &DeadlineInfo,
+    current_epoch: ChainEpoch,
+) -> bool {
+    deadline_info.index < policy.wpost_period_deadlines
+        && deadline_at_epoch(policy, deadline_info, current_epoch).is_open()
+}
+
+/// Whether a `DeclareFaults` message for sectors of the deadline of `deadline_info` is accepted
+/// if executed at `current_epoch`. Declarations target the next instance of the deadline that has
+/// not elapsed, and are rejected from its fault cutoff, which precedes its opening.
+pub fn can_declare_fault_now(
+    policy: &Policy,
+    deadline_info: &DeadlineInfo,
+    current_epoch: ChainEpoch,
+) -> bool {
+    deadline_info.index < policy.wpost_period_deadlines
+        && !deadline_at_epoch(policy, deadline_info, current_epoch).fault_cutoff_passed()
+}
+
+/// Whether a `DeclareFaultsRecovered` message for sectors of the deadline of `deadline_info` is
+/// accepted if executed at `current_epoch`. Recoveries follow the same cutoff as faults.
+pub fn can_declare_recovery_now(
+    policy: &Policy,
+    deadline_info: &DeadlineInfo,
+    current_epoch: ChainEpoch,
+) -> bool {
+    can_declare_fault_now(policy, deadline_info, current_epoch)
+}
+
 // Determine current period start and deadline index directly from current epoch and
 // the offset implied by the proving period. This works correctly even for the state
 // of a miner actor without an active deadline cron
@@ -1,7 +1,10 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::cmp::max;
+
 use fil_actors_shared::v9::network::EPOCHS_IN_DAY;
+use fvm_shared::bigint::BigInt;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use lazy_static::lazy_static;
@@ -34,6 +37,9 @@ lazy_static! {
 
     /// Base penalty for a successful disputed window post proof.
     pub static ref BASE_PENALTY_FOR_DISPUTED_WINDOW_POST: TokenAmount = TokenAmount::from_whole(20);
+
+    /// Floor of the gas fee used to compute the network fee of aggregates and batches.
+    pub static ref BATCH_BALANCER: TokenAmount = TokenAmount::from_nano(5);
 }
 
 // Projection period of expected daily sector block reward penalised when a fault is continued after initial detection.
@@ -48,3 +54,43 @@ pub const CONTINUED_FAULT_PROJECTION_PERIOD: ChainEpoch =
 
 // Maximum number of lifetime days penalized when a sector is terminated.
 pub const TERMINATION_LIFETIME_CAP: ChainEpoch = 140;
+
+const BATCH_DISCOUNT_NUM: u32 = 1;
+const BATCH_DISCOUNT_DENOM: u32 = 20;
+
+lazy_static! {
+    pub static ref ESTIMATED_SINGLE_PROVE_COMMIT_GAS_USAGE: BigInt = BigInt::from(49299973);
+    pub static ref ESTIMATED_SINGLE_PRE_COMMIT_GAS_USAGE: BigInt = BigInt::from(16433324);
+}
+
+pub fn aggregate_prove_commit_network_fee(
+    aggregate_size: i64,
+    base_fee: &TokenAmount,
+) -> TokenAmount {
+    aggregate_network_fee(
+        aggregate_size,
+        &ESTIMATED_SINGLE_PROVE_COMMIT_GAS_USAGE,
+        base_fee,
+    )
+}
+
+pub fn aggregate_pre_commit_network_fee(
+    aggregate_size: i64,
+    base_fee: &TokenAmount,
+) -> TokenAmount {
+    aggregate_network_fee(
+        aggregate_size,
+        &ESTIMATED_SINGLE_PRE_COMMIT_GAS_USAGE,
+        base_fee,
+    )
+}
+
+pub fn aggregate_network_fee(
+    aggregate_size: i64,
+    gas_usage: &BigInt,
+    base_fee: &TokenAmount,
+) -> TokenAmount {
+    let effective_gas_fee = max(base_fee, &*BATCH_BALANCER);
+    let network_fee_num = effective_gas_fee * gas_usage * aggregate_size * BATCH_DISCOUNT_NUM;
+    network_fee_num.div_floor(BATCH_DISCOUNT_DENOM)
+}
@@ -0,0 +1,165 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+#[cfg(feature = "json")]
+use fil_actors_shared::lotus_json;
+use fvm_ipld_bitfield::BitField;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
+
+fil_actors_shared::versioned_state! {
+    actor: "miner",
+    type: Miner,
+    /// The fields of the miner state common to all versions, with version-agnostic types, to compare
+    /// states across an upgrade.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub info: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+        pub pre_commit_deposits: TokenAmount,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+        pub locked_funds: TokenAmount,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub vesting_funds: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+        pub fee_debt: TokenAmount,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+        pub initial_pledge: TokenAmount,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub pre_committed_sectors: Cid,
+        #[cfg_attr(
+            feature = "json",
+            serde(rename = "PreCommittedSectorsCleanUp", with = "lotus_json::cid")
+        )]
+        pub pre_committed_sectors_cleanup: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub allocated_sectors: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub sectors: Cid,
+        pub proving_period_start: ChainEpoch,
+        pub current_deadline: u64,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub deadlines: Cid,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::bitfield"))]
+        pub early_terminations: BitField,
+        pub deadline_cron_active: bool,
+    }
+    versions {
+        V8 => 8, crate::v8::State;
+        V9 => 9, crate::v9::State;
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        info: st.info,
+        pre_commit_deposits: TokenAmount::from_atto(
+            st.pre_commit_deposits.atto().clone(),
+        ),
+        locked_funds: TokenAmount::from_atto(st.locked_funds.atto().clone()),
+        vesting_funds: st.vesting_funds,
+        fee_debt: TokenAmount::from_atto(st.fee_debt.atto().clone()),
+        initial_pledge: TokenAmount::from_atto(st.initial_pledge.atto().clone()),
+        pre_committed_sectors: st.pre_committed_sectors,
+        pre_committed_sectors_cleanup: st.pre_committed_sectors_cleanup,
+        allocated_sectors: st.allocated_sectors,
+        sectors: st.sectors,
+        proving_period_start: st.proving_period_start,
+        current_deadline: st.current_deadline,
+        deadlines: st.deadlines,
+        early_terminations: st.early_terminations.clone(),
+        deadline_cron_active: st.deadline_cron_active,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::{MinerInfo, State};
+    use fil_actors_shared::actor_type::ActorType;
+    use fil_actors_shared::bundle::KnownCids;
+    use fil_actors_shared::v16::runtime::Policy;
+    use fil_actors_shared::versioned::UpgradeVersions;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::{CborStore, IPLD_RAW};
+    use fvm_shared4::sector::RegisteredPoStProof;
+    use fvm_shared4::version::NetworkVersion;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    #[test]
+    fn test_load_dual() {
+        let store = MemoryBlockstore::new();
+        let info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let info = store.put_cbor(&info, Code::Blake2b256).unwrap();
+        let mut st = State::new(&Policy::default(), &store, info, 0, 0).unwrap();
+        let pre_root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+        st.fee_debt = TokenAmount::from_atto(5);
+        let post_root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let code = |name: &str, version: u64| {
+            Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(format!("{name}{version}").as_bytes()))
+        };
+        let versions = UpgradeVersions::at(NetworkVersion::V25).unwrap();
+        let mut known = KnownCids::default();
+        for version in [7, versions.pre, versions.post] {
+            known.insert("testnet", version, ActorType::Miner, code("miner", version));
+            known.insert("testnet", version, ActorType::Power, code("power", version));
+        }
+        let (pre, post) = load_dual(
+            &store,
+            &known,
+            &code("miner", versions.pre),
+            &pre_root,
+            &code("miner", versions.post),
+            &post_root,
+        )
+        .unwrap();
+        assert_eq!((pre.version(), post.version()), (15, 16));
+        let changes = pre.normalize().unwrap().changes(&post.normalize().unwrap());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "fee_debt");
+
+        // Versions this crate does not decode, codes of other actors and unknown codes fail.
+        let (pre, post) = (&pre_root, &post_root);
+        let miner = |version| code("miner", version);
+        assert!(load_dual(&store, &known, &miner(7), pre, &miner(16), post).is_err());
+        let power = code("power", 16);
+        assert!(load_dual(&store, &known, &miner(15), pre, &power, post).is_err());
+        assert!(load_dual(&store, &known, &miner(15), pre, &miner(17), post).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_lotus_json() {
+        let fixture = include_str!("../testdata/lotus_state_v16.json");
+        let state: NormalizedState = serde_json::from_str(fixture).unwrap();
+        assert_eq!(state.fee_debt, TokenAmount::from_atto(1500));
+        assert_eq!(state.current_deadline, 31);
+        assert_eq!(
+            state.early_terminations.iter().collect::<Vec<_>>(),
+            [3, 4, 10]
+        );
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::from_str::<serde_json::Value>(fixture).unwrap()
+        );
+    }
+}
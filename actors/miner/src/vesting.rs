@@ -0,0 +1,80 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
+
+use crate::v16::VestingFunds;
+use crate::versioned::VersionedState;
+
+impl VersionedState {
+    /// Returns the vesting schedule of the miner, the funds which vest at each epoch, in epoch
+    /// order.
+    ///
+    /// The vesting table has had the same layout since v8, a single block holding the list of
+    /// `(epoch, amount)` entries, so it is decoded the same way whatever the version.
+    pub fn vesting_funds<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<Vec<(ChainEpoch, TokenAmount)>> {
+        let root = self.normalize()?.vesting_funds;
+        let funds: VestingFunds = store
+            .get_cbor(&root)?
+            .with_context(|| format!("vesting funds {root} not found"))?;
+        Ok(funds
+            .funds
+            .into_iter()
+            .map(|fund| (fund.epoch, fund.amount))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v16::{MinerInfo, State, VestingFund};
+    use fil_actors_shared::v16::runtime::Policy;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared4::sector::RegisteredPoStProof;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_vesting_funds() {
+        let store = MemoryBlockstore::new();
+        let info = MinerInfo::new(
+            100,
+            101,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let info = store.put_cbor(&info, Code::Blake2b256).unwrap();
+        let mut st = State::new(&Policy::default(), &store, info, 0, 0).unwrap();
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+        let state = VersionedState::load(&store, 16, &root).unwrap();
+        assert!(state.vesting_funds(&store).unwrap().is_empty());
+
+        let fund = |epoch, amount| VestingFund {
+            epoch,
+            amount: TokenAmount::from_atto(amount),
+        };
+        let funds = VestingFunds {
+            funds: vec![fund(100, 5), fund(200, 7)],
+        };
+        st.save_vesting_funds(&store, &funds).unwrap();
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+        let state = VersionedState::load(&store, 16, &root).unwrap();
+        assert_eq!(
+            state.vesting_funds(&store).unwrap(),
+            [
+                (100, TokenAmount::from_atto(5)),
+                (200, TokenAmount::from_atto(7))
+            ]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::anyhow;
+use fvm_ipld_bitfield::BitField;
+use fvm_ipld_encoding::to_vec;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::randomness::{Randomness, RANDOMNESS_LENGTH};
+use fvm_shared4::sector::{PoStProof, RegisteredPoStProof};
+
+use crate::v16::{PoStPartition, SubmitWindowedPoStParams};
+
+/// Estimates the size in bytes of the encoded `SubmitWindowedPoSt` parameters proving the given
+/// number of partitions, so that providers can decide ahead of time whether to split a deadline
+/// across several messages.
+///
+/// The estimate assumes no skipped sectors and a single proof type, and is a lower bound
+/// otherwise. The parameters encoding has not changed since v8.
+pub fn estimate_post_message_size(
+    partitions: u64,
+    proof_type: RegisteredPoStProof,
+) -> anyhow::Result<usize> {
+    let proof_size = proof_type.proof_size().map_err(|e| anyhow!(e))?;
+    let params = SubmitWindowedPoStParams {
+        deadline: u64::MAX,
+        partitions: (0..partitions)
+            .map(|index| PoStPartition {
+                index,
+                skipped: BitField::new(),
+            })
+            .collect(),
+        proofs: vec![PoStProof {
+            post_proof: proof_type,
+            proof_bytes: vec![0; proof_size * partitions as usize],
+        }],
+        chain_commit_epoch: ChainEpoch::MAX,
+        chain_commit_rand: Randomness(vec![0; RANDOMNESS_LENGTH]),
+    };
+    Ok(to_vec(&params)?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_post_message_size() {
+        let proof_type = RegisteredPoStProof::StackedDRGWindow32GiBV1P1;
+        let one = estimate_post_message_size(1, proof_type).unwrap();
+        let two = estimate_post_message_size(2, proof_type).unwrap();
+        assert!(one > 192);
+        assert!(two - one >= 192);
+
+        assert!(estimate_post_message_size(1, RegisteredPoStProof::Invalid(-1)).is_err());
+    }
+}
@@ -16,7 +16,7 @@ use indexmap::IndexMap;
 use num_traits::Zero;
 
 use super::make_map_with_root;
-use super::types::Transaction;
+use super::types::{ApprovalOutcome, Transaction};
 use super::TxnID;
 
 /// Multisig actor state
@@ -70,6 +70,53 @@ impl State {
         TokenAmount::from_atto(numerator.atto().div_ceil(&denominator))
     }
 
+    /// Determines what approving pending transaction `txn_id` by `approver` (an ID address) would
+    /// do, given the actor's current balance, mirroring the checks of the `Approve` method.
+    pub fn simulate_approval<BS: Blockstore>(
+        &self,
+        store: &BS,
+        txn_id: TxnID,
+        approver: &Address,
+        balance: &TokenAmount,
+        curr_epoch: ChainEpoch,
+    ) -> anyhow::Result<ApprovalOutcome> {
+        if !self.is_signer(approver) {
+            return Ok(ApprovalOutcome::NotSigner);
+        }
+        let txn = make_map_with_root::<_, Transaction>(&self.pending_txs, store)?
+            .get(&txn_id.key())?
+            .cloned();
+        let Some(txn) = txn else {
+            return Ok(ApprovalOutcome::NotFound);
+        };
+        if txn.approved.contains(approver) {
+            return Ok(ApprovalOutcome::Duplicate);
+        }
+
+        let approvals = txn.approved.len() as u64 + 1;
+        if approvals < self.num_approvals_threshold {
+            let remaining_signers = self
+                .signers
+                .iter()
+                .filter(|s| *s != approver && !txn.approved.contains(s))
+                .cloned()
+                .collect();
+            return Ok(ApprovalOutcome::Pending {
+                approvals_needed: self.num_approvals_threshold - approvals,
+                remaining_signers,
+            });
+        }
+
+        let spendable = txn.value.is_zero()
+            || (balance >= &txn.value
+                && balance - &txn.value >= self.amount_locked(curr_epoch - self.start_epoch));
+        if spendable {
+            Ok(ApprovalOutcome::Executes)
+        } else {
+            Ok(ApprovalOutcome::InsufficientFunds)
+        }
+    }
+
     /// Iterates all pending transactions and removes an address from each list of approvals,
     /// if present.  If an approval list becomes empty, the pending transaction is deleted.
     pub fn purge_approvals<BS: Blockstore>(
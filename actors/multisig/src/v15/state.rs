@@ -15,7 +15,7 @@ use num_traits::Zero;
 use fil_actors_shared::actor_error_v15;
 use fil_actors_shared::v15::{ActorError, Config, Map2, DEFAULT_HAMT_CONFIG};
 
-use super::types::Transaction;
+use super::types::{ApprovalOutcome, Transaction};
 use super::TxnID;
 
 pub type PendingTxnMap<BS> = Map2<BS, TxnID, Transaction>;
@@ -72,6 +72,54 @@ impl State {
         TokenAmount::from_atto(numerator.atto().div_ceil(&denominator))
     }
 
+    /// Determines what approving pending transaction `txn_id` by `approver` (an ID address) would
+    /// do, given the actor's current balance, mirroring the checks of the `Approve` method.
+    pub fn simulate_approval<BS: Blockstore>(
+        &self,
+        store: &BS,
+        txn_id: TxnID,
+        approver: &Address,
+        balance: &TokenAmount,
+        curr_epoch: ChainEpoch,
+    ) -> anyhow::Result<ApprovalOutcome> {
+        if !self.is_signer(approver) {
+            return Ok(ApprovalOutcome::NotSigner);
+        }
+        let txn =
+            PendingTxnMap::load(store, &self.pending_txs, PENDING_TXN_CONFIG, "pending txns")?
+                .get(&txn_id)?
+                .cloned();
+        let Some(txn) = txn else {
+            return Ok(ApprovalOutcome::NotFound);
+        };
+        if txn.approved.contains(approver) {
+            return Ok(ApprovalOutcome::Duplicate);
+        }
+
+        let approvals = txn.approved.len() as u64 + 1;
+        if approvals < self.num_approvals_threshold {
+            let remaining_signers = self
+                .signers
+                .iter()
+                .filter(|s| *s != approver && !txn.approved.contains(s))
+                .cloned()
+                .collect();
+            return Ok(ApprovalOutcome::Pending {
+                approvals_needed: self.num_approvals_threshold - approvals,
+                remaining_signers,
+            });
+        }
+
+        let spendable = txn.value.is_zero()
+            || (balance >= &txn.value
+                && balance - &txn.value >= self.amount_locked(curr_epoch - self.start_epoch));
+        if spendable {
+            Ok(ApprovalOutcome::Executes)
+        } else {
+            Ok(ApprovalOutcome::InsufficientFunds)
+        }
+    }
+
     /// Iterates all pending transactions and removes an address from each list of approvals,
     /// if present.  If an approval list becomes empty, the pending transaction is deleted.
     pub fn purge_approvals<BS: Blockstore>(
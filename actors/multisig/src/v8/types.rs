@@ -152,3 +152,25 @@ pub struct LockBalanceParams {
     pub unlock_duration: ChainEpoch,
     pub amount: TokenAmount,
 }
+
+/// Outcome of a signer approving a pending transaction, see `State::simulate_approval`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ApprovalOutcome {
+    /// The approver is not a signer, so the approval would be rejected.
+    NotSigner,
+    /// There is no pending transaction with the given ID.
+    NotFound,
+    /// The approver has already approved the transaction, so the approval would be rejected.
+    Duplicate,
+    /// The transaction would remain pending. `remaining_signers` lists the signers that have not
+    /// approved yet, `approvals_needed` of which must still approve.
+    Pending {
+        approvals_needed: u64,
+        remaining_signers: Vec<Address>,
+    },
+    /// The approval reaches the threshold and the transaction would be executed.
+    Executes,
+    /// The approval reaches the threshold, but the value can't be spent yet because of the
+    /// balance or the vesting lock, so the approval would fail.
+    InsufficientFunds,
+}
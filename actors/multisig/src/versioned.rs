@@ -0,0 +1,192 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+#[cfg(feature = "json")]
+use fil_actors_shared::lotus_json;
+use fil_actors_shared::versioned::normalize_address;
+use fvm_shared4::address::Address;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
+
+/// The transaction ID type of all versions. It has not changed since v8.
+pub use crate::v16::TxnID;
+
+/// The vesting schedule of a multisig: the initial balance unlocks linearly over
+/// `unlock_duration` epochs from `start_epoch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "PascalCase")
+)]
+pub struct UnlockSchedule {
+    #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+    pub initial_balance: TokenAmount,
+    pub start_epoch: ChainEpoch,
+    pub unlock_duration: ChainEpoch,
+}
+
+fil_actors_shared::versioned_state! {
+    actor: "multisig",
+    type: Multisig,
+    /// The fields of the multisig state, with version-agnostic types. The layout has not changed
+    /// since v8.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::address::vec"))]
+        pub signers: Vec<Address>,
+        pub num_approvals_threshold: u64,
+        #[cfg_attr(feature = "json", serde(rename = "NextTxnID"))]
+        pub next_tx_id: TxnID,
+        /// Flattened in the Lotus JSON, like in the state.
+        #[cfg_attr(feature = "json", serde(flatten))]
+        pub unlock_schedule: UnlockSchedule,
+        /// Root of the pending transactions, a HAMT keyed by transaction ID.
+        #[cfg_attr(feature = "json", serde(rename = "PendingTxns", with = "lotus_json::cid"))]
+        pub pending_txs: Cid,
+    }
+    versions {
+        V8 => 8, crate::v8::State;
+        V9 => 9, crate::v9::State;
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        signers: st
+            .signers
+            .iter()
+            .map(|signer| normalize_address(&signer.to_bytes()))
+            .collect::<anyhow::Result<_>>()?,
+        num_approvals_threshold: st.num_approvals_threshold,
+        next_tx_id: TxnID(st.next_tx_id.0),
+        unlock_schedule: UnlockSchedule {
+            initial_balance: TokenAmount::from_atto(st.initial_balance.atto().clone()),
+            start_epoch: st.start_epoch,
+            unlock_duration: st.unlock_duration,
+        },
+        pending_txs: st.pending_txs,
+    }
+}
+
+impl VersionedState {
+    /// Returns the addresses allowed to propose and approve transactions, as set by the
+    /// multisig. They are ID addresses for multisigs created or updated after v2.
+    pub fn signers(&self) -> anyhow::Result<Vec<Address>> {
+        Ok(self.normalize()?.signers)
+    }
+
+    /// Returns the number of approvals required to execute a transaction.
+    pub fn num_approvals_threshold(&self) -> anyhow::Result<u64> {
+        Ok(self.normalize()?.num_approvals_threshold)
+    }
+
+    /// Returns the ID the next proposed transaction will get.
+    pub fn next_txn_id(&self) -> anyhow::Result<TxnID> {
+        Ok(self.normalize()?.next_tx_id)
+    }
+
+    pub fn initial_balance(&self) -> anyhow::Result<TokenAmount> {
+        Ok(self.normalize()?.unlock_schedule.initial_balance)
+    }
+
+    pub fn unlock_schedule(&self) -> anyhow::Result<UnlockSchedule> {
+        Ok(self.normalize()?.unlock_schedule)
+    }
+
+    /// Returns the part of the initial balance still locked at the given epoch, with the vesting
+    /// rules of the actors version.
+    pub fn locked_balance(&self, epoch: ChainEpoch) -> TokenAmount {
+        let locked = match self {
+            Self::V8(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+            Self::V9(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+            Self::V10(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+            Self::V11(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+            Self::V12(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+            Self::V13(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+            Self::V14(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+            Self::V15(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+            Self::V16(st) => st.amount_locked(epoch - st.start_epoch).atto().clone(),
+        };
+        TokenAmount::from_atto(locked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_versioned_state() {
+        let store = MemoryBlockstore::new();
+        let pending_txs = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        let st = crate::v8::State {
+            signers: vec![
+                fvm_shared::address::Address::new_id(100),
+                fvm_shared::address::Address::new_id(101),
+            ],
+            num_approvals_threshold: 2,
+            next_tx_id: crate::v8::TxnID(5),
+            initial_balance: fvm_shared::econ::TokenAmount::from_atto(1000),
+            start_epoch: 100,
+            unlock_duration: 10,
+            pending_txs,
+        };
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let pre = VersionedState::load(&store, 8, &root).unwrap();
+        let post = VersionedState::load(&store, 16, &root).unwrap();
+        assert_eq!(pre.signers().unwrap(), [Address::new_id(100), Address::new_id(101)]);
+        assert_eq!(pre.num_approvals_threshold().unwrap(), 2);
+        assert_eq!(pre.next_txn_id().unwrap(), TxnID(5));
+        assert_eq!(pre.initial_balance().unwrap(), TokenAmount::from_atto(1000));
+        assert_eq!(pre.unlock_schedule().unwrap().unlock_duration, 10);
+        assert_eq!(pre.locked_balance(50), TokenAmount::from_atto(1000));
+        assert_eq!(pre.locked_balance(105), TokenAmount::from_atto(500));
+        assert!(pre.locked_balance(110).is_zero());
+        assert!(pre.normalize().unwrap().changes(&post.normalize().unwrap()).is_empty());
+        assert!(VersionedState::load(&store, 7, &root).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_lotus_json() {
+        let pending_txs: Cid = "bafy2bzaceatmk3tltlnrmfonzfntld6c46irnrxwcwdkm2wcnnqo3ngdiku5w"
+            .parse()
+            .unwrap();
+        let state = NormalizedState {
+            signers: vec![Address::new_id(100), Address::new_id(101)],
+            num_approvals_threshold: 2,
+            next_tx_id: TxnID(5),
+            unlock_schedule: UnlockSchedule {
+                initial_balance: TokenAmount::from_atto(1000),
+                start_epoch: 100,
+                unlock_duration: 10,
+            },
+            pending_txs,
+        };
+        let value = serde_json::json!({
+            "Signers": ["f0100", "f0101"],
+            "NumApprovalsThreshold": 2,
+            "NextTxnID": 5,
+            "InitialBalance": "1000",
+            "StartEpoch": 100,
+            "UnlockDuration": 10,
+            "PendingTxns": { "/": pending_txs.to_string() },
+        });
+        assert_eq!(serde_json::to_value(&state).unwrap(), value);
+        assert_eq!(serde_json::from_value::<NormalizedState>(value).unwrap(), state);
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use cid::Cid;
+#[cfg(feature = "json")]
+use fil_actors_shared::lotus_json;
+use fil_actors_shared::versioned::normalize_address;
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared4::address::Address;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
+
+/// The lane state type of all versions. Its fields and encoding have not changed since v8, so the
+/// lanes of any version decode as the latest one.
+pub use crate::v16::LaneState;
+
+fil_actors_shared::versioned_state! {
+    actor: "payment channel",
+    type: PaymentChannel,
+    /// The fields of the payment channel state, with version-agnostic types. The layout has not
+    /// changed since v8.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::address"))]
+        pub from: Address,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::address"))]
+        pub to: Address,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::token_amount"))]
+        pub to_send: TokenAmount,
+        /// Epoch at which the channel can be collected, 0 if it is not settling.
+        pub settling_at: ChainEpoch,
+        pub min_settle_height: ChainEpoch,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub lane_states: Cid,
+    }
+    versions {
+        V8 => 8, crate::v8::State;
+        V9 => 9, crate::v9::State;
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        from: normalize_address(&st.from.to_bytes())?,
+        to: normalize_address(&st.to.to_bytes())?,
+        to_send: TokenAmount::from_atto(st.to_send.atto().clone()),
+        settling_at: st.settling_at,
+        min_settle_height: st.min_settle_height,
+        lane_states: st.lane_states,
+    }
+}
+
+impl VersionedState {
+    /// Runs a function over the lanes of the channel, in lane ID order.
+    pub fn for_each_lane<BS, F>(&self, store: &BS, mut f: F) -> anyhow::Result<()>
+    where
+        BS: Blockstore,
+        F: FnMut(u64, &LaneState) -> anyhow::Result<()>,
+    {
+        let root = self.normalize()?.lane_states;
+        Amt::<LaneState, _>::load(&root, store)
+            .with_context(|| format!("failed to load lane states {root}"))?
+            .for_each(|id, lane| f(id, lane))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v8::LANE_STATES_AMT_BITWIDTH;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_versioned_state() {
+        let store = MemoryBlockstore::new();
+        let mut lanes = Amt::new_with_bit_width(&store, LANE_STATES_AMT_BITWIDTH);
+        for (id, nonce) in [(0, 1), (3, 7)] {
+            let lane = crate::v8::LaneState {
+                redeemed: fvm_shared::econ::TokenAmount::from_atto(10 * nonce),
+                nonce,
+            };
+            lanes.set(id, lane).unwrap();
+        }
+        let mut st = crate::v8::State::new(
+            fvm_shared::address::Address::new_id(100),
+            fvm_shared::address::Address::new_id(101),
+            lanes.flush().unwrap(),
+        );
+        st.settling_at = 500;
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let state = VersionedState::load(&store, 8, &root).unwrap();
+        let normalized = state.normalize().unwrap();
+        assert_eq!(normalized.from, Address::new_id(100));
+        assert_eq!(normalized.to, Address::new_id(101));
+        assert_eq!(normalized.settling_at, 500);
+        let mut lanes = Vec::new();
+        state
+            .for_each_lane(&store, |id, lane| {
+                lanes.push((id, lane.nonce, lane.redeemed.clone()));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            lanes,
+            [
+                (0, 1, TokenAmount::from_atto(10)),
+                (3, 7, TokenAmount::from_atto(70))
+            ]
+        );
+
+        // The same state reads the same as a v16 one.
+        let pre = VersionedState::load(&store, 8, &root).unwrap();
+        let post = VersionedState::load(&store, 16, &root).unwrap();
+        assert!(pre.normalize().unwrap().changes(&post.normalize().unwrap()).is_empty());
+        assert!(VersionedState::load(&store, 7, &root).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+// Compares reads of the power table through the versioned power state with the version-agnostic
+// path, which decodes blocks through IPLD. Run with `cargo bench -p fil_actor_power_state`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fil_actor_power_state::v16::{set_claim, Claim, State};
+use fil_actors_shared::v16::runtime::Policy;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::address::Address;
+use fvm_shared4::sector::{RegisteredPoStProof, StoragePower};
+use fvm_shared4::HAMT_BIT_WIDTH;
+use ipld_core::ipld::Ipld;
+
+const MINERS: u64 = 5_000;
+
+fn power_state(store: &MemoryBlockstore) -> State {
+    let mut st = State::new(store).unwrap();
+    let mut claims = st.load_claims(store).unwrap();
+    for id in 0..MINERS {
+        let power: StoragePower = StoragePower::from(id) << 40;
+        let claim = Claim {
+            window_post_proof_type: RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+            raw_byte_power: power.clone(),
+            quality_adj_power: power,
+        };
+        set_claim(&mut claims, &Address::new_id(1000 + id), claim).unwrap();
+    }
+    st.save_claims(&mut claims).unwrap();
+    st
+}
+
+fn power_table(c: &mut Criterion) {
+    let store = MemoryBlockstore::new();
+    let st = power_state(&store);
+    let policy = Policy::default();
+    let mut group = c.benchmark_group("power_table");
+    group.sample_size(20);
+    group.bench_function("versioned", |b| {
+        b.iter(|| {
+            let mut total = StoragePower::default();
+            st.load_claims(black_box(&store))
+                .unwrap()
+                .for_each(|_, claim| {
+                    total += &claim.quality_adj_power;
+                    Ok(())
+                })
+                .unwrap();
+            total
+        })
+    });
+    group.bench_function("ipld", |b| {
+        b.iter(|| {
+            let mut total = StoragePower::default();
+            Hamt::<_, Ipld, BytesKey>::load_with_bit_width(
+                &st.claims,
+                black_box(&store),
+                HAMT_BIT_WIDTH,
+            )
+            .unwrap()
+            .for_each(|_, claim| {
+                let claim: Claim = ipld_core::serde::from_ipld(claim.clone())?;
+                total += claim.quality_adj_power;
+                Ok(())
+            })
+            .unwrap();
+            total
+        })
+    });
+    group.bench_function("above_consensus_minimum", |b| {
+        b.iter(|| {
+            st.miners_above_consensus_minimum(black_box(&store), &policy)
+                .unwrap()
+                .len()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, power_table);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_shared4::clock::ChainEpoch;
+
+/// An inconsistency between the cron bookkeeping of the power actor and the epoch of its state,
+/// found by `State::check_cron`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CronIssue {
+    /// Cron did not process the epochs from `first_cron_epoch` to the epoch of the state.
+    Stalled {
+        first_cron_epoch: ChainEpoch,
+        epoch: ChainEpoch,
+    },
+    /// `first_cron_epoch` is past the epoch following the state, so cron would skip epochs.
+    Ahead {
+        first_cron_epoch: ChainEpoch,
+        epoch: ChainEpoch,
+    },
+    /// Events are enrolled at an epoch cron already went past, and will never be processed.
+    StaleEvents {
+        event_epoch: ChainEpoch,
+        first_cron_epoch: ChainEpoch,
+    },
+}
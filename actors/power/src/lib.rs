@@ -1,6 +1,10 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+pub mod cron;
+#[cfg(feature = "legacy-compat")]
+pub mod legacy;
+pub mod summary;
 pub mod v10;
 pub mod v11;
 pub mod v12;
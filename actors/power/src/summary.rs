@@ -0,0 +1,108 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_shared4::econ::TokenAmount;
+use fvm_shared4::sector::StoragePower;
+use fvm_shared4::smooth::FilterEstimate;
+
+/// The network totals of a power actor state, as returned by `State::summary`, with the types of
+/// the latest version. They are read from the state root, without loading the claims.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerSummary {
+    /// Raw byte power of the miners above the consensus minimum, updated as claims change.
+    pub total_raw_byte_power: StoragePower,
+    /// Quality-adjusted power of the miners above the consensus minimum, updated as claims
+    /// change.
+    pub total_quality_adj_power: StoragePower,
+    /// Raw bytes committed by all miners, including those below the consensus minimum.
+    pub total_bytes_committed: StoragePower,
+    /// Quality-adjusted bytes committed by all miners, including those below the consensus
+    /// minimum.
+    pub total_qa_bytes_committed: StoragePower,
+    pub total_pledge_collateral: TokenAmount,
+    /// Raw byte power snapshotted at the end of the last cron tick, used for this epoch.
+    pub this_epoch_raw_byte_power: StoragePower,
+    /// Quality-adjusted power snapshotted at the end of the last cron tick, used for this epoch.
+    pub this_epoch_quality_adj_power: StoragePower,
+    /// Pledge collateral snapshotted at the end of the last cron tick, used for this epoch.
+    pub this_epoch_pledge_collateral: TokenAmount,
+    /// Raw byte power of the network as `StateMinerPower` reports it: the committed bytes of all
+    /// miners while fewer than the consensus minimum number of miners have reached the minimum
+    /// power, [`total_raw_byte_power`](Self::total_raw_byte_power) otherwise.
+    pub current_raw_byte_power: StoragePower,
+    /// Quality-adjusted counterpart of
+    /// [`current_raw_byte_power`](Self::current_raw_byte_power).
+    pub current_quality_adj_power: StoragePower,
+    pub miner_count: i64,
+    /// Number of miners having proven the minimum consensus power.
+    pub miner_above_min_power_count: i64,
+    /// Smoothed estimate of the quality-adjusted power, used for block rewards and pledges.
+    pub this_epoch_qa_power_smoothed: FilterEstimate,
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_summary() {
+        let store = MemoryBlockstore::new();
+        let mut v8 = crate::v8::State::new(&store).unwrap();
+        v8.total_raw_byte_power = 1.into();
+        v8.total_quality_adj_power = 2.into();
+        v8.total_bytes_committed = 3.into();
+        v8.total_qa_bytes_committed = 4.into();
+        v8.total_pledge_collateral = fvm_shared::econ::TokenAmount::from_atto(5);
+        v8.this_epoch_raw_byte_power = 6.into();
+        v8.this_epoch_quality_adj_power = 7.into();
+        v8.this_epoch_pledge_collateral = fvm_shared::econ::TokenAmount::from_atto(8);
+        v8.miner_count = 9;
+        v8.miner_above_min_power_count = 1;
+        v8.this_epoch_qa_power_smoothed =
+            fvm_shared::smooth::FilterEstimate::new(10.into(), 11.into());
+
+        let mut v16 = crate::v16::State::new(&store).unwrap();
+        v16.total_raw_byte_power = 1.into();
+        v16.total_quality_adj_power = 2.into();
+        v16.total_bytes_committed = 3.into();
+        v16.total_qa_bytes_committed = 4.into();
+        v16.total_pledge_collateral = fvm_shared4::econ::TokenAmount::from_atto(5);
+        v16.this_epoch_raw_byte_power = 6.into();
+        v16.this_epoch_quality_adj_power = 7.into();
+        v16.this_epoch_pledge_collateral = fvm_shared4::econ::TokenAmount::from_atto(8);
+        v16.miner_count = 9;
+        v16.miner_above_min_power_count = 1;
+        v16.this_epoch_qa_power_smoothed =
+            fil_actors_shared::v16::builtin::reward::smooth::FilterEstimate::new(
+                10.into(),
+                11.into(),
+            );
+
+        let summary = v8.summary();
+        assert_eq!(summary.total_raw_byte_power, 1.into());
+        assert_eq!(summary.total_quality_adj_power, 2.into());
+        assert_eq!(summary.total_bytes_committed, 3.into());
+        assert_eq!(summary.total_qa_bytes_committed, 4.into());
+        assert_eq!(summary.total_pledge_collateral.atto(), &5.into());
+        assert_eq!(summary.this_epoch_raw_byte_power, 6.into());
+        assert_eq!(summary.this_epoch_quality_adj_power, 7.into());
+        assert_eq!(summary.this_epoch_pledge_collateral.atto(), &8.into());
+        assert_eq!(summary.miner_count, 9);
+        assert_eq!(summary.miner_above_min_power_count, 1);
+        assert_eq!(
+            summary.this_epoch_qa_power_smoothed,
+            fvm_shared4::smooth::FilterEstimate::new(10.into(), 11.into())
+        );
+        // Below the consensus minimum, the committed bytes are reported.
+        assert_eq!(summary.current_raw_byte_power, 3.into());
+        assert_eq!(summary.current_quality_adj_power, 4.into());
+        assert_eq!(summary, v16.summary());
+
+        v8.miner_above_min_power_count = 4;
+        v16.miner_above_min_power_count = 4;
+        let summary = v8.summary();
+        assert_eq!(summary.current_raw_byte_power, 1.into());
+        assert_eq!(summary.current_quality_adj_power, 2.into());
+        assert_eq!(summary, v16.summary());
+    }
+}
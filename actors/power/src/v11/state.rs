@@ -26,6 +26,8 @@ use lazy_static::lazy_static;
 use num_traits::Signed;
 
 use super::{CONSENSUS_MINER_MIN_MINERS, CRON_QUEUE_AMT_BITWIDTH, CRON_QUEUE_HAMT_BITWIDTH};
+use crate::cron::CronIssue;
+use crate::summary::PowerSummary;
 
 lazy_static! {
     /// genesis power in bytes = 750,000 GiB
@@ -142,6 +144,31 @@ impl State {
         }
     }
 
+    /// Lists the miners meeting the consensus minimum power, as checked by
+    /// [`Self::miner_nominal_power_meets_consensus_minimum`], in a single pass over the claims.
+    pub fn miners_above_consensus_minimum<BS: Blockstore>(
+        &self,
+        store: &BS,
+        policy: &Policy,
+    ) -> Result<Vec<Address>, ActorError> {
+        let below_min_miners = self.miner_above_min_power_count < CONSENSUS_MINER_MIN_MINERS;
+        let claims =
+            make_map_with_root_and_bitwidth::<_, Claim>(&self.claims, store, HAMT_BIT_WIDTH)
+                .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to load claims")?;
+        let mut miners = Vec::new();
+        claims
+            .for_each(|key, claim| {
+                let min_power = consensus_miner_min_power(policy, claim.window_post_proof_type)?;
+                let power = &claim.raw_byte_power;
+                if power >= &min_power || (below_min_miners && power.is_positive()) {
+                    miners.push(Address::from_bytes(key)?);
+                }
+                Ok(())
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate claims")?;
+        Ok(miners)
+    }
+
     pub fn miner_power<BS: Blockstore>(
         &self,
         s: &BS,
@@ -151,6 +178,76 @@ impl State {
         get_claim(&claims, miner).map(|s| s.cloned())
     }
 
+    /// Checks the cron bookkeeping against the epoch of the state, that is the epoch whose tipset
+    /// produced it. The cron tick ending that epoch processes the events enrolled up to it and
+    /// moves `first_cron_epoch` to the next epoch, so any other value means cron stalled or the
+    /// bookkeeping was broken, e.g. by a migration.
+    pub fn check_cron<BS: Blockstore>(
+        &self,
+        store: &BS,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<Vec<CronIssue>> {
+        let mut issues = Vec::new();
+        let first_cron_epoch = self.first_cron_epoch;
+        if first_cron_epoch <= epoch {
+            issues.push(CronIssue::Stalled {
+                first_cron_epoch,
+                epoch,
+            });
+        } else if first_cron_epoch > epoch + 1 {
+            issues.push(CronIssue::Ahead {
+                first_cron_epoch,
+                epoch,
+            });
+        }
+
+        let events = Multimap::from_root(
+            store,
+            &self.cron_event_queue,
+            CRON_QUEUE_HAMT_BITWIDTH,
+            CRON_QUEUE_AMT_BITWIDTH,
+        )?;
+        events.for_all::<_, CronEvent>(|key, _| {
+            let (event_epoch, _) = ChainEpoch::decode_var(key)
+                .ok_or_else(|| anyhow::anyhow!("invalid cron event epoch key {key:?}"))?;
+            if event_epoch < first_cron_epoch {
+                issues.push(CronIssue::StaleEvents {
+                    event_epoch,
+                    first_cron_epoch,
+                });
+            }
+            Ok(())
+        })?;
+        Ok(issues)
+    }
+
+    /// Returns the network totals of the state.
+    pub fn summary(&self) -> PowerSummary {
+        let (current_raw_byte_power, current_quality_adj_power) = self.current_total_power();
+        PowerSummary {
+            total_raw_byte_power: self.total_raw_byte_power.clone(),
+            total_quality_adj_power: self.total_quality_adj_power.clone(),
+            total_bytes_committed: self.total_bytes_committed.clone(),
+            total_qa_bytes_committed: self.total_qa_bytes_committed.clone(),
+            total_pledge_collateral: fvm_shared4::econ::TokenAmount::from_atto(
+                self.total_pledge_collateral.atto().clone(),
+            ),
+            this_epoch_raw_byte_power: self.this_epoch_raw_byte_power.clone(),
+            this_epoch_quality_adj_power: self.this_epoch_quality_adj_power.clone(),
+            this_epoch_pledge_collateral: fvm_shared4::econ::TokenAmount::from_atto(
+                self.this_epoch_pledge_collateral.atto().clone(),
+            ),
+            current_raw_byte_power,
+            current_quality_adj_power,
+            miner_count: self.miner_count,
+            miner_above_min_power_count: self.miner_above_min_power_count,
+            this_epoch_qa_power_smoothed: fvm_shared4::smooth::FilterEstimate {
+                position: self.this_epoch_qa_power_smoothed.position.clone(),
+                velocity: self.this_epoch_qa_power_smoothed.velocity.clone(),
+            },
+        }
+    }
+
     pub fn current_total_power(&self) -> (StoragePower, StoragePower) {
         if self.miner_above_min_power_count < CONSENSUS_MINER_MIN_MINERS {
             (
@@ -31,6 +31,8 @@ use fil_actors_shared::v14::{
 };
 
 use super::CONSENSUS_MINER_MIN_MINERS;
+use crate::cron::CronIssue;
+use crate::summary::PowerSummary;
 
 lazy_static! {
     /// genesis power in bytes = 750,000 GiB
@@ -144,6 +146,30 @@ impl State {
         }
     }
 
+    /// Lists the miners meeting the consensus minimum power, as checked by
+    /// [`Self::miner_nominal_power_meets_consensus_minimum`], in a single pass over the claims.
+    pub fn miners_above_consensus_minimum<BS: Blockstore>(
+        &self,
+        store: &BS,
+        policy: &Policy,
+    ) -> Result<Vec<Address>, ActorError> {
+        let below_min_miners = self.miner_above_min_power_count < CONSENSUS_MINER_MIN_MINERS;
+        let mut miners = Vec::new();
+        self.load_claims(store)?.for_each(|miner, claim| {
+            let min_power = consensus_miner_min_power(policy, claim.window_post_proof_type)
+                .context_code(
+                    ExitCode::USR_ILLEGAL_STATE,
+                    "could not get miner min power from proof type",
+                )?;
+            let power = &claim.raw_byte_power;
+            if power >= &min_power || (below_min_miners && power.is_positive()) {
+                miners.push(miner);
+            }
+            Ok(())
+        })?;
+        Ok(miners)
+    }
+
     pub fn miner_power<BS: Blockstore>(
         &self,
         s: &BS,
@@ -259,6 +285,72 @@ impl State {
         Ok(())
     }
 
+    /// Checks the cron bookkeeping against the epoch of the state, that is the epoch whose tipset
+    /// produced it. The cron tick ending that epoch processes the events enrolled up to it and
+    /// moves `first_cron_epoch` to the next epoch, so any other value means cron stalled or the
+    /// bookkeeping was broken, e.g. by a migration.
+    pub fn check_cron<BS: Blockstore>(
+        &self,
+        store: &BS,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<Vec<CronIssue>> {
+        let mut issues = Vec::new();
+        let first_cron_epoch = self.first_cron_epoch;
+        if first_cron_epoch <= epoch {
+            issues.push(CronIssue::Stalled {
+                first_cron_epoch,
+                epoch,
+            });
+        } else if first_cron_epoch > epoch + 1 {
+            issues.push(CronIssue::Ahead {
+                first_cron_epoch,
+                epoch,
+            });
+        }
+
+        let events = Multimap::from_root(
+            store,
+            &self.cron_event_queue,
+            CRON_QUEUE_HAMT_BITWIDTH,
+            CRON_QUEUE_AMT_BITWIDTH,
+        )?;
+        events.for_all::<_, CronEvent>(|key, _| {
+            let (event_epoch, _) = ChainEpoch::decode_var(key)
+                .ok_or_else(|| anyhow::anyhow!("invalid cron event epoch key {key:?}"))?;
+            if event_epoch < first_cron_epoch {
+                issues.push(CronIssue::StaleEvents {
+                    event_epoch,
+                    first_cron_epoch,
+                });
+            }
+            Ok(())
+        })?;
+        Ok(issues)
+    }
+
+    /// Returns the network totals of the state.
+    pub fn summary(&self) -> PowerSummary {
+        let (current_raw_byte_power, current_quality_adj_power) = self.current_total_power();
+        PowerSummary {
+            total_raw_byte_power: self.total_raw_byte_power.clone(),
+            total_quality_adj_power: self.total_quality_adj_power.clone(),
+            total_bytes_committed: self.total_bytes_committed.clone(),
+            total_qa_bytes_committed: self.total_qa_bytes_committed.clone(),
+            total_pledge_collateral: self.total_pledge_collateral.clone(),
+            this_epoch_raw_byte_power: self.this_epoch_raw_byte_power.clone(),
+            this_epoch_quality_adj_power: self.this_epoch_quality_adj_power.clone(),
+            this_epoch_pledge_collateral: self.this_epoch_pledge_collateral.clone(),
+            current_raw_byte_power,
+            current_quality_adj_power,
+            miner_count: self.miner_count,
+            miner_above_min_power_count: self.miner_above_min_power_count,
+            this_epoch_qa_power_smoothed: fvm_shared4::smooth::FilterEstimate {
+                position: self.this_epoch_qa_power_smoothed.position.clone(),
+                velocity: self.this_epoch_qa_power_smoothed.velocity.clone(),
+            },
+        }
+    }
+
     pub fn current_total_power(&self) -> (StoragePower, StoragePower) {
         if self.miner_above_min_power_count < CONSENSUS_MINER_MIN_MINERS {
             (
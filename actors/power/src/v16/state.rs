@@ -31,6 +31,8 @@ use fil_actors_shared::v16::{
 };
 
 use super::CONSENSUS_MINER_MIN_MINERS;
+use crate::cron::CronIssue;
+use crate::summary::PowerSummary;
 
 lazy_static! {
     /// genesis power in bytes = 750,000 GiB
@@ -153,6 +155,30 @@ impl State {
         }
     }
 
+    /// Lists the miners meeting the consensus minimum power, as checked by
+    /// [`Self::miner_nominal_power_meets_consensus_minimum`], in a single pass over the claims.
+    pub fn miners_above_consensus_minimum<BS: Blockstore>(
+        &self,
+        store: &BS,
+        policy: &Policy,
+    ) -> Result<Vec<Address>, ActorError> {
+        let below_min_miners = self.miner_above_min_power_count < CONSENSUS_MINER_MIN_MINERS;
+        let mut miners = Vec::new();
+        self.load_claims(store)?.for_each(|miner, claim| {
+            let min_power = consensus_miner_min_power(policy, claim.window_post_proof_type)
+                .context_code(
+                    ExitCode::USR_ILLEGAL_STATE,
+                    "could not get miner min power from proof type",
+                )?;
+            let power = &claim.raw_byte_power;
+            if power >= &min_power || (below_min_miners && power.is_positive()) {
+                miners.push(miner);
+            }
+            Ok(())
+        })?;
+        Ok(miners)
+    }
+
     pub fn miner_power<BS: Blockstore>(
         &self,
         s: &BS,
@@ -268,6 +294,72 @@ impl State {
         Ok(())
     }
 
+    /// Checks the cron bookkeeping against the epoch of the state, that is the epoch whose tipset
+    /// produced it. The cron tick ending that epoch processes the events enrolled up to it and
+    /// moves `first_cron_epoch` to the next epoch, so any other value means cron stalled or the
+    /// bookkeeping was broken, e.g. by a migration.
+    pub fn check_cron<BS: Blockstore>(
+        &self,
+        store: &BS,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<Vec<CronIssue>> {
+        let mut issues = Vec::new();
+        let first_cron_epoch = self.first_cron_epoch;
+        if first_cron_epoch <= epoch {
+            issues.push(CronIssue::Stalled {
+                first_cron_epoch,
+                epoch,
+            });
+        } else if first_cron_epoch > epoch + 1 {
+            issues.push(CronIssue::Ahead {
+                first_cron_epoch,
+                epoch,
+            });
+        }
+
+        let events = Multimap::from_root(
+            store,
+            &self.cron_event_queue,
+            CRON_QUEUE_HAMT_BITWIDTH,
+            CRON_QUEUE_AMT_BITWIDTH,
+        )?;
+        events.for_all::<_, CronEvent>(|key, _| {
+            let (event_epoch, _) = ChainEpoch::decode_var(key)
+                .ok_or_else(|| anyhow::anyhow!("invalid cron event epoch key {key:?}"))?;
+            if event_epoch < first_cron_epoch {
+                issues.push(CronIssue::StaleEvents {
+                    event_epoch,
+                    first_cron_epoch,
+                });
+            }
+            Ok(())
+        })?;
+        Ok(issues)
+    }
+
+    /// Returns the network totals of the state.
+    pub fn summary(&self) -> PowerSummary {
+        let (current_raw_byte_power, current_quality_adj_power) = self.current_total_power();
+        PowerSummary {
+            total_raw_byte_power: self.total_raw_byte_power.clone(),
+            total_quality_adj_power: self.total_quality_adj_power.clone(),
+            total_bytes_committed: self.total_bytes_committed.clone(),
+            total_qa_bytes_committed: self.total_qa_bytes_committed.clone(),
+            total_pledge_collateral: self.total_pledge_collateral.clone(),
+            this_epoch_raw_byte_power: self.this_epoch_raw_byte_power.clone(),
+            this_epoch_quality_adj_power: self.this_epoch_quality_adj_power.clone(),
+            this_epoch_pledge_collateral: self.this_epoch_pledge_collateral.clone(),
+            current_raw_byte_power,
+            current_quality_adj_power,
+            miner_count: self.miner_count,
+            miner_above_min_power_count: self.miner_above_min_power_count,
+            this_epoch_qa_power_smoothed: fvm_shared4::smooth::FilterEstimate {
+                position: self.this_epoch_qa_power_smoothed.position.clone(),
+                velocity: self.this_epoch_qa_power_smoothed.velocity.clone(),
+            },
+        }
+    }
+
     pub fn current_total_power(&self) -> (StoragePower, StoragePower) {
         if self.miner_above_min_power_count < CONSENSUS_MINER_MIN_MINERS {
             (
@@ -447,6 +539,7 @@ pub fn consensus_miner_min_power(
 
 #[cfg(test)]
 mod test {
+    use fvm_ipld_blockstore::MemoryBlockstore;
     use fvm_shared4::clock::ChainEpoch;
 
     use super::*;
@@ -468,4 +561,84 @@ mod test {
         assert_eq!(b3, epoch_key(e3));
         assert_eq!(b4, epoch_key(e4));
     }
+
+    #[test]
+    fn test_miners_above_consensus_minimum() {
+        let store = MemoryBlockstore::new();
+        let policy = Policy::default();
+        let mut st = State::new(&store).unwrap();
+        let mut claims = st.load_claims(&store).unwrap();
+        for (id, power) in [
+            (100, policy.minimum_consensus_power.clone()),
+            (101, StoragePower::from(1)),
+            (102, StoragePower::from(0)),
+        ] {
+            let claim = Claim {
+                window_post_proof_type: RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+                raw_byte_power: power.clone(),
+                quality_adj_power: power,
+            };
+            set_claim(&mut claims, &Address::new_id(id), claim).unwrap();
+        }
+        st.save_claims(&mut claims).unwrap();
+
+        // Below the minimum number of miners, any miner with power qualifies.
+        let mut miners = st.miners_above_consensus_minimum(&store, &policy).unwrap();
+        miners.sort();
+        assert_eq!(miners, vec![Address::new_id(100), Address::new_id(101)]);
+
+        st.miner_above_min_power_count = CONSENSUS_MINER_MIN_MINERS;
+        let miners = st.miners_above_consensus_minimum(&store, &policy).unwrap();
+        assert_eq!(miners, vec![Address::new_id(100)]);
+        for miner in [100, 101, 102] {
+            let (_, meets) = st
+                .miner_nominal_power_meets_consensus_minimum(&policy, &store, miner)
+                .unwrap();
+            assert_eq!(meets, miners.contains(&Address::new_id(miner)));
+        }
+    }
+
+    #[test]
+    fn test_check_cron() {
+        let store = MemoryBlockstore::new();
+        let mut st = State::new(&store).unwrap();
+        st.first_cron_epoch = 11;
+        assert_eq!(st.check_cron(&store, 10).unwrap(), vec![]);
+        assert_eq!(
+            st.check_cron(&store, 12).unwrap(),
+            vec![CronIssue::Stalled {
+                first_cron_epoch: 11,
+                epoch: 12
+            }]
+        );
+
+        let mut events = Multimap::from_root(
+            &store,
+            &st.cron_event_queue,
+            CRON_QUEUE_HAMT_BITWIDTH,
+            CRON_QUEUE_AMT_BITWIDTH,
+        )
+        .unwrap();
+        let event = CronEvent {
+            miner_addr: Address::new_id(100),
+            callback_payload: RawBytes::default(),
+        };
+        st._append_cron_event(&mut events, 20, event).unwrap();
+        st.cron_event_queue = events.root().unwrap();
+        // Events enrolled before the cron start can only come from broken bookkeeping.
+        st.first_cron_epoch = 30;
+        assert_eq!(
+            st.check_cron(&store, 20).unwrap(),
+            vec![
+                CronIssue::Ahead {
+                    first_cron_epoch: 30,
+                    epoch: 20
+                },
+                CronIssue::StaleEvents {
+                    event_epoch: 20,
+                    first_cron_epoch: 30
+                }
+            ]
+        );
+    }
 }
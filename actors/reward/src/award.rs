@@ -0,0 +1,108 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::ensure;
+use fil_actors_shared::v16::EXPECTED_LEADERS_PER_EPOCH;
+use fvm_shared4::econ::TokenAmount;
+
+/// How the reward actor splits the reward of a block between the block reward and the gas
+/// reward, and the penalty it forwards to the miner.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockRewardSplit {
+    /// Share of the epoch reward won by the block, capped by the reward actor balance.
+    pub block_reward: TokenAmount,
+    /// Gas reward of the block's messages.
+    pub gas_reward: TokenAmount,
+    /// Amount sent to the miner, `block_reward + gas_reward`.
+    pub total_reward: TokenAmount,
+    /// Penalty the miner is asked to burn. It is forwarded unchanged: the `PENALTY_MULTIPLIER`
+    /// constant is a remnant of specs-actors and is not applied by builtin-actors.
+    pub penalty: TokenAmount,
+}
+
+/// Recomputes the `AwardBlockReward` reward split exactly as the reward actor does. The logic is
+/// the same for all actors versions shipped in this crate (v8 onwards).
+///
+/// `reward_balance` is the balance of the reward actor before the award, which caps the total
+/// reward paid.
+pub fn award_block_reward_split(
+    this_epoch_reward: &TokenAmount,
+    win_count: i64,
+    gas_reward: &TokenAmount,
+    penalty: &TokenAmount,
+    reward_balance: &TokenAmount,
+) -> anyhow::Result<BlockRewardSplit> {
+    ensure!(
+        win_count > 0,
+        "invalid win count {win_count}, must be positive"
+    );
+    ensure!(
+        !gas_reward.is_negative(),
+        "negative gas reward {gas_reward}"
+    );
+    ensure!(!penalty.is_negative(), "negative penalty {penalty}");
+
+    let mut block_reward = (this_epoch_reward * win_count).div_floor(EXPECTED_LEADERS_PER_EPOCH);
+    let mut total_reward = &block_reward + gas_reward;
+    if total_reward > *reward_balance {
+        total_reward = reward_balance.clone();
+        block_reward = &total_reward - gas_reward;
+        ensure!(
+            !block_reward.is_negative(),
+            "block reward {block_reward} below zero"
+        );
+    }
+
+    Ok(BlockRewardSplit {
+        block_reward,
+        gas_reward: gas_reward.clone(),
+        total_reward,
+        penalty: penalty.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_award_block_reward_split() {
+        let epoch_reward = TokenAmount::from_whole(100);
+        let gas = TokenAmount::from_whole(1);
+        let penalty = TokenAmount::from_atto(7);
+
+        let split = award_block_reward_split(
+            &epoch_reward,
+            2,
+            &gas,
+            &penalty,
+            &TokenAmount::from_whole(1000),
+        )
+        .unwrap();
+        assert_eq!(split.block_reward, TokenAmount::from_whole(40));
+        assert_eq!(split.total_reward, TokenAmount::from_whole(41));
+        assert_eq!(split.penalty, penalty);
+
+        // Capped by the reward actor balance.
+        let split = award_block_reward_split(
+            &epoch_reward,
+            2,
+            &gas,
+            &penalty,
+            &TokenAmount::from_whole(11),
+        )
+        .unwrap();
+        assert_eq!(split.block_reward, TokenAmount::from_whole(10));
+        assert_eq!(split.total_reward, TokenAmount::from_whole(11));
+
+        assert!(award_block_reward_split(&epoch_reward, 0, &gas, &penalty, &gas).is_err());
+        assert!(award_block_reward_split(
+            &epoch_reward,
+            1,
+            &gas,
+            &penalty,
+            &TokenAmount::from_atto(1)
+        )
+        .is_err());
+    }
+}
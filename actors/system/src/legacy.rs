@@ -0,0 +1,21 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+// The per-version crates replaced by this one, as deprecated aliases of its version modules, so
+// that their users can import them under their old names and migrate one version at a time, e.g.
+// `use fil_actor_system_state::legacy::fil_actor_system_v8;`.
+
+#[deprecated(note = "use `fil_actor_system_state::v8` instead")]
+pub mod fil_actor_system_v8 {
+    pub use crate::v8::*;
+}
+
+#[deprecated(note = "use `fil_actor_system_state::v9` instead")]
+pub mod fil_actor_system_v9 {
+    pub use crate::v9::*;
+}
+
+#[deprecated(note = "use `fil_actor_system_state::v10` instead")]
+pub mod fil_actor_system_v10 {
+    pub use crate::v10::*;
+}
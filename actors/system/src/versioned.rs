@@ -0,0 +1,74 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use cid::Cid;
+#[cfg(feature = "json")]
+use fil_actors_shared::lotus_json;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+
+fil_actors_shared::versioned_state! {
+    actor: "system",
+    type: System,
+    /// The fields of the system actor state. The layout has not changed since v8.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        /// Root of the builtin actor registry, the `(name, code CID)` entries of the manifest.
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub builtin_actors: Cid,
+    }
+    versions {
+        V8 => 8, crate::v8::State;
+        V9 => 9, crate::v9::State;
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        builtin_actors: st.builtin_actors,
+    }
+}
+
+impl VersionedState {
+    /// Loads the builtin actor registry, the `(name, code CID)` entries of the manifest of the
+    /// actors version the state belongs to.
+    pub fn builtin_actors<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<Vec<(String, Cid)>> {
+        let root = self.normalize()?.builtin_actors;
+        store
+            .get_cbor(&root)?
+            .with_context(|| format!("builtin actor registry {root} not found"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_builtin_actors() {
+        let store = MemoryBlockstore::new();
+        let account = store.put_cbor(&"account", Code::Blake2b256).unwrap();
+        let registry = vec![("account".to_string(), account)];
+        let st = crate::v8::State {
+            builtin_actors: store.put_cbor(&registry, Code::Blake2b256).unwrap(),
+        };
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let pre = VersionedState::load(&store, 8, &root).unwrap();
+        let post = VersionedState::load(&store, 16, &root).unwrap();
+        assert_eq!(pre.builtin_actors(&store).unwrap(), registry);
+        assert!(pre.normalize().unwrap().changes(&post.normalize().unwrap()).is_empty());
+        assert!(VersionedState::load(&store, 7, &root).is_err());
+    }
+}
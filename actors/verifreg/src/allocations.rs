@@ -0,0 +1,112 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::ensure;
+use fil_actors_shared::ids::AllocationID;
+use fil_actors_shared::multimap::{parse_uint_key, NestedMapReader};
+use fil_actors_shared::page::{Page, PageCursor};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared4::ActorID;
+
+use crate::versioned::VersionedState;
+
+/// The allocation type of all versions since v9. Its fields and encoding have not changed since
+/// allocations were introduced, so the allocations of any version decode as the latest one.
+pub use crate::v16::Allocation;
+
+impl VersionedState {
+    /// Lists up to `limit` allocations of a client, starting from `cursor` or from the first one,
+    /// as [`crate::v16::State::list_allocations`] does for its version. v8 states have no
+    /// allocations.
+    pub fn list_allocations<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: ActorID,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<(AllocationID, Allocation)>> {
+        let Some(allocations) = self.normalize()?.allocations else {
+            ensure!(
+                cursor.is_none(),
+                "cursor past the allocations of a v8 state"
+            );
+            return Ok(Page {
+                entries: vec![],
+                next_cursor: None,
+            });
+        };
+        let page = NestedMapReader::load(store, &allocations)?
+            .list_uint_page(client, cursor, limit)?;
+        Ok(page.map(|(id, allocation)| (AllocationID(id), allocation)))
+    }
+
+    /// Returns the allocations of all clients, in allocation ID order. This walks the whole
+    /// allocations table, so prefer [`VersionedState::list_allocations`] for a single client.
+    pub fn list_all_allocations<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<Vec<(AllocationID, Allocation)>> {
+        let Some(allocations) = self.normalize()?.allocations else {
+            return Ok(vec![]);
+        };
+        let mut entries = Vec::new();
+        NestedMapReader::<_, Allocation>::load(store, &allocations)?.for_each(
+            |_, id, allocation| {
+                entries.push((AllocationID(parse_uint_key(id)?), allocation.clone()));
+                Ok(())
+            },
+        )?;
+        entries.sort_by_key(|(id, _)| *id);
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_shared::address::Address;
+    use fvm_shared::piece::PaddedPieceSize;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_list_all_allocations() {
+        let store = MemoryBlockstore::new();
+        let mut st = crate::v9::State::new(&store, Address::new_id(80)).unwrap();
+        let allocation = |client, size| crate::v9::Allocation {
+            client,
+            provider: 1000,
+            data: Default::default(),
+            size: PaddedPieceSize(size),
+            term_min: 100,
+            term_max: 200,
+            expiration: 50,
+        };
+        st.insert_allocations(
+            &store,
+            100,
+            [allocation(100, 1), allocation(100, 2)].into_iter(),
+        )
+        .unwrap();
+        st.insert_allocations(&store, 101, [allocation(101, 3)].into_iter())
+            .unwrap();
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let state = VersionedState::load(&store, 9, &root).unwrap();
+        let all = state.list_all_allocations(&store).unwrap();
+        let ids: Vec<_> = all
+            .iter()
+            .map(|(id, a)| (id.0, a.client, a.size.0))
+            .collect();
+        assert_eq!(ids, [(1, 100, 1), (2, 100, 2), (3, 101, 3)]);
+
+        let page = state.list_allocations(&store, 101, None, 10).unwrap();
+        assert_eq!(page.entries, [all[2].clone()]);
+        assert!(state
+            .list_allocations(&store, 102, None, 10)
+            .unwrap()
+            .entries
+            .is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::ensure;
+use fil_actors_shared::ids::ClaimID;
+use fil_actors_shared::multimap::{parse_uint_key, NestedMapReader};
+use fil_actors_shared::page::{Page, PageCursor};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared4::ActorID;
+
+use crate::versioned::VersionedState;
+
+/// The claim type of all versions since v9. Like allocations, claims have kept their fields and
+/// encoding since they were introduced, so the claims of any version decode as the latest one.
+pub use crate::v16::Claim;
+
+impl VersionedState {
+    /// Lists up to `limit` claims of a provider, starting from `cursor` or from the first one, as
+    /// [`crate::v16::State::list_claims`] does for its version. v8 states have no claims.
+    pub fn list_claims<BS: Blockstore>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<(ClaimID, Claim)>> {
+        let Some(claims) = self.normalize()?.claims else {
+            ensure!(cursor.is_none(), "cursor past the claims of a v8 state");
+            return Ok(Page {
+                entries: vec![],
+                next_cursor: None,
+            });
+        };
+        let page = NestedMapReader::load(store, &claims)?.list_uint_page(provider, cursor, limit)?;
+        Ok(page.map(|(id, claim)| (ClaimID(id), claim)))
+    }
+
+    /// Runs a function over the claims of all providers, in no particular order. This walks the
+    /// whole claims table, so prefer [`VersionedState::list_claims`] for a single provider.
+    pub fn for_each_claim<BS, F>(&self, store: &BS, mut f: F) -> anyhow::Result<()>
+    where
+        BS: Blockstore,
+        F: FnMut(ClaimID, &Claim) -> anyhow::Result<()>,
+    {
+        let Some(claims) = self.normalize()?.claims else {
+            return Ok(());
+        };
+        NestedMapReader::<_, Claim>::load(store, &claims)?
+            .for_each(|_, id, claim| f(ClaimID(parse_uint_key(id)?), claim))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_shared4::address::Address;
+    use fvm_shared4::piece::PaddedPieceSize;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_list_claims() {
+        let store = MemoryBlockstore::new();
+        let mut st = crate::v12::State::new(&store, Address::new_id(80)).unwrap();
+        let claim = |provider, size| crate::v12::Claim {
+            provider,
+            client: 100,
+            data: Default::default(),
+            size: PaddedPieceSize(size),
+            term_min: 100,
+            term_max: 200,
+            term_start: 10,
+            sector: 7,
+        };
+        st.put_claims(
+            &store,
+            vec![
+                (1, claim(1000, 1)),
+                (2, claim(1000, 2)),
+                (3, claim(1001, 4)),
+            ],
+        )
+        .unwrap();
+        let root = store.put_cbor(&st, Code::Blake2b256).unwrap();
+
+        let state = VersionedState::load(&store, 12, &root).unwrap();
+        let mut claimed = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = state.list_claims(&store, 1000, cursor.as_ref(), 1).unwrap();
+            claimed.extend(page.entries.iter().map(|(id, c)| (id.0, c.size.0)));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        claimed.sort();
+        assert_eq!(claimed, [(1, 1), (2, 2)]);
+
+        let mut total = 0;
+        state
+            .for_each_claim(&store, |_, claim| {
+                total += claim.size.0;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(total, 7);
+    }
+}
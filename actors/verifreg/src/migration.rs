@@ -0,0 +1,204 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeSet;
+
+use fil_actors_shared::v8::make_map_with_root_and_bitwidth;
+use frc46_token::token::state::{actor_id_key, TokenState};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::bigint_ser::BigIntDe;
+use fvm_shared::HAMT_BIT_WIDTH;
+use fvm_shared4::econ::TokenAmount;
+
+use crate::{v8, v9};
+
+/// Converts a key of the address-keyed maps of the v8 registry into the key of the DataCap token
+/// balances, as the v9 migration does when moving verified clients to the DataCap actor. Only ID
+/// addresses are valid keys.
+pub fn hamt_addr_key_to_key(key: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let address = Address::from_bytes(key)?;
+    Ok(actor_id_key(address.id()?).0)
+}
+
+/// An entry of the v8 registry which was not carried over as expected by the v9 migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum V9MigrationMismatch {
+    /// A verifier whose allowance is missing or differs in the v9 registry.
+    Verifier {
+        verifier: Address,
+        expected: v8::DataCap,
+        actual: Option<v9::DataCap>,
+    },
+    /// A verifier of the v9 registry which is not in the v8 one.
+    UnexpectedVerifier { verifier: Address },
+    /// A verified client whose DataCap token balance is missing or differs. The balance is the
+    /// v8 DataCap in whole tokens.
+    Client {
+        client: Address,
+        expected: TokenAmount,
+        actual: Option<TokenAmount>,
+    },
+}
+
+/// Outcome of [`check_v9_migration`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct V9MigrationReport {
+    /// Number of verifiers of the v8 registry.
+    pub verifiers: u64,
+    /// Number of verified clients of the v8 registry.
+    pub clients: u64,
+    pub mismatches: Vec<V9MigrationMismatch>,
+}
+
+impl V9MigrationReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Checks that the verifiers and verified clients of a v8 registry were re-keyed by the v9
+/// migration: verifiers keep their allowance in the v9 registry, and the DataCap of the verified
+/// clients is their balance of the DataCap token, whose state must be provided.
+///
+/// Balances of other holders, such as the registry itself for the allocations of pending verified
+/// deals, are not checked.
+pub fn check_v9_migration<BS: Blockstore>(
+    store: &BS,
+    old: &v8::State,
+    new: &v9::State,
+    datacap_token: &TokenState,
+) -> anyhow::Result<V9MigrationReport> {
+    let mut report = V9MigrationReport::default();
+
+    let old_verifiers =
+        make_map_with_root_and_bitwidth::<_, BigIntDe>(&old.verifiers, store, HAMT_BIT_WIDTH)?;
+    let new_verifiers = new.load_verifiers(store)?;
+    let mut migrated = BTreeSet::new();
+    old_verifiers.for_each(|key, cap| {
+        report.verifiers += 1;
+        let actual = new_verifiers.get(key)?.map(|cap| cap.0.clone());
+        if actual.as_ref() != Some(&cap.0) {
+            report.mismatches.push(V9MigrationMismatch::Verifier {
+                verifier: Address::from_bytes(key)?,
+                expected: cap.0.clone(),
+                actual,
+            });
+        }
+        migrated.insert(key.0.clone());
+        Ok(())
+    })?;
+    new_verifiers.for_each(|key, _| {
+        if !migrated.contains(&key.0) {
+            report
+                .mismatches
+                .push(V9MigrationMismatch::UnexpectedVerifier {
+                    verifier: Address::from_bytes(key)?,
+                });
+        }
+        Ok(())
+    })?;
+
+    let old_clients = make_map_with_root_and_bitwidth::<_, BigIntDe>(
+        &old.verified_clients,
+        store,
+        HAMT_BIT_WIDTH,
+    )?;
+    let balances = datacap_token.get_balance_map(store)?;
+    old_clients.for_each(|key, cap| {
+        report.clients += 1;
+        let expected = TokenAmount::from_whole(cap.0.clone());
+        let actual = balances
+            .get(hamt_addr_key_to_key(key)?.as_slice())?
+            .cloned();
+        if actual.as_ref() != Some(&expected) {
+            report.mismatches.push(V9MigrationMismatch::Client {
+                client: Address::from_bytes(key)?,
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    })?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_actors_shared::v8::make_empty_map;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_hamt_addr_key_to_key() {
+        let key = hamt_addr_key_to_key(&Address::new_id(1234).to_bytes()).unwrap();
+        assert_eq!(key, actor_id_key(1234).0);
+        let secp = Address::new_secp256k1(&[1; 65]).unwrap();
+        assert!(hamt_addr_key_to_key(&secp.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_check_v9_migration() {
+        let store = MemoryBlockstore::default();
+        let (verifier, client, other_client) = (
+            Address::new_id(100),
+            Address::new_id(101),
+            Address::new_id(102),
+        );
+
+        let mut verifiers = make_empty_map::<_, BigIntDe>(&store, HAMT_BIT_WIDTH);
+        verifiers
+            .set(verifier.to_bytes().into(), BigIntDe(1000.into()))
+            .unwrap();
+        let mut clients = make_empty_map::<_, BigIntDe>(&store, HAMT_BIT_WIDTH);
+        clients
+            .set(client.to_bytes().into(), BigIntDe(500.into()))
+            .unwrap();
+        clients
+            .set(other_client.to_bytes().into(), BigIntDe(200.into()))
+            .unwrap();
+        let old = v8::State {
+            verifiers: verifiers.flush().unwrap(),
+            verified_clients: clients.flush().unwrap(),
+            ..v8::State::new(&store, Address::new_id(80)).unwrap()
+        };
+
+        let mut new = v9::State::new(&store, Address::new_id(80)).unwrap();
+        new.put_verifier(&store, &verifier, &1000.into()).unwrap();
+        let mut token = TokenState::new(&store).unwrap();
+        token
+            .change_balance_by(&store, 101, &TokenAmount::from_whole(500))
+            .unwrap();
+        token
+            .change_balance_by(&store, 102, &TokenAmount::from_whole(200))
+            .unwrap();
+
+        let report = check_v9_migration(&store, &old, &new, &token).unwrap();
+        assert!(report.is_ok(), "{report:?}");
+        assert_eq!((report.verifiers, report.clients), (1, 2));
+
+        // A client balance not scaled to whole tokens, and a verifier added by the migration.
+        token
+            .change_balance_by(&store, 102, &-TokenAmount::from_whole(200))
+            .unwrap();
+        token
+            .change_balance_by(&store, 102, &TokenAmount::from_atto(200))
+            .unwrap();
+        new.put_verifier(&store, &Address::new_id(103), &1.into())
+            .unwrap();
+        let report = check_v9_migration(&store, &old, &new, &token).unwrap();
+        assert_eq!(
+            report.mismatches,
+            vec![
+                V9MigrationMismatch::UnexpectedVerifier {
+                    verifier: Address::new_id(103)
+                },
+                V9MigrationMismatch::Client {
+                    client: other_client,
+                    expected: TokenAmount::from_whole(200),
+                    actual: Some(TokenAmount::from_atto(200)),
+                },
+            ]
+        );
+    }
+}
@@ -1,21 +1,30 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::collections::BTreeSet;
+
 use cid::Cid;
 use fil_actors_shared::actor_error_v12;
+use fil_actors_shared::ext::TokenStateExt;
+use fil_actors_shared::multimap::{parse_uint_key, uint_key, NestedMapReader, Page, PageCursor};
 use fil_actors_shared::v12::{ActorError, AsActorError, Config, Map2, MapMap, DEFAULT_HAMT_CONFIG};
+use frc46_token::token::state::TokenState;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared4::address::Address;
 use fvm_shared4::bigint::bigint_ser::BigIntDe;
 use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
 use fvm_shared4::error::ExitCode;
 use fvm_shared4::piece::PaddedPieceSize;
 use fvm_shared4::sector::SectorNumber;
 use fvm_shared4::{ActorID, HAMT_BIT_WIDTH};
 
 use crate::v12::{AddrPairKey, AllocationID, ClaimID};
-use crate::v12::{DataCap, RemoveDataCapProposalID};
+use crate::v12::{
+    DataCap, ExpiredAllocationRefund, RemoveDataCapProposalID, VerifiedClientStatus,
+    VerifiedRegistryStats,
+};
 
 pub type DataCapMap<BS> = Map2<BS, Address, BigIntDe>;
 pub const DATACAP_MAP_CONFIG: Config = DEFAULT_HAMT_CONFIG;
@@ -114,6 +123,138 @@ impl State {
         )
     }
 
+    /// Returns the number of allocations made by a client that have been neither claimed nor
+    /// removed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, store), ret)
+    )]
+    pub fn allocation_count<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: ActorID,
+    ) -> Result<u64, ActorError> {
+        let mut allocs = self.load_allocs(store)?;
+        let mut count = 0;
+        allocs
+            .for_each_in(client, |_, _| {
+                count += 1;
+                Ok(())
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate allocations")?;
+        Ok(count)
+    }
+
+    /// Estimates what `RemoveExpiredAllocations` would return to a client at `current_epoch` when
+    /// called without allocation IDs. Like the actor, allocations are expired from their
+    /// expiration epoch on, and each returns DataCap equal to its size.
+    pub fn estimate_expired_allocation_refunds<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: ActorID,
+        current_epoch: ChainEpoch,
+    ) -> Result<ExpiredAllocationRefund, ActorError> {
+        let mut refund = ExpiredAllocationRefund::default();
+        NestedMapReader::<_, Allocation>::load(store, &self.allocations)
+            .and_then(|allocs| {
+                allocs.for_each_in(&uint_key(client), |key, alloc| {
+                    if current_epoch >= alloc.expiration {
+                        refund.allocation_ids.push(parse_uint_key(key)?);
+                        refund.data_cap += DataCap::from(alloc.size.0);
+                    }
+                    Ok(())
+                })
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate allocations")?;
+        Ok(refund)
+    }
+
+    /// Returns the DataCap and pending allocation count of a client. Since v9, DataCap is held
+    /// as a balance of the DataCap token actor, whose token state must be provided.
+    pub fn verified_client_status<BS: Blockstore>(
+        &self,
+        store: &BS,
+        datacap_token: &TokenState,
+        client: ActorID,
+    ) -> Result<VerifiedClientStatus, ActorError> {
+        let balance = datacap_token
+            .get_balance_opt(store, client)
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to get datacap balance")?;
+        Ok(VerifiedClientStatus {
+            data_cap: balance.map(|balance| balance.atto() / TokenAmount::PRECISION),
+            allocations: self.allocation_count(store, client)?,
+        })
+    }
+
+    /// Computes network-wide statistics of the allocations and claims, in a single pass over
+    /// each table. Allocations are expired once `current_epoch` is past their expiration.
+    pub fn stats<BS: Blockstore>(
+        &self,
+        store: &BS,
+        current_epoch: ChainEpoch,
+    ) -> Result<VerifiedRegistryStats, ActorError> {
+        let mut stats = VerifiedRegistryStats::default();
+        let mut clients = BTreeSet::new();
+        let mut providers = BTreeSet::new();
+        NestedMapReader::<_, Allocation>::load(store, &self.allocations)
+            .and_then(|allocs| {
+                allocs.for_each(|_, _, alloc| {
+                    stats.allocations += 1;
+                    stats.allocated_bytes += alloc.size.0;
+                    if current_epoch > alloc.expiration {
+                        stats.expired_allocations += 1;
+                        stats.expired_unclaimed_bytes += alloc.size.0;
+                    }
+                    clients.insert(alloc.client);
+                    providers.insert(alloc.provider);
+                    Ok(())
+                })
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate allocations")?;
+        NestedMapReader::<_, Claim>::load(store, &self.claims)
+            .and_then(|claims| {
+                claims.for_each(|_, _, claim| {
+                    stats.claims += 1;
+                    stats.claimed_bytes += claim.size.0;
+                    clients.insert(claim.client);
+                    providers.insert(claim.provider);
+                    Ok(())
+                })
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate claims")?;
+        stats.clients = clients.len() as u64;
+        stats.providers = providers.len() as u64;
+        Ok(stats)
+    }
+
+    /// Lists up to `limit` allocations of a client, starting from `cursor` or from the first one,
+    /// without loading the other allocations. The order is unspecified but stable as long as the
+    /// allocations of the client are unchanged.
+    pub fn list_allocations<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: ActorID,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Page<(AllocationID, Allocation)>, ActorError> {
+        NestedMapReader::<_, Allocation>::load(store, &self.allocations)
+            .and_then(|allocs| allocs.list_uint_page(client, cursor, limit))
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to list allocations")
+    }
+
+    /// Lists up to `limit` claims of a provider, see [`State::list_allocations`].
+    pub fn list_claims<BS: Blockstore>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Page<(ClaimID, Claim)>, ActorError> {
+        NestedMapReader::<_, Claim>::load(store, &self.claims)
+            .and_then(|claims| claims.list_uint_page(provider, cursor, limit))
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to list claims")
+    }
+
     pub fn save_allocs<BS: Blockstore>(
         &mut self,
         allocs: &mut MapMap<'_, BS, Allocation, ActorID, AllocationID>,
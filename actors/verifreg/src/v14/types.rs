@@ -1,13 +1,17 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use anyhow::ensure;
 use cid::Cid;
 use fil_actors_shared::v14::{BatchReturn, MapKey};
+use frc46_token::receiver::FRC46_TOKEN_TYPE;
 use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::RawBytes;
 use fvm_shared4::address::Address;
 use fvm_shared4::bigint::{bigint_ser, BigInt};
 use fvm_shared4::clock::ChainEpoch;
 use fvm_shared4::crypto::signature::Signature;
+use fvm_shared4::econ::TokenAmount;
 use fvm_shared4::piece::PaddedPieceSize;
 use fvm_shared4::sector::SectorNumber;
 use fvm_shared4::sector::StoragePower;
@@ -230,6 +234,48 @@ pub struct AllocationsResponse {
     pub new_allocations: Vec<AllocationID>,
 }
 
+/// Parameters of a universal receiver hook call, see FRC-0046.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct UniversalReceiverParams {
+    /// Asset type, `FRC46_TOKEN_TYPE` for datacap transfers.
+    pub type_: u32,
+    pub payload: RawBytes,
+}
+
+/// Payload of an FRC-0046 token receiver hook.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct FRC46TokenReceived {
+    pub from: ActorID,
+    pub to: ActorID,
+    pub operator: ActorID,
+    pub amount: TokenAmount,
+    pub operator_data: RawBytes,
+    pub token_data: RawBytes,
+}
+
+/// A datacap transfer to the verified registry, decoded from the parameters of its
+/// `UniversalReceiverHook` call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataCapTransfer {
+    pub received: FRC46TokenReceived,
+    /// Allocations and claim extensions requested through the operator data.
+    pub requests: AllocationRequests,
+}
+
+impl DataCapTransfer {
+    pub fn decode(params: &RawBytes) -> anyhow::Result<Self> {
+        let params: UniversalReceiverParams = params.deserialize()?;
+        ensure!(
+            params.type_ == FRC46_TOKEN_TYPE,
+            "unexpected receiver hook type {}",
+            params.type_
+        );
+        let received: FRC46TokenReceived = params.payload.deserialize()?;
+        let requests = received.operator_data.deserialize()?;
+        Ok(Self { received, requests })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct GetClaimsParams {
     pub provider: ActorID,
@@ -258,3 +304,47 @@ pub struct RemoveExpiredClaimsReturn {
     // Results for each processed claim.
     pub results: BatchReturn,
 }
+
+/// DataCap and pending allocations of a verified client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedClientStatus {
+    /// Remaining DataCap, or `None` if the address is not a verified client.
+    pub data_cap: Option<DataCap>,
+    /// Number of allocations made by the client that have been neither claimed nor removed.
+    pub allocations: u64,
+}
+
+/// DataCap which `RemoveExpiredAllocations` would return to a client, see
+/// `State::estimate_expired_allocation_refunds`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExpiredAllocationRefund {
+    /// Expired allocations, in the order the actor finds them.
+    pub allocation_ids: Vec<AllocationID>,
+    /// Sum of the sizes of the expired allocations, in bytes.
+    pub data_cap: DataCap,
+}
+
+impl ExpiredAllocationRefund {
+    /// Returns the DataCap tokens transferred back to the client, one whole token per byte.
+    pub fn tokens(&self) -> TokenAmount {
+        TokenAmount::from_whole(self.data_cap.clone())
+    }
+}
+
+/// Network-wide statistics of the verified registry, see `State::stats`. Sizes are padded piece
+/// sizes in bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifiedRegistryStats {
+    /// Allocations neither claimed nor removed, including the expired ones.
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+    /// Allocations which can no longer be claimed, awaiting removal.
+    pub expired_allocations: u64,
+    pub expired_unclaimed_bytes: u64,
+    pub claims: u64,
+    pub claimed_bytes: u64,
+    /// Number of distinct clients with allocations or claims.
+    pub clients: u64,
+    /// Number of distinct providers with allocations or claims.
+    pub providers: u64,
+}
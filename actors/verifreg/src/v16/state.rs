@@ -1,22 +1,31 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::collections::BTreeSet;
+
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared4::address::Address;
 use fvm_shared4::bigint::bigint_ser::BigIntDe;
 use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::econ::TokenAmount;
 use fvm_shared4::error::ExitCode;
 use fvm_shared4::piece::PaddedPieceSize;
 use fvm_shared4::sector::SectorNumber;
 use fvm_shared4::{ActorID, HAMT_BIT_WIDTH};
 
 use fil_actors_shared::actor_error_v16;
+use fil_actors_shared::ext::TokenStateExt;
+use fil_actors_shared::multimap::{parse_uint_key, uint_key, NestedMapReader, Page, PageCursor};
 use fil_actors_shared::v16::{ActorError, AsActorError, Config, Map2, MapMap, DEFAULT_HAMT_CONFIG};
+use frc46_token::token::state::TokenState;
 
 use crate::v16::{AddrPairKey, AllocationID, ClaimID};
-use crate::v16::{DataCap, RemoveDataCapProposalID};
+use crate::v16::{
+    DataCap, ExpiredAllocationRefund, RemoveDataCapProposalID, VerifiedClientStatus,
+    VerifiedRegistryStats,
+};
 
 pub type DataCapMap<BS> = Map2<BS, Address, BigIntDe>;
 pub const DATACAP_MAP_CONFIG: Config = DEFAULT_HAMT_CONFIG;
@@ -115,6 +124,138 @@ impl State {
         )
     }
 
+    /// Returns the number of allocations made by a client that have been neither claimed nor
+    /// removed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, store), ret)
+    )]
+    pub fn allocation_count<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: ActorID,
+    ) -> Result<u64, ActorError> {
+        let mut allocs = self.load_allocs(store)?;
+        let mut count = 0;
+        allocs
+            .for_each_in(client, |_, _| {
+                count += 1;
+                Ok(())
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate allocations")?;
+        Ok(count)
+    }
+
+    /// Estimates what `RemoveExpiredAllocations` would return to a client at `current_epoch` when
+    /// called without allocation IDs. Like the actor, allocations are expired from their
+    /// expiration epoch on, and each returns DataCap equal to its size.
+    pub fn estimate_expired_allocation_refunds<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: ActorID,
+        current_epoch: ChainEpoch,
+    ) -> Result<ExpiredAllocationRefund, ActorError> {
+        let mut refund = ExpiredAllocationRefund::default();
+        NestedMapReader::<_, Allocation>::load(store, &self.allocations)
+            .and_then(|allocs| {
+                allocs.for_each_in(&uint_key(client), |key, alloc| {
+                    if current_epoch >= alloc.expiration {
+                        refund.allocation_ids.push(parse_uint_key(key)?);
+                        refund.data_cap += DataCap::from(alloc.size.0);
+                    }
+                    Ok(())
+                })
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate allocations")?;
+        Ok(refund)
+    }
+
+    /// Returns the DataCap and pending allocation count of a client. Since v9, DataCap is held
+    /// as a balance of the DataCap token actor, whose token state must be provided.
+    pub fn verified_client_status<BS: Blockstore>(
+        &self,
+        store: &BS,
+        datacap_token: &TokenState,
+        client: ActorID,
+    ) -> Result<VerifiedClientStatus, ActorError> {
+        let balance = datacap_token
+            .get_balance_opt(store, client)
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to get datacap balance")?;
+        Ok(VerifiedClientStatus {
+            data_cap: balance.map(|balance| balance.atto() / TokenAmount::PRECISION),
+            allocations: self.allocation_count(store, client)?,
+        })
+    }
+
+    /// Computes network-wide statistics of the allocations and claims, in a single pass over
+    /// each table. Allocations are expired once `current_epoch` is past their expiration.
+    pub fn stats<BS: Blockstore>(
+        &self,
+        store: &BS,
+        current_epoch: ChainEpoch,
+    ) -> Result<VerifiedRegistryStats, ActorError> {
+        let mut stats = VerifiedRegistryStats::default();
+        let mut clients = BTreeSet::new();
+        let mut providers = BTreeSet::new();
+        NestedMapReader::<_, Allocation>::load(store, &self.allocations)
+            .and_then(|allocs| {
+                allocs.for_each(|_, _, alloc| {
+                    stats.allocations += 1;
+                    stats.allocated_bytes += alloc.size.0;
+                    if current_epoch > alloc.expiration {
+                        stats.expired_allocations += 1;
+                        stats.expired_unclaimed_bytes += alloc.size.0;
+                    }
+                    clients.insert(alloc.client);
+                    providers.insert(alloc.provider);
+                    Ok(())
+                })
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate allocations")?;
+        NestedMapReader::<_, Claim>::load(store, &self.claims)
+            .and_then(|claims| {
+                claims.for_each(|_, _, claim| {
+                    stats.claims += 1;
+                    stats.claimed_bytes += claim.size.0;
+                    clients.insert(claim.client);
+                    providers.insert(claim.provider);
+                    Ok(())
+                })
+            })
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to iterate claims")?;
+        stats.clients = clients.len() as u64;
+        stats.providers = providers.len() as u64;
+        Ok(stats)
+    }
+
+    /// Lists up to `limit` allocations of a client, starting from `cursor` or from the first one,
+    /// without loading the other allocations. The order is unspecified but stable as long as the
+    /// allocations of the client are unchanged.
+    pub fn list_allocations<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: ActorID,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Page<(AllocationID, Allocation)>, ActorError> {
+        NestedMapReader::<_, Allocation>::load(store, &self.allocations)
+            .and_then(|allocs| allocs.list_uint_page(client, cursor, limit))
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to list allocations")
+    }
+
+    /// Lists up to `limit` claims of a provider, see [`State::list_allocations`].
+    pub fn list_claims<BS: Blockstore>(
+        &self,
+        store: &BS,
+        provider: ActorID,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Page<(ClaimID, Claim)>, ActorError> {
+        NestedMapReader::<_, Claim>::load(store, &self.claims)
+            .and_then(|claims| claims.list_uint_page(provider, cursor, limit))
+            .context_code(ExitCode::USR_ILLEGAL_STATE, "failed to list claims")
+    }
+
     pub fn save_allocs<BS: Blockstore>(
         &mut self,
         allocs: &mut MapMap<'_, BS, Allocation, ActorID, AllocationID>,
@@ -267,3 +408,183 @@ where
         "HAMT lookup failure getting claim",
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_verified_client_status() {
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(80)).unwrap();
+        let mut token = TokenState::new(&store).unwrap();
+        let (client, other) = (101, 102);
+
+        let allocation = Allocation {
+            client,
+            provider: 1000,
+            data: Cid::default(),
+            size: PaddedPieceSize(2048),
+            term_min: 100,
+            term_max: 200,
+            expiration: 50,
+        };
+        state
+            .insert_allocations(&store, client, vec![allocation.clone(), allocation])
+            .unwrap();
+        token
+            .change_balance_by(&store, client, &TokenAmount::from_whole(4096))
+            .unwrap();
+
+        let status = state
+            .verified_client_status(&store, &token, client)
+            .unwrap();
+        assert_eq!(
+            status,
+            VerifiedClientStatus {
+                data_cap: Some(DataCap::from(4096)),
+                allocations: 2,
+            }
+        );
+
+        let status = state.verified_client_status(&store, &token, other).unwrap();
+        assert_eq!(status.data_cap, None);
+        assert_eq!(status.allocations, 0);
+    }
+
+    #[test]
+    fn test_estimate_expired_allocation_refunds() {
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(80)).unwrap();
+        let client = 101;
+        let allocation = |size, expiration| Allocation {
+            client,
+            provider: 1000,
+            data: Cid::default(),
+            size: PaddedPieceSize(size),
+            term_min: 100,
+            term_max: 200,
+            expiration,
+        };
+        state
+            .insert_allocations(
+                &store,
+                client,
+                vec![
+                    allocation(2048, 50),
+                    allocation(1024, 60),
+                    allocation(512, 70),
+                ],
+            )
+            .unwrap();
+
+        // Allocations expire at their expiration epoch.
+        let refund = state
+            .estimate_expired_allocation_refunds(&store, client, 60)
+            .unwrap();
+        assert_eq!(refund.allocation_ids.len(), 2);
+        assert_eq!(refund.data_cap, DataCap::from(3072));
+        assert_eq!(refund.tokens(), TokenAmount::from_whole(3072));
+
+        let refund = state
+            .estimate_expired_allocation_refunds(&store, 102, 100)
+            .unwrap();
+        assert_eq!(refund, ExpiredAllocationRefund::default());
+    }
+
+    #[test]
+    fn test_stats() {
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(80)).unwrap();
+        let allocation = |client, provider, expiration| Allocation {
+            client,
+            provider,
+            data: Cid::default(),
+            size: PaddedPieceSize(2048),
+            term_min: 100,
+            term_max: 200,
+            expiration,
+        };
+        state
+            .insert_allocations(
+                &store,
+                101,
+                vec![allocation(101, 1000, 50), allocation(101, 1001, 150)],
+            )
+            .unwrap();
+        state
+            .insert_allocations(&store, 102, vec![allocation(102, 1000, 150)])
+            .unwrap();
+        let claim = Claim {
+            provider: 1002,
+            client: 103,
+            data: Cid::default(),
+            size: PaddedPieceSize(4096),
+            term_min: 100,
+            term_max: 200,
+            term_start: 10,
+            sector: 1,
+        };
+        state.put_claims(&store, vec![(10, claim)]).unwrap();
+
+        let stats = state.stats(&store, 100).unwrap();
+        assert_eq!(
+            stats,
+            VerifiedRegistryStats {
+                allocations: 3,
+                allocated_bytes: 3 * 2048,
+                expired_allocations: 1,
+                expired_unclaimed_bytes: 2048,
+                claims: 1,
+                claimed_bytes: 4096,
+                clients: 3,
+                providers: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_list_allocations() {
+        let store = MemoryBlockstore::default();
+        let mut state = State::new(&store, Address::new_id(80)).unwrap();
+        let client = 101;
+        let allocations = (0..5)
+            .map(|i| Allocation {
+                client,
+                provider: 1000,
+                data: Cid::default(),
+                size: PaddedPieceSize(2048),
+                term_min: 100,
+                term_max: 200,
+                expiration: 50 + i,
+            })
+            .collect();
+        let ids = state
+            .insert_allocations(&store, client, allocations)
+            .unwrap();
+
+        let mut listed = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = state
+                .list_allocations(&store, client, cursor.as_ref(), 2)
+                .unwrap();
+            assert!(page.entries.len() <= 2);
+            listed.extend(page.entries);
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        listed.sort_by_key(|(id, _)| *id);
+        assert_eq!(listed.iter().map(|(id, _)| *id).collect::<Vec<_>>(), ids);
+        assert_eq!(listed[4].1.expiration, 54);
+
+        let page = state.list_allocations(&store, 102, None, 2).unwrap();
+        assert!(page.entries.is_empty());
+        assert_eq!(page.next_cursor, None);
+        let page = state.list_claims(&store, 1000, None, 2).unwrap();
+        assert!(page.entries.is_empty());
+    }
+}
@@ -2,12 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use cid::Cid;
-use fil_actors_shared::v8::make_empty_map;
+use fil_actors_shared::v8::{make_empty_map, make_map_with_root_and_bitwidth};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared::address::Address;
+use fvm_shared::bigint::bigint_ser::BigIntDe;
 use fvm_shared::HAMT_BIT_WIDTH;
 
+use super::{DataCap, VerifiedClientStatus};
+
 #[derive(Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct State {
     pub root_key: Address,
@@ -29,4 +32,31 @@ impl State {
             remove_data_cap_proposal_ids: empty_map,
         })
     }
+
+    /// Returns the remaining DataCap of a verified client, or `None` if the address is not a
+    /// verified client. `client` must be an ID address.
+    pub fn get_verified_client_data_cap<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: &Address,
+    ) -> anyhow::Result<Option<DataCap>> {
+        let clients = make_map_with_root_and_bitwidth::<_, BigIntDe>(
+            &self.verified_clients,
+            store,
+            HAMT_BIT_WIDTH,
+        )?;
+        Ok(clients.get(&client.to_bytes())?.map(|cap| cap.0.clone()))
+    }
+
+    /// Returns the DataCap of a client. Before v9, DataCap is tracked by the registry itself.
+    pub fn verified_client_status<BS: Blockstore>(
+        &self,
+        store: &BS,
+        client: &Address,
+    ) -> anyhow::Result<VerifiedClientStatus> {
+        Ok(VerifiedClientStatus {
+            data_cap: self.get_verified_client_data_cap(store, client)?,
+            allocations: 0,
+        })
+    }
 }
@@ -88,3 +88,13 @@ impl AddrPairKey {
         first
     }
 }
+
+/// DataCap and pending allocations of a verified client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedClientStatus {
+    /// Remaining DataCap, or `None` if the address is not a verified client.
+    pub data_cap: Option<DataCap>,
+    /// Number of allocations made by the client. Always zero, as allocations were introduced in
+    /// v9.
+    pub allocations: u64,
+}
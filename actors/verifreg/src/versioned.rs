@@ -0,0 +1,100 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+#[cfg(feature = "json")]
+use fil_actors_shared::lotus_json;
+use fil_actors_shared::versioned::normalize_address;
+use fvm_shared4::address::Address;
+
+fil_actors_shared::versioned_state! {
+    actor: "verified registry",
+    type: VerifiedRegistry,
+    /// The fields of the verified registry state, with version-agnostic types, to compare states
+    /// across an upgrade. The v9 migration moved verified clients to the DataCap actor and added
+    /// allocations and claims, so each side has the fields of its version only. See
+    /// [`crate::migration`] for the re-keying.
+    #[cfg_attr(
+        feature = "json",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "PascalCase")
+    )]
+    pub struct NormalizedState {
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::address"))]
+        pub root_key: Address,
+        #[cfg_attr(feature = "json", serde(with = "lotus_json::cid"))]
+        pub verifiers: Cid,
+        #[cfg_attr(
+            feature = "json",
+            serde(rename = "RemoveDataCapProposalIDs", with = "lotus_json::cid")
+        )]
+        pub remove_data_cap_proposal_ids: Cid,
+        /// Until v8.
+        #[cfg_attr(
+            feature = "json",
+            serde(default, skip_serializing_if = "Option::is_none", with = "lotus_json::cid::opt")
+        )]
+        pub verified_clients: Option<Cid>,
+        /// Since v9.
+        #[cfg_attr(
+            feature = "json",
+            serde(default, skip_serializing_if = "Option::is_none", with = "lotus_json::cid::opt")
+        )]
+        pub allocations: Option<Cid>,
+        /// Since v9.
+        #[cfg_attr(feature = "json", serde(default, skip_serializing_if = "Option::is_none"))]
+        pub next_allocation_id: Option<u64>,
+        /// Since v9.
+        #[cfg_attr(
+            feature = "json",
+            serde(default, skip_serializing_if = "Option::is_none", with = "lotus_json::cid::opt")
+        )]
+        pub claims: Option<Cid>,
+    }
+    versions {
+        V8 => 8, crate::v8::State => |st| NormalizedState {
+            root_key: normalize_address(&st.root_key.to_bytes())?,
+            verifiers: st.verifiers,
+            remove_data_cap_proposal_ids: st.remove_data_cap_proposal_ids,
+            verified_clients: Some(st.verified_clients),
+            allocations: None,
+            next_allocation_id: None,
+            claims: None,
+        };
+        V9 => 9, crate::v9::State;
+        V10 => 10, crate::v10::State;
+        V11 => 11, crate::v11::State;
+        V12 => 12, crate::v12::State;
+        V13 => 13, crate::v13::State;
+        V14 => 14, crate::v14::State;
+        V15 => 15, crate::v15::State;
+        V16 => 16, crate::v16::State;
+    }
+    normalize |st| NormalizedState {
+        root_key: normalize_address(&st.root_key.to_bytes())?,
+        verifiers: st.verifiers,
+        remove_data_cap_proposal_ids: st.remove_data_cap_proposal_ids,
+        verified_clients: None,
+        allocations: Some(st.allocations),
+        next_allocation_id: Some(st.next_allocation_id),
+        claims: Some(st.claims),
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lotus_json() {
+        let fixture = include_str!("../testdata/lotus_state_v16.json");
+        let state: NormalizedState = serde_json::from_str(fixture).unwrap();
+        assert_eq!(state.root_key, Address::new_id(80));
+        assert_eq!(state.next_allocation_id, Some(98305));
+        assert_eq!(state.verified_clients, None);
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::from_str::<serde_json::Value>(fixture).unwrap()
+        );
+    }
+}
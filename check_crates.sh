@@ -6,6 +6,7 @@ set -euxo pipefail
 function check_crate {
     pushd "$1" > /dev/null
     cargo check
+    cargo check --no-default-features
     cargo test --no-run
     popd > /dev/null
 }
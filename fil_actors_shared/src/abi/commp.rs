@@ -3,7 +3,8 @@
 
 use cid::Cid;
 use fvm_shared::{
-    commcid::data_commitment_v1_to_cid, piece::PieceInfo as PieceInfoV2,
+    commcid::data_commitment_v1_to_cid,
+    piece::{zero_piece_commitment, PaddedPieceSize, PieceInfo as PieceInfoV2},
     sector::RegisteredSealProof as RegisteredSealProofV2,
 };
 
@@ -32,6 +33,40 @@ pub fn compute_unsealed_sector_cid_v2(
     data_commitment_v1_to_cid(&comm_d).map_err(anyhow::Error::msg)
 }
 
+/// Outcome of [`verify_unsealed_sector_cid_v2`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommDVerification {
+    pub expected: Cid,
+    pub computed: Cid,
+}
+
+impl CommDVerification {
+    pub fn is_valid(&self) -> bool {
+        self.expected == self.computed
+    }
+}
+
+/// Recomputes the unsealed sector CID (`CommD`) from the pieces of a sector and compares it with
+/// the expected one, e.g. the `unsealed_cid` of its pre-commitment. A sector without pieces holds
+/// no data, and its `CommD` is the zero commitment of the sector size.
+pub fn verify_unsealed_sector_cid_v2(
+    proof_type: RegisteredSealProofV2,
+    pieces: &[PieceInfoV2],
+    expected: &Cid,
+) -> anyhow::Result<CommDVerification> {
+    let computed = if pieces.is_empty() {
+        let sector_size = proof_type.sector_size().map_err(anyhow::Error::msg)?;
+        data_commitment_v1_to_cid(&zero_piece_commitment(PaddedPieceSize(sector_size as u64)))
+            .map_err(anyhow::Error::msg)?
+    } else {
+        compute_unsealed_sector_cid_v2(proof_type, pieces)?
+    };
+    Ok(CommDVerification {
+        expected: *expected,
+        computed,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::process::Command;
@@ -245,4 +280,22 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn verify_unsealed_sector_cid_v2_test() -> Result<()> {
+        let proof = RegisteredSealProofV2::StackedDRG2KiBV1P1;
+        let zero = data_commitment_v1_to_cid(&zero_piece_commitment_v2(PaddedPieceSizeV2(2048)))
+            .map_err(Error::msg)?;
+        ensure!(verify_unsealed_sector_cid_v2(proof, &[], &zero)?.is_valid());
+
+        // A piece filling the sector is its own data commitment.
+        let piece = PieceInfoV2 {
+            cid: Cid::from_str("baga6ea4seaqknzm22isnhsxt2s4dnw45kfywmhenngqq3nc7jvecakoca6ksyhy")?,
+            size: PaddedPieceSizeV2(2048),
+        };
+        let verification = verify_unsealed_sector_cid_v2(proof, &[piece.clone()], &zero)?;
+        ensure!(!verification.is_valid());
+        ensure!(verification.computed == piece.cid);
+        Ok(())
+    }
 }
@@ -1,4 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+#[cfg(feature = "proofs")]
 pub mod commp;
+pub mod path;
+pub mod proofs;
@@ -0,0 +1,179 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use cid::Cid;
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::address::Address;
+use fvm_shared4::HAMT_BIT_WIDTH;
+use ipld_core::ipld::Ipld;
+
+use crate::multimap::uint_key;
+
+/// Resolves a `/`-separated path from the block with the given CID, e.g. a miner state, and
+/// returns the decoded node at its end. This works on any DAG-CBOR state, without its types, so
+/// it can be used to inspect the states of versions which are not supported yet.
+///
+/// Links are followed as needed. Each segment selects, depending on its form:
+/// - `amt:<index>`, the value at the given index of the AMT whose root is the current node,
+/// - `hamt:<key>`, or `hamt<bit width>:<key>` for HAMTs without the default bit width, the value
+///   at the given key of the HAMT whose root is the current node. The key is an address
+///   (`f01234`), a CID, an unsigned integer (encoded as a varint key, as for actor and deal IDs),
+///   or else the bytes of the key itself,
+/// - otherwise, in a list, such as a tuple-encoded structure, the element at the given index,
+///   and in a map, the value at the given key.
+///
+/// Structures are tuple-encoded, so their fields are selected by position: with the v16 miner
+/// state, `9/amt:1234` is the sector 1234 and `12/0/3/0/amt:0/2` the faults of the first
+/// partition of the deadline 3. A path ending on a link returns the linked block.
+pub fn resolve_path<BS: Blockstore>(store: &BS, root: &Cid, path: &str) -> anyhow::Result<Ipld> {
+    let mut node = Ipld::Link(*root);
+    let mut resolved = String::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        node = select(store, node, segment)
+            .with_context(|| format!("failed to resolve {segment} at /{resolved}"))?;
+        resolved.push_str(segment);
+        resolved.push('/');
+    }
+    match node {
+        Ipld::Link(cid) => load(store, &cid),
+        node => Ok(node),
+    }
+}
+
+fn select<BS: Blockstore>(store: &BS, node: Ipld, segment: &str) -> anyhow::Result<Ipld> {
+    if let Some(index) = segment.strip_prefix("amt:") {
+        let index = index.parse().context("expected an AMT index")?;
+        return Amt::<Ipld, _>::load(&collection_root(node)?, store)?
+            .get(index)?
+            .cloned()
+            .with_context(|| format!("no AMT entry {index}"));
+    }
+    if let Some((bit_width, key)) = segment
+        .strip_prefix("hamt")
+        .and_then(|rest| rest.split_once(':'))
+    {
+        let bit_width = match bit_width {
+            "" => HAMT_BIT_WIDTH,
+            bit_width => bit_width.parse().context("expected a HAMT bit width")?,
+        };
+        return Hamt::<_, Ipld, BytesKey>::load_with_bit_width(
+            &collection_root(node)?,
+            store,
+            bit_width,
+        )?
+        .get(&BytesKey(hamt_key(key)))?
+        .cloned()
+        .context("no HAMT entry");
+    }
+    let node = match node {
+        Ipld::Link(cid) => load(store, &cid)?,
+        node => node,
+    };
+    match node {
+        Ipld::List(mut elements) => {
+            let index: usize = segment.parse().context("expected a list index")?;
+            anyhow::ensure!(
+                index < elements.len(),
+                "index out of range of {} elements",
+                elements.len()
+            );
+            Ok(elements.swap_remove(index))
+        }
+        Ipld::Map(mut entries) => entries.remove(segment).context("no map entry"),
+        node => bail!("cannot select into {node:?}"),
+    }
+}
+
+/// Returns the CID of the root of the collection a node links to.
+fn collection_root(node: Ipld) -> anyhow::Result<Cid> {
+    match node {
+        Ipld::Link(cid) => Ok(cid),
+        node => bail!("expected a link to a collection, found {node:?}"),
+    }
+}
+
+fn load<BS: Blockstore>(store: &BS, cid: &Cid) -> anyhow::Result<Ipld> {
+    store
+        .get_cbor(cid)?
+        .with_context(|| format!("block {cid} not found"))
+}
+
+fn hamt_key(segment: &str) -> Vec<u8> {
+    if let Ok(address) = Address::from_str(segment) {
+        address.to_bytes()
+    } else if let Ok(cid) = Cid::from_str(segment) {
+        cid.to_bytes()
+    } else if let Ok(key) = segment.parse() {
+        uint_key(key)
+    } else {
+        segment.as_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_resolve_path() {
+        let store = MemoryBlockstore::new();
+        let leaf = store
+            .put_cbor(&(7u64, "leaf".to_string()), Code::Blake2b256)
+            .unwrap();
+        let mut amt = Amt::<Ipld, _>::new(&store);
+        amt.set(1234, Ipld::List(vec![Ipld::Link(leaf)])).unwrap();
+        let amt = amt.flush().unwrap();
+        let mut hamt = Hamt::<_, Ipld, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        hamt.set(BytesKey(Address::new_id(1000).to_bytes()), Ipld::Integer(1))
+            .unwrap();
+        hamt.set(BytesKey(uint_key(42)), Ipld::Integer(2)).unwrap();
+        let hamt = hamt.flush().unwrap();
+        let mut wide = Hamt::<_, Ipld, BytesKey>::new_with_bit_width(&store, 6);
+        wide.set(BytesKey(b"key".to_vec()), Ipld::Integer(3))
+            .unwrap();
+        let wide = wide.flush().unwrap();
+        let root = store
+            .put_cbor(&(1u64, amt, hamt, wide), Code::Blake2b256)
+            .unwrap();
+
+        assert_eq!(resolve_path(&store, &root, "0").unwrap(), Ipld::Integer(1));
+        assert_eq!(
+            resolve_path(&store, &root, "/1/amt:1234/0/1").unwrap(),
+            Ipld::String("leaf".into())
+        );
+        // A path ending on a link returns the linked block.
+        assert_eq!(
+            resolve_path(&store, &root, "1/amt:1234/0").unwrap(),
+            Ipld::List(vec![Ipld::Integer(7), Ipld::String("leaf".into())])
+        );
+        assert_eq!(
+            resolve_path(&store, &root, "2/hamt:f01000").unwrap(),
+            Ipld::Integer(1)
+        );
+        assert_eq!(
+            resolve_path(&store, &root, "2/hamt:42").unwrap(),
+            Ipld::Integer(2)
+        );
+
+        assert_eq!(
+            resolve_path(&store, &root, "3/hamt6:key").unwrap(),
+            Ipld::Integer(3)
+        );
+
+        let err = resolve_path(&store, &root, "1/amt:5").unwrap_err();
+        assert_eq!(err.to_string(), "failed to resolve amt:5 at /1/");
+        // Collections are only looked up where the path says so.
+        assert!(resolve_path(&store, &root, "1/1234").is_err());
+        assert!(resolve_path(&store, &root, "0/amt:0").is_err());
+        assert!(resolve_path(&store, &root, "4").is_err());
+        assert!(resolve_path(&store, &root, "0/0").is_err());
+    }
+}
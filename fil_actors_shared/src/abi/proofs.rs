@@ -0,0 +1,115 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::anyhow;
+use fvm_shared4::sector::{
+    RegisteredPoStProof, RegisteredSealProof, RegisteredUpdateProof, SectorSize,
+};
+
+/// Kind of PoRep a seal proof is used with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoRepKind {
+    /// Interactive PoRep, challenged after the pre-commit.
+    Interactive,
+    /// Interactive PoRep with synthetic challenges, see FIP-0059.
+    Synthetic,
+    /// Non-interactive PoRep, proven without a pre-commit, see FIP-0092.
+    NonInteractive,
+}
+
+/// Properties of a seal proof type which do not depend on the network version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SealProofProperties {
+    pub sector_size: SectorSize,
+    pub porep: PoRepKind,
+    /// Whether the proof is one of the original V1 proofs, which can no longer be used to seal
+    /// new sectors.
+    pub legacy: bool,
+    /// Proof used for Window PoSt of sectors sealed with this proof.
+    pub window_post_proof: RegisteredPoStProof,
+    /// Proof used for Winning PoSt of sectors sealed with this proof.
+    pub winning_post_proof: RegisteredPoStProof,
+    /// Proof used to update sectors sealed with this proof with SnapDeals.
+    pub update_proof: RegisteredUpdateProof,
+}
+
+/// Returns the properties of a seal proof, given as any `fvm_shared` version of
+/// `RegisteredSealProof`. Whether a proof is accepted by the miner actor at a given network
+/// version is a matter of policy, see `fil_actor_miner_state::policy_lookup`.
+pub fn seal_proof_properties(proof: impl Into<i64>) -> anyhow::Result<SealProofProperties> {
+    use RegisteredSealProof::*;
+
+    let proof = RegisteredSealProof::from(proof.into());
+    let (porep, legacy) = match proof {
+        StackedDRG2KiBV1 | StackedDRG512MiBV1 | StackedDRG8MiBV1 | StackedDRG32GiBV1
+        | StackedDRG64GiBV1 => (PoRepKind::Interactive, true),
+        StackedDRG2KiBV1P1 | StackedDRG512MiBV1P1 | StackedDRG8MiBV1P1 | StackedDRG32GiBV1P1
+        | StackedDRG64GiBV1P1 => (PoRepKind::Interactive, false),
+        StackedDRG2KiBV1P1_Feat_SyntheticPoRep
+        | StackedDRG512MiBV1P1_Feat_SyntheticPoRep
+        | StackedDRG8MiBV1P1_Feat_SyntheticPoRep
+        | StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+        | StackedDRG64GiBV1P1_Feat_SyntheticPoRep => (PoRepKind::Synthetic, false),
+        StackedDRG2KiBV1P2_Feat_NiPoRep
+        | StackedDRG512MiBV1P2_Feat_NiPoRep
+        | StackedDRG8MiBV1P2_Feat_NiPoRep
+        | StackedDRG32GiBV1P2_Feat_NiPoRep
+        | StackedDRG64GiBV1P2_Feat_NiPoRep => (PoRepKind::NonInteractive, false),
+        Invalid(i) => anyhow::bail!("invalid seal proof {i}"),
+    };
+
+    let sector_size = proof.sector_size().map_err(|e| anyhow!(e))?;
+    let winning_post_proof = match sector_size {
+        SectorSize::_2KiB => RegisteredPoStProof::StackedDRGWinning2KiBV1,
+        SectorSize::_8MiB => RegisteredPoStProof::StackedDRGWinning8MiBV1,
+        SectorSize::_512MiB => RegisteredPoStProof::StackedDRGWinning512MiBV1,
+        SectorSize::_32GiB => RegisteredPoStProof::StackedDRGWinning32GiBV1,
+        SectorSize::_64GiB => RegisteredPoStProof::StackedDRGWinning64GiBV1,
+    };
+    Ok(SealProofProperties {
+        sector_size,
+        porep,
+        legacy,
+        window_post_proof: proof
+            .registered_window_post_proof()
+            .map_err(|e| anyhow!(e))?,
+        winning_post_proof,
+        update_proof: proof.registered_update_proof().map_err(|e| anyhow!(e))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use RegisteredSealProof::*;
+
+    #[test]
+    fn test_seal_proof_properties() {
+        let ni = seal_proof_properties(StackedDRG32GiBV1P2_Feat_NiPoRep).unwrap();
+        assert_eq!(ni.sector_size, SectorSize::_32GiB);
+        assert_eq!(ni.porep, PoRepKind::NonInteractive);
+        assert_eq!(
+            ni.window_post_proof,
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1
+        );
+        assert_eq!(
+            ni.winning_post_proof,
+            RegisteredPoStProof::StackedDRGWinning32GiBV1
+        );
+        assert_eq!(ni.update_proof, RegisteredUpdateProof::StackedDRG32GiBV1);
+
+        // Older fvm_shared versions are accepted as well.
+        let legacy =
+            seal_proof_properties(fvm_shared::sector::RegisteredSealProof::StackedDRG64GiBV1)
+                .unwrap();
+        assert_eq!(legacy.sector_size, SectorSize::_64GiB);
+        assert_eq!(legacy.porep, PoRepKind::Interactive);
+        assert!(legacy.legacy);
+
+        let synthetic = seal_proof_properties(StackedDRG2KiBV1P1_Feat_SyntheticPoRep).unwrap();
+        assert_eq!(synthetic.porep, PoRepKind::Synthetic);
+        assert!(!synthetic.legacy);
+
+        assert!(seal_proof_properties(-1).is_err());
+    }
+}
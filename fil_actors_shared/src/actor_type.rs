@@ -0,0 +1,144 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::{Serialize, Serializer};
+
+/// Builtin actor types, across all actors versions. The discriminants match the builtin actor
+/// type IDs of the FVM and are stable, so they can be persisted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u64)]
+pub enum ActorType {
+    System = 1,
+    Init = 2,
+    Cron = 3,
+    Account = 4,
+    Power = 5,
+    Miner = 6,
+    Market = 7,
+    PaymentChannel = 8,
+    Multisig = 9,
+    Reward = 10,
+    VerifiedRegistry = 11,
+    DataCap = 12,
+    Placeholder = 13,
+    EVM = 14,
+    EAM = 15,
+    EthAccount = 16,
+}
+
+impl ActorType {
+    /// All actor types, in ID order.
+    pub const ALL: [ActorType; 16] = [
+        ActorType::System,
+        ActorType::Init,
+        ActorType::Cron,
+        ActorType::Account,
+        ActorType::Power,
+        ActorType::Miner,
+        ActorType::Market,
+        ActorType::PaymentChannel,
+        ActorType::Multisig,
+        ActorType::Reward,
+        ActorType::VerifiedRegistry,
+        ActorType::DataCap,
+        ActorType::Placeholder,
+        ActorType::EVM,
+        ActorType::EAM,
+        ActorType::EthAccount,
+    ];
+
+    /// Returns the name of the actor in the builtin-actors manifest.
+    pub fn manifest_name(&self) -> &'static str {
+        match self {
+            ActorType::System => "system",
+            ActorType::Init => "init",
+            ActorType::Cron => "cron",
+            ActorType::Account => "account",
+            ActorType::Power => "storagepower",
+            ActorType::Miner => "storageminer",
+            ActorType::Market => "storagemarket",
+            ActorType::PaymentChannel => "paymentchannel",
+            ActorType::Multisig => "multisig",
+            ActorType::Reward => "reward",
+            ActorType::VerifiedRegistry => "verifiedregistry",
+            ActorType::DataCap => "datacap",
+            ActorType::Placeholder => "placeholder",
+            ActorType::EVM => "evm",
+            ActorType::EAM => "eam",
+            ActorType::EthAccount => "ethaccount",
+        }
+    }
+
+    /// Parses a name of the builtin-actors manifest.
+    pub fn from_manifest_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.manifest_name() == name)
+    }
+}
+
+impl From<ActorType> for u64 {
+    fn from(actor_type: ActorType) -> Self {
+        actor_type as u64
+    }
+}
+
+impl TryFrom<u64> for ActorType {
+    type Error = anyhow::Error;
+
+    fn try_from(id: u64) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|t| u64::from(*t) == id)
+            .ok_or_else(|| anyhow!("unknown actor type {id}"))
+    }
+}
+
+impl FromStr for ActorType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_manifest_name(s).ok_or_else(|| anyhow!("unknown actor name {s}"))
+    }
+}
+
+impl fmt::Display for ActorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.manifest_name())
+    }
+}
+
+impl Serialize for ActorType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.manifest_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actor_type_round_trip() {
+        for (i, actor_type) in ActorType::ALL.into_iter().enumerate() {
+            assert_eq!(u64::from(actor_type), i as u64 + 1);
+            assert_eq!(ActorType::try_from(i as u64 + 1).unwrap(), actor_type);
+            assert_eq!(
+                ActorType::from_manifest_name(actor_type.manifest_name()),
+                Some(actor_type)
+            );
+            // Same names as the builtin actor types of the runtime.
+            let builtin: crate::v16::vm_api::builtin::Type =
+                num_traits::FromPrimitive::from_u64(i as u64 + 1).unwrap();
+            assert_eq!(builtin.name(), actor_type.manifest_name());
+        }
+        assert_eq!(
+            "storageminer".parse::<ActorType>().unwrap(),
+            ActorType::Miner
+        );
+        assert_eq!(ActorType::from_manifest_name("miner"), None);
+        assert!(ActorType::try_from(0).is_err());
+    }
+}
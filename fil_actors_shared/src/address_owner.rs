@@ -0,0 +1,209 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::bail;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{from_slice, to_vec};
+use fvm_shared4::address::Address;
+use fvm_shared4::ActorID;
+use ipld_core::ipld::Ipld;
+
+use crate::actor_type::ActorType;
+use crate::state_check::{ActorHead, StateTree};
+use crate::v16::vm_api::ActorState;
+
+/// Who owns an address, as told by the type of the actor it resolves to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressOwner {
+    /// A BLS or secp256k1 account.
+    Account,
+    /// An Ethereum account, whose delegated address is its f410 address.
+    EthAccount,
+    /// A delegated address that received funds before an actor was deployed at it.
+    Placeholder,
+    /// A storage provider, whose funds are controlled by its owner and beneficiary.
+    Miner,
+    /// Any other actor, `None` if its code is not in the manifest of the state tree.
+    Other(Option<ActorType>),
+}
+
+/// Outcome of [`classify_address_owner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressClassification {
+    pub id: ActorID,
+    pub owner: AddressOwner,
+    /// The actor entry of the state tree, including its nonce and, from state tree v5 on, its
+    /// delegated address.
+    pub actor: ActorState,
+}
+
+/// Resolves an address in the state tree with the given root and returns the entry of its actor,
+/// or `None` if the address is not assigned to an actor.
+///
+/// ID addresses are looked up directly, other addresses through the address map of the init
+/// actor. Entries of state trees older than v5 have no delegated address.
+pub fn load_actor<BS: Blockstore>(
+    store: &BS,
+    state_root: &Cid,
+    address: &Address,
+) -> anyhow::Result<Option<(ActorID, ActorState)>> {
+    lookup_actor(&StateTree::load(store, state_root)?, address)
+}
+
+/// Classifies the owner of an ID, BLS, secp256k1 or delegated address in the state tree with the
+/// given root, or returns `None` if the address is not assigned to an actor.
+///
+/// Only the actor an address resolves to is classified: accounts acting as the owner or worker of
+/// a miner are reported as accounts.
+pub fn classify_address_owner<BS: Blockstore>(
+    store: &BS,
+    state_root: &Cid,
+    address: &Address,
+) -> anyhow::Result<Option<AddressClassification>> {
+    let tree = StateTree::load(store, state_root)?;
+    let Some((id, actor)) = lookup_actor(&tree, address)? else {
+        return Ok(None);
+    };
+    let owner = match tree.actor_type(&actor.code) {
+        Some(ActorType::Account) => AddressOwner::Account,
+        Some(ActorType::EthAccount) => AddressOwner::EthAccount,
+        Some(ActorType::Placeholder) => AddressOwner::Placeholder,
+        Some(ActorType::Miner) => AddressOwner::Miner,
+        other => AddressOwner::Other(other),
+    };
+    Ok(Some(AddressClassification { id, owner, actor }))
+}
+
+fn lookup_actor<BS: Blockstore>(
+    tree: &StateTree<'_, BS>,
+    address: &Address,
+) -> anyhow::Result<Option<(ActorID, ActorState)>> {
+    let Some(id) = tree.lookup_id(address)? else {
+        return Ok(None);
+    };
+    Ok(tree.actor_state(id)?.map(|actor| (id, actor)))
+}
+
+/// Decodes an actor entry of any state tree version into an [`ActorState`].
+pub(crate) fn decode_actor(entry: &Ipld) -> anyhow::Result<ActorState> {
+    // Checks the code and state links first, for a consistent error on malformed entries.
+    ActorHead::decode(entry)?;
+    let Ipld::List(fields) = entry else {
+        bail!("actor entry is not a list");
+    };
+    let mut fields = fields.clone();
+    // The delegated address was appended to the actor entries in state tree v5.
+    if fields.len() == 4 {
+        fields.push(Ipld::Null);
+    }
+    Ok(from_slice(&to_vec(&Ipld::List(fields))?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::{CborStore, IPLD_RAW};
+    use fvm_ipld_hamt::{BytesKey, Hamt};
+    use fvm_shared4::econ::TokenAmount;
+    use fvm_shared4::HAMT_BIT_WIDTH;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    fn code(name: &str) -> Cid {
+        Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(name.as_bytes()))
+    }
+
+    #[test]
+    fn test_classify_address_owner() {
+        let store = MemoryBlockstore::new();
+        let names = [
+            "system",
+            "init",
+            "account",
+            "placeholder",
+            "storageminer",
+            "multisig",
+        ];
+        let manifest: Vec<(String, Cid)> = names.iter().map(|n| (n.to_string(), code(n))).collect();
+        let manifest = store.put_cbor(&manifest, Code::Blake2b256).unwrap();
+        let system_state = store.put_cbor(&(manifest,), Code::Blake2b256).unwrap();
+
+        let secp = Address::new_secp256k1(&[1; 65]).unwrap();
+        let delegated = Address::new_delegated(10, &[2; 20]).unwrap();
+        let mut address_map =
+            Hamt::<_, ActorID, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        address_map.set(BytesKey(secp.to_bytes()), 100).unwrap();
+        address_map
+            .set(BytesKey(delegated.to_bytes()), 101)
+            .unwrap();
+        let init_state = store
+            .put_cbor(
+                &(address_map.flush().unwrap(), 103u64, "test"),
+                Code::Blake2b256,
+            )
+            .unwrap();
+
+        let empty = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        let mut actors =
+            Hamt::<_, ActorState, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        for (id, name, state, delegated_address) in [
+            (0, "system", system_state, None),
+            (1, "init", init_state, None),
+            (100, "account", empty, None),
+            (101, "placeholder", empty, Some(delegated)),
+            (102, "storageminer", empty, None),
+        ] {
+            let actor = ActorState {
+                code: code(name),
+                state,
+                sequence: id,
+                balance: TokenAmount::from_atto(id),
+                delegated_address,
+            };
+            actors
+                .set(BytesKey(Address::new_id(id).to_bytes()), actor)
+                .unwrap();
+        }
+        let actors = actors.flush().unwrap();
+        let root = store
+            .put_cbor(&(5u64, actors, empty), Code::Blake2b256)
+            .unwrap();
+
+        let classify = |address: &Address| {
+            classify_address_owner(&store, &root, address)
+                .unwrap()
+                .map(|c| (c.id, c.owner, c.actor.delegated_address))
+        };
+        assert_eq!(classify(&secp), Some((100, AddressOwner::Account, None)));
+        assert_eq!(
+            classify(&delegated),
+            Some((101, AddressOwner::Placeholder, Some(delegated)))
+        );
+        assert_eq!(
+            classify(&Address::new_id(102)),
+            Some((102, AddressOwner::Miner, None))
+        );
+        assert_eq!(
+            classify(&Address::new_id(1)),
+            Some((1, AddressOwner::Other(Some(ActorType::Init)), None))
+        );
+        assert_eq!(classify(&Address::new_id(103)), None);
+        assert_eq!(classify(&Address::new_bls(&[3; 48]).unwrap()), None);
+    }
+
+    #[test]
+    fn test_decode_actor_without_delegated_address() {
+        // Actor entries of state trees before v5 have four fields.
+        let entry = Ipld::List(vec![
+            Ipld::Link(code("account")),
+            Ipld::Link(code("state")),
+            Ipld::Integer(7),
+            Ipld::Bytes(vec![]),
+        ]);
+        let actor = decode_actor(&entry).unwrap();
+        assert_eq!(actor.sequence, 7);
+        assert_eq!(actor.delegated_address, None);
+        assert!(decode_actor(&Ipld::List(vec![])).is_err());
+    }
+}
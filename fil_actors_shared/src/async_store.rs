@@ -0,0 +1,261 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::future::Future;
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use futures::stream::{self, Stream};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{from_slice, BytesDe};
+use ipld_core::ipld::Ipld;
+use serde::de::DeserializeOwned;
+
+use crate::diff::{expand_amt_node, hamt_bucket_entries, AmtNode, RawAmtNode};
+
+/// Read access to a store of IPLD blocks that may need to wait for them, e.g. when they are
+/// fetched from the network. This is the async counterpart of the read side of [`Blockstore`].
+pub trait AsyncBlockstore {
+    /// Gets the bytes of a block, `None` if it is not in the store.
+    fn get(&self, cid: &Cid) -> impl Future<Output = anyhow::Result<Option<Vec<u8>>>> + Send;
+}
+
+/// Exposes a synchronous [`Blockstore`] as an [`AsyncBlockstore`], e.g. for a local cache in
+/// front of a network store. Blocks are read when requested, blocking the caller.
+pub struct SyncStore<BS>(pub BS);
+
+impl<BS: Blockstore> AsyncBlockstore for SyncStore<BS> {
+    fn get(&self, cid: &Cid) -> impl Future<Output = anyhow::Result<Option<Vec<u8>>>> + Send {
+        std::future::ready(self.0.get(cid))
+    }
+}
+
+/// Gets and decodes a DAG-CBOR block, `None` if it is not in the store.
+pub async fn get_cbor_async<S, T>(store: &S, cid: &Cid) -> anyhow::Result<Option<T>>
+where
+    S: AsyncBlockstore,
+    T: DeserializeOwned,
+{
+    match store.get(cid).await? {
+        Some(bytes) => Ok(Some(from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Async loading of states and other DAG-CBOR values from their CID, as in
+/// `State::load_async(&store, &head).await`. It is implemented for every deserializable type, and
+/// its futures can be spawned on multi-threaded executors.
+pub trait LoadAsync: DeserializeOwned + Send {
+    fn load_async<S: AsyncBlockstore + Sync>(
+        store: &S,
+        cid: &Cid,
+    ) -> impl Future<Output = anyhow::Result<Self>> + Send;
+}
+
+impl<T: DeserializeOwned + Send> LoadAsync for T {
+    async fn load_async<S: AsyncBlockstore + Sync>(store: &S, cid: &Cid) -> anyhow::Result<Self> {
+        get_cbor_async(store, cid)
+            .await?
+            .with_context(|| format!("block {cid} not found"))
+    }
+}
+
+enum HamtItem {
+    Node(Cid),
+    Entry(Vec<u8>, Ipld),
+}
+
+/// Streams the `(key, value)` entries of a HAMT of any bit width in the HAMT order, loading its
+/// nodes as they are reached. Keys are left undecoded, values are decoded as `V`, which may be
+/// [`Ipld`] to defer their decoding.
+pub fn hamt_entries<'s, S, V>(
+    store: &'s S,
+    root: &Cid,
+) -> impl Stream<Item = anyhow::Result<(Vec<u8>, V)>> + 's
+where
+    S: AsyncBlockstore,
+    V: DeserializeOwned + 's,
+{
+    stream::try_unfold(vec![HamtItem::Node(*root)], move |mut stack| async move {
+        while let Some(item) = stack.pop() {
+            let cid = match item {
+                HamtItem::Entry(key, value) => {
+                    return Ok(Some(((key, ipld_core::serde::from_ipld(value)?), stack)))
+                }
+                HamtItem::Node(cid) => cid,
+            };
+            let (_, pointers): (BytesDe, Vec<Ipld>) = get_cbor_async(store, &cid)
+                .await?
+                .with_context(|| format!("HAMT node {cid} not found"))?;
+            let mut items = Vec::new();
+            for pointer in pointers {
+                match pointer {
+                    Ipld::Link(cid) => items.push(HamtItem::Node(cid)),
+                    Ipld::List(bucket) => items.extend(
+                        hamt_bucket_entries(&bucket)?
+                            .into_iter()
+                            .map(|(key, value)| HamtItem::Entry(key, value)),
+                    ),
+                    _ => return Err(anyhow!("malformed HAMT pointer")),
+                }
+            }
+            stack.extend(items.into_iter().rev());
+        }
+        Ok(None)
+    })
+}
+
+enum AmtItem {
+    Node { cid: Cid, height: u32, offset: u64 },
+    Value(u64, Ipld),
+}
+
+/// Streams the `(index, value)` entries of an AMT in index order, loading its nodes as they are
+/// reached. Values are decoded as `V`, which may be [`Ipld`] to defer their decoding.
+pub fn amt_entries<'s, S, V>(
+    store: &'s S,
+    root: &Cid,
+) -> impl Stream<Item = anyhow::Result<(u64, V)>> + 's
+where
+    S: AsyncBlockstore,
+    V: DeserializeOwned + 's,
+{
+    let root = *root;
+    stream::try_unfold(None, move |stack: Option<(u32, Vec<AmtItem>)>| async move {
+        let (bit_width, mut stack) = match stack {
+            Some(stack) => stack,
+            None => {
+                let (bit_width, height, _count, node): (u32, u32, u64, RawAmtNode) =
+                    get_cbor_async(store, &root)
+                        .await?
+                        .with_context(|| format!("AMT root {root} not found"))?;
+                let mut stack = Vec::new();
+                push_amt_node(&mut stack, bit_width, 0, expand_amt_node(height, node))?;
+                (bit_width, stack)
+            }
+        };
+        while let Some(item) = stack.pop() {
+            match item {
+                AmtItem::Value(index, value) => {
+                    let value = ipld_core::serde::from_ipld(value)?;
+                    return Ok(Some(((index, value), Some((bit_width, stack)))));
+                }
+                AmtItem::Node {
+                    cid,
+                    height,
+                    offset,
+                } => {
+                    let node = get_cbor_async(store, &cid)
+                        .await?
+                        .with_context(|| format!("AMT node {cid} not found"))?;
+                    push_amt_node(&mut stack, bit_width, offset, expand_amt_node(height, node))?;
+                }
+            }
+        }
+        Ok(None)
+    })
+}
+
+/// Pushes the children of an AMT node covering the indexes starting at `offset` on the stack, so
+/// that they are popped in index order.
+fn push_amt_node(
+    stack: &mut Vec<AmtItem>,
+    bit_width: u32,
+    offset: u64,
+    node: AmtNode,
+) -> anyhow::Result<()> {
+    if node.height == 0 {
+        stack.extend(
+            node.values
+                .into_iter()
+                .rev()
+                .map(|(i, value)| AmtItem::Value(offset + i, value)),
+        );
+        return Ok(());
+    }
+    let child_span = 1u64
+        .checked_shl(bit_width * node.height)
+        .context("AMT too high to iterate")?;
+    stack.extend(node.links.into_iter().rev().map(|(i, cid)| AmtItem::Node {
+        cid,
+        height: node.height - 1,
+        offset: offset + i * child_span,
+    }));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::TryStreamExt;
+    use fvm_ipld_amt::Amt;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_ipld_hamt::{BytesKey, Hamt};
+    use multihash_codetable::Code;
+    use std::sync::Mutex;
+
+    /// A store shared between threads, as network stores are.
+    struct SharedStore(Mutex<MemoryBlockstore>);
+
+    impl AsyncBlockstore for SharedStore {
+        fn get(&self, cid: &Cid) -> impl Future<Output = anyhow::Result<Option<Vec<u8>>>> + Send {
+            std::future::ready(self.0.lock().unwrap().get(cid))
+        }
+    }
+
+    #[test]
+    fn test_load_async() {
+        let store = MemoryBlockstore::new();
+        let cid = store.put_cbor(&(1u64, "state"), Code::Blake2b256).unwrap();
+        let store = SharedStore(Mutex::new(store));
+        let state = block_on(<(u64, String)>::load_async(&store, &cid)).unwrap();
+        assert_eq!(state, (1, "state".to_string()));
+
+        let missing = Cid::default();
+        assert!(block_on(<(u64, String)>::load_async(&store, &missing)).is_err());
+    }
+
+    #[test]
+    fn test_hamt_entries() {
+        let store = MemoryBlockstore::new();
+        let mut hamt = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, 2);
+        for i in 0..200u64 {
+            hamt.set(BytesKey(i.to_be_bytes().to_vec()), i * 2).unwrap();
+        }
+        let root = hamt.flush().unwrap();
+        let mut expected = Vec::new();
+        hamt.for_each(|key, value| {
+            expected.push((key.0.clone(), *value));
+            Ok(())
+        })
+        .unwrap();
+
+        let store = SyncStore(store);
+        let entries: Vec<(Vec<u8>, u64)> =
+            block_on(hamt_entries(&store, &root).try_collect()).unwrap();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_amt_entries() {
+        let store = MemoryBlockstore::new();
+        let mut amt = Amt::new_with_bit_width(&store, 2);
+        let indexes = [0u64, 3, 17, 64, 1000, 1001];
+        for i in indexes {
+            amt.set(i, format!("value {i}")).unwrap();
+        }
+        let root = amt.flush().unwrap();
+
+        let store = SyncStore(store);
+        let entries: Vec<(u64, String)> =
+            block_on(amt_entries(&store, &root).try_collect()).unwrap();
+        let expected: Vec<_> = indexes.iter().map(|i| (*i, format!("value {i}"))).collect();
+        assert_eq!(entries, expected);
+
+        let empty = Amt::<u64, _>::new(&store.0).flush().unwrap();
+        let entries: Vec<(u64, u64)> = block_on(amt_entries(&store, &empty).try_collect()).unwrap();
+        assert!(entries.is_empty());
+    }
+}
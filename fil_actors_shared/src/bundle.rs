@@ -0,0 +1,599 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, bail, ensure, Context};
+use cid::Cid;
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_encoding::{from_slice, CborStore};
+use multihash_codetable::{Code, MultihashDigest};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::actor_type::ActorType;
+use crate::state_check::{load_manifest, StateTree, INIT_ACTOR_ID, SYSTEM_ACTOR_ID};
+
+/// Version of the only manifest format, the one referenced by the system actor.
+pub const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct CarHeader {
+    roots: Vec<Cid>,
+    version: u64,
+}
+
+/// The manifest of a builtin-actors release, mapping actor names to code CIDs.
+///
+/// The root block of a manifest is `(version, data)`, where `data` links to the list of
+/// `(actor name, code CID)` pairs. On chain, the `builtin_actors` field of the system actor state
+/// links to that data rather than to the root, see [`Manifest::load_data`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub version: u32,
+    pub actors: Vec<(String, Cid)>,
+}
+
+impl Manifest {
+    /// Loads the manifest with the given root CID from a store.
+    pub fn load<BS: Blockstore>(store: &BS, root: &Cid) -> anyhow::Result<Self> {
+        let (version, data): (u32, Cid) = store
+            .get_cbor(root)?
+            .with_context(|| format!("manifest {root} not found"))?;
+        Ok(Self {
+            version,
+            ..Self::load_data(store, &data)?
+        })
+    }
+
+    /// Loads the manifest whose data has the given CID from a store, such as the `builtin_actors`
+    /// of the system actor state. The version is then [`MANIFEST_VERSION`].
+    pub fn load_data<BS: Blockstore>(store: &BS, data: &Cid) -> anyhow::Result<Self> {
+        let actors: Vec<(String, Cid)> = store
+            .get_cbor(data)?
+            .with_context(|| format!("manifest data {data} not found"))?;
+        Ok(Self {
+            version: MANIFEST_VERSION,
+            actors,
+        })
+    }
+
+    /// Returns the code CID of the named actor.
+    pub fn code_cid(&self, name: &str) -> Option<&Cid> {
+        self.actors
+            .iter()
+            .find_map(|(actor, cid)| (actor == name).then_some(cid))
+    }
+
+    /// Returns the code CID of the given actor type, if the manifest has it.
+    pub fn actor_code(&self, actor_type: ActorType) -> Option<&Cid> {
+        self.code_cid(actor_type.manifest_name())
+    }
+
+    /// Returns the type of the actor with the given code CID, if it belongs to this manifest and
+    /// is a known actor type.
+    pub fn actor_type(&self, code: &Cid) -> Option<ActorType> {
+        self.actors
+            .iter()
+            .find(|(_, cid)| cid == code)
+            .and_then(|(name, _)| ActorType::from_manifest_name(name))
+    }
+}
+
+/// The manifest of the builtin actors deployed on a network at an actors version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkManifest {
+    /// Network name, as recorded in the init actor state.
+    pub network: String,
+    pub actors_version: u64,
+    pub manifest: Manifest,
+}
+
+impl NetworkManifest {
+    /// Reads the manifest referenced by the system actor in the state tree with the given root,
+    /// and the network name from the init actor. The actors version is not recorded in the state
+    /// tree, so it is given by the caller, e.g. from [`crate::version::actors_version`] at the
+    /// epoch of the state.
+    pub fn fetch_from_store<BS: Blockstore>(
+        store: &BS,
+        root: &Cid,
+        actors_version: u64,
+    ) -> anyhow::Result<Self> {
+        let tree = StateTree::load(store, root)?;
+        let system = tree.actor(SYSTEM_ACTOR_ID)?.context("system actor not found")?;
+        let init = tree.actor(INIT_ACTOR_ID)?.context("init actor not found")?;
+        let (_, _, network): (Cid, u64, String) = store
+            .get_cbor(&init.state)
+            .context("failed to decode init actor state")?
+            .context("init actor state not found")?;
+        Ok(Self {
+            network,
+            actors_version,
+            manifest: Manifest {
+                version: MANIFEST_VERSION,
+                actors: load_manifest(store, &system.state)?,
+            },
+        })
+    }
+}
+
+/// Code CIDs of the builtin actors by network and actors version, so that embedders can refresh
+/// them from chain state when a new builtin-actors release lands, see
+/// [`KnownCids::update_from`], or load the registries distributed by the operators of private
+/// networks at startup, see [`KnownCids::merge`].
+///
+/// It (de)serializes as nested maps with the CIDs as strings, e.g. in YAML:
+///
+/// ```yaml
+/// calibrationnet:
+///   16:
+///     account: bafk2bzaceb...
+///     cron: bafk2bzacec...
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KnownCids(BTreeMap<String, BTreeMap<u64, BTreeMap<String, Cid>>>);
+
+impl KnownCids {
+    /// Returns the code CID of an actor on a network at an actors version.
+    pub fn code_cid(&self, network: &str, actors_version: u64, actor: ActorType) -> Option<&Cid> {
+        self.0
+            .get(network)?
+            .get(&actors_version)?
+            .get(actor.manifest_name())
+    }
+
+    /// Returns the actors version and type of the actor with the given code CID on a network.
+    pub fn actor_type(&self, network: &str, code: &Cid) -> Option<(u64, ActorType)> {
+        self.0.get(network)?.iter().find_map(|(version, actors)| {
+            actors
+                .iter()
+                .find(|(_, cid)| *cid == code)
+                .and_then(|(name, _)| ActorType::from_manifest_name(name))
+                .map(|actor_type| (*version, actor_type))
+        })
+    }
+
+    /// Returns the actors version and type of the actor with the given code CID, whatever the
+    /// network. A code CID is the hash of the actor bytecode, so it belongs to a single release.
+    pub fn resolve(&self, code: &Cid) -> Option<(u64, ActorType)> {
+        self.0
+            .keys()
+            .find_map(|network| self.actor_type(network, code))
+    }
+
+    /// Records the code CID of an actor on a network at an actors version, and returns the one it
+    /// replaces.
+    pub fn insert(
+        &mut self,
+        network: &str,
+        actors_version: u64,
+        actor: ActorType,
+        code: Cid,
+    ) -> Option<Cid> {
+        self.0
+            .entry(network.to_string())
+            .or_default()
+            .entry(actors_version)
+            .or_default()
+            .insert(actor.manifest_name().to_string(), code)
+    }
+
+    /// Records the code CIDs of the given manifests, see [`KnownCids::merge`]. Returns whether
+    /// any CID was added.
+    pub fn update_from<'a>(
+        &mut self,
+        manifests: impl IntoIterator<Item = &'a NetworkManifest>,
+    ) -> anyhow::Result<bool> {
+        let mut update = Self::default();
+        for manifest in manifests {
+            let actors = manifest.manifest.actors.iter().cloned().collect();
+            let versions = BTreeMap::from([(manifest.actors_version, actors)]);
+            update.merge(&Self(BTreeMap::from([(manifest.network.clone(), versions)])))?;
+        }
+        self.merge(&update)
+    }
+
+    /// Adds the CIDs of networks, actors versions and actors which are not known yet. A CID which
+    /// differs from the known one for the same network, actors version and actor is a conflict:
+    /// nothing is merged then. Returns whether any CID was added.
+    pub fn merge(&mut self, other: &KnownCids) -> anyhow::Result<bool> {
+        for (network, version, name, cid) in other.entries() {
+            let known = self
+                .0
+                .get(network)
+                .and_then(|versions| versions.get(&version))
+                .and_then(|actors| actors.get(name));
+            if let Some(known) = known {
+                ensure!(
+                    known == cid,
+                    "conflicting code CIDs for {name} on {network} at actors v{version}: {known} \
+                     is known, got {cid}"
+                );
+            }
+        }
+        let mut changed = false;
+        for (network, version, name, cid) in other.entries() {
+            let previous = self
+                .0
+                .entry(network.clone())
+                .or_default()
+                .entry(version)
+                .or_default()
+                .insert(name.clone(), *cid);
+            changed |= previous.is_none();
+        }
+        Ok(changed)
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (&String, u64, &String, &Cid)> {
+        self.0.iter().flat_map(|(network, versions)| {
+            versions.iter().flat_map(move |(version, actors)| {
+                actors
+                    .iter()
+                    .map(move |(name, cid)| (network, *version, name, cid))
+            })
+        })
+    }
+
+    /// Parses known CIDs from YAML.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> anyhow::Result<Self> {
+        Ok(serde_yaml::from_str(yaml)?)
+    }
+
+    /// Serializes the known CIDs to YAML.
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> anyhow::Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Parses known CIDs from a YAML reader, such as a registry file.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_reader(reader: impl std::io::Read) -> anyhow::Result<Self> {
+        Ok(serde_yaml::from_reader(reader)?)
+    }
+
+    /// Writes the known CIDs as YAML.
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml_writer(&self, writer: impl std::io::Write) -> anyhow::Result<()> {
+        Ok(serde_yaml::to_writer(writer, self)?)
+    }
+}
+
+type KnownCidStrings = BTreeMap<String, BTreeMap<u64, BTreeMap<String, String>>>;
+
+impl Serialize for KnownCids {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let strings: KnownCidStrings = self
+            .0
+            .iter()
+            .map(|(network, versions)| {
+                let versions = versions
+                    .iter()
+                    .map(|(version, actors)| {
+                        let actors = actors
+                            .iter()
+                            .map(|(name, cid)| (name.clone(), cid.to_string()))
+                            .collect();
+                        (*version, actors)
+                    })
+                    .collect();
+                (network.clone(), versions)
+            })
+            .collect();
+        strings.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KnownCids {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let strings = KnownCidStrings::deserialize(deserializer)?;
+        let mut known = BTreeMap::new();
+        for (network, versions) in strings {
+            let mut known_versions = BTreeMap::new();
+            for (version, actors) in versions {
+                let actors = actors
+                    .into_iter()
+                    .map(|(name, cid)| {
+                        let cid = Cid::try_from(cid.as_str()).map_err(serde::de::Error::custom)?;
+                        Ok((name, cid))
+                    })
+                    .collect::<Result<_, D::Error>>()?;
+                known_versions.insert(version, actors);
+            }
+            known.insert(network, known_versions);
+        }
+        Ok(Self(known))
+    }
+}
+
+/// A builtin-actors bundle, as published in the CAR files of builtin-actors releases. The root of
+/// the bundle is its [`Manifest`], and the code CIDs are the raw WASM modules of the actors.
+#[derive(Debug)]
+pub struct Bundle {
+    root: Cid,
+    manifest: Manifest,
+    blocks: MemoryBlockstore,
+}
+
+impl Bundle {
+    /// Parses a bundle from the bytes of its CARv1 file, checking the integrity of every block.
+    pub fn from_car(car: &[u8]) -> anyhow::Result<Self> {
+        let mut rest = car;
+        let header: CarHeader = from_slice(read_section(&mut rest)?)?;
+        ensure!(
+            header.version == 1,
+            "unsupported CAR version {}",
+            header.version
+        );
+        let [root] = header.roots[..] else {
+            bail!("expected a single root, found {}", header.roots.len());
+        };
+
+        let blocks = MemoryBlockstore::new();
+        while !rest.is_empty() {
+            let mut section = read_section(&mut rest)?;
+            let cid = Cid::read_bytes(&mut section)?;
+            let code = Code::try_from(cid.hash().code())?;
+            ensure!(
+                code.digest(section) == *cid.hash(),
+                "block {cid} does not match its hash"
+            );
+            blocks.put_keyed(&cid, section)?;
+        }
+
+        let manifest = Manifest::load(&blocks, &root)?;
+        Ok(Self {
+            root,
+            manifest,
+            blocks,
+        })
+    }
+
+    /// Returns the CID of the root of the bundle manifest. The system actor state references the
+    /// manifest data instead, see [`Manifest::load_data`].
+    pub fn manifest_cid(&self) -> &Cid {
+        &self.root
+    }
+
+    /// Returns the bundle manifest.
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Returns the version of the manifest format.
+    pub fn manifest_version(&self) -> u32 {
+        self.manifest.version
+    }
+
+    /// Returns the `(actor name, code CID)` pairs of the bundle, in manifest order.
+    pub fn actors(&self) -> &[(String, Cid)] {
+        &self.manifest.actors
+    }
+
+    /// Returns the code CID of the named actor.
+    pub fn code_cid(&self, name: &str) -> Option<&Cid> {
+        self.manifest.code_cid(name)
+    }
+
+    /// Returns the WASM module of the named actor, if it is included in the bundle.
+    pub fn wasm(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match self.code_cid(name) {
+            Some(cid) => self.blocks.get(cid),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks that the bundle contains exactly the expected actors with the expected code CIDs.
+    pub fn verify<'a>(
+        &self,
+        expected: impl IntoIterator<Item = (&'a str, Cid)>,
+    ) -> anyhow::Result<()> {
+        let mut expected: HashMap<&str, Cid> = expected.into_iter().collect();
+        for (name, cid) in self.actors() {
+            let expected_cid = expected
+                .remove(name.as_str())
+                .ok_or_else(|| anyhow!("unexpected actor {name} in bundle"))?;
+            ensure!(
+                *cid == expected_cid,
+                "code CID mismatch for actor {name}: expected {expected_cid}, found {cid}"
+            );
+        }
+        if let Some(name) = expected.keys().next() {
+            bail!("actor {name} is missing from the bundle");
+        }
+        Ok(())
+    }
+}
+
+/// Reads a varint length-prefixed section of a CAR file.
+fn read_section<'a>(data: &mut &'a [u8]) -> anyhow::Result<&'a [u8]> {
+    let (len, rest) = unsigned_varint::decode::u64(data)?;
+    let len = usize::try_from(len)?;
+    ensure!(rest.len() >= len, "unexpected end of CAR data");
+    let (section, rest) = rest.split_at(len);
+    *data = rest;
+    Ok(section)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_encoding::{to_vec, DAG_CBOR, IPLD_RAW};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Header {
+        roots: Vec<Cid>,
+        version: u64,
+    }
+
+    fn block(codec: u64, data: Vec<u8>) -> (Cid, Vec<u8>) {
+        (Cid::new_v1(codec, Code::Blake2b256.digest(&data)), data)
+    }
+
+    fn write_section(car: &mut Vec<u8>, data: &[u8]) {
+        let mut buf = unsigned_varint::encode::u64_buffer();
+        car.extend_from_slice(unsigned_varint::encode::u64(data.len() as u64, &mut buf));
+        car.extend_from_slice(data);
+    }
+
+    fn bundle_car() -> (Vec<u8>, Cid) {
+        let (code, wasm) = block(IPLD_RAW, b"\0asm".to_vec());
+        let (data_cid, data) = block(
+            DAG_CBOR,
+            to_vec(&vec![("system".to_string(), code)]).unwrap(),
+        );
+        let (root, manifest) = block(DAG_CBOR, to_vec(&(1u32, data_cid)).unwrap());
+
+        let mut car = Vec::new();
+        let header = Header {
+            roots: vec![root],
+            version: 1,
+        };
+        write_section(&mut car, &to_vec(&header).unwrap());
+        for (cid, data) in [(root, manifest), (data_cid, data), (code, wasm)] {
+            let mut section = cid.to_bytes();
+            section.extend_from_slice(&data);
+            write_section(&mut car, &section);
+        }
+        (car, code)
+    }
+
+    #[test]
+    fn test_read_bundle() {
+        let (car, code) = bundle_car();
+        let bundle = Bundle::from_car(&car).unwrap();
+        assert_eq!(bundle.manifest_version(), 1);
+        assert_eq!(bundle.code_cid("system"), Some(&code));
+        assert_eq!(bundle.manifest().actor_code(ActorType::System), Some(&code));
+        assert_eq!(bundle.manifest().actor_type(&code), Some(ActorType::System));
+        assert_eq!(bundle.wasm("system").unwrap(), Some(b"\0asm".to_vec()));
+        assert_eq!(bundle.wasm("init").unwrap(), None);
+        assert_eq!(
+            Manifest::load(&bundle.blocks, bundle.manifest_cid()).unwrap(),
+            *bundle.manifest()
+        );
+
+        bundle.verify([("system", code)]).unwrap();
+        assert!(bundle.verify([]).is_err());
+        assert!(bundle.verify([("system", code), ("init", code)]).is_err());
+    }
+
+    fn state_tree(store: &MemoryBlockstore, actors: &[(String, Cid)]) -> Cid {
+        use fvm_ipld_hamt::{BytesKey, Hamt};
+        use fvm_shared4::address::Address;
+        use fvm_shared4::HAMT_BIT_WIDTH;
+        use ipld_core::ipld::Ipld;
+
+        let data = store.put_cbor(&actors, Code::Blake2b256).unwrap();
+        let system_state = store.put_cbor(&(data,), Code::Blake2b256).unwrap();
+        let init_state = store
+            .put_cbor(&(data, 1000u64, "calibrationnet"), Code::Blake2b256)
+            .unwrap();
+        let mut tree = Hamt::<_, Ipld, BytesKey>::new_with_bit_width(store, HAMT_BIT_WIDTH);
+        for (id, name, state) in [(0, "system", system_state), (1, "init", init_state)] {
+            let code = actors.iter().find(|(n, _)| n == name).unwrap().1;
+            let actor = Ipld::List(vec![
+                Ipld::Link(code),
+                Ipld::Link(state),
+                Ipld::Integer(0),
+                Ipld::Bytes(vec![]),
+            ]);
+            tree.set(BytesKey(Address::new_id(id).to_bytes()), actor)
+                .unwrap();
+        }
+        let tree = tree.flush().unwrap();
+        let info = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        store
+            .put_cbor(&(5u64, tree, info), Code::Blake2b256)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_known_cids() {
+        let code = |name: &str| block(IPLD_RAW, name.as_bytes().to_vec()).0;
+        let actors: Vec<_> = ["system", "init"]
+            .map(|name| (name.to_string(), code(name)))
+            .into();
+        let store = MemoryBlockstore::new();
+        let root = state_tree(&store, &actors);
+
+        let manifest = NetworkManifest::fetch_from_store(&store, &root, 16).unwrap();
+        assert_eq!(manifest.network, "calibrationnet");
+        assert_eq!(manifest.manifest.actors, actors);
+
+        let mut known = KnownCids::default();
+        assert!(known.update_from([&manifest]).unwrap());
+        assert!(!known.update_from([&manifest]).unwrap());
+        assert_eq!(
+            known.code_cid("calibrationnet", 16, ActorType::Init),
+            Some(&code("init"))
+        );
+        assert_eq!(known.code_cid("mainnet", 16, ActorType::Init), None);
+        assert_eq!(
+            known.actor_type("calibrationnet", &code("system")),
+            Some((16, ActorType::System))
+        );
+
+        let yaml = serde_yaml::to_string(&known).unwrap();
+        assert!(yaml.starts_with("calibrationnet:\n  16:\n    init: "));
+        assert_eq!(serde_yaml::from_str::<KnownCids>(&yaml).unwrap(), known);
+        let json = serde_json::to_string(&known).unwrap();
+        assert_eq!(serde_json::from_str::<KnownCids>(&json).unwrap(), known);
+        assert!(serde_yaml::from_str::<KnownCids>("mainnet: {16: {init: nope}}").is_err());
+    }
+
+    #[test]
+    fn test_merge_known_cids() {
+        let code = |name: &str| block(IPLD_RAW, name.as_bytes().to_vec()).0;
+        let mut known = KnownCids::default();
+        known.insert("calibrationnet", 16, ActorType::Init, code("init"));
+
+        // A private network registry, which also agrees with the known calibrationnet CID.
+        let mut registry = KnownCids::default();
+        registry.insert("calibrationnet", 16, ActorType::Init, code("init"));
+        registry.insert("devnet", 16, ActorType::Init, code("devnet init"));
+        assert!(known.merge(&registry).unwrap());
+        assert!(!known.merge(&registry).unwrap());
+        assert_eq!(
+            known.code_cid("devnet", 16, ActorType::Init),
+            Some(&code("devnet init"))
+        );
+
+        // A differing CID for the same actor fails the whole merge.
+        let mut conflicting = KnownCids::default();
+        conflicting.insert("devnet", 16, ActorType::Cron, code("devnet cron"));
+        conflicting.insert("devnet", 16, ActorType::Init, code("other init"));
+        let err = known.merge(&conflicting).unwrap_err();
+        assert!(err.to_string().starts_with("conflicting code CIDs for init on devnet"));
+        assert_eq!(known.code_cid("devnet", 16, ActorType::Cron), None);
+
+        let manifest = NetworkManifest {
+            network: "devnet".into(),
+            actors_version: 16,
+            manifest: Manifest {
+                version: MANIFEST_VERSION,
+                actors: vec![("init".into(), code("other init"))],
+            },
+        };
+        assert!(known.update_from([&manifest]).is_err());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_known_cids_yaml_io() {
+        let mut known = KnownCids::default();
+        let code = block(IPLD_RAW, b"init".to_vec()).0;
+        known.insert("devnet", 16, ActorType::Init, code);
+        let mut yaml = Vec::new();
+        known.to_yaml_writer(&mut yaml).unwrap();
+        assert_eq!(KnownCids::from_yaml_reader(yaml.as_slice()).unwrap(), known);
+    }
+
+    #[test]
+    fn test_corrupted_block_is_rejected() {
+        let (mut car, _) = bundle_car();
+        *car.last_mut().unwrap() ^= 0xff;
+        assert!(Bundle::from_car(&car).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+/// A blockstore wrapper memoizing the blocks read through it, meant to be held for the duration
+/// of a query session.
+///
+/// Flows such as checking several sectors of the same miner load the same deadline, partition
+/// and sector AMT nodes over and over. Wrapping the store in a `StateCache` turns the repeated
+/// loads into map lookups. Blocks are immutable, so cached entries never become stale; the
+/// explicit invalidation only serves to bound memory use.
+#[derive(Debug, Default)]
+pub struct StateCache<BS> {
+    inner: BS,
+    blocks: RwLock<HashMap<Cid, Vec<u8>>>,
+}
+
+impl<BS> StateCache<BS> {
+    pub fn new(inner: BS) -> Self {
+        Self {
+            inner,
+            blocks: Default::default(),
+        }
+    }
+
+    /// Returns the wrapped store.
+    pub fn inner(&self) -> &BS {
+        &self.inner
+    }
+
+    /// Returns the number of cached blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Returns `true` if no block is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Evicts a block from the cache. Returns `true` if it was cached.
+    pub fn invalidate(&self, cid: &Cid) -> bool {
+        self.blocks
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(cid)
+            .is_some()
+    }
+
+    /// Evicts all blocks from the cache.
+    pub fn clear(&self) {
+        self.blocks
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+impl<BS: Blockstore> Blockstore for StateCache<BS> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        if let Some(block) = self.blocks.read().unwrap_or_else(|e| e.into_inner()).get(k) {
+            return Ok(Some(block.clone()));
+        }
+        let block = self.inner.get(k)?;
+        if let Some(block) = &block {
+            self.blocks
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(*k, block.clone());
+        }
+        Ok(block)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.inner.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        if self
+            .blocks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(k)
+        {
+            return Ok(true);
+        }
+        self.inner.has(k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use multihash_codetable::Code;
+    use std::cell::Cell;
+
+    #[derive(Default)]
+    struct CountingStore {
+        store: MemoryBlockstore,
+        gets: Cell<usize>,
+    }
+
+    impl Blockstore for CountingStore {
+        fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            self.gets.set(self.gets.get() + 1);
+            self.store.get(k)
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+            self.store.put_keyed(k, block)
+        }
+    }
+
+    #[test]
+    fn test_repeated_loads_hit_the_cache() {
+        let cache = StateCache::new(CountingStore::default());
+        let cid = cache
+            .put_cbor(&(1u64, "deadline"), Code::Blake2b256)
+            .unwrap();
+
+        for _ in 0..3 {
+            let value: (u64, String) = cache.get_cbor(&cid).unwrap().unwrap();
+            assert_eq!(value.0, 1);
+        }
+        assert_eq!(cache.inner().gets.get(), 1);
+        assert_eq!(cache.len(), 1);
+
+        assert!(cache.invalidate(&cid));
+        assert!(cache.is_empty());
+        cache.get(&cid).unwrap();
+        assert_eq!(cache.inner().gets.get(), 2);
+    }
+}
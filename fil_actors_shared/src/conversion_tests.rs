@@ -0,0 +1,160 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt::Debug;
+
+use cid::Cid;
+use fvm_ipld_encoding::{from_slice, to_vec, DAG_CBOR};
+use ipld_core::ipld::Ipld;
+use multihash_codetable::{Code, MultihashDigest};
+use quickcheck::{Arbitrary, Gen};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Conversions of a structure between the types of two adjacent actor versions, checked by
+/// [`check_conversion`]. Implement it for the types of a custom version to run them through the
+/// same checks as the builtin versions.
+pub trait Conversion {
+    type Old: Debug + PartialEq + Serialize;
+    type New: Debug + PartialEq + Serialize;
+
+    /// Indexes of the encoded fields of the old type whose meaning changed in the new version,
+    /// which are not compared by [`check_conversion`].
+    const CHANGED_FIELDS: &'static [usize] = &[];
+
+    fn arbitrary(g: &mut Gen) -> Self::Old;
+
+    fn upgrade(old: &Self::Old) -> anyhow::Result<Self::New>;
+
+    fn downgrade(new: &Self::New) -> anyhow::Result<Self::Old>;
+}
+
+/// Converts a value to the type of another version by re-decoding its encoding, for versions
+/// with the same layout.
+pub fn cbor_convert<A: Serialize, B: DeserializeOwned>(value: &A) -> anyhow::Result<B> {
+    Ok(from_slice(&to_vec(value)?)?)
+}
+
+/// Runs a [`Conversion`] on `tests` arbitrary values, and panics with the first value for which:
+/// - a conversion fails,
+/// - the encoded fields of the upgraded value differ from the fields of the old value, except
+///   the [`Conversion::CHANGED_FIELDS`] and the fields appended by the new version,
+/// - downgrading the upgraded value does not give back the old value.
+pub fn check_conversion<C: Conversion>(tests: u64) {
+    let mut g = Gen::new(100);
+    for _ in 0..tests {
+        let old = C::arbitrary(&mut g);
+        if let Err(e) = check_value::<C>(&old) {
+            panic!("conversion check failed for {old:?}: {e:#}");
+        }
+    }
+}
+
+fn check_value<C: Conversion>(old: &C::Old) -> anyhow::Result<()> {
+    let new = C::upgrade(old)?;
+    let old_fields = encoded_fields(old)?;
+    let new_fields = encoded_fields(&new)?;
+    for (i, (a, b)) in old_fields.iter().zip(&new_fields).enumerate() {
+        anyhow::ensure!(
+            C::CHANGED_FIELDS.contains(&i) || a == b,
+            "field {i} changed from {a:?} to {b:?}"
+        );
+    }
+    let downgraded = C::downgrade(&new)?;
+    anyhow::ensure!(
+        downgraded == *old,
+        "round trip through {new:?} gave {downgraded:?}"
+    );
+    Ok(())
+}
+
+/// Returns the encoded fields of a tuple-encoded structure.
+fn encoded_fields<T: Serialize>(value: &T) -> anyhow::Result<Vec<Ipld>> {
+    match from_slice(&to_vec(value)?)? {
+        Ipld::List(fields) => Ok(fields),
+        other => anyhow::bail!("expected a tuple-encoded structure, found {other:?}"),
+    }
+}
+
+/// Returns an arbitrary DAG-CBOR CID, for the link fields of the generated structures.
+pub fn arbitrary_cid(g: &mut Gen) -> Cid {
+    Cid::new_v1(
+        DAG_CBOR,
+        Code::Blake2b256.digest(&u64::arbitrary(g).to_be_bytes()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_encoding::tuple::*;
+
+    #[derive(Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+    struct Old {
+        a: u64,
+        b: bool,
+    }
+
+    #[derive(Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+    struct New {
+        a: u64,
+        b: u32,
+        c: Cid,
+    }
+
+    struct Upgrade;
+
+    impl Conversion for Upgrade {
+        type Old = Old;
+        type New = New;
+        const CHANGED_FIELDS: &'static [usize] = &[1];
+
+        fn arbitrary(g: &mut Gen) -> Old {
+            Old {
+                a: u64::arbitrary(g),
+                b: bool::arbitrary(g),
+            }
+        }
+
+        fn upgrade(old: &Old) -> anyhow::Result<New> {
+            Ok(New {
+                a: old.a,
+                b: old.b.into(),
+                c: Cid::default(),
+            })
+        }
+
+        fn downgrade(new: &New) -> anyhow::Result<Old> {
+            Ok(Old {
+                a: new.a,
+                b: new.b != 0,
+            })
+        }
+    }
+
+    struct Lossy;
+
+    impl Conversion for Lossy {
+        type Old = Old;
+        type New = Old;
+
+        fn arbitrary(g: &mut Gen) -> Old {
+            Upgrade::arbitrary(g)
+        }
+
+        fn upgrade(old: &Old) -> anyhow::Result<Old> {
+            cbor_convert(old)
+        }
+
+        fn downgrade(new: &Old) -> anyhow::Result<Old> {
+            Ok(Old { a: 0, b: new.b })
+        }
+    }
+
+    #[test]
+    fn test_check_conversion() {
+        check_conversion::<Upgrade>(100);
+        let lossy = std::panic::catch_unwind(|| check_conversion::<Lossy>(100));
+        assert!(lossy.is_err());
+    }
+}
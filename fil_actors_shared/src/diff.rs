@@ -0,0 +1,445 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{BytesDe, CborStore};
+use ipld_core::ipld::Ipld;
+use serde::de::DeserializeOwned;
+
+/// A change to an entry of a collection between two versions of it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<K, V> {
+    Added(K, V),
+    Removed(K, V),
+    Modified(K, V, V),
+}
+
+impl<K, V> Change<K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Change::Added(k, _) | Change::Removed(k, _) | Change::Modified(k, _, _) => k,
+        }
+    }
+}
+
+impl<K> Change<K, Ipld> {
+    /// Decodes the values of a change computed by [`diff_hamt`] or [`diff_amt`].
+    pub fn decode<V: DeserializeOwned>(self) -> anyhow::Result<Change<K, V>> {
+        let decode = |value| ipld_core::serde::from_ipld(value).context("failed to decode value");
+        Ok(match self {
+            Change::Added(k, v) => Change::Added(k, decode(v)?),
+            Change::Removed(k, v) => Change::Removed(k, decode(v)?),
+            Change::Modified(k, old, new) => Change::Modified(k, decode(old)?, decode(new)?),
+        })
+    }
+}
+
+/// Computes the entries that differ between two HAMTs, with keys and values left undecoded.
+///
+/// The HAMTs are compared structurally: sub-trees with the same CID on both sides are skipped
+/// without being loaded, so the cost is proportional to the size of the change rather than the
+/// size of the HAMTs. Changes are returned grouped by HAMT position rather than by key.
+pub fn diff_hamt<BS: Blockstore>(
+    store: &BS,
+    old: &Cid,
+    new: &Cid,
+) -> anyhow::Result<Vec<Change<Vec<u8>, Ipld>>> {
+    let mut changes = Vec::new();
+    diff_hamt_opt(store, Some(old), Some(new), &mut changes)?;
+    Ok(changes)
+}
+
+/// Computes the entries that differ between two AMTs, with values left undecoded, in index
+/// order. Like [`diff_hamt`], unchanged sub-trees are skipped without being loaded.
+pub fn diff_amt<BS: Blockstore>(
+    store: &BS,
+    old: &Cid,
+    new: &Cid,
+) -> anyhow::Result<Vec<Change<u64, Ipld>>> {
+    let mut changes = Vec::new();
+    diff_amt_opt(store, Some(old), Some(new), &mut changes)?;
+    Ok(changes)
+}
+
+/// Same as [`diff_hamt`], with the values decoded as `V`.
+pub fn diff_hamt_decoded<BS: Blockstore, V: DeserializeOwned>(
+    store: &BS,
+    old: &Cid,
+    new: &Cid,
+) -> anyhow::Result<Vec<Change<Vec<u8>, V>>> {
+    diff_hamt(store, old, new)?
+        .into_iter()
+        .map(Change::decode)
+        .collect()
+}
+
+/// Same as [`diff_amt`], with the values decoded as `V`.
+pub fn diff_amt_decoded<BS: Blockstore, V: DeserializeOwned>(
+    store: &BS,
+    old: &Cid,
+    new: &Cid,
+) -> anyhow::Result<Vec<Change<u64, V>>> {
+    diff_amt(store, old, new)?
+        .into_iter()
+        .map(Change::decode)
+        .collect()
+}
+
+/// Same as [`diff_hamt`], where a missing HAMT is equivalent to an empty one.
+pub(crate) fn diff_hamt_opt<BS: Blockstore>(
+    store: &BS,
+    old: Option<&Cid>,
+    new: Option<&Cid>,
+    changes: &mut Vec<Change<Vec<u8>, Ipld>>,
+) -> anyhow::Result<()> {
+    if old == new {
+        return Ok(());
+    }
+    let old = old.map(|c| load_hamt_node(store, c)).transpose()?;
+    let new = new.map(|c| load_hamt_node(store, c)).transpose()?;
+    diff_hamt_nodes(
+        store,
+        old.unwrap_or_default(),
+        new.unwrap_or_default(),
+        changes,
+    )
+}
+
+/// Same as [`diff_amt`], where a missing AMT is equivalent to an empty one.
+pub(crate) fn diff_amt_opt<BS: Blockstore>(
+    store: &BS,
+    old: Option<&Cid>,
+    new: Option<&Cid>,
+    changes: &mut Vec<Change<u64, Ipld>>,
+) -> anyhow::Result<()> {
+    if old == new {
+        return Ok(());
+    }
+    let old = old.map(|c| load_amt_root(store, c)).transpose()?;
+    let new = new.map(|c| load_amt_root(store, c)).transpose()?;
+    let bit_width = match (&old, &new) {
+        (Some(old), Some(new)) if old.bit_width != new.bit_width => {
+            return Err(anyhow!(
+                "cannot diff AMTs with bit widths {} and {}",
+                old.bit_width,
+                new.bit_width
+            ))
+        }
+        (Some(root), _) | (_, Some(root)) => root.bit_width,
+        (None, None) => return Ok(()),
+    };
+    let height = old
+        .iter()
+        .chain(new.iter())
+        .map(|root| root.height)
+        .max()
+        .unwrap_or_default();
+    diff_amt_nodes(
+        store,
+        bit_width,
+        height,
+        0,
+        old.map(|root| root.node),
+        new.map(|root| root.node),
+        changes,
+    )
+}
+
+/// A HAMT node, mapping the set positions of its bitfield to pointers.
+type HamtNode = BTreeMap<usize, Ipld>;
+
+fn load_hamt_node<BS: Blockstore>(store: &BS, cid: &Cid) -> anyhow::Result<HamtNode> {
+    let (BytesDe(bitfield), pointers): (BytesDe, Vec<Ipld>) = store
+        .get_cbor(cid)?
+        .with_context(|| format!("HAMT node {cid} not found"))?;
+    // The bitfield is big-endian, with leading zero bytes trimmed.
+    let positions = (0..bitfield.len() * 8).filter(|i| {
+        let byte = bitfield[bitfield.len() - 1 - i / 8];
+        byte & (1 << (i % 8)) != 0
+    });
+    Ok(positions.zip(pointers).collect())
+}
+
+fn diff_hamt_nodes<BS: Blockstore>(
+    store: &BS,
+    old: HamtNode,
+    new: HamtNode,
+    changes: &mut Vec<Change<Vec<u8>, Ipld>>,
+) -> anyhow::Result<()> {
+    let positions: BTreeSet<usize> = old.keys().chain(new.keys()).copied().collect();
+    for position in positions {
+        match (old.get(&position), new.get(&position)) {
+            (Some(Ipld::Link(old)), Some(Ipld::Link(new))) => {
+                if old != new {
+                    let old = load_hamt_node(store, old)?;
+                    let new = load_hamt_node(store, new)?;
+                    diff_hamt_nodes(store, old, new, changes)?;
+                }
+            }
+            (old, new) => {
+                let mut old_entries = BTreeMap::new();
+                let mut new_entries = BTreeMap::new();
+                if let Some(old) = old {
+                    collect_hamt_entries(store, old, &mut old_entries)?;
+                }
+                if let Some(new) = new {
+                    collect_hamt_entries(store, new, &mut new_entries)?;
+                }
+                diff_maps(old_entries, new_entries, changes);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collects all entries below a HAMT pointer, which is either a link to a node or a bucket of
+/// `[key, value]` pairs.
+fn collect_hamt_entries<BS: Blockstore>(
+    store: &BS,
+    pointer: &Ipld,
+    entries: &mut BTreeMap<Vec<u8>, Ipld>,
+) -> anyhow::Result<()> {
+    match pointer {
+        Ipld::Link(cid) => {
+            for pointer in load_hamt_node(store, cid)?.values() {
+                collect_hamt_entries(store, pointer, entries)?;
+            }
+        }
+        Ipld::List(bucket) => entries.extend(hamt_bucket_entries(bucket)?),
+        _ => return Err(anyhow!("malformed HAMT pointer")),
+    }
+    Ok(())
+}
+
+/// Decodes the `[key, value]` pairs of a HAMT bucket.
+pub(crate) fn hamt_bucket_entries(bucket: &[Ipld]) -> anyhow::Result<Vec<(Vec<u8>, Ipld)>> {
+    bucket
+        .iter()
+        .map(|kv| match kv {
+            Ipld::List(kv) => match kv.as_slice() {
+                [Ipld::Bytes(key), value] => Ok((key.clone(), value.clone())),
+                _ => Err(anyhow!("malformed HAMT entry")),
+            },
+            _ => Err(anyhow!("malformed HAMT entry")),
+        })
+        .collect()
+}
+
+fn diff_maps<K: Ord, V: PartialEq>(
+    mut old: BTreeMap<K, V>,
+    new: BTreeMap<K, V>,
+    changes: &mut Vec<Change<K, V>>,
+) {
+    for (key, new_value) in new {
+        match old.remove(&key) {
+            None => changes.push(Change::Added(key, new_value)),
+            Some(old_value) if old_value != new_value => {
+                changes.push(Change::Modified(key, old_value, new_value))
+            }
+            Some(_) => {}
+        }
+    }
+    changes.extend(old.into_iter().map(|(k, v)| Change::Removed(k, v)));
+}
+
+struct AmtRoot {
+    bit_width: u32,
+    height: u32,
+    node: AmtNode,
+}
+
+/// An AMT node at a given height, mapping the set positions of its bitmap to links (above the
+/// leaves) or values (in the leaves).
+#[derive(Clone)]
+pub(crate) struct AmtNode {
+    pub height: u32,
+    pub links: BTreeMap<u64, Cid>,
+    pub values: BTreeMap<u64, Ipld>,
+}
+
+/// Serialized AMT node: bitmap, links and values.
+pub(crate) type RawAmtNode = (BytesDe, Vec<Cid>, Vec<Ipld>);
+
+fn load_amt_root<BS: Blockstore>(store: &BS, cid: &Cid) -> anyhow::Result<AmtRoot> {
+    let (bit_width, height, _count, node): (u32, u32, u64, RawAmtNode) = store
+        .get_cbor(cid)?
+        .with_context(|| format!("AMT root {cid} not found"))?;
+    Ok(AmtRoot {
+        bit_width,
+        height,
+        node: expand_amt_node(height, node),
+    })
+}
+
+fn load_amt_node<BS: Blockstore>(store: &BS, cid: &Cid, height: u32) -> anyhow::Result<AmtNode> {
+    let node = store
+        .get_cbor(cid)?
+        .with_context(|| format!("AMT node {cid} not found"))?;
+    Ok(expand_amt_node(height, node))
+}
+
+pub(crate) fn expand_amt_node(height: u32, (BytesDe(bmap), links, values): RawAmtNode) -> AmtNode {
+    let positions =
+        (0..bmap.len() as u64 * 8).filter(|i| bmap[*i as usize / 8] & (1 << (i % 8)) != 0);
+    AmtNode {
+        height,
+        links: positions.clone().zip(links).collect(),
+        values: positions.zip(values).collect(),
+    }
+}
+
+/// Diffs two AMT sub-trees covering the indexes starting at `offset`, at the given level. A
+/// node lower than the level is equivalent to a chain of nodes whose first link leads to it,
+/// which is how AMTs of different heights are compared.
+fn diff_amt_nodes<BS: Blockstore>(
+    store: &BS,
+    bit_width: u32,
+    level: u32,
+    offset: u64,
+    old: Option<AmtNode>,
+    new: Option<AmtNode>,
+    changes: &mut Vec<Change<u64, Ipld>>,
+) -> anyhow::Result<()> {
+    if level == 0 {
+        let old = old.map(|node| node.values).unwrap_or_default();
+        let new = new.map(|node| node.values).unwrap_or_default();
+        let mut level_changes = Vec::new();
+        diff_maps(old, new, &mut level_changes);
+        level_changes.sort_by_key(|change| *change.key());
+        changes.extend(level_changes.into_iter().map(|change| match change {
+            Change::Added(i, v) => Change::Added(offset + i, v),
+            Change::Removed(i, v) => Change::Removed(offset + i, v),
+            Change::Modified(i, o, n) => Change::Modified(offset + i, o, n),
+        }));
+        return Ok(());
+    }
+
+    let child_span = 1u64
+        .checked_shl(bit_width * level)
+        .context("AMT too high to diff")?;
+    let children = |node: &Option<AmtNode>| -> BTreeMap<u64, Child> {
+        match node {
+            None => BTreeMap::new(),
+            Some(node) if node.height < level => BTreeMap::from([(0, Child::Node(node.clone()))]),
+            Some(node) => node
+                .links
+                .iter()
+                .map(|(i, cid)| (*i, Child::Link(*cid)))
+                .collect(),
+        }
+    };
+    let mut old_children = children(&old);
+    let mut new_children = children(&new);
+    let positions: BTreeSet<u64> = old_children
+        .keys()
+        .chain(new_children.keys())
+        .copied()
+        .collect();
+    for position in positions {
+        let old = old_children.remove(&position);
+        let new = new_children.remove(&position);
+        if let (Some(Child::Link(old)), Some(Child::Link(new))) = (&old, &new) {
+            if old == new {
+                continue;
+            }
+        }
+        let resolve = |child: Option<Child>| -> anyhow::Result<Option<AmtNode>> {
+            Ok(match child {
+                None => None,
+                Some(Child::Node(node)) => Some(node),
+                Some(Child::Link(cid)) => Some(load_amt_node(store, &cid, level - 1)?),
+            })
+        };
+        diff_amt_nodes(
+            store,
+            bit_width,
+            level - 1,
+            offset + position * child_span,
+            resolve(old)?,
+            resolve(new)?,
+            changes,
+        )?;
+    }
+    Ok(())
+}
+
+enum Child {
+    Link(Cid),
+    Node(AmtNode),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_amt::Amt;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_hamt::{BytesKey, Hamt};
+
+    #[test]
+    fn test_diff_hamt() {
+        let store = MemoryBlockstore::new();
+        let mut hamt = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, 5);
+        for i in 0..1000u64 {
+            hamt.set(BytesKey(i.to_be_bytes().to_vec()), i).unwrap();
+        }
+        let old = hamt.flush().unwrap();
+        hamt.set(BytesKey(5u64.to_be_bytes().to_vec()), 50).unwrap();
+        hamt.delete(&BytesKey(7u64.to_be_bytes().to_vec())).unwrap();
+        hamt.set(BytesKey(1000u64.to_be_bytes().to_vec()), 1000)
+            .unwrap();
+        let new = hamt.flush().unwrap();
+
+        let mut changes = diff_hamt(&store, &old, &new).unwrap();
+        changes.sort_by(|a, b| a.key().cmp(b.key()));
+        let key = |i: u64| i.to_be_bytes().to_vec();
+        assert_eq!(
+            changes,
+            vec![
+                Change::Modified(key(5), Ipld::Integer(5), Ipld::Integer(50)),
+                Change::Removed(key(7), Ipld::Integer(7)),
+                Change::Added(key(1000), Ipld::Integer(1000)),
+            ]
+        );
+        assert!(diff_hamt(&store, &new, &new).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_amt_across_heights() {
+        let store = MemoryBlockstore::new();
+        let mut amt = Amt::<u64, _>::new_with_bit_width(&store, 3);
+        for i in 0..5u64 {
+            amt.set(i, i).unwrap();
+        }
+        let old = amt.flush().unwrap();
+        amt.set(3, 30).unwrap();
+        amt.delete(4).unwrap();
+        amt.set(1000, 1000).unwrap();
+        let new = amt.flush().unwrap();
+
+        let expected = vec![
+            Change::Modified(3, Ipld::Integer(3), Ipld::Integer(30)),
+            Change::Removed(4, Ipld::Integer(4)),
+            Change::Added(1000, Ipld::Integer(1000)),
+        ];
+        assert_eq!(diff_amt(&store, &old, &new).unwrap(), expected);
+
+        let reversed: Vec<_> = diff_amt(&store, &new, &old).unwrap();
+        assert_eq!(reversed.len(), 3);
+        assert_eq!(reversed[2], Change::Removed(1000, Ipld::Integer(1000)));
+
+        let decoded = diff_amt_decoded::<_, u64>(&store, &old, &new).unwrap();
+        assert_eq!(
+            decoded,
+            vec![
+                Change::Modified(3, 3, 30),
+                Change::Removed(4, 4),
+                Change::Added(1000, 1000),
+            ]
+        );
+        assert!(diff_amt_decoded::<_, String>(&store, &old, &new).is_err());
+    }
+}
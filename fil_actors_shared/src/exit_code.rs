@@ -0,0 +1,354 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use serde::Serialize;
+
+use crate::actor_type::ActorType;
+use crate::version::{actor_versions, MAX_ACTORS_VERSION};
+
+/// First exit code available to the actors for their own errors. Codes below are common to all
+/// actors.
+pub const FIRST_ACTOR_SPECIFIC_EXIT_CODE: u32 = 32;
+
+/// Name and meaning of an exit code, for a range of builtin-actors versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ExitCodeInfo {
+    /// Actor defining the code, `None` for the codes common to all actors.
+    pub actor: Option<ActorType>,
+    pub code: u32,
+    /// Name of the constant in the builtin-actors and the FVM.
+    pub name: &'static str,
+    pub description: &'static str,
+    /// First version using the code.
+    pub since: u64,
+    /// Last version using the code, if it has been removed since.
+    pub until: Option<u64>,
+}
+
+impl ExitCodeInfo {
+    const fn common(code: u32, name: &'static str, description: &'static str) -> Self {
+        Self {
+            actor: None,
+            code,
+            name,
+            description,
+            since: 8,
+            until: None,
+        }
+    }
+
+    const fn actor(
+        actor: ActorType,
+        code: u32,
+        name: &'static str,
+        description: &'static str,
+        since: u64,
+    ) -> Self {
+        Self {
+            actor: Some(actor),
+            code,
+            name,
+            description,
+            since,
+            until: None,
+        }
+    }
+
+    const fn since(mut self, since: u64) -> Self {
+        self.since = since;
+        self
+    }
+
+    const fn until(mut self, until: u64) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Returns whether the code has this meaning in the given version.
+    pub fn applies(&self, version: u64) -> bool {
+        version >= self.since
+            && version <= self.until.unwrap_or(MAX_ACTORS_VERSION)
+            && self
+                .actor
+                .is_none_or(|actor| actor_versions(actor).contains(&version))
+    }
+}
+
+/// Exit codes of the FVM and of the builtin actors, so that receipts can be rendered without
+/// depending on the actor crates. The codes common to all actors come first, then the
+/// actor-specific ones, in actor type ID order.
+pub const EXIT_CODES: &[ExitCodeInfo] = &[
+    ExitCodeInfo::common(0, "OK", "The message was executed successfully."),
+    ExitCodeInfo::common(
+        1,
+        "SYS_SENDER_INVALID",
+        "The message sender does not exist or is not an account.",
+    ),
+    ExitCodeInfo::common(
+        2,
+        "SYS_SENDER_STATE_INVALID",
+        "The message nonce does not match the sender's, or the sender cannot cover the gas.",
+    ),
+    ExitCodeInfo::common(3, "SYS_INVALID_METHOD", "The method does not exist.").until(9),
+    ExitCodeInfo::common(
+        4,
+        "SYS_ILLEGAL_INSTRUCTION",
+        "The actor executed an illegal instruction.",
+    ),
+    ExitCodeInfo::common(
+        5,
+        "SYS_INVALID_RECEIVER",
+        "The receiver does not exist and could not be created implicitly.",
+    ),
+    ExitCodeInfo::common(
+        6,
+        "SYS_INSUFFICIENT_FUNDS",
+        "The sender does not have the funds to transfer the message value.",
+    ),
+    ExitCodeInfo::common(7, "SYS_OUT_OF_GAS", "The message ran out of gas."),
+    ExitCodeInfo::common(
+        9,
+        "SYS_ILLEGAL_EXIT_CODE",
+        "The actor aborted with a reserved exit code.",
+    ),
+    ExitCodeInfo::common(
+        10,
+        "SYS_ASSERTION_FAILED",
+        "An assertion failed in the FVM.",
+    ),
+    ExitCodeInfo::common(
+        11,
+        "SYS_MISSING_RETURN",
+        "The actor returned a block handle that does not exist.",
+    ),
+    ExitCodeInfo::common(
+        16,
+        "USR_ILLEGAL_ARGUMENT",
+        "The method parameters are invalid.",
+    ),
+    ExitCodeInfo::common(17, "USR_NOT_FOUND", "A requested resource does not exist."),
+    ExitCodeInfo::common(
+        18,
+        "USR_FORBIDDEN",
+        "The caller is not allowed to perform the action.",
+    ),
+    ExitCodeInfo::common(
+        19,
+        "USR_INSUFFICIENT_FUNDS",
+        "The actor does not have the funds for the action.",
+    ),
+    ExitCodeInfo::common(
+        20,
+        "USR_ILLEGAL_STATE",
+        "The actor state is invalid or inconsistent.",
+    ),
+    ExitCodeInfo::common(
+        21,
+        "USR_SERIALIZATION",
+        "A value could not be serialized or deserialized.",
+    ),
+    ExitCodeInfo::common(
+        22,
+        "USR_UNHANDLED_MESSAGE",
+        "The actor does not handle the method.",
+    ),
+    ExitCodeInfo::common(
+        23,
+        "USR_UNSPECIFIED",
+        "The actor failed with an unspecified error.",
+    ),
+    ExitCodeInfo::common(
+        24,
+        "USR_ASSERTION_FAILED",
+        "An assertion failed in the actor.",
+    ),
+    ExitCodeInfo::common(
+        25,
+        "USR_READ_ONLY",
+        "The actor attempted to modify state in a read-only call.",
+    )
+    .since(10),
+    ExitCodeInfo::common(
+        26,
+        "USR_NOT_PAYABLE",
+        "The method cannot receive funds but value was transferred.",
+    )
+    .since(10),
+    ExitCodeInfo::actor(
+        ActorType::Power,
+        32,
+        "ERR_TOO_MANY_PROVE_COMMITS",
+        "Too many sectors are awaiting the batched proof verification of this epoch.",
+        11,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::Miner,
+        1000,
+        "ERR_BALANCE_INVARIANTS_BROKEN",
+        "The miner balance does not cover its locked funds, pre-commit deposits and pledge.",
+        8,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::Miner,
+        1001,
+        "ERR_NOTIFICATION_SEND_FAILED",
+        "A notification of the sector activation could not be sent.",
+        13,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::Miner,
+        1002,
+        "ERR_NOTIFICATION_RECEIVER_ABORTED",
+        "The receiver of a notification of the sector activation aborted.",
+        13,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::Miner,
+        1003,
+        "ERR_NOTIFICATION_RESPONSE_INVALID",
+        "The response to a notification of the sector activation could not be decoded.",
+        13,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::Miner,
+        1004,
+        "ERR_NOTIFICATION_REJECTED",
+        "The receiver of a notification of the sector activation rejected it.",
+        13,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::Market,
+        32,
+        "EX_DEAL_EXPIRED",
+        "The deal has expired or was terminated, and its information is no longer available.",
+        10,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::Market,
+        33,
+        "EX_DEAL_NOT_ACTIVATED",
+        "The deal has not been activated yet.",
+        13,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::PaymentChannel,
+        32,
+        "ERR_CHANNEL_STATE_UPDATE_AFTER_SETTLED",
+        "The channel state cannot be updated after the channel has settled.",
+        11,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::EVM,
+        33,
+        "EVM_CONTRACT_REVERTED",
+        "The contract reverted.",
+        10,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::EVM,
+        34,
+        "EVM_CONTRACT_INVALID_INSTRUCTION",
+        "The contract executed the INVALID instruction.",
+        10,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::EVM,
+        35,
+        "EVM_CONTRACT_UNDEFINED_INSTRUCTION",
+        "The contract executed an undefined instruction.",
+        10,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::EVM,
+        36,
+        "EVM_CONTRACT_STACK_UNDERFLOW",
+        "The contract popped from an empty stack.",
+        10,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::EVM,
+        37,
+        "EVM_CONTRACT_STACK_OVERFLOW",
+        "The contract exceeded the maximum stack depth.",
+        10,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::EVM,
+        38,
+        "EVM_CONTRACT_ILLEGAL_MEMORY_ACCESS",
+        "The contract accessed memory out of bounds.",
+        10,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::EVM,
+        39,
+        "EVM_CONTRACT_BAD_JUMPDEST",
+        "The contract jumped to an invalid destination.",
+        10,
+    ),
+    ExitCodeInfo::actor(
+        ActorType::EVM,
+        40,
+        "EVM_CONTRACT_SELFDESTRUCT_FAILED",
+        "The contract failed to self-destruct.",
+        10,
+    ),
+];
+
+/// Describes the exit code of a message sent to an actor of the given type and version. Codes
+/// below [`FIRST_ACTOR_SPECIFIC_EXIT_CODE`] are common to all actors; the others depend on the
+/// actor, and are `None` if it does not define them in that version.
+pub fn describe_exit_code(
+    actor: ActorType,
+    version: u64,
+    code: u32,
+) -> Option<&'static ExitCodeInfo> {
+    EXIT_CODES.iter().find(|info| {
+        info.code == code && info.actor.is_none_or(|a| a == actor) && info.applies(version)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_exit_code() {
+        let name = |actor, version, code| describe_exit_code(actor, version, code).map(|i| i.name);
+        assert_eq!(name(ActorType::Miner, 16, 16), Some("USR_ILLEGAL_ARGUMENT"));
+        assert_eq!(
+            name(ActorType::Miner, 8, 1000),
+            Some("ERR_BALANCE_INVARIANTS_BROKEN")
+        );
+        assert_eq!(name(ActorType::Miner, 12, 1001), None);
+        assert_eq!(
+            name(ActorType::Miner, 13, 1001),
+            Some("ERR_NOTIFICATION_SEND_FAILED")
+        );
+        assert_eq!(name(ActorType::Market, 16, 32), Some("EX_DEAL_EXPIRED"));
+        assert_eq!(
+            name(ActorType::Power, 16, 32),
+            Some("ERR_TOO_MANY_PROVE_COMMITS")
+        );
+        assert_eq!(name(ActorType::Market, 9, 3), Some("SYS_INVALID_METHOD"));
+        assert_eq!(name(ActorType::Market, 10, 3), None);
+        // The EVM actor only exists since v10.
+        assert_eq!(name(ActorType::EVM, 9, 33), None);
+
+        for (i, info) in EXIT_CODES.iter().enumerate() {
+            assert_eq!(
+                info.actor.is_none(),
+                info.code < FIRST_ACTOR_SPECIFIC_EXIT_CODE,
+                "{}",
+                info.name
+            );
+            assert!(
+                EXIT_CODES[..i]
+                    .iter()
+                    .all(|other| (other.actor, other.code) != (info.actor, info.code)),
+                "{} is listed twice",
+                info.name
+            );
+        }
+    }
+}
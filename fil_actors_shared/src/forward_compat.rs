@@ -0,0 +1,153 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{from_slice, to_vec};
+use ipld_core::ipld::Ipld;
+use serde::de::DeserializeOwned;
+
+/// Something that was tolerated while decoding with [`from_slice_best_effort`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeWarning {
+    /// The encoded tuple has more fields than the schema used for decoding. Actor upgrades
+    /// usually append fields to tuple-encoded state, so the trailing fields were ignored.
+    UnknownTrailingFields { known: usize, found: usize },
+    /// The state belongs to an actors version newer than this crate supports, so it was decoded
+    /// with the schema of the newest supported version.
+    UnknownVersion { version: u64, decoded_as: u64 },
+}
+
+/// A value decoded in best-effort mode, along with the incompatibilities that were tolerated.
+#[derive(Debug, Clone)]
+pub struct BestEffort<T> {
+    pub value: T,
+    pub warnings: Vec<DecodeWarning>,
+}
+
+impl<T> BestEffort<T> {
+    /// Returns `true` if the value decoded without tolerating anything.
+    pub fn is_exact(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Maps the value, keeping the warnings.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> BestEffort<U> {
+        BestEffort {
+            value: f(self.value),
+            warnings: self.warnings,
+        }
+    }
+}
+
+/// Decodes CBOR with the given (typically newest known) schema, tolerating fields appended by
+/// newer actor versions.
+///
+/// Strict decoding is attempted first. If it fails and the data is a CBOR list, trailing
+/// elements are dropped one by one until the remainder decodes. This is meant for read-only
+/// tooling that should keep partially working across unexpected network upgrades, and must not
+/// be used where the decoded state is written back.
+pub fn from_slice_best_effort<T: DeserializeOwned>(bytes: &[u8]) -> anyhow::Result<BestEffort<T>> {
+    let strict_err = match from_slice::<T>(bytes) {
+        Ok(value) => {
+            return Ok(BestEffort {
+                value,
+                warnings: vec![],
+            })
+        }
+        Err(e) => e,
+    };
+
+    let Ok(Ipld::List(fields)) = from_slice::<Ipld>(bytes) else {
+        return Err(strict_err.into());
+    };
+    for known in (0..fields.len()).rev() {
+        let truncated = to_vec(&Ipld::List(fields[..known].to_vec()))?;
+        if let Ok(value) = from_slice::<T>(&truncated) {
+            return Ok(BestEffort {
+                value,
+                warnings: vec![DecodeWarning::UnknownTrailingFields {
+                    known,
+                    found: fields.len(),
+                }],
+            });
+        }
+    }
+    Err(strict_err.into())
+}
+
+/// Loads and decodes a block with [`from_slice_best_effort`].
+pub fn get_cbor_best_effort<BS, T>(store: &BS, cid: &Cid) -> anyhow::Result<BestEffort<T>>
+where
+    BS: Blockstore,
+    T: DeserializeOwned,
+{
+    let bytes = store
+        .get(cid)?
+        .with_context(|| format!("block {cid} not found"))?;
+    from_slice_best_effort(&bytes).with_context(|| format!("failed to decode block {cid}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_encoding::tuple::*;
+
+    #[derive(Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+    struct Old {
+        a: u64,
+        b: String,
+    }
+
+    #[derive(Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+    struct New {
+        a: u64,
+        b: String,
+        c: Vec<u8>,
+        d: bool,
+    }
+
+    #[test]
+    fn test_exact_decode_has_no_warnings() {
+        let bytes = to_vec(&Old {
+            a: 1,
+            b: "x".into(),
+        })
+        .unwrap();
+        let decoded = from_slice_best_effort::<Old>(&bytes).unwrap();
+        assert!(decoded.is_exact());
+        assert_eq!(decoded.value.a, 1);
+    }
+
+    #[test]
+    fn test_trailing_fields_are_dropped() {
+        let bytes = to_vec(&New {
+            a: 1,
+            b: "x".into(),
+            c: vec![1, 2],
+            d: true,
+        })
+        .unwrap();
+        assert!(from_slice::<Old>(&bytes).is_err());
+
+        let decoded = from_slice_best_effort::<Old>(&bytes).unwrap();
+        assert_eq!(
+            decoded.value,
+            Old {
+                a: 1,
+                b: "x".into()
+            }
+        );
+        assert_eq!(
+            decoded.warnings,
+            vec![DecodeWarning::UnknownTrailingFields { known: 2, found: 4 }]
+        );
+    }
+
+    #[test]
+    fn test_incompatible_data_fails() {
+        let bytes = to_vec(&("not a number", "x")).unwrap();
+        assert!(from_slice_best_effort::<Old>(&bytes).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::cell::RefCell;
+
+use anyhow::{bail, ensure};
+use cid::Cid;
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::RawBytes;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use ipld_core::ipld::Ipld;
+use multihash_codetable::{Code, MultihashDigest};
+
+/// The blocks of a HAMT read when looking up a key, from the root to the node holding the key or
+/// showing its absence.
+///
+/// Blocks are addressed by their hash, so a client holding the root CID, e.g. from a state root
+/// it trusts, can check the lookup without trusting the node which served the proof nor having
+/// access to the rest of the state.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Clone, PartialEq)]
+pub struct HamtProof {
+    pub root: Cid,
+    pub key: BytesKey,
+    pub blocks: Vec<(Cid, RawBytes)>,
+}
+
+impl HamtProof {
+    /// Checks that the proof is about `key` in the HAMT with the `trusted_root`, checks the blocks
+    /// against their CIDs and replays the lookup, returning the value of the key, or `None` if
+    /// the HAMT does not contain it. Fails if the proof is about another root or key, or if the
+    /// blocks are not the complete lookup path.
+    pub fn verify(
+        &self,
+        trusted_root: &Cid,
+        key: &[u8],
+        bit_width: u32,
+    ) -> anyhow::Result<Option<Ipld>> {
+        ensure!(
+            self.root == *trusted_root,
+            "proof is for root {}, expected {trusted_root}",
+            self.root
+        );
+        ensure!(self.key.0 == key, "proof is for another key");
+        let store = MemoryBlockstore::new();
+        for (cid, block) in &self.blocks {
+            let code = Code::try_from(cid.hash().code())?;
+            ensure!(
+                code.digest(block) == *cid.hash(),
+                "block {cid} does not match its hash"
+            );
+            store.put_keyed(cid, block)?;
+        }
+        let hamt = Hamt::<_, Ipld, BytesKey>::load_with_bit_width(&self.root, &store, bit_width)?;
+        Ok(hamt.get(&self.key)?.cloned())
+    }
+}
+
+/// Looks up a key in the HAMT with the given root and returns the blocks read, see
+/// [`HamtProof`]. The proof is produced whether or not the key is present.
+pub fn prove_hamt_key<BS: Blockstore>(
+    store: &BS,
+    root: &Cid,
+    key: &[u8],
+    bit_width: u32,
+) -> anyhow::Result<HamtProof> {
+    let recorder = RecordingStore {
+        inner: store,
+        blocks: RefCell::default(),
+    };
+    let key = BytesKey(key.to_vec());
+    Hamt::<_, Ipld, BytesKey>::load_with_bit_width(root, &recorder, bit_width)?.get(&key)?;
+    Ok(HamtProof {
+        root: *root,
+        key,
+        blocks: recorder.blocks.into_inner(),
+    })
+}
+
+/// A read-only blockstore wrapper keeping the blocks read through it.
+struct RecordingStore<'a, BS> {
+    inner: &'a BS,
+    blocks: RefCell<Vec<(Cid, RawBytes)>>,
+}
+
+impl<BS: Blockstore> Blockstore for RecordingStore<'_, BS> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        let block = self.inner.get(k)?;
+        if let Some(block) = &block {
+            let mut blocks = self.blocks.borrow_mut();
+            if !blocks.iter().any(|(cid, _)| cid == k) {
+                blocks.push((*k, RawBytes::new(block.clone())));
+            }
+        }
+        Ok(block)
+    }
+
+    fn put_keyed(&self, k: &Cid, _block: &[u8]) -> anyhow::Result<()> {
+        bail!("cannot write block {k} while recording a proof")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_shared4::HAMT_BIT_WIDTH;
+
+    #[test]
+    fn test_hamt_proof() {
+        let store = MemoryBlockstore::new();
+        let mut hamt = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        for i in 0..1000u64 {
+            hamt.set(BytesKey(i.to_be_bytes().to_vec()), i).unwrap();
+        }
+        let root = hamt.flush().unwrap();
+
+        let proof = prove_hamt_key(&store, &root, &42u64.to_be_bytes(), HAMT_BIT_WIDTH).unwrap();
+        assert!(proof.blocks.len() > 1);
+        let key = 42u64.to_be_bytes();
+        assert_eq!(
+            proof.verify(&root, &key, HAMT_BIT_WIDTH).unwrap(),
+            Some(Ipld::Integer(42))
+        );
+        let decoded: HamtProof =
+            fvm_ipld_encoding::from_slice(&fvm_ipld_encoding::to_vec(&proof).unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+
+        let absent = prove_hamt_key(&store, &root, b"absent", HAMT_BIT_WIDTH).unwrap();
+        assert_eq!(
+            absent.verify(&root, b"absent", HAMT_BIT_WIDTH).unwrap(),
+            None
+        );
+
+        // A proof about another key or a HAMT other than the trusted one is rejected, even if it
+        // is valid on its own.
+        assert!(proof.verify(&root, b"absent", HAMT_BIT_WIDTH).is_err());
+        let mut other = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        other.set(BytesKey(key.to_vec()), 0).unwrap();
+        let forged_root = other.flush().unwrap();
+        let forged = prove_hamt_key(&store, &forged_root, &key, HAMT_BIT_WIDTH).unwrap();
+        assert_eq!(
+            forged.verify(&forged_root, &key, HAMT_BIT_WIDTH).unwrap(),
+            Some(Ipld::Integer(0))
+        );
+        assert!(forged.verify(&root, &key, HAMT_BIT_WIDTH).is_err());
+
+        // Tampered or missing blocks are rejected.
+        let mut tampered = proof.clone();
+        tampered.blocks[0].1 = RawBytes::new(vec![0x80]);
+        assert!(tampered.verify(&root, &key, HAMT_BIT_WIDTH).is_err());
+        let mut truncated = proof;
+        truncated.blocks.pop();
+        assert!(truncated.verify(&root, &key, HAMT_BIT_WIDTH).is_err());
+    }
+}
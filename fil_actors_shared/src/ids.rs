@@ -0,0 +1,93 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+macro_rules! id_newtype {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        ///
+        /// It is encoded as the bare integer, so it can replace a `u64` in encoded structures.
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            Default,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            Serialize,
+            Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub u64);
+
+        impl From<u64> for $name {
+            fn from(id: u64) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for u64 {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+id_newtype!(
+    /// ID of a storage deal, the key of the market deal proposals and states.
+    DealID
+);
+id_newtype!(
+    /// Number of a sector, unique per miner.
+    SectorNumber
+);
+id_newtype!(
+    /// ID of a verified registry claim, unique across providers.
+    ClaimID
+);
+id_newtype!(
+    /// ID of a verified registry allocation, unique across clients.
+    AllocationID
+);
+
+/// The bare integer aliases used by the versioned states, for code moving to the newtypes
+/// one structure at a time: conversions in both directions are `From` impls.
+pub mod raw {
+    pub use fvm_shared4::deal::DealID;
+    pub use fvm_shared4::sector::SectorNumber;
+
+    pub type AllocationID = u64;
+    pub type ClaimID = u64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_encoding::{from_slice, to_vec};
+
+    #[test]
+    fn test_ids_encode_as_integers() {
+        let raw: raw::SectorNumber = 42;
+        let number = SectorNumber::from(raw);
+        assert_eq!(to_vec(&number).unwrap(), to_vec(&raw).unwrap());
+        assert_eq!(
+            from_slice::<DealID>(&to_vec(&7u64).unwrap()).unwrap(),
+            DealID(7)
+        );
+        assert_eq!(u64::from(ClaimID(3)), 3);
+        assert_eq!(number.to_string(), "42");
+    }
+}
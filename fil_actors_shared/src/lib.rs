@@ -2,6 +2,38 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 pub mod abi;
+pub mod actor_type;
+pub mod address_owner;
+#[cfg(feature = "async")]
+pub mod async_store;
+pub mod bundle;
+pub mod cache;
+/// Property checks of the conversions of structures between adjacent actor versions.
+#[cfg(feature = "arb")]
+pub mod conversion_tests;
+pub mod diff;
+pub mod exit_code;
+pub mod forward_compat;
+pub mod hamt_proof;
+pub mod ids;
+#[cfg(feature = "json")]
+pub mod lotus_json;
+pub mod multimap;
+pub mod overlay;
+pub mod page;
+pub mod prefetch;
+/// The version-agnostic surface of this crate in one import, `use fil_actors_shared::prelude::*`.
+///
+/// Versioned items, such as the states, `Policy` and `ActorError`, are not included: they live in
+/// the version namespaces (`v8` to `v16`) of this crate and of the actor crates, and are imported
+/// from the version being read.
+pub mod prelude;
+pub mod randomness;
+pub mod reindex;
+pub mod stable_hash;
+pub mod state_check;
+pub mod state_diff;
+pub mod state_invariants;
 pub mod v10;
 pub mod v11;
 pub mod v12;
@@ -11,21 +43,36 @@ pub mod v15;
 pub mod v16;
 pub mod v8;
 pub mod v9;
+pub mod version;
+pub mod versioned;
 
-// Re-exports
+// Re-exports, each gated behind a `reexport-*` feature
+#[cfg(feature = "reexport-ipld")]
 pub extern crate cid;
+#[cfg(feature = "reexport-proofs")]
 pub extern crate filecoin_proofs_api;
+#[cfg(feature = "reexport-token")]
 pub extern crate frc46_token;
+#[cfg(feature = "reexport-amt")]
 pub extern crate fvm_ipld_amt;
+#[cfg(feature = "reexport-bitfield")]
 pub extern crate fvm_ipld_bitfield;
+#[cfg(feature = "reexport-ipld")]
 pub extern crate fvm_ipld_blockstore;
+#[cfg(feature = "reexport-ipld")]
 pub extern crate fvm_ipld_encoding;
+#[cfg(feature = "reexport-hamt")]
 pub extern crate fvm_ipld_hamt;
+#[cfg(feature = "reexport-fvm_shared2")]
 pub extern crate fvm_shared as fvm_shared2;
+#[cfg(feature = "reexport-fvm_shared3")]
 pub extern crate fvm_shared3;
+#[cfg(feature = "reexport-fvm_shared4")]
 pub extern crate fvm_shared4;
+#[cfg(feature = "reexport-ipld")]
 pub extern crate multihash_codetable;
 
+#[cfg(feature = "token")]
 pub mod ext {
     use frc46_token::token::state::{actor_id_key, StateError, TokenState};
     use fvm_ipld_blockstore::Blockstore;
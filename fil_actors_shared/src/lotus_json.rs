@@ -0,0 +1,206 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+/// Serializes big integers as decimal strings, as `go-state-types` `big.Int` does.
+pub mod big_int {
+    use num_bigint::BigInt;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_str_radix(10))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        BigInt::parse_bytes(s.as_bytes(), 10)
+            .ok_or_else(|| de::Error::custom(format!("invalid big int: {s}")))
+    }
+}
+
+/// Serializes token amounts as attoFIL decimal strings.
+pub mod token_amount {
+    use fvm_shared4::econ::TokenAmount;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &TokenAmount, serializer: S) -> Result<S::Ok, S::Error> {
+        super::big_int::serialize(value.atto(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<TokenAmount, D::Error> {
+        super::big_int::deserialize(deserializer).map(TokenAmount::from_atto)
+    }
+}
+
+/// Serializes byte fields as standard padded base64, as Go's `encoding/json` does for `[]byte`.
+pub mod bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine as _;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        STANDARD.decode(s).map_err(de::Error::custom)
+    }
+}
+
+/// Serializes CIDs as `{"/": "<cid>"}`, the DAG-JSON link form used by Lotus.
+pub mod cid {
+    use cid::Cid;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Link {
+        #[serde(rename = "/")]
+        cid: String,
+    }
+
+    pub fn serialize<S: Serializer>(value: &Cid, serializer: S) -> Result<S::Ok, S::Error> {
+        Link {
+            cid: value.to_string(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cid, D::Error> {
+        let link = Link::deserialize(deserializer)?;
+        link.cid.parse().map_err(serde::de::Error::custom)
+    }
+
+    /// Same as the parent module for optional CIDs, which Lotus renders as `null`.
+    pub mod opt {
+        use cid::Cid;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper(#[serde(with = "super")] Cid);
+
+        pub fn serialize<S: Serializer>(
+            value: &Option<Cid>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            value.map(Wrapper).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Cid>, D::Error> {
+            Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(cid)| cid))
+        }
+    }
+}
+
+/// Serializes addresses as strings, e.g. `f01234`, as Lotus does. The network prefix is the one
+/// of the current network; both prefixes are accepted when deserializing.
+pub mod address {
+    use fvm_shared4::address::Address;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+
+    /// Same as the parent module for lists of addresses.
+    pub mod vec {
+        use fvm_shared4::address::Address;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper(#[serde(with = "super")] Address);
+
+        pub fn serialize<S: Serializer>(
+            value: &[Address],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(value.iter().map(|address| Wrapper(*address)))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<Address>, D::Error> {
+            Ok(Vec::<Wrapper>::deserialize(deserializer)?
+                .into_iter()
+                .map(|Wrapper(address)| address)
+                .collect())
+        }
+    }
+}
+
+/// Serializes bitfields as the run lengths of their RLE+ encoding, starting with a run of unset
+/// bits, as Lotus does.
+pub use fvm_ipld_bitfield::json as bitfield;
+
+#[cfg(test)]
+mod tests {
+    use ::cid::Cid;
+    use fvm_ipld_bitfield::BitField;
+    use fvm_shared4::address::Address;
+    use fvm_shared4::econ::TokenAmount;
+    use num_bigint::BigInt;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Sample {
+        #[serde(with = "super::big_int")]
+        power: BigInt,
+        #[serde(with = "super::token_amount")]
+        balance: TokenAmount,
+        #[serde(with = "super::bytes")]
+        peer_id: Vec<u8>,
+        #[serde(with = "super::cid")]
+        code: Cid,
+        #[serde(with = "super::cid::opt")]
+        pending: Option<Cid>,
+        #[serde(with = "super::address")]
+        owner: Address,
+        #[serde(with = "super::address::vec")]
+        signers: Vec<Address>,
+        #[serde(with = "super::bitfield")]
+        faults: BitField,
+    }
+
+    #[test]
+    fn test_go_conventions_roundtrip() {
+        let code: Cid = "bafk2bzaceaqwxllfycpq6decpsnkqjdeycpysh5acubonjae7u3wciydlkvki"
+            .parse()
+            .unwrap();
+        let sample = Sample {
+            power: BigInt::from(-34359738368i64),
+            balance: TokenAmount::from_whole(2),
+            peer_id: vec![0, 1, 2, 0xff],
+            code,
+            pending: None,
+            owner: Address::new_id(1234),
+            signers: vec![Address::new_id(1), Address::new_id(2)],
+            faults: BitField::try_from_bits([2, 3, 4, 8]).unwrap(),
+        };
+
+        let value = serde_json::to_value(&sample).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "Power": "-34359738368",
+                "Balance": "2000000000000000000",
+                "PeerId": "AAEC/w==",
+                "Code": { "/": code.to_string() },
+                "Pending": null,
+                "Owner": "f01234",
+                "Signers": ["f01", "f02"],
+                "Faults": [2, 3, 3, 1],
+            })
+        );
+        assert_eq!(serde_json::from_value::<Sample>(value).unwrap(), sample);
+    }
+}
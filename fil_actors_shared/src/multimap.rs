@@ -0,0 +1,243 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::marker::PhantomData;
+
+use anyhow::Context;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::HAMT_BIT_WIDTH;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use crate::page::{Page, PageCursor};
+
+/// Read-only view of a HAMT whose values are the roots of other HAMTs.
+///
+/// This is the layout of the `SetMultimap` and `MapMap` structures embedded in actor states, such
+/// as the market deal ops by epoch (a set of deal IDs per epoch) and the verified registry
+/// allocations and claims (maps of allocations per client and claims per provider). Their typed
+/// wrappers live in each version namespace with slightly different APIs, while the layout has not
+/// changed since v8: both levels use a bit width of 5. For a `SetMultimap`, `V` is `()`.
+///
+/// Keys are exposed as raw bytes, since their encoding depends on the structure and the version,
+/// e.g. actor IDs and deal IDs are unsigned varints, see [`parse_uint_key`].
+pub struct NestedMapReader<'bs, BS, V> {
+    store: &'bs BS,
+    outer: Hamt<&'bs BS, Cid, BytesKey>,
+    value_type: PhantomData<V>,
+}
+
+impl<'bs, BS, V> NestedMapReader<'bs, BS, V>
+where
+    BS: Blockstore,
+    V: DeserializeOwned + Serialize,
+{
+    pub fn load(store: &'bs BS, root: &Cid) -> anyhow::Result<Self> {
+        let outer = Hamt::load_with_bit_width(root, store, HAMT_BIT_WIDTH)
+            .with_context(|| format!("failed to load outer map {root}"))?;
+        Ok(Self {
+            store,
+            outer,
+            value_type: PhantomData,
+        })
+    }
+
+    fn load_inner(&self, root: &Cid) -> anyhow::Result<Hamt<&'bs BS, V, BytesKey>> {
+        Hamt::load_with_bit_width(root, self.store, HAMT_BIT_WIDTH)
+            .with_context(|| format!("failed to load inner map {root}"))
+    }
+
+    /// Runs a function over the outer keys and the roots of their inner maps.
+    pub fn for_each_outer<F>(&self, mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&[u8], &Cid) -> anyhow::Result<()>,
+    {
+        self.outer.for_each(|key, root| f(key, root))?;
+        Ok(())
+    }
+
+    /// Runs a function over the entries of the inner map of an outer key, if any.
+    pub fn for_each_in<F>(&self, outer_key: &[u8], mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&[u8], &V) -> anyhow::Result<()>,
+    {
+        let Some(root) = self.outer.get(outer_key)? else {
+            return Ok(());
+        };
+        self.load_inner(root)?
+            .for_each(|key, value| f(key, value))?;
+        Ok(())
+    }
+
+    /// Runs a function over at most `limit` entries of the inner map of an outer key, starting at
+    /// `cursor` or at the first entry, and returns the cursor of the next entry, if any.
+    ///
+    /// Entries are visited in the HAMT order, which is stable as long as the map is unchanged.
+    /// Resuming from a cursor whose entry has since been removed fails.
+    pub fn for_each_in_ranged<F>(
+        &self,
+        outer_key: &[u8],
+        cursor: Option<&PageCursor>,
+        limit: usize,
+        mut f: F,
+    ) -> anyhow::Result<Option<PageCursor>>
+    where
+        F: FnMut(&[u8], &V) -> anyhow::Result<()>,
+    {
+        let Some(root) = self.outer.get(outer_key)? else {
+            anyhow::ensure!(cursor.is_none(), "cursor past an empty map");
+            return Ok(None);
+        };
+        let (_, next) = self.load_inner(root)?.for_each_ranged(
+            cursor.map(PageCursor::as_bytes),
+            Some(limit),
+            |key, value| f(key, value),
+        )?;
+        Ok(next.map(|key| PageCursor::from_bytes(key.0)))
+    }
+
+    /// Lists a page of the inner map of an outer key, for maps whose outer and inner keys are both
+    /// unsigned integers, see [`NestedMapReader::for_each_in_ranged`].
+    pub fn list_uint_page(
+        &self,
+        outer_key: u64,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> anyhow::Result<Page<(u64, V)>>
+    where
+        V: Clone,
+    {
+        let mut entries = Vec::new();
+        let next_cursor =
+            self.for_each_in_ranged(&uint_key(outer_key), cursor, limit, |key, value| {
+                entries.push((parse_uint_key(key)?, value.clone()));
+                Ok(())
+            })?;
+        Ok(Page {
+            entries,
+            next_cursor,
+        })
+    }
+
+    /// Runs a function over all `(outer key, inner key, value)` entries.
+    pub fn for_each<F>(&self, mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&[u8], &[u8], &V) -> anyhow::Result<()>,
+    {
+        self.outer.for_each(|outer_key, root| {
+            self.load_inner(root)?
+                .for_each(|inner_key, value| f(outer_key, inner_key, value))?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+/// Encodes an unsigned integer key, as used for actor, deal, allocation and claim IDs.
+pub fn uint_key(key: u64) -> Vec<u8> {
+    let mut buf = unsigned_varint::encode::u64_buffer();
+    unsigned_varint::encode::u64(key, &mut buf).to_vec()
+}
+
+/// Decodes an unsigned integer key, see [`uint_key`].
+pub fn parse_uint_key(key: &[u8]) -> anyhow::Result<u64> {
+    let (value, rest) = unsigned_varint::decode::u64(key)?;
+    anyhow::ensure!(rest.is_empty(), "trailing bytes after varint key");
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    #[test]
+    fn test_nested_map_reader() {
+        let store = MemoryBlockstore::new();
+        let mut outer = Hamt::<_, Cid, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        for (outer_key, values) in [(1u64, vec![10u64, 11]), (2, vec![20])] {
+            let mut inner = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+            for value in values {
+                inner.set(BytesKey(uint_key(value)), value * 100).unwrap();
+            }
+            outer
+                .set(BytesKey(uint_key(outer_key)), inner.flush().unwrap())
+                .unwrap();
+        }
+        let root = outer.flush().unwrap();
+
+        let reader = NestedMapReader::<_, u64>::load(&store, &root).unwrap();
+        let mut entries = Vec::new();
+        reader
+            .for_each(|outer_key, inner_key, value| {
+                entries.push((
+                    parse_uint_key(outer_key)?,
+                    parse_uint_key(inner_key)?,
+                    *value,
+                ));
+                Ok(())
+            })
+            .unwrap();
+        entries.sort();
+        assert_eq!(entries, vec![(1, 10, 1000), (1, 11, 1100), (2, 20, 2000)]);
+
+        let mut count = 0;
+        reader
+            .for_each_in(&uint_key(1), |_, _| {
+                count += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+        reader
+            .for_each_in(&uint_key(3), |_, _| panic!("no entry expected"))
+            .unwrap();
+        assert!(parse_uint_key(&[0x80]).is_err());
+    }
+
+    #[test]
+    fn test_for_each_in_ranged() {
+        let store = MemoryBlockstore::new();
+        let mut inner = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        for value in 0..10u64 {
+            inner.set(BytesKey(uint_key(value)), value).unwrap();
+        }
+        let mut outer = Hamt::<_, Cid, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        outer
+            .set(BytesKey(uint_key(1)), inner.flush().unwrap())
+            .unwrap();
+        let reader = NestedMapReader::<_, u64>::load(&store, &outer.flush().unwrap()).unwrap();
+
+        let mut values = Vec::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            cursor = reader
+                .for_each_in_ranged(&uint_key(1), cursor.as_ref(), 4, |_, value| {
+                    values.push(*value);
+                    Ok(())
+                })
+                .unwrap();
+            pages += 1;
+            match &cursor {
+                // Cursors survive a round trip through their bytes.
+                Some(c) => cursor = Some(PageCursor::from_bytes(c.as_bytes().to_vec())),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 3);
+        values.sort();
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+
+        let next = reader
+            .for_each_in_ranged(&uint_key(2), None, 4, |_, _| panic!("no entry expected"))
+            .unwrap();
+        assert_eq!(next, None);
+        let missing = PageCursor::from_bytes(uint_key(42));
+        assert!(reader
+            .for_each_in_ranged(&uint_key(1), Some(&missing), 4, |_, _| Ok(()))
+            .is_err());
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+
+/// A blockstore wrapper buffering the blocks written through it in memory, on top of the wrapped
+/// store, which is only read from.
+///
+/// Dry runs of state transitions, such as simulating a sector replacement, write the nodes of
+/// the updated collections. Running them against an overlay keeps those unreachable blocks out of
+/// the wrapped store: they are discarded with the overlay.
+#[derive(Debug, Default)]
+pub struct OverlayStore<BS> {
+    inner: BS,
+    writes: MemoryBlockstore,
+}
+
+impl<BS> OverlayStore<BS> {
+    pub fn new(inner: BS) -> Self {
+        Self {
+            inner,
+            writes: MemoryBlockstore::new(),
+        }
+    }
+
+    /// Returns the wrapped store.
+    pub fn inner(&self) -> &BS {
+        &self.inner
+    }
+}
+
+impl<BS: Blockstore> Blockstore for OverlayStore<BS> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        match self.writes.get(k)? {
+            Some(block) => Ok(Some(block)),
+            None => self.inner.get(k),
+        }
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.writes.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        Ok(self.writes.has(k)? || self.inner.has(k)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_encoding::CborStore;
+    use multihash_codetable::Code;
+
+    #[test]
+    fn test_writes_are_buffered() {
+        let store = MemoryBlockstore::new();
+        let existing = store.put_cbor(&1u64, Code::Blake2b256).unwrap();
+
+        let overlay = OverlayStore::new(&store);
+        let written = overlay.put_cbor(&2u64, Code::Blake2b256).unwrap();
+        assert_eq!(overlay.get_cbor::<u64>(&existing).unwrap(), Some(1));
+        assert_eq!(overlay.get_cbor::<u64>(&written).unwrap(), Some(2));
+        assert!(overlay.has(&written).unwrap());
+        assert!(!store.has(&written).unwrap());
+    }
+}
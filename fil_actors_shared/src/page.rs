@@ -0,0 +1,264 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::ops::{Bound, ControlFlow, RangeBounds};
+
+use anyhow::Context;
+use cid::Cid;
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_hamt::{BytesKey, Hamt, HashAlgorithm, Sha256};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::multimap::{parse_uint_key, uint_key};
+
+/// Opaque position of a paginated listing. Its bytes can be handed to clients and back to resume
+/// the listing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PageCursor(Vec<u8>);
+
+impl PageCursor {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// A page of a listing, with the cursor of the next page, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    pub entries: Vec<T>,
+    pub next_cursor: Option<PageCursor>,
+}
+
+impl<T> Page<T> {
+    /// Maps the entries of the page, keeping its cursor.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            entries: self.entries.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+/// Lists at most `limit` entries of an AMT, such as the miner sectors or the market deal
+/// proposals, starting at `cursor` or at the first entry.
+///
+/// Entries are listed in index order and the cursor is the index of the next entry, so a listing
+/// can be resumed on another snapshot of the AMT: it continues at the first index at or after the
+/// cursor, whether or not that entry still exists, and never lists an index twice.
+pub fn list_amt_page<BS, V>(
+    store: &BS,
+    root: &Cid,
+    cursor: Option<&PageCursor>,
+    limit: usize,
+) -> anyhow::Result<Page<(u64, V)>>
+where
+    BS: Blockstore,
+    V: DeserializeOwned + Serialize + Clone,
+{
+    let start = cursor
+        .map(|cursor| parse_uint_key(cursor.as_bytes()))
+        .transpose()
+        .context("invalid AMT cursor")?;
+    let amt =
+        Amt::<V, _>::load(root, store).with_context(|| format!("failed to load AMT {root}"))?;
+    let mut entries = Vec::new();
+    let (_, next) = amt.for_each_ranged(start, Some(limit as u64), |index, value| {
+        entries.push((index, value.clone()));
+        Ok(())
+    })?;
+    Ok(Page {
+        entries,
+        next_cursor: next.map(|index| PageCursor(uint_key(index))),
+    })
+}
+
+/// Lists at most `limit` entries of a HAMT with the given bit width, such as the market balance
+/// tables, starting at `cursor` or at the first entry. Keys are left encoded.
+///
+/// Entries are listed in HAMT order, i.e. by hash of their keys rather than by key, which is the
+/// same for all snapshots holding the same keys. The cursor is the key of the next entry. When
+/// that entry has since been removed, the listing resumes at the first entry whose key hashes
+/// after the cursor.
+pub fn list_hamt_page<BS, V>(
+    store: &BS,
+    root: &Cid,
+    bit_width: u32,
+    cursor: Option<&PageCursor>,
+    limit: usize,
+) -> anyhow::Result<Page<(Vec<u8>, V)>>
+where
+    BS: Blockstore,
+    V: DeserializeOwned + Serialize + Clone,
+{
+    let hamt = Hamt::<_, V, BytesKey>::load_with_bit_width(root, store, bit_width)
+        .with_context(|| format!("failed to load HAMT {root}"))?;
+    let (iter, resume_after) = match cursor {
+        Some(cursor) if hamt.contains_key(cursor.as_bytes())? => {
+            (hamt.iter_from(cursor.as_bytes())?, None)
+        }
+        cursor => (hamt.iter(), cursor.map(|c| Sha256::hash(c.as_bytes()))),
+    };
+    let mut iter = iter.skip_while(|entry| match (entry, &resume_after) {
+        (Ok((key, _)), Some(position)) => Sha256::hash(*key) <= *position,
+        _ => false,
+    });
+    let mut entries = Vec::new();
+    for entry in iter.by_ref().take(limit) {
+        let (key, value) = entry?;
+        entries.push((key.0.clone(), value.clone()));
+    }
+    let next = iter.next().transpose()?;
+    Ok(Page {
+        entries,
+        next_cursor: next.map(|(key, _)| PageCursor(key.0.clone())),
+    })
+}
+
+/// Runs a function over the entries of an AMT whose indexes are in `range`, in index order, until
+/// it returns [`ControlFlow::Break`]. Iteration starts at the beginning of the range, so the
+/// entries before it are not loaded, and stops at its end.
+pub fn for_each_amt_in_range<V, BS, F>(
+    amt: &Amt<V, BS>,
+    range: impl RangeBounds<u64>,
+    mut f: F,
+) -> anyhow::Result<ControlFlow<()>>
+where
+    V: DeserializeOwned + Serialize,
+    BS: Blockstore,
+    F: FnMut(u64, &V) -> anyhow::Result<ControlFlow<()>>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => match start.checked_add(1) {
+            Some(start) => start,
+            None => return Ok(ControlFlow::Continue(())),
+        },
+        Bound::Unbounded => 0,
+    };
+    let mut flow = ControlFlow::Continue(());
+    amt.for_each_while_ranged(Some(start), None, |index, value| {
+        if !range.contains(&index) {
+            return Ok(false);
+        }
+        flow = f(index, value)?;
+        Ok(flow.is_continue())
+    })?;
+    Ok(flow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared4::HAMT_BIT_WIDTH;
+
+    #[test]
+    fn test_list_amt_page() {
+        let store = MemoryBlockstore::new();
+        let mut amt = Amt::<u64, _>::new(&store);
+        for index in [1, 3, 5, 7, 9] {
+            amt.set(index, index * 10).unwrap();
+        }
+        let root = amt.flush().unwrap();
+
+        let page = list_amt_page::<_, u64>(&store, &root, None, 2).unwrap();
+        assert_eq!(page.entries, [(1, 10), (3, 30)]);
+        // The listing resumes on a snapshot in which the next entry was removed.
+        amt.delete(5).unwrap();
+        let root = amt.flush().unwrap();
+        let page = list_amt_page::<_, u64>(&store, &root, page.next_cursor.as_ref(), 2).unwrap();
+        assert_eq!(page.entries, [(7, 70), (9, 90)]);
+        assert_eq!(page.next_cursor, None);
+
+        let invalid = PageCursor::from_bytes(vec![0x80]);
+        assert!(list_amt_page::<_, u64>(&store, &root, Some(&invalid), 2).is_err());
+    }
+
+    #[test]
+    fn test_list_hamt_page() {
+        let store = MemoryBlockstore::new();
+        let mut hamt = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        for key in 0..10u64 {
+            hamt.set(BytesKey(uint_key(key)), key).unwrap();
+        }
+        let root = hamt.flush().unwrap();
+
+        let mut keys = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = list_hamt_page::<_, u64>(&store, &root, HAMT_BIT_WIDTH, cursor.as_ref(), 3)
+                .unwrap();
+            assert!(page.entries.len() <= 3);
+            keys.extend(page.entries.into_iter().map(|(key, _)| key));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        // Listings of the same map are in the same order.
+        let all = list_hamt_page::<_, u64>(&store, &root, HAMT_BIT_WIDTH, None, 10).unwrap();
+        assert_eq!(
+            keys,
+            all.entries
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>()
+        );
+
+        // The listing resumes on a snapshot in which the next entry was removed.
+        let page = list_hamt_page::<_, u64>(&store, &root, HAMT_BIT_WIDTH, None, 3).unwrap();
+        let removed = page.next_cursor.clone().unwrap();
+        hamt.delete(removed.as_bytes()).unwrap();
+        let root = hamt.flush().unwrap();
+        let page =
+            list_hamt_page::<_, u64>(&store, &root, HAMT_BIT_WIDTH, Some(&removed), 10).unwrap();
+        assert_eq!(
+            page.entries
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>(),
+            keys[4..]
+        );
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn test_for_each_amt_in_range() {
+        let store = MemoryBlockstore::new();
+        let mut amt = Amt::<u64, _>::new(&store);
+        for index in [1, 3, 5, 7, 9] {
+            amt.set(index, index * 10).unwrap();
+        }
+
+        let collect = |range: (Bound<u64>, Bound<u64>), stop_at: u64| {
+            let mut values = Vec::new();
+            let flow = for_each_amt_in_range(&amt, range, |index, value| {
+                values.push(*value);
+                Ok(if index == stop_at {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                })
+            })
+            .unwrap();
+            (values, flow.is_break())
+        };
+        use Bound::*;
+        assert_eq!(
+            collect((Included(3), Excluded(9)), 0),
+            (vec![30, 50, 70], false)
+        );
+        assert_eq!(
+            collect((Excluded(3), Unbounded), 0),
+            (vec![50, 70, 90], false)
+        );
+        assert_eq!(collect((Unbounded, Unbounded), 5), (vec![10, 30, 50], true));
+        assert_eq!(collect((Excluded(u64::MAX), Unbounded), 0), (vec![], false));
+    }
+}
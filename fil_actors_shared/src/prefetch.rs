@@ -0,0 +1,402 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::DAG_CBOR;
+use ipld_core::ipld::Ipld;
+
+/// Issues read-ahead requests on behalf of a [`PrefetchStore`].
+///
+/// Implementations are expected to return immediately, e.g. by spawning the requests on an async
+/// runtime, and to hand the fetched blocks back through the [`PrefetchSink`] as they arrive. Every
+/// requested block must eventually be delivered or reported as failed, as reads of a block in
+/// flight wait for it.
+pub trait Prefetcher {
+    fn prefetch(&self, cids: Vec<Cid>, sink: PrefetchSink);
+}
+
+/// Receiver of prefetched blocks, cheap to clone and safe to send across threads.
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchSink {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    pending: Mutex<Pending>,
+    /// Notified whenever a block stops being in flight.
+    arrived: Condvar,
+}
+
+/// Blocks requested from the [`Prefetcher`] and not read yet.
+#[derive(Debug, Default)]
+struct Pending {
+    /// Requested and not delivered yet.
+    in_flight: HashSet<Cid>,
+    /// Delivered and not read yet, oldest first.
+    delivered: VecDeque<(Cid, Vec<u8>)>,
+    /// Requested and failed, not read yet.
+    failed: HashSet<Cid>,
+}
+
+impl PrefetchSink {
+    /// Hands a fetched block over to the store. Blocks which are not awaited, e.g. because they
+    /// have been read from the wrapped store in the meantime, are dropped.
+    pub fn deliver(&self, cid: Cid, block: Vec<u8>) {
+        let mut pending = self.lock();
+        if pending.in_flight.remove(&cid) {
+            pending.delivered.push_back((cid, block));
+            self.shared.arrived.notify_all();
+        }
+    }
+
+    /// Reports that a block could not be fetched. It no longer counts against the fan-out and is
+    /// read from the wrapped store when needed.
+    pub fn fail(&self, cid: &Cid) {
+        let mut pending = self.lock();
+        if pending.in_flight.remove(cid) {
+            pending.failed.insert(*cid);
+            self.shared.arrived.notify_all();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.shared.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits until the given block is no longer in flight.
+    fn wait_for<'a>(
+        &self,
+        mut pending: MutexGuard<'a, Pending>,
+        cid: &Cid,
+    ) -> MutexGuard<'a, Pending> {
+        while pending.in_flight.contains(cid) {
+            pending = self
+                .shared
+                .arrived
+                .wait(pending)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        pending
+    }
+}
+
+/// A blockstore wrapper reading ahead the children of the nodes loaded through it.
+///
+/// AMT and HAMT traversals, such as the `for_each` iterators of the actor states, load the nodes
+/// of a collection one at a time. Against remote stores (S3, IPFS gateways) the per-block latency
+/// then dominates large scans. Whenever a DAG-CBOR block is read, the blocks it links to are
+/// queued in traversal order, and up to `fanout` of them are kept in flight through the
+/// [`Prefetcher`], so that the siblings visited next are fetched concurrently. Prefetched blocks
+/// are served once and then dropped; reads of a block which is still in flight wait for it, and
+/// blocks whose fetch failed are read from the wrapped store.
+///
+/// At most `fanout` blocks are requested or delivered and not read at any time. A block which has
+/// not been requested shows that the traversal skipped the blocks read ahead, as ranged
+/// iterations and lookups do, or started over: the delivered blocks and the links queued before
+/// it are then dropped. The requests in flight are kept, so that their blocks are still served if
+/// the traversal reaches them.
+pub struct PrefetchStore<BS, P> {
+    inner: BS,
+    prefetcher: P,
+    fanout: usize,
+    sink: PrefetchSink,
+    window: Mutex<Window>,
+}
+
+/// Links queued for read-ahead.
+#[derive(Default)]
+struct Window {
+    queue: VecDeque<Cid>,
+}
+
+impl<BS, P> PrefetchStore<BS, P> {
+    pub fn new(inner: BS, prefetcher: P, fanout: usize) -> Self {
+        Self {
+            inner,
+            prefetcher,
+            fanout,
+            sink: PrefetchSink::default(),
+            window: Default::default(),
+        }
+    }
+
+    /// Returns the wrapped store.
+    pub fn inner(&self) -> &BS {
+        &self.inner
+    }
+}
+
+impl<BS: Blockstore, P: Prefetcher> PrefetchStore<BS, P> {
+    fn read_ahead(&self, cid: &Cid, block: Option<&[u8]>) {
+        if self.fanout == 0 {
+            return;
+        }
+        let mut links = Vec::new();
+        if let Some(block) = block.filter(|_| cid.codec() == DAG_CBOR) {
+            // Blocks which are not valid DAG-CBOR are simply not read ahead.
+            if let Ok(node) = fvm_ipld_encoding::from_slice::<Ipld>(block) {
+                collect_links(&node, &mut links);
+            }
+        }
+
+        let batch = {
+            let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+            let mut pending = self.sink.lock();
+            // Traversals are depth-first: the children of the node just read come next.
+            for link in links.into_iter().rev() {
+                window.queue.push_front(link);
+            }
+            let mut batch = Vec::new();
+            while pending.in_flight.len() + pending.delivered.len() < self.fanout {
+                let Some(link) = window.queue.pop_front() else {
+                    break;
+                };
+                let delivered = pending.delivered.iter().any(|(cid, _)| *cid == link);
+                if link != *cid && !delivered && pending.in_flight.insert(link) {
+                    pending.failed.remove(&link);
+                    batch.push(link);
+                }
+            }
+            batch
+        };
+        if !batch.is_empty() {
+            self.prefetcher.prefetch(batch, self.sink.clone());
+        }
+    }
+}
+
+impl<BS: Blockstore, P: Prefetcher> Blockstore for PrefetchStore<BS, P> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        let (prefetched, failed) = {
+            let mut pending = self.sink.wait_for(self.sink.lock(), k);
+            let index = pending.delivered.iter().position(|(cid, _)| cid == k);
+            let prefetched = index
+                .and_then(|index| pending.delivered.remove(index))
+                .map(|(_, block)| block);
+            (prefetched, pending.failed.remove(k))
+        };
+        if prefetched.is_none() && !failed {
+            let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+            let mut pending = self.sink.lock();
+            pending.delivered.clear();
+            pending.failed.clear();
+            match window.queue.iter().position(|cid| cid == k) {
+                Some(index) => drop(window.queue.drain(..=index)),
+                None => window.queue.clear(),
+            }
+        }
+        let block = match prefetched {
+            Some(block) => Some(block),
+            None => self.inner.get(k)?,
+        };
+        self.read_ahead(k, block.as_deref());
+        Ok(block)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.inner.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        if self.sink.lock().delivered.iter().any(|(cid, _)| cid == k) {
+            return Ok(true);
+        }
+        self.inner.has(k)
+    }
+}
+
+/// Collects the links of a node, in order.
+fn collect_links(node: &Ipld, links: &mut Vec<Cid>) {
+    match node {
+        Ipld::Link(cid) => links.push(*cid),
+        Ipld::List(items) => items.iter().for_each(|item| collect_links(item, links)),
+        Ipld::Map(entries) => entries.values().for_each(|item| collect_links(item, links)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_amt::Amt;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use std::cell::{Cell, RefCell};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct CountingStore {
+        store: MemoryBlockstore,
+        gets: Cell<usize>,
+    }
+
+    impl Blockstore for CountingStore {
+        fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            self.gets.set(self.gets.get() + 1);
+            self.store.get(k)
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+            self.store.put_keyed(k, block)
+        }
+    }
+
+    /// Fetches synchronously from a store, recording the batches requested.
+    struct StorePrefetcher<'a> {
+        store: &'a MemoryBlockstore,
+        batches: RefCell<Vec<usize>>,
+    }
+
+    impl Prefetcher for StorePrefetcher<'_> {
+        fn prefetch(&self, cids: Vec<Cid>, sink: PrefetchSink) {
+            self.batches.borrow_mut().push(cids.len());
+            for cid in cids {
+                if let Some(block) = self.store.get(&cid).unwrap() {
+                    sink.deliver(cid, block);
+                }
+            }
+        }
+    }
+
+    type Delivery = (Vec<(Cid, Vec<u8>)>, PrefetchSink);
+
+    /// Fetches from a store and delivers from another thread, after the read which requested the
+    /// blocks has returned, as an async fetcher does.
+    struct DelayedPrefetcher<'a> {
+        store: &'a MemoryBlockstore,
+        deliveries: mpsc::Sender<Delivery>,
+    }
+
+    impl Prefetcher for DelayedPrefetcher<'_> {
+        fn prefetch(&self, cids: Vec<Cid>, sink: PrefetchSink) {
+            let blocks = cids
+                .into_iter()
+                .map(|cid| (cid, self.store.get(&cid).unwrap().unwrap()))
+                .collect();
+            self.deliveries.send((blocks, sink)).unwrap();
+        }
+    }
+
+    /// Fails every request.
+    struct FailingPrefetcher;
+
+    impl Prefetcher for FailingPrefetcher {
+        fn prefetch(&self, cids: Vec<Cid>, sink: PrefetchSink) {
+            cids.iter().for_each(|cid| sink.fail(cid));
+        }
+    }
+
+    fn amt_with_8_leaves(remote: &CountingStore) -> Cid {
+        let mut amt = Amt::<u64, _>::new_with_bit_width(&remote.store, 3);
+        for i in 0..64 {
+            amt.set(i, i).unwrap();
+        }
+        amt.flush().unwrap()
+    }
+
+    #[test]
+    fn test_amt_nodes_are_read_ahead() {
+        let remote = CountingStore::default();
+        let root = amt_with_8_leaves(&remote);
+
+        let prefetcher = StorePrefetcher {
+            store: &remote.store,
+            batches: Default::default(),
+        };
+        let store = PrefetchStore::new(&remote, prefetcher, 4);
+        let amt = Amt::<u64, _>::load(&root, &store).unwrap();
+        let mut count = 0;
+        amt.for_each(|_, _| {
+            count += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 64);
+
+        // Only the root is read from the wrapped store, its 8 children are read ahead with at
+        // most 4 in flight.
+        assert_eq!(remote.gets.get(), 1);
+        let batches = store.prefetcher.batches.borrow();
+        assert_eq!(batches.iter().sum::<usize>(), 8);
+        assert!(batches.iter().all(|n| *n <= 4));
+    }
+
+    #[test]
+    fn test_blocks_in_flight_are_awaited() {
+        let remote = CountingStore::default();
+        let root = amt_with_8_leaves(&remote);
+
+        let (deliveries, receiver) = mpsc::channel::<Delivery>();
+        let delivery = thread::spawn(move || {
+            for (blocks, sink) in receiver {
+                thread::sleep(Duration::from_millis(5));
+                for (cid, block) in blocks {
+                    sink.deliver(cid, block);
+                }
+            }
+        });
+        let prefetcher = DelayedPrefetcher {
+            store: &remote.store,
+            deliveries,
+        };
+        let store = PrefetchStore::new(&remote, prefetcher, 4);
+        let amt = Amt::<u64, _>::load(&root, &store).unwrap();
+        let mut count = 0;
+        amt.for_each(|_, _| {
+            count += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 64);
+
+        // The leaves are still in flight when the iteration reaches them: their reads wait for
+        // the deliveries instead of going to the wrapped store.
+        assert_eq!(remote.gets.get(), 1);
+        assert!(store.sink.lock().in_flight.is_empty());
+        drop(store);
+        delivery.join().unwrap();
+    }
+
+    #[test]
+    fn test_skipped_and_failed_blocks_are_released() {
+        let remote = CountingStore::default();
+        let root = amt_with_8_leaves(&remote);
+
+        // The first 4 leaves are read ahead along with the root and skipped by the iteration.
+        let prefetcher = StorePrefetcher {
+            store: &remote.store,
+            batches: Default::default(),
+        };
+        let store = PrefetchStore::new(&remote, prefetcher, 4);
+        let amt = Amt::<u64, _>::load(&root, &store).unwrap();
+        let mut count = 0;
+        amt.for_each_ranged(Some(40), None, |_, _| {
+            count += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 24);
+        // Reading the 6th leaf drops the skipped ones, and the next leaves are still read ahead.
+        assert_eq!(remote.gets.get(), 2);
+        assert!(store.sink.lock().delivered.len() <= 4);
+
+        // Failed requests leave room for the next ones.
+        let remote = CountingStore::default();
+        let root = amt_with_8_leaves(&remote);
+        let store = PrefetchStore::new(&remote, FailingPrefetcher, 4);
+        let amt = Amt::<u64, _>::load(&root, &store).unwrap();
+        amt.for_each(|_, _| Ok(())).unwrap();
+        assert_eq!(remote.gets.get(), 9);
+        assert!(store.sink.lock().in_flight.is_empty());
+
+        // Blocks which are not awaited are dropped.
+        store.sink.deliver(root, vec![]);
+        assert!(store.sink.lock().delivered.is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+pub use crate::actor_type::ActorType;
+pub use crate::address_owner::{
+    classify_address_owner, load_actor, AddressClassification, AddressOwner,
+};
+#[cfg(feature = "async")]
+pub use crate::async_store::{amt_entries, hamt_entries, AsyncBlockstore, LoadAsync, SyncStore};
+pub use crate::bundle::{Bundle, KnownCids, Manifest, NetworkManifest};
+pub use crate::cache::StateCache;
+pub use crate::diff::{diff_amt, diff_amt_decoded, diff_hamt, diff_hamt_decoded, Change};
+pub use crate::ext::TokenStateExt;
+pub use crate::forward_compat::{
+    from_slice_best_effort, get_cbor_best_effort, BestEffort, DecodeWarning,
+};
+pub use crate::hamt_proof::{prove_hamt_key, HamtProof};
+pub use crate::ids::{AllocationID, ClaimID, DealID, SectorNumber};
+pub use crate::multimap::NestedMapReader;
+pub use crate::overlay::OverlayStore;
+pub use crate::page::{for_each_amt_in_range, list_amt_page, list_hamt_page, Page, PageCursor};
+pub use crate::prefetch::{PrefetchSink, PrefetchStore, Prefetcher};
+pub use crate::reindex::{reindex, MinerChanges, StateChangeSet};
+pub use crate::stable_hash::stable_hash;
+pub use crate::state_check::{quick_check, QuickCheckReport};
+pub use crate::state_diff::{diff_actor_state, ActorStateDiff, KeyChanges};
+pub use crate::state_invariants::{check_state_tree_invariants, ActorViolation, InvariantReport};
+pub use crate::version::{
+    actor_versions, actors_version, capability, is_supported, max_supported_actors_version,
+    require_version, Capability, UnsupportedVersion, MAX_ACTORS_VERSION, MIN_ACTORS_VERSION,
+};
+pub use crate::version_gate;
+pub use crate::versioned::{code_version, normalize_address, FieldChange, UpgradeVersions};
@@ -0,0 +1,116 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_ipld_encoding::to_vec;
+use fvm_shared4::address::Address;
+use fvm_shared4::clock::ChainEpoch;
+use fvm_shared4::randomness::RANDOMNESS_LENGTH;
+
+/// The tag values have never been renumbered, so the latest definition is valid for all
+/// actor versions. Older versions simply lack the newer variants.
+pub use crate::v16::runtime::DomainSeparationTag;
+
+/// Entropy used by the miner actor (and by block production) for randomness bound to a miner:
+/// the CBOR encoding of the miner's address.
+///
+/// This is the entropy for [`DomainSeparationTag::SealRandomness`],
+/// [`DomainSeparationTag::InteractiveSealChallengeSeed`],
+/// [`DomainSeparationTag::WindowedPoStChallengeSeed`],
+/// [`DomainSeparationTag::WinningPoStChallengeSeed`],
+/// [`DomainSeparationTag::ElectionProofProduction`] and
+/// [`DomainSeparationTag::TicketProduction`]. Note that the miner actor always uses its ID
+/// address here.
+pub fn miner_address_entropy(miner: &Address) -> anyhow::Result<Vec<u8>> {
+    Ok(to_vec(miner)?)
+}
+
+/// Entropy used by the miner actor for [`DomainSeparationTag::PoStChainCommit`] randomness,
+/// which is not bound to a particular miner.
+pub const POST_CHAIN_COMMIT_ENTROPY: &[u8] = &[];
+
+/// Derives randomness from a randomness base (ticket or beacon digest) exactly as the actors do.
+///
+/// The hashed preimage is `tag (i64 BE) || rbase || round (i64 BE) || entropy`. On-chain the
+/// hasher is `blake2b-256`.
+pub fn draw_randomness(
+    hasher: impl FnOnce(&[u8]) -> [u8; RANDOMNESS_LENGTH],
+    rbase: &[u8; RANDOMNESS_LENGTH],
+    pers: DomainSeparationTag,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> [u8; RANDOMNESS_LENGTH] {
+    hasher(&randomness_preimage(rbase, pers, round, entropy))
+}
+
+/// Returns the bytes that [`draw_randomness`] hashes.
+pub fn randomness_preimage(
+    rbase: &[u8; RANDOMNESS_LENGTH],
+    pers: DomainSeparationTag,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(RANDOMNESS_LENGTH + 8 + 8 + entropy.len());
+
+    // Append the personalization value
+    data.extend_from_slice(&(pers as i64).to_be_bytes());
+
+    // Append the randomness
+    data.extend_from_slice(rbase);
+
+    // Append the round
+    data.extend_from_slice(&round.to_be_bytes());
+
+    // Append the entropy
+    data.extend_from_slice(entropy);
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_miner_address_entropy() {
+        // CBOR byte string header (0x43) followed by the ID address payload for f01000.
+        let entropy = miner_address_entropy(&Address::new_id(1000)).unwrap();
+        assert_eq!(entropy, vec![0x43, 0x00, 0xe8, 0x07]);
+    }
+
+    #[test]
+    fn test_randomness_preimage_layout() {
+        let rbase = [7u8; RANDOMNESS_LENGTH];
+        let preimage = randomness_preimage(
+            &rbase,
+            DomainSeparationTag::SealRandomness,
+            -1,
+            &[0xaa, 0xbb],
+        );
+
+        assert_eq!(preimage.len(), 8 + RANDOMNESS_LENGTH + 8 + 2);
+        assert_eq!(&preimage[..8], &5i64.to_be_bytes());
+        assert_eq!(&preimage[8..8 + RANDOMNESS_LENGTH], &rbase);
+        assert_eq!(
+            &preimage[8 + RANDOMNESS_LENGTH..16 + RANDOMNESS_LENGTH],
+            &[0xff; 8]
+        );
+        assert_eq!(&preimage[16 + RANDOMNESS_LENGTH..], &[0xaa, 0xbb]);
+    }
+
+    #[test]
+    fn test_draw_randomness_hashes_preimage() {
+        let rbase = [1u8; RANDOMNESS_LENGTH];
+        let out = draw_randomness(
+            |data| {
+                let mut digest = [0u8; RANDOMNESS_LENGTH];
+                digest[0] = data.len() as u8;
+                digest
+            },
+            &rbase,
+            DomainSeparationTag::PoStChainCommit,
+            10,
+            POST_CHAIN_COMMIT_ENTROPY,
+        );
+        assert_eq!(out[0], (8 + RANDOMNESS_LENGTH + 8) as u8);
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeSet;
+
+use anyhow::Context;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared4::address::Address;
+use fvm_shared4::ActorID;
+
+use crate::diff::{diff_amt_opt, diff_hamt_opt, Change};
+use crate::ids::{ClaimID, DealID, SectorNumber};
+use crate::state_check::{ActorHead, StateTree};
+use crate::state_diff::{
+    diff_nested_uint_hamt, state_field, MARKET_PROPOSALS_FIELD, MARKET_STATES_FIELD,
+    MINER_SECTORS_FIELD, VERIFREG_CLAIMS_FIELD,
+};
+
+const MARKET_ACTOR_ID: ActorID = 5;
+const VERIFREG_ACTOR_ID: ActorID = 6;
+
+/// Sector changes of a miner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinerChanges {
+    pub address: Address,
+    pub sectors_added: Vec<SectorNumber>,
+    pub sectors_removed: Vec<SectorNumber>,
+    /// Sectors whose on-chain info changed, e.g. when extended, updated or migrated.
+    pub sectors_modified: Vec<SectorNumber>,
+}
+
+impl MinerChanges {
+    pub fn is_empty(&self) -> bool {
+        self.sectors_added.is_empty()
+            && self.sectors_removed.is_empty()
+            && self.sectors_modified.is_empty()
+    }
+}
+
+/// Compact summary of the changes between two state trees, for indexers to update their
+/// databases incrementally.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateChangeSet {
+    pub actors_added: Vec<Address>,
+    pub actors_removed: Vec<Address>,
+    /// Actors whose code, state, nonce or balance changed.
+    pub actors_changed: Vec<Address>,
+    /// Miners with sector changes, including added and removed miners.
+    pub miners: Vec<MinerChanges>,
+    /// Deals whose proposal or state was added, removed or changed.
+    pub deals_changed: Vec<DealID>,
+    /// `(provider, claim ID)` of the verified registry claims added, removed or changed.
+    pub claims_changed: Vec<(ActorID, ClaimID)>,
+}
+
+/// Computes the changes between the state trees with the given roots.
+///
+/// The state trees may belong to different actors versions, as is the case across network
+/// upgrades. Collections rewritten by a migration are then reported as changed in full.
+pub fn reindex<BS: Blockstore>(
+    store: &BS,
+    old_root: &Cid,
+    new_root: &Cid,
+) -> anyhow::Result<StateChangeSet> {
+    let old = StateTree::load(store, old_root).context("failed to load old state tree")?;
+    let new = StateTree::load(store, new_root).context("failed to load new state tree")?;
+
+    let mut actor_changes = Vec::new();
+    diff_hamt_opt(
+        store,
+        Some(&old.actors_root),
+        Some(&new.actors_root),
+        &mut actor_changes,
+    )?;
+
+    let mut changes = StateChangeSet::default();
+    for change in actor_changes {
+        let address = Address::from_bytes(change.key())?;
+        let (old_actor, new_actor) = match &change {
+            Change::Added(_, actor) => {
+                changes.actors_added.push(address);
+                (None, Some(ActorHead::decode(actor)?))
+            }
+            Change::Removed(_, actor) => {
+                changes.actors_removed.push(address);
+                (Some(ActorHead::decode(actor)?), None)
+            }
+            Change::Modified(_, old_actor, new_actor) => {
+                changes.actors_changed.push(address);
+                (
+                    Some(ActorHead::decode(old_actor)?),
+                    Some(ActorHead::decode(new_actor)?),
+                )
+            }
+        };
+        let old_miner = old_actor.filter(|a| old.is_miner(a));
+        let new_miner = new_actor.filter(|a| new.is_miner(a));
+        if old_miner.is_some() || new_miner.is_some() {
+            let miner = miner_changes(store, address, old_miner, new_miner)?;
+            if !miner.is_empty() {
+                changes.miners.push(miner);
+            }
+        }
+    }
+    changes.actors_added.sort();
+    changes.actors_removed.sort();
+    changes.actors_changed.sort();
+
+    let old_market = old.actor(MARKET_ACTOR_ID)?;
+    let new_market = new.actor(MARKET_ACTOR_ID)?;
+    if old_market.as_ref().map(|a| a.state) != new_market.as_ref().map(|a| a.state) {
+        changes.deals_changed = deal_changes(store, old_market, new_market)?;
+    }
+
+    let old_verifreg = old.actor(VERIFREG_ACTOR_ID)?;
+    let new_verifreg = new.actor(VERIFREG_ACTOR_ID)?;
+    if old_verifreg.as_ref().map(|a| a.state) != new_verifreg.as_ref().map(|a| a.state) {
+        changes.claims_changed = claim_changes(store, old_verifreg, new_verifreg)?;
+    }
+
+    Ok(changes)
+}
+
+fn miner_changes<BS: Blockstore>(
+    store: &BS,
+    address: Address,
+    old: Option<ActorHead>,
+    new: Option<ActorHead>,
+) -> anyhow::Result<MinerChanges> {
+    let old_sectors = state_field(store, old.as_ref().map(|a| &a.state), MINER_SECTORS_FIELD)?;
+    let new_sectors = state_field(store, new.as_ref().map(|a| &a.state), MINER_SECTORS_FIELD)?;
+    let mut sector_changes = Vec::new();
+    diff_amt_opt(
+        store,
+        old_sectors.as_ref(),
+        new_sectors.as_ref(),
+        &mut sector_changes,
+    )?;
+
+    let mut miner = MinerChanges {
+        address,
+        sectors_added: Vec::new(),
+        sectors_removed: Vec::new(),
+        sectors_modified: Vec::new(),
+    };
+    for change in sector_changes {
+        match change {
+            Change::Added(n, _) => miner.sectors_added.push(n.into()),
+            Change::Removed(n, _) => miner.sectors_removed.push(n.into()),
+            Change::Modified(n, _, _) => miner.sectors_modified.push(n.into()),
+        }
+    }
+    Ok(miner)
+}
+
+fn deal_changes<BS: Blockstore>(
+    store: &BS,
+    old: Option<ActorHead>,
+    new: Option<ActorHead>,
+) -> anyhow::Result<Vec<DealID>> {
+    let mut deals = BTreeSet::new();
+    for field in [MARKET_PROPOSALS_FIELD, MARKET_STATES_FIELD] {
+        let old_root = state_field(store, old.as_ref().map(|a| &a.state), field)?;
+        let new_root = state_field(store, new.as_ref().map(|a| &a.state), field)?;
+        let mut changes = Vec::new();
+        diff_amt_opt(store, old_root.as_ref(), new_root.as_ref(), &mut changes)?;
+        deals.extend(changes.iter().map(|change| DealID(*change.key())));
+    }
+    Ok(deals.into_iter().collect())
+}
+
+fn claim_changes<BS: Blockstore>(
+    store: &BS,
+    old: Option<ActorHead>,
+    new: Option<ActorHead>,
+) -> anyhow::Result<Vec<(ActorID, ClaimID)>> {
+    let old_root = state_field(store, old.as_ref().map(|a| &a.state), VERIFREG_CLAIMS_FIELD)?;
+    let new_root = state_field(store, new.as_ref().map(|a| &a.state), VERIFREG_CLAIMS_FIELD)?;
+    let claims: BTreeSet<_> = diff_nested_uint_hamt(store, old_root.as_ref(), new_root.as_ref())?
+        .iter()
+        .map(|change| {
+            let (provider, claim) = *change.key();
+            (provider, ClaimID(claim))
+        })
+        .collect();
+    Ok(claims.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_amt::Amt;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::{CborStore, IPLD_RAW};
+    use fvm_ipld_hamt::{BytesKey, Hamt};
+    use fvm_shared4::HAMT_BIT_WIDTH;
+    use ipld_core::ipld::Ipld;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    fn code(name: &str, version: u64) -> Cid {
+        Cid::new_v1(
+            IPLD_RAW,
+            Code::Blake2b256.digest(format!("{name}/{version}").as_bytes()),
+        )
+    }
+
+    fn actor(code: Cid, state: Cid) -> Ipld {
+        Ipld::List(vec![
+            Ipld::Link(code),
+            Ipld::Link(state),
+            Ipld::Integer(0),
+            Ipld::Bytes(vec![]),
+            Ipld::Null,
+        ])
+    }
+
+    fn amt(store: &MemoryBlockstore, entries: &[(u64, u64)]) -> Cid {
+        let mut amt = Amt::<u64, _>::new_with_bit_width(store, 5);
+        for (k, v) in entries {
+            amt.set(*k, *v).unwrap();
+        }
+        amt.flush().unwrap()
+    }
+
+    fn key(id: u64) -> BytesKey {
+        let mut buf = unsigned_varint::encode::u64_buffer();
+        BytesKey(unsigned_varint::encode::u64(id, &mut buf).to_vec())
+    }
+
+    /// Builds a state tree with a single miner and the market and verifreg actors, with states
+    /// reduced to the fields read by `reindex`.
+    fn state_tree(
+        store: &MemoryBlockstore,
+        version: u64,
+        sectors: &[(u64, u64)],
+        deals: &[(u64, u64)],
+        claims: &[(u64, u64)],
+    ) -> Cid {
+        let put = |value: &Ipld| store.put_cbor(value, Code::Blake2b256).unwrap();
+        let names = [
+            "system",
+            "storagemarket",
+            "verifiedregistry",
+            "storageminer",
+        ];
+        let manifest: Vec<_> = names
+            .iter()
+            .map(|n| (n.to_string(), code(n, version)))
+            .collect();
+        let manifest = store.put_cbor(&manifest, Code::Blake2b256).unwrap();
+        let system_state = put(&Ipld::List(vec![Ipld::Link(manifest)]));
+
+        let mut miner_fields = vec![Ipld::Null; MINER_SECTORS_FIELD];
+        miner_fields.push(Ipld::Link(amt(store, sectors)));
+        let miner_state = put(&Ipld::List(miner_fields));
+
+        let deals = amt(store, deals);
+        let market_state = put(&Ipld::List(vec![Ipld::Link(deals), Ipld::Link(deals)]));
+
+        let mut provider_claims = Hamt::<_, u64, BytesKey>::new_with_bit_width(store, 5);
+        for (id, claim) in claims {
+            provider_claims.set(key(*id), *claim).unwrap();
+        }
+        let mut all_claims = Hamt::<_, Cid, BytesKey>::new_with_bit_width(store, 5);
+        all_claims
+            .set(key(1000), provider_claims.flush().unwrap())
+            .unwrap();
+        let mut verifreg_fields = vec![Ipld::Null; VERIFREG_CLAIMS_FIELD];
+        verifreg_fields.push(Ipld::Link(all_claims.flush().unwrap()));
+        let verifreg_state = put(&Ipld::List(verifreg_fields));
+
+        let mut actors = Hamt::<_, Ipld, BytesKey>::new_with_bit_width(store, HAMT_BIT_WIDTH);
+        for (id, name, state) in [
+            (0, "system", system_state),
+            (5, "storagemarket", market_state),
+            (6, "verifiedregistry", verifreg_state),
+            (1000, "storageminer", miner_state),
+        ] {
+            actors
+                .set(
+                    BytesKey(Address::new_id(id).to_bytes()),
+                    actor(code(name, version), state),
+                )
+                .unwrap();
+        }
+        let actors = actors.flush().unwrap();
+        let info = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        store
+            .put_cbor(&(5u64, actors, info), Code::Blake2b256)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reindex() {
+        let store = MemoryBlockstore::new();
+        let old = state_tree(&store, 15, &[(1, 1), (2, 2)], &[(10, 0)], &[(7, 0)]);
+        let new = state_tree(&store, 15, &[(1, 1), (2, 20), (3, 3)], &[(10, 0)], &[]);
+
+        let changes = reindex(&store, &old, &new).unwrap();
+        let miner = Address::new_id(1000);
+        assert_eq!(changes.actors_changed, vec![Address::new_id(6), miner]);
+        assert_eq!(
+            changes.miners,
+            vec![MinerChanges {
+                address: miner,
+                sectors_added: vec![SectorNumber(3)],
+                sectors_removed: vec![],
+                sectors_modified: vec![SectorNumber(2)],
+            }]
+        );
+        assert!(changes.deals_changed.is_empty());
+        assert_eq!(changes.claims_changed, vec![(1000, ClaimID(7))]);
+
+        assert_eq!(
+            reindex(&store, &new, &new).unwrap(),
+            StateChangeSet::default()
+        );
+    }
+
+    #[test]
+    fn test_reindex_across_upgrade() {
+        let store = MemoryBlockstore::new();
+        let old = state_tree(&store, 15, &[(1, 1)], &[(10, 0)], &[(7, 0)]);
+        let new = state_tree(&store, 16, &[(1, 1)], &[(10, 0), (11, 0)], &[(7, 0)]);
+
+        // Every actor gets a new code, but only the collections that changed are reported.
+        let changes = reindex(&store, &old, &new).unwrap();
+        assert_eq!(changes.actors_changed.len(), 4);
+        assert!(changes.miners.is_empty());
+        assert_eq!(changes.deals_changed, vec![DealID(11)]);
+        assert!(changes.claims_changed.is_empty());
+    }
+}
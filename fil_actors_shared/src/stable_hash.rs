@@ -0,0 +1,48 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_ipld_encoding::to_vec;
+use multihash_codetable::{Code, MultihashDigest};
+use serde::Serialize;
+
+/// Version of the hashing scheme of [`stable_hash`]. It is only bumped when the hash of a value
+/// changes, e.g. if the hashed envelope changes, so hashes computed by different releases of
+/// this crate can be compared as long as this version is the same.
+pub const STABLE_HASH_SCHEMA_VERSION: u64 = 1;
+
+/// Computes a deterministic 32 bytes hash of a value, for downstream pipelines to detect changes
+/// and deduplicate exports cheaply.
+///
+/// The hash is the Blake2b-256 digest of the DAG-CBOR encoding of
+/// `[STABLE_HASH_SCHEMA_VERSION, value]`. DAG-CBOR is canonical, so the hash only depends on the
+/// serialized form of the value: it stays stable as long as the type serializes the same, which
+/// is the case of all the on-chain types of this workspace. Types with maps should use ordered
+/// maps, such as `BTreeMap`, since the hash of a value whose serialization is not deterministic is
+/// not deterministic either.
+pub fn stable_hash<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<[u8; 32]> {
+    let bytes = to_vec(&(STABLE_HASH_SCHEMA_VERSION, value))?;
+    let digest = Code::Blake2b256.digest(&bytes);
+    let mut hash = [0; 32];
+    hash.copy_from_slice(digest.digest());
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_stable_hash() {
+        let value = BTreeMap::from([("b", 2u64), ("a", 1)]);
+        let hash = stable_hash(&value).unwrap();
+        assert_eq!(hash, stable_hash(&value.clone()).unwrap());
+        assert_ne!(hash, stable_hash(&BTreeMap::from([("a", 1u64)])).unwrap());
+
+        // Changing this hash requires bumping STABLE_HASH_SCHEMA_VERSION.
+        assert_eq!(
+            hex::encode(stable_hash(&(1u64, "sector", vec![true, false])).unwrap()),
+            "ba642b5ded0c6176c8272316f7ae50a3afe3785a5cff9edeebe287ea2bf88056"
+        );
+    }
+}
@@ -0,0 +1,481 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt::Display;
+
+use anyhow::{bail, Context};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::address::{Address, Payload};
+use fvm_shared4::{ActorID, HAMT_BIT_WIDTH};
+use ipld_core::ipld::Ipld;
+use serde::de::IgnoredAny;
+
+use crate::actor_type::ActorType;
+use crate::address_owner::decode_actor;
+use crate::bundle::{KnownCids, Manifest};
+use crate::v16::vm_api::ActorState;
+use crate::versioned::code_version;
+
+/// ID of the system actor, whose state links to the builtin actors manifest.
+pub const SYSTEM_ACTOR_ID: ActorID = 0;
+/// ID of the init actor, whose state maps robust addresses to IDs.
+pub const INIT_ACTOR_ID: ActorID = 1;
+
+const POWER_CLAIMS_FIELD: usize = 13;
+
+/// Singleton actors and their IDs. Actors absent from the manifest of the state
+/// being checked (e.g. the datacap actor before v9) are skipped.
+const SINGLETONS: &[(ActorType, u64)] = &[
+    (ActorType::System, SYSTEM_ACTOR_ID),
+    (ActorType::Init, INIT_ACTOR_ID),
+    (ActorType::Reward, 2),
+    (ActorType::Cron, 3),
+    (ActorType::Power, 4),
+    (ActorType::Market, 5),
+    (ActorType::VerifiedRegistry, 6),
+    (ActorType::DataCap, 7),
+    (ActorType::EAM, 10),
+];
+
+/// Outcome of [`quick_check`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuickCheckReport {
+    /// Version of the state tree, `None` if the state root could not be decoded.
+    pub state_root_version: Option<u64>,
+    /// Actors version of the singleton actor codes, `None` if none of them is known.
+    pub actors_version: Option<u64>,
+    /// Number of actors whose state was loaded.
+    pub actors_checked: usize,
+    /// Problems found, empty if the check passed.
+    pub failures: Vec<String>,
+}
+
+impl QuickCheckReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    fn fail(&mut self, failure: impl Display) {
+        self.failures.push(failure.to_string());
+    }
+}
+
+/// Code and state CIDs of an actor. These are the first fields of the actor entries of every
+/// state tree version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActorHead {
+    pub code: Cid,
+    pub state: Cid,
+}
+
+impl ActorHead {
+    pub fn decode(actor: &Ipld) -> anyhow::Result<Self> {
+        match actor {
+            Ipld::List(fields) => match fields.as_slice() {
+                [Ipld::Link(code), Ipld::Link(state), ..] => Ok(Self {
+                    code: *code,
+                    state: *state,
+                }),
+                _ => bail!("malformed actor entry"),
+            },
+            _ => bail!("actor entry is not a list"),
+        }
+    }
+}
+
+/// A state tree with its manifest, used to look up actors and classify them by type.
+pub struct StateTree<'bs, BS> {
+    store: &'bs BS,
+    pub(crate) actors_root: Cid,
+    actors: Hamt<&'bs BS, Ipld, BytesKey>,
+    manifest: Vec<(String, Cid)>,
+}
+
+impl<'bs, BS: Blockstore> StateTree<'bs, BS> {
+    pub fn load(store: &'bs BS, root: &Cid) -> anyhow::Result<Self> {
+        let (_, actors_root) = load_state_root(store, root)?;
+        let actors = Hamt::load_with_bit_width(&actors_root, store, HAMT_BIT_WIDTH)
+            .context("failed to load actors HAMT")?;
+        let system = actors
+            .get(&BytesKey(Address::new_id(SYSTEM_ACTOR_ID).to_bytes()))?
+            .context("system actor not found")?;
+        let manifest = load_manifest(store, &ActorHead::decode(system)?.state)?;
+        Ok(Self {
+            store,
+            actors_root,
+            actors,
+            manifest,
+        })
+    }
+
+    /// Returns the type of the actor with the given code, or `None` if the code is not in the
+    /// manifest of the state tree.
+    pub fn actor_type(&self, code: &Cid) -> Option<ActorType> {
+        self.manifest
+            .iter()
+            .find(|(_, manifest_code)| manifest_code == code)
+            .and_then(|(name, _)| ActorType::from_manifest_name(name))
+    }
+
+    /// Returns whether the actor is a storage miner.
+    pub fn is_miner(&self, actor: &ActorHead) -> bool {
+        self.actor_type(&actor.code) == Some(ActorType::Miner)
+    }
+
+    /// Returns the actor with the given ID, or `None` if there is none.
+    pub fn actor(&self, id: ActorID) -> anyhow::Result<Option<ActorHead>> {
+        self.actors
+            .get(&BytesKey(Address::new_id(id).to_bytes()))?
+            .map(ActorHead::decode)
+            .transpose()
+    }
+
+    /// Returns the whole entry of the actor with the given ID, or `None` if there is none.
+    /// Entries of state trees older than v5 have no delegated address.
+    pub fn actor_state(&self, id: ActorID) -> anyhow::Result<Option<ActorState>> {
+        self.actors
+            .get(&BytesKey(Address::new_id(id).to_bytes()))?
+            .map(decode_actor)
+            .transpose()
+            .with_context(|| format!("invalid actor f0{id}"))
+    }
+
+    /// Resolves an address to an actor ID, through the address map of the init actor for
+    /// addresses other than ID addresses. Returns `None` if the address is not assigned.
+    pub fn lookup_id(&self, address: &Address) -> anyhow::Result<Option<ActorID>> {
+        if let Payload::ID(id) = address.payload() {
+            return Ok(Some(*id));
+        }
+        let init = self.actor(INIT_ACTOR_ID)?.context("init actor not found")?;
+        let (address_map, ..): (Cid, IgnoredAny, IgnoredAny) = self
+            .store
+            .get_cbor(&init.state)
+            .context("failed to decode init actor state")?
+            .context("init actor state not found")?;
+        let address_map = Hamt::<_, ActorID, BytesKey>::load_with_bit_width(
+            &address_map,
+            self.store,
+            HAMT_BIT_WIDTH,
+        )
+        .context("failed to load init actor address map")?;
+        Ok(address_map.get(&BytesKey(address.to_bytes()))?.copied())
+    }
+}
+
+/// Performs a cheap integrity check of the state tree with the given root, meant to gate chain
+/// snapshots before running the full invariant checks. The check verifies that:
+/// - the state root decodes and the actors HAMT loads,
+/// - the system actor state resolves to the builtin actors manifest,
+/// - every singleton actor in the manifest exists with the manifest code, which must be a known
+///   code of its type and of the same actors version as the other singletons, and a decodable
+///   state,
+/// - the states of up to `sample_size` miner actors decode. Miners are taken from the claims of
+///   the power actor, so the cost of the check does not grow with the number of actors.
+///
+/// Actor states are only checked to be well-formed DAG-CBOR, since their layout depends on the
+/// actors version. Typed decoding is left to the actor crates.
+pub fn quick_check<BS: Blockstore>(
+    store: &BS,
+    state_root: &Cid,
+    known: &KnownCids,
+    sample_size: usize,
+) -> QuickCheckReport {
+    let mut report = QuickCheckReport::default();
+    if let Err(e) = check(store, state_root, known, sample_size, &mut report) {
+        report.fail(format!("{e:#}"));
+    }
+    report
+}
+
+fn check<BS: Blockstore>(
+    store: &BS,
+    state_root: &Cid,
+    known: &KnownCids,
+    sample_size: usize,
+    report: &mut QuickCheckReport,
+) -> anyhow::Result<()> {
+    let (version, actors_root) = load_state_root(store, state_root)?;
+    report.state_root_version = Some(version);
+
+    let actors =
+        Hamt::<_, Ipld, BytesKey>::load_with_bit_width(&actors_root, store, HAMT_BIT_WIDTH)
+            .context("failed to load actors HAMT")?;
+    let get_actor = |id: u64| -> anyhow::Result<ActorHead> {
+        let actor = actors
+            .get(&BytesKey(Address::new_id(id).to_bytes()))?
+            .with_context(|| format!("actor f0{id} not found"))?;
+        ActorHead::decode(actor).with_context(|| format!("invalid actor f0{id}"))
+    };
+
+    let system = get_actor(SYSTEM_ACTOR_ID)?;
+    let manifest = load_manifest(store, &system.state)?;
+    let code_of = |actor_type: ActorType| {
+        manifest
+            .iter()
+            .find_map(|(name, code)| (name == actor_type.manifest_name()).then_some(*code))
+    };
+
+    let mut power = None;
+    for &(actor_type, id) in SINGLETONS {
+        let name = actor_type.manifest_name();
+        let Some(expected_code) = code_of(actor_type) else {
+            continue;
+        };
+        let actor = match get_actor(id) {
+            Ok(actor) if actor.code != expected_code => {
+                report.fail(format!(
+                    "{name} actor f0{id} has code {}, expected {expected_code}",
+                    actor.code
+                ));
+                continue;
+            }
+            Ok(actor) => actor,
+            Err(e) => {
+                report.fail(format!("{name} actor: {e:#}"));
+                continue;
+            }
+        };
+        match code_version(known, &actor.code, actor_type) {
+            Ok(version) => match report.actors_version {
+                Some(expected) if version != expected => report.fail(format!(
+                    "{name} actor f0{id} has the code of actors v{version}, expected \
+                     v{expected}"
+                )),
+                _ => report.actors_version = Some(version),
+            },
+            Err(e) => report.fail(format!("{name} actor f0{id}: {e:#}")),
+        }
+        check_state(store, name, id, &actor.state, report);
+        if actor_type == ActorType::Power {
+            power = Some(actor);
+        }
+    }
+
+    let Some(miner_code) = code_of(ActorType::Miner) else {
+        report.fail("storage miner missing from the manifest");
+        return Ok(());
+    };
+    let Some(power) = power else {
+        report.fail("cannot sample miners without a valid power actor");
+        return Ok(());
+    };
+    let claims = load_power_claims(store, &power.state)?;
+    for entry in claims.iter().take(sample_size) {
+        let (key, _) = entry.context("failed to iterate power claims")?;
+        let Some(id) = Address::from_bytes(key).ok().and_then(|addr| addr.id().ok()) else {
+            report.fail("power claim of a non-ID address");
+            continue;
+        };
+        match get_actor(id) {
+            Ok(actor) if actor.code != miner_code => report.fail(format!(
+                "power claim of actor f0{id} with code {}, expected {miner_code}",
+                actor.code
+            )),
+            Ok(actor) => check_state(
+                store,
+                ActorType::Miner.manifest_name(),
+                id,
+                &actor.state,
+                report,
+            ),
+            Err(e) => report.fail(format!("power claim of a missing miner: {e:#}")),
+        }
+    }
+    Ok(())
+}
+
+/// Loads the claims HAMT of a power actor state, keyed by miner address. Its position in the
+/// state has not moved since v8.
+fn load_power_claims<'bs, BS: Blockstore>(
+    store: &'bs BS,
+    power_state: &Cid,
+) -> anyhow::Result<Hamt<&'bs BS, Ipld, BytesKey>> {
+    let state: Ipld = store
+        .get_cbor(power_state)
+        .context("failed to decode power actor state")?
+        .context("power actor state not found")?;
+    let claims = match &state {
+        Ipld::List(fields) => match fields.get(POWER_CLAIMS_FIELD) {
+            Some(Ipld::Link(claims)) => *claims,
+            _ => bail!("power actor state has no claims"),
+        },
+        _ => bail!("power actor state is not a list"),
+    };
+    Hamt::load_with_bit_width(&claims, store, HAMT_BIT_WIDTH)
+        .context("failed to load power claims")
+}
+
+/// Loads a state root, returning the state tree version and the root of the actors HAMT.
+pub(crate) fn load_state_root<BS: Blockstore>(
+    store: &BS,
+    state_root: &Cid,
+) -> anyhow::Result<(u64, Cid)> {
+    let (version, actors_root, _info): (u64, Cid, Cid) = store
+        .get_cbor(state_root)
+        .context("failed to decode state root")?
+        .with_context(|| format!("state root {state_root} not found"))?;
+    Ok((version, actors_root))
+}
+
+/// Loads the `(actor name, code CID)` pairs of the builtin actors manifest referenced by the
+/// state of the system actor.
+pub(crate) fn load_manifest<BS: Blockstore>(
+    store: &BS,
+    system_state: &Cid,
+) -> anyhow::Result<Vec<(String, Cid)>> {
+    let (manifest_data,): (Cid,) = store
+        .get_cbor(system_state)
+        .context("failed to decode system actor state")?
+        .context("system actor state not found")?;
+    Ok(Manifest::load_data(store, &manifest_data)
+        .context("failed to load builtin actors manifest")?
+        .actors)
+}
+
+fn check_state<BS: Blockstore>(
+    store: &BS,
+    name: &str,
+    id: u64,
+    state: &Cid,
+    report: &mut QuickCheckReport,
+) {
+    match store.get_cbor::<IgnoredAny>(state) {
+        Ok(Some(_)) => report.actors_checked += 1,
+        Ok(None) => report.fail(format!("{name} actor f0{id} state {state} not found")),
+        Err(e) => report.fail(format!("{name} actor f0{id} state does not decode: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::{DAG_CBOR, IPLD_RAW};
+    use multihash_codetable::{Code, MultihashDigest};
+
+    const NAMES: [&str; 4] = ["system", "init", "storagepower", "storageminer"];
+
+    fn code(name: &str) -> Cid {
+        Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(name.as_bytes()))
+    }
+
+    fn known() -> KnownCids {
+        let mut known = KnownCids::default();
+        for name in NAMES {
+            let actor_type = ActorType::from_manifest_name(name).unwrap();
+            known.insert("testnet", 16, actor_type, code(name));
+        }
+        known
+    }
+
+    fn actor(code: Cid, state: Cid) -> Ipld {
+        Ipld::List(vec![
+            Ipld::Link(code),
+            Ipld::Link(state),
+            Ipld::Integer(0),
+            Ipld::Bytes(vec![]),
+            Ipld::Null,
+        ])
+    }
+
+    fn state_tree(store: &MemoryBlockstore, miner_states: &[Cid]) -> Cid {
+        let manifest: Vec<(String, Cid)> = NAMES.iter().map(|n| (n.to_string(), code(n))).collect();
+        let manifest = store.put_cbor(&manifest, Code::Blake2b256).unwrap();
+        let system_state = store.put_cbor(&(manifest,), Code::Blake2b256).unwrap();
+        let init_state = store
+            .put_cbor(&("init", 1000u64), Code::Blake2b256)
+            .unwrap();
+
+        let mut claims = Hamt::<_, Ipld, BytesKey>::new_with_bit_width(store, HAMT_BIT_WIDTH);
+        let mut actors = Hamt::<_, Ipld, BytesKey>::new_with_bit_width(store, HAMT_BIT_WIDTH);
+        for (id, miner_state) in (1000..).zip(miner_states) {
+            let key = BytesKey(Address::new_id(id).to_bytes());
+            claims.set(key.clone(), Ipld::Null).unwrap();
+            actors
+                .set(key, actor(code("storageminer"), *miner_state))
+                .unwrap();
+        }
+        let mut power_state = vec![Ipld::Integer(0); POWER_CLAIMS_FIELD];
+        power_state.push(Ipld::Link(claims.flush().unwrap()));
+        let power_state = store
+            .put_cbor(&Ipld::List(power_state), Code::Blake2b256)
+            .unwrap();
+
+        for (id, actor) in [
+            (0, actor(code("system"), system_state)),
+            (1, actor(code("init"), init_state)),
+            (4, actor(code("storagepower"), power_state)),
+        ] {
+            actors
+                .set(BytesKey(Address::new_id(id).to_bytes()), actor)
+                .unwrap();
+        }
+        let actors = actors.flush().unwrap();
+        let info = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        store
+            .put_cbor(&(5u64, actors, info), Code::Blake2b256)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_quick_check() {
+        let store = MemoryBlockstore::new();
+        let known = known();
+        let miner_state = store.put_cbor(&(1u64, 2u64), Code::Blake2b256).unwrap();
+        let root = state_tree(&store, &[miner_state, miner_state]);
+
+        let report = quick_check(&store, &root, &known, 10);
+        assert!(report.passed(), "{:?}", report.failures);
+        assert_eq!(report.state_root_version, Some(5));
+        assert_eq!(report.actors_version, Some(16));
+        assert_eq!(report.actors_checked, 5);
+
+        // Only `sample_size` miners are checked.
+        let report = quick_check(&store, &root, &known, 1);
+        assert!(report.passed(), "{:?}", report.failures);
+        assert_eq!(report.actors_checked, 4);
+
+        // A miner state missing from the store is reported.
+        let missing = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(b"missing"));
+        let root = state_tree(&store, &[missing]);
+        let report = quick_check(&store, &root, &known, 10);
+        assert_eq!(report.failures.len(), 1);
+
+        let report = quick_check(&store, &missing, &known, 10);
+        assert_eq!(report.state_root_version, None);
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn test_quick_check_codes() {
+        let store = MemoryBlockstore::new();
+        let miner_state = store.put_cbor(&(1u64, 2u64), Code::Blake2b256).unwrap();
+        let root = state_tree(&store, &[miner_state]);
+
+        // An unknown singleton code is reported.
+        let report = quick_check(&store, &root, &KnownCids::default(), 10);
+        assert_eq!(report.failures.len(), 3, "{:?}", report.failures);
+        assert_eq!(report.actors_version, None);
+
+        // So is a singleton code of another actors version.
+        let mut known = known();
+        known.insert("testnet", 15, ActorType::Init, code("init"));
+        known.insert("testnet", 16, ActorType::Init, code("init v16"));
+        let report = quick_check(&store, &root, &known, 10);
+        assert_eq!(
+            report.failures,
+            ["init actor f01 has the code of actors v15, expected v16"]
+        );
+
+        // And a code known as another actor type.
+        let mut known = KnownCids::default();
+        for (version, name) in (1..).zip(NAMES) {
+            known.insert("testnet", version, ActorType::Cron, code(name));
+        }
+        let report = quick_check(&store, &root, &known, 10);
+        assert_eq!(report.failures.len(), 3, "{:?}", report.failures);
+    }
+}
@@ -0,0 +1,369 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared4::address::Address;
+use fvm_shared4::ActorID;
+use ipld_core::ipld::Ipld;
+
+use crate::actor_type::ActorType;
+use crate::diff::{diff_amt_opt, diff_hamt_opt, Change};
+use crate::ids::{AllocationID, ClaimID, DealID, SectorNumber};
+use crate::multimap::parse_uint_key;
+
+// Positions of the collections in the actor states. Unless noted, they have not moved since v8.
+pub(crate) const MINER_PRECOMMITS_FIELD: usize = 6;
+pub(crate) const MINER_SECTORS_FIELD: usize = 9;
+pub(crate) const MARKET_PROPOSALS_FIELD: usize = 0;
+pub(crate) const MARKET_STATES_FIELD: usize = 1;
+/// Moved in v15, when the ramp fields of FIP-0081 were inserted before it.
+const POWER_CLAIMS_FIELD: usize = 13;
+const POWER_CLAIMS_FIELD_V15: usize = 15;
+/// Allocations and claims were introduced in v9.
+const VERIFREG_ALLOCATIONS_FIELD: usize = 3;
+pub(crate) const VERIFREG_CLAIMS_FIELD: usize = 5;
+
+/// Keys of the entries added, removed and modified in a collection of an actor state, each in
+/// key order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyChanges<K> {
+    pub added: Vec<K>,
+    pub removed: Vec<K>,
+    pub modified: Vec<K>,
+}
+
+impl<K> Default for KeyChanges<K> {
+    fn default() -> Self {
+        Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            modified: Vec::new(),
+        }
+    }
+}
+
+impl<K: Ord> KeyChanges<K> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    fn collect<J, V>(
+        changes: Vec<Change<J, V>>,
+        mut key: impl FnMut(J) -> anyhow::Result<K>,
+    ) -> anyhow::Result<Self> {
+        let mut keys = Self::default();
+        for change in changes {
+            match change {
+                Change::Added(k, _) => keys.added.push(key(k)?),
+                Change::Removed(k, _) => keys.removed.push(key(k)?),
+                Change::Modified(k, _, _) => keys.modified.push(key(k)?),
+            }
+        }
+        keys.added.sort();
+        keys.removed.sort();
+        keys.modified.sort();
+        Ok(keys)
+    }
+}
+
+/// Changes to the collections of an actor state between two versions of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActorStateDiff {
+    Miner {
+        sectors: KeyChanges<SectorNumber>,
+        precommits: KeyChanges<SectorNumber>,
+    },
+    Market {
+        proposals: KeyChanges<DealID>,
+        states: KeyChanges<DealID>,
+    },
+    Power {
+        /// Miners whose claim was added, removed or changed.
+        claims: KeyChanges<Address>,
+    },
+    VerifiedRegistry {
+        /// `(client, allocation ID)` of the allocations.
+        allocations: KeyChanges<(ActorID, AllocationID)>,
+        /// `(provider, claim ID)` of the claims.
+        claims: KeyChanges<(ActorID, ClaimID)>,
+    },
+    /// The state of an actor whose collections are not diffed, which is only known to have
+    /// changed if the state CIDs differ.
+    Other,
+}
+
+/// Computes the changes to the collections of an actor state, e.g. the sectors added to a miner or
+/// the deals of the market. The actor type of a code CID is given by
+/// [`crate::bundle::Manifest::actor_type`].
+///
+/// The states are read by the positions of their fields, so the old and new states may be of
+/// different actors versions, as is the case across network upgrades. Collections rewritten by a
+/// migration are then reported as changed in full. Like [`crate::diff::diff_hamt`], unchanged
+/// sub-trees are skipped, so the cost is proportional to the size of the change.
+pub fn diff_actor_state<BS: Blockstore>(
+    store: &BS,
+    actor: ActorType,
+    old_version: u64,
+    old_state: &Cid,
+    new_version: u64,
+    new_state: &Cid,
+) -> anyhow::Result<ActorStateDiff> {
+    let old = Some(old_state);
+    let new = Some(new_state);
+    let fields = |field: usize| -> anyhow::Result<_> {
+        Ok((
+            state_field(store, old, field)?,
+            state_field(store, new, field)?,
+        ))
+    };
+    Ok(match actor {
+        ActorType::Miner => {
+            let (old_sectors, new_sectors) = fields(MINER_SECTORS_FIELD)?;
+            let (old_precommits, new_precommits) = fields(MINER_PRECOMMITS_FIELD)?;
+            ActorStateDiff::Miner {
+                sectors: amt_changes(store, old_sectors, new_sectors)?,
+                precommits: KeyChanges::collect(
+                    hamt_changes(store, old_precommits, new_precommits)?,
+                    |key| Ok(SectorNumber(parse_uint_key(&key)?)),
+                )?,
+            }
+        }
+        ActorType::Market => {
+            let (old_proposals, new_proposals) = fields(MARKET_PROPOSALS_FIELD)?;
+            let (old_states, new_states) = fields(MARKET_STATES_FIELD)?;
+            ActorStateDiff::Market {
+                proposals: amt_changes(store, old_proposals, new_proposals)?,
+                states: amt_changes(store, old_states, new_states)?,
+            }
+        }
+        ActorType::Power => {
+            let claims_field = |version| {
+                if version >= 15 {
+                    POWER_CLAIMS_FIELD_V15
+                } else {
+                    POWER_CLAIMS_FIELD
+                }
+            };
+            let old_claims = state_field(store, old, claims_field(old_version))?;
+            let new_claims = state_field(store, new, claims_field(new_version))?;
+            ActorStateDiff::Power {
+                claims: KeyChanges::collect(hamt_changes(store, old_claims, new_claims)?, |key| {
+                    Ok(Address::from_bytes(&key)?)
+                })?,
+            }
+        }
+        ActorType::VerifiedRegistry => {
+            // v8 states have neither allocations nor claims.
+            let field = |state, version, field| match version {
+                8 => Ok(None),
+                _ => state_field(store, state, field),
+            };
+            let old_allocations = field(old, old_version, VERIFREG_ALLOCATIONS_FIELD)?;
+            let new_allocations = field(new, new_version, VERIFREG_ALLOCATIONS_FIELD)?;
+            let old_claims = field(old, old_version, VERIFREG_CLAIMS_FIELD)?;
+            let new_claims = field(new, new_version, VERIFREG_CLAIMS_FIELD)?;
+            ActorStateDiff::VerifiedRegistry {
+                allocations: nested_changes(store, old_allocations, new_allocations)?,
+                claims: nested_changes(store, old_claims, new_claims)?,
+            }
+        }
+        _ => ActorStateDiff::Other,
+    })
+}
+
+/// Loads an actor state and returns the link at the given field, if the state has it.
+pub(crate) fn state_field<BS: Blockstore>(
+    store: &BS,
+    state: Option<&Cid>,
+    field: usize,
+) -> anyhow::Result<Option<Cid>> {
+    let Some(state) = state else {
+        return Ok(None);
+    };
+    let fields: Ipld = store
+        .get_cbor(state)?
+        .with_context(|| format!("actor state {state} not found"))?;
+    match fields {
+        Ipld::List(fields) => match fields.get(field) {
+            None => Ok(None),
+            Some(Ipld::Link(cid)) => Ok(Some(*cid)),
+            Some(_) => Err(anyhow!("state field {field} is not a link")),
+        },
+        _ => Err(anyhow!("actor state is not a list")),
+    }
+}
+
+/// Computes the changes between two maps of maps keyed by integers at both levels, such as the
+/// verified registry allocations and claims, as `(outer key, inner key)` changes.
+pub(crate) fn diff_nested_uint_hamt<BS: Blockstore>(
+    store: &BS,
+    old: Option<&Cid>,
+    new: Option<&Cid>,
+) -> anyhow::Result<Vec<Change<(u64, u64), Ipld>>> {
+    let mut outer_changes = Vec::new();
+    diff_hamt_opt(store, old, new, &mut outer_changes)?;
+
+    let link = |ipld: &Ipld| match ipld {
+        Ipld::Link(cid) => Ok(*cid),
+        _ => Err(anyhow!("nested map entry is not a link")),
+    };
+    let mut changes = Vec::new();
+    for change in outer_changes {
+        let outer = parse_uint_key(change.key())?;
+        let (old_inner, new_inner) = match &change {
+            Change::Added(_, new) => (None, Some(link(new)?)),
+            Change::Removed(_, old) => (Some(link(old)?), None),
+            Change::Modified(_, old, new) => (Some(link(old)?), Some(link(new)?)),
+        };
+        let mut inner_changes = Vec::new();
+        diff_hamt_opt(
+            store,
+            old_inner.as_ref(),
+            new_inner.as_ref(),
+            &mut inner_changes,
+        )?;
+        for change in inner_changes {
+            let key = |inner: &[u8]| anyhow::Ok((outer, parse_uint_key(inner)?));
+            changes.push(match change {
+                Change::Added(k, v) => Change::Added(key(&k)?, v),
+                Change::Removed(k, v) => Change::Removed(key(&k)?, v),
+                Change::Modified(k, old, new) => Change::Modified(key(&k)?, old, new),
+            });
+        }
+    }
+    Ok(changes)
+}
+
+fn amt_changes<BS, K>(
+    store: &BS,
+    old: Option<Cid>,
+    new: Option<Cid>,
+) -> anyhow::Result<KeyChanges<K>>
+where
+    BS: Blockstore,
+    K: From<u64> + Ord,
+{
+    let mut changes = Vec::new();
+    diff_amt_opt(store, old.as_ref(), new.as_ref(), &mut changes)?;
+    KeyChanges::collect(changes, |index| Ok(index.into()))
+}
+
+fn nested_changes<BS, K>(
+    store: &BS,
+    old: Option<Cid>,
+    new: Option<Cid>,
+) -> anyhow::Result<KeyChanges<(ActorID, K)>>
+where
+    BS: Blockstore,
+    K: From<u64> + Ord,
+{
+    KeyChanges::collect(
+        diff_nested_uint_hamt(store, old.as_ref(), new.as_ref())?,
+        |(outer, inner)| Ok((outer, inner.into())),
+    )
+}
+
+fn hamt_changes<BS: Blockstore>(
+    store: &BS,
+    old: Option<Cid>,
+    new: Option<Cid>,
+) -> anyhow::Result<Vec<Change<Vec<u8>, Ipld>>> {
+    let mut changes = Vec::new();
+    diff_hamt_opt(store, old.as_ref(), new.as_ref(), &mut changes)?;
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_amt::Amt;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_hamt::{BytesKey, Hamt};
+    use fvm_shared4::HAMT_BIT_WIDTH;
+    use multihash_codetable::Code;
+
+    /// Builds an actor state with the given collections, and nulls for the other fields.
+    fn state(store: &MemoryBlockstore, collections: &[(usize, Cid)]) -> Cid {
+        let len = collections
+            .iter()
+            .map(|(field, _)| field + 1)
+            .max()
+            .unwrap_or(0);
+        let mut fields = vec![Ipld::Null; len];
+        for (field, collection) in collections {
+            fields[*field] = Ipld::Link(*collection);
+        }
+        store
+            .put_cbor(&Ipld::List(fields), Code::Blake2b256)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_diff_actor_state() {
+        let store = MemoryBlockstore::new();
+        let amt = |entries: &[(u64, u64)]| {
+            let mut amt = Amt::<u64, _>::new_with_bit_width(&store, 5);
+            for (k, v) in entries {
+                amt.set(*k, *v).unwrap();
+            }
+            amt.flush().unwrap()
+        };
+        let precommits = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH)
+            .flush()
+            .unwrap();
+        let miner = |sectors| {
+            state(
+                &store,
+                &[
+                    (MINER_PRECOMMITS_FIELD, precommits),
+                    (MINER_SECTORS_FIELD, sectors),
+                ],
+            )
+        };
+        let old = miner(amt(&[(1, 1), (2, 2)]));
+        let new = miner(amt(&[(2, 20), (3, 3)]));
+        let ActorStateDiff::Miner {
+            sectors,
+            precommits,
+        } = diff_actor_state(&store, ActorType::Miner, 16, &old, 16, &new).unwrap()
+        else {
+            panic!("not a miner diff");
+        };
+        assert_eq!(sectors.added, [SectorNumber(3)]);
+        assert_eq!(sectors.removed, [SectorNumber(1)]);
+        assert_eq!(sectors.modified, [SectorNumber(2)]);
+        assert!(precommits.is_empty());
+
+        // The power claims moved in v15.
+        let claims = |entries: &[(u64, u64)]| {
+            let mut hamt = Hamt::<_, u64, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+            for (id, power) in entries {
+                hamt.set(BytesKey(Address::new_id(*id).to_bytes()), *power)
+                    .unwrap();
+            }
+            hamt.flush().unwrap()
+        };
+        let old = state(&store, &[(POWER_CLAIMS_FIELD, claims(&[(1000, 1)]))]);
+        let new = state(
+            &store,
+            &[(POWER_CLAIMS_FIELD_V15, claims(&[(1000, 1), (1001, 1)]))],
+        );
+        assert_eq!(
+            diff_actor_state(&store, ActorType::Power, 14, &old, 15, &new).unwrap(),
+            ActorStateDiff::Power {
+                claims: KeyChanges {
+                    added: vec![Address::new_id(1001)],
+                    ..Default::default()
+                }
+            }
+        );
+
+        assert_eq!(
+            diff_actor_state(&store, ActorType::Account, 16, &old, 16, &new).unwrap(),
+            ActorStateDiff::Other
+        );
+    }
+}
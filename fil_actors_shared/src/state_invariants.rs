@@ -0,0 +1,191 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared4::address::Address;
+use fvm_shared4::HAMT_BIT_WIDTH;
+use ipld_core::ipld::Ipld;
+
+use crate::actor_type::ActorType;
+use crate::address_owner::decode_actor;
+use crate::state_check::{load_manifest, load_state_root, ActorHead, SYSTEM_ACTOR_ID};
+use crate::v16::vm_api::ActorState;
+
+/// A violated invariant of an actor of the state tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActorViolation {
+    /// ID address of the actor.
+    pub address: Address,
+    /// Type of the actor, `None` if its code is not in the manifest of the state tree.
+    pub actor_type: Option<ActorType>,
+    pub message: String,
+}
+
+/// Outcome of [`check_state_tree_invariants`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvariantReport {
+    /// Number of actors whose state was checked.
+    pub actors_checked: usize,
+    /// Violations found, empty if the check passed.
+    pub violations: Vec<ActorViolation>,
+}
+
+impl InvariantReport {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Checks the invariants of every actor of the state tree with the given root.
+///
+/// The tree itself only tells apart the actors whose code is not in its manifest and the actors
+/// with a negative balance. The invariants of actor states depend on the actors version, and are
+/// checked by `check_actor`, which is called with the type and entry of every other actor. The
+/// actor crates provide the checks, e.g. the `check_state_invariants` of the miner
+/// `VersionedState`, which the caller loads with the actors version and policy of the tree:
+///
+/// ```ignore
+/// check_state_tree_invariants(store, &root, |actor_type, actor| match actor_type {
+///     ActorType::Miner => VersionedState::load(store, version, &actor.state)?
+///         .check_state_invariants(store, &policy, &actor.balance),
+///     _ => Ok(vec![]),
+/// })
+/// ```
+///
+/// Errors of `check_actor` are reported as violations of the actor, so that a state that fails
+/// to load does not stop the walk. Fails if the tree itself cannot be loaded.
+pub fn check_state_tree_invariants<BS, F>(
+    store: &BS,
+    state_root: &Cid,
+    mut check_actor: F,
+) -> anyhow::Result<InvariantReport>
+where
+    BS: Blockstore,
+    F: FnMut(ActorType, &ActorState) -> anyhow::Result<Vec<String>>,
+{
+    let (_, actors_root) = load_state_root(store, state_root)?;
+    let actors =
+        Hamt::<_, Ipld, BytesKey>::load_with_bit_width(&actors_root, store, HAMT_BIT_WIDTH)
+            .context("failed to load actors HAMT")?;
+    let system = actors
+        .get(&BytesKey(Address::new_id(SYSTEM_ACTOR_ID).to_bytes()))?
+        .context("system actor not found")?;
+    let manifest = load_manifest(store, &ActorHead::decode(system)?.state)?;
+    let actor_type = |code: &Cid| {
+        manifest
+            .iter()
+            .find(|(_, cid)| cid == code)
+            .and_then(|(name, _)| ActorType::from_manifest_name(name))
+    };
+
+    let mut report = InvariantReport::default();
+    for entry in actors.iter() {
+        let (key, actor) = entry.context("failed to iterate actors HAMT")?;
+        let address = Address::from_bytes(key).context("invalid actor address")?;
+        let actor = decode_actor(actor).with_context(|| format!("invalid actor {address}"))?;
+        let actor_type = actor_type(&actor.code);
+        let mut violation = |message: String| {
+            report.violations.push(ActorViolation {
+                address,
+                actor_type,
+                message,
+            })
+        };
+
+        if actor.balance.is_negative() {
+            violation(format!("negative balance {}", actor.balance));
+        }
+        let Some(actor_type) = actor_type else {
+            violation(format!("code {} is not in the manifest", actor.code));
+            continue;
+        };
+        match check_actor(actor_type, &actor) {
+            Ok(messages) => messages.into_iter().for_each(violation),
+            Err(e) => violation(format!("failed to check state: {e:#}")),
+        }
+        report.actors_checked += 1;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::{CborStore, IPLD_RAW};
+    use fvm_shared4::econ::TokenAmount;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    fn code(name: &str) -> Cid {
+        Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(name.as_bytes()))
+    }
+
+    #[test]
+    fn test_check_state_tree_invariants() {
+        let store = MemoryBlockstore::new();
+        let manifest: Vec<(String, Cid)> = ["system", "account", "storageminer"]
+            .iter()
+            .map(|n| (n.to_string(), code(n)))
+            .collect();
+        let manifest = store.put_cbor(&manifest, Code::Blake2b256).unwrap();
+        let system_state = store.put_cbor(&(manifest,), Code::Blake2b256).unwrap();
+
+        let empty = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        let mut actors =
+            Hamt::<_, ActorState, BytesKey>::new_with_bit_width(&store, HAMT_BIT_WIDTH);
+        for (id, name, state, balance) in [
+            (0, "system", system_state, 0),
+            (100, "account", empty, -1),
+            (101, "storageminer", empty, 5),
+            (102, "storageminer", system_state, 5),
+            (103, "unknown", empty, 0),
+        ] {
+            let actor = ActorState {
+                code: code(name),
+                state,
+                sequence: 0,
+                balance: TokenAmount::from_atto(balance),
+                delegated_address: None,
+            };
+            actors
+                .set(BytesKey(Address::new_id(id).to_bytes()), actor)
+                .unwrap();
+        }
+        let actors = actors.flush().unwrap();
+        let root = store
+            .put_cbor(&(5u64, actors, empty), Code::Blake2b256)
+            .unwrap();
+
+        let report =
+            check_state_tree_invariants(&store, &root, |actor_type, actor| match actor_type {
+                ActorType::Miner if actor.state != empty => bail!("bad state"),
+                ActorType::Miner => Ok(vec!["miner violation".to_string()]),
+                _ => Ok(vec![]),
+            })
+            .unwrap();
+        assert_eq!(report.actors_checked, 4);
+        let mut violations: Vec<_> = report
+            .violations
+            .iter()
+            .map(|v| (v.address.id().unwrap(), v.message.as_str()))
+            .collect();
+        violations.sort();
+        assert_eq!(
+            violations,
+            [
+                (100, "negative balance -0.000000000000000001"),
+                (101, "miner violation"),
+                (102, "failed to check state: bad state"),
+                (
+                    103,
+                    &*format!("code {} is not in the manifest", code("unknown"))
+                ),
+            ]
+        );
+        assert!(!report.passed());
+    }
+}
@@ -15,7 +15,10 @@ use unsigned_varint::decode::Error as UVarintError;
 pub use self::actor_error::*;
 pub use self::builtin::*;
 pub use self::util::*;
-pub use {fvm_ipld_amt, fvm_ipld_hamt};
+#[cfg(feature = "reexport-amt")]
+pub use fvm_ipld_amt;
+#[cfg(feature = "reexport-hamt")]
+pub use fvm_ipld_hamt;
 
 pub mod actor_error;
 pub mod builtin;
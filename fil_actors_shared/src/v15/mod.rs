@@ -5,15 +5,18 @@ pub use self::actor_error::*;
 pub use self::builtin::*;
 pub use self::util::*;
 use cid::Cid;
+#[cfg(feature = "reexport-amt")]
+pub use fvm_ipld_amt;
 use fvm_ipld_amt::Amt;
 use fvm_ipld_blockstore::Blockstore;
+#[cfg(feature = "reexport-hamt")]
+pub use fvm_ipld_hamt;
 use fvm_ipld_hamt::Sha256;
 use fvm_ipld_hamt::{BytesKey, Error as HamtError, Hamt};
 use fvm_shared4::bigint::BigInt;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use unsigned_varint::decode::Error as UVarintError;
-pub use {fvm_ipld_amt, fvm_ipld_hamt};
 
 pub mod actor_error;
 pub mod builtin;
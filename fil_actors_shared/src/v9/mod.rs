@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use cid::Cid;
+#[cfg(feature = "reexport-amt")]
+pub use fvm_ipld_amt;
 use fvm_ipld_amt::Amt;
 use fvm_ipld_blockstore::Blockstore;
+#[cfg(feature = "reexport-hamt")]
+pub use fvm_ipld_hamt;
 use fvm_ipld_hamt::{BytesKey, Error as HamtError, Hamt};
 use fvm_shared::address::Address;
 use fvm_shared::bigint::BigInt;
@@ -11,7 +15,6 @@ pub use fvm_shared::BLOCKS_PER_EPOCH as EXPECTED_LEADERS_PER_EPOCH;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use unsigned_varint::decode::Error as UVarintError;
-pub use {fvm_ipld_amt, fvm_ipld_hamt};
 
 pub use self::actor_error::*;
 pub use self::builtin::*;
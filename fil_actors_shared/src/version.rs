@@ -0,0 +1,272 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use fvm_shared4::version::NetworkVersion;
+use serde::Serialize;
+
+use crate::actor_type::ActorType;
+
+/// Oldest builtin-actors version whose state definitions are shipped in this workspace.
+pub const MIN_ACTORS_VERSION: u64 = 8;
+
+/// Newest builtin-actors version whose state definitions are shipped in this workspace.
+pub const MAX_ACTORS_VERSION: u64 = 16;
+
+/// Returns the builtin-actors version deployed at the given network version, or `None` if that
+/// network version predates [`MIN_ACTORS_VERSION`] or is newer than this crate knows about.
+pub fn actors_version(network_version: NetworkVersion) -> Option<u64> {
+    let version = match u32::from(network_version) {
+        16 => 8,
+        17 => 9,
+        18 => 10,
+        19 | 20 => 11,
+        21 => 12,
+        22 => 13,
+        23 => 14,
+        24 => 15,
+        25 => 16,
+        _ => return None,
+    };
+    Some(version)
+}
+
+/// Returns the newest builtin-actors version supported by this crate, for embedding applications
+/// to check at runtime, see [`MAX_ACTORS_VERSION`].
+pub fn max_supported_actors_version() -> u64 {
+    MAX_ACTORS_VERSION
+}
+
+/// Returns the builtin-actors versions whose state definitions of the given actor are shipped in
+/// this workspace. Actors introduced after [`MIN_ACTORS_VERSION`] start at their first version.
+pub fn actor_versions(actor: ActorType) -> RangeInclusive<u64> {
+    let since = match actor {
+        ActorType::DataCap => 9,
+        ActorType::Placeholder | ActorType::EVM | ActorType::EAM | ActorType::EthAccount => 10,
+        _ => MIN_ACTORS_VERSION,
+    };
+    since..=MAX_ACTORS_VERSION
+}
+
+/// An accessor available on the states of an actor for a range of builtin-actors versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Capability {
+    pub actor: ActorType,
+    pub name: &'static str,
+    /// First version providing the accessor.
+    pub since: u64,
+    /// Last version providing the accessor, if it has been removed since.
+    pub until: Option<u64>,
+}
+
+impl Capability {
+    const fn new(actor: ActorType, name: &'static str, since: u64) -> Self {
+        Self {
+            actor,
+            name,
+            since,
+            until: None,
+        }
+    }
+
+    /// Returns whether the accessor is available on the states of the given version.
+    pub fn supports(&self, version: u64) -> bool {
+        version >= self.since
+            && version <= self.until.unwrap_or(MAX_ACTORS_VERSION)
+            && actor_versions(self.actor).contains(&version)
+    }
+
+    /// Like [`Capability::supports`], but returns the error to surface to the caller.
+    pub fn require(&self, version: u64) -> Result<(), UnsupportedVersion> {
+        if self.supports(version) {
+            return Ok(());
+        }
+        Err(UnsupportedVersion {
+            feature: self.name,
+            version,
+            since: self.since.max(*actor_versions(self.actor).start()),
+            until: self.until,
+        })
+    }
+}
+
+/// Capability matrix of the version-dependent accessors, so that applications can gate features
+/// before loading a state rather than failing deep inside a call. Accessors available on all the
+/// versions of an actor are listed with the first version of that actor.
+pub const CAPABILITIES: &[Capability] = &[
+    Capability::new(ActorType::Market, "provider_sectors", 13),
+    Capability::new(ActorType::Market, "provider_sector_deal_ids", 13),
+    Capability::new(ActorType::Market, "check_provider_sector_deals", 13),
+    Capability::new(ActorType::Market, "sector_pieces", 13),
+    Capability::new(ActorType::Market, "termination_impact", 13),
+    Capability::new(ActorType::Market, "verify_sector_commd", 10),
+    Capability::new(ActorType::Miner, "pre_commit_deposit_breakdown", 8),
+    Capability::new(ActorType::Miner, "total_initial_pledge_breakdown", 8),
+    Capability::new(ActorType::Miner, "simulate_replace_sectors", 8),
+    Capability::new(ActorType::Miner, "post_submission_windows", 8),
+    Capability::new(ActorType::Miner, "active_beneficiary", 9),
+    Capability::new(ActorType::Miner, "beneficiary_change_messages", 9),
+    Capability::new(ActorType::VerifiedRegistry, "verified_client_status", 8),
+    Capability::new(ActorType::VerifiedRegistry, "allocations", 9),
+    Capability::new(ActorType::VerifiedRegistry, "claims", 9),
+    Capability::new(ActorType::VerifiedRegistry, "stats", 9),
+    Capability::new(ActorType::VerifiedRegistry, "list_allocations", 9),
+    Capability::new(ActorType::VerifiedRegistry, "list_claims", 9),
+    Capability::new(ActorType::EVM, "contract_lifecycle", 10),
+];
+
+/// Looks up an entry of [`CAPABILITIES`].
+pub fn capability(actor: ActorType, name: &str) -> Option<&'static Capability> {
+    CAPABILITIES
+        .iter()
+        .find(|c| c.actor == actor && c.name == name)
+}
+
+/// Returns whether an accessor of [`CAPABILITIES`] is available on the states of the given
+/// version. Unknown accessors are reported as unsupported.
+pub fn is_supported(actor: ActorType, name: &str, version: u64) -> bool {
+    capability(actor, name).is_some_and(|c| c.supports(version))
+}
+
+/// Error returned when a feature is not available on the version of the state being processed,
+/// see [`require_version`] and [`version_gate!`](crate::version_gate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedVersion {
+    pub feature: &'static str,
+    /// Version of the state being processed.
+    pub version: u64,
+    /// First version providing the feature.
+    pub since: u64,
+    /// Last version providing the feature, if it has been removed since.
+    pub until: Option<u64>,
+}
+
+impl fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is not available on actors version {}",
+            self.feature, self.version
+        )?;
+        match self.until {
+            Some(until) => write!(f, ", it requires versions {} to {until}", self.since),
+            None => write!(f, ", it requires version {} or later", self.since),
+        }
+    }
+}
+
+impl std::error::Error for UnsupportedVersion {}
+
+/// Checks that a feature introduced in actors version `since` is available on the states of the
+/// given version.
+pub fn require_version(
+    feature: &'static str,
+    version: u64,
+    since: u64,
+) -> Result<(), UnsupportedVersion> {
+    if version >= since {
+        return Ok(());
+    }
+    Err(UnsupportedVersion {
+        feature,
+        version,
+        since,
+        until: None,
+    })
+}
+
+/// Runs a closure only if the state being processed is at least of the given actors version, and
+/// returns its result, or an [`UnsupportedVersion`] error naming the feature otherwise. This is
+/// meant for applications processing states of mixed versions around upgrade epochs, e.g.
+/// `version_gate!(version >= 13, "sector_pieces", || st.load_provider_sectors(store))`. A version
+/// which is not a plain identifier or literal must be parenthesized.
+#[macro_export]
+macro_rules! version_gate {
+    ($version:tt >= $since:expr, $feature:expr, $f:expr) => {
+        $crate::version::require_version($feature, $version, $since).map(|()| ($f)())
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actors_version() {
+        assert_eq!(actors_version(NetworkVersion::V15), None);
+        assert_eq!(
+            actors_version(NetworkVersion::V16),
+            Some(MIN_ACTORS_VERSION)
+        );
+        assert_eq!(actors_version(NetworkVersion::V19), Some(11));
+        assert_eq!(
+            actors_version(NetworkVersion::V25),
+            Some(MAX_ACTORS_VERSION)
+        );
+        assert_eq!(actors_version(NetworkVersion::new(u32::MAX)), None);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert_eq!(max_supported_actors_version(), MAX_ACTORS_VERSION);
+        assert_eq!(actor_versions(ActorType::Miner), 8..=MAX_ACTORS_VERSION);
+        assert_eq!(actor_versions(ActorType::EVM), 10..=MAX_ACTORS_VERSION);
+
+        assert!(!is_supported(ActorType::Market, "provider_sectors", 12));
+        assert!(is_supported(ActorType::Market, "provider_sectors", 13));
+        assert!(is_supported(
+            ActorType::Market,
+            "provider_sectors",
+            MAX_ACTORS_VERSION
+        ));
+        assert!(!is_supported(
+            ActorType::Market,
+            "provider_sectors",
+            MAX_ACTORS_VERSION + 1
+        ));
+        assert!(!is_supported(ActorType::Miner, "provider_sectors", 13));
+        assert!(!is_supported(ActorType::Miner, "active_beneficiary", 8));
+
+        for c in CAPABILITIES {
+            assert!(actor_versions(c.actor).contains(&c.since), "{c:?}");
+            assert_eq!(capability(c.actor, c.name), Some(c));
+        }
+
+        let json = serde_json::to_value(capability(ActorType::EVM, "contract_lifecycle")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "actor": "evm",
+                "name": "contract_lifecycle",
+                "since": 10,
+                "until": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_version_gate() {
+        let version = 12;
+        assert_eq!(version_gate!(version >= 12, "gated", || 1), Ok(1));
+        let err = version_gate!(version >= 13, "gated", || 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "gated is not available on actors version 12, it requires version 13 or later"
+        );
+
+        let c = capability(ActorType::EVM, "contract_lifecycle").unwrap();
+        assert!(c.require(10).is_ok());
+        let err = c.require(9).unwrap_err();
+        assert_eq!((err.feature, err.since, err.until), (c.name, 10, None));
+        let removed = Capability {
+            until: Some(12),
+            ..*c
+        };
+        assert_eq!(
+            removed.require(13).unwrap_err().to_string(),
+            "contract_lifecycle is not available on actors version 13, it requires versions 10 to 12"
+        );
+    }
+}
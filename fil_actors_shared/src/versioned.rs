@@ -0,0 +1,445 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt::Debug;
+
+use anyhow::{ensure, Context};
+use cid::Cid;
+use fvm_shared4::address::Address;
+use fvm_shared4::version::NetworkVersion;
+
+use crate::actor_type::ActorType;
+use crate::bundle::KnownCids;
+use crate::version::actors_version;
+
+/// Builtin-actors versions on both sides of a network upgrade. At the upgrade tipset, the parent
+/// state root holds the actor states of the `pre` version and the child state root those of the
+/// `post` version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpgradeVersions {
+    pub pre: u64,
+    pub post: u64,
+}
+
+impl UpgradeVersions {
+    /// Returns the actors versions before and after the upgrade to the given network version, or
+    /// `None` if either of them is not supported by this crate, see [`actors_version`]. Upgrades
+    /// which did not ship new actors, such as the one to network version 20, have equal versions.
+    pub fn at(network_version: NetworkVersion) -> Option<Self> {
+        let previous = u32::from(network_version).checked_sub(1)?;
+        Some(Self {
+            pre: actors_version(NetworkVersion::from(previous))?,
+            post: actors_version(network_version)?,
+        })
+    }
+}
+
+/// A field of a normalized actor state whose value differs on both sides of an upgrade. Values
+/// are in their `Debug` formatting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub pre: String,
+    pub post: String,
+}
+
+impl FieldChange {
+    /// Returns the change of a field, or `None` if its values are equal.
+    pub fn of<T: PartialEq + Debug>(field: &'static str, pre: &T, post: &T) -> Option<Self> {
+        (pre != post).then(|| Self {
+            field,
+            pre: format!("{pre:?}"),
+            post: format!("{post:?}"),
+        })
+    }
+}
+
+/// Converts an address of any version to the latest address type, through its byte encoding.
+/// Fails if the bytes, which come from state data, are not a valid address.
+pub fn normalize_address(address: &[u8]) -> anyhow::Result<Address> {
+    Address::from_bytes(address).with_context(|| {
+        let hex: String = address.iter().map(|b| format!("{b:02x}")).collect();
+        format!("invalid address {hex}")
+    })
+}
+
+/// Returns the actors version of an actor code CID, after checking that it is the code of the
+/// given actor type.
+pub fn code_version(known: &KnownCids, code: &Cid, actor_type: ActorType) -> anyhow::Result<u64> {
+    let (version, code_type) = known
+        .resolve(code)
+        .with_context(|| format!("unknown actor code {code}"))?;
+    ensure!(
+        code_type == actor_type,
+        "actor code {code} is the {} code of actors v{version}, not a {} code",
+        code_type.manifest_name(),
+        actor_type.manifest_name()
+    );
+    Ok(version)
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use cid::Cid;
+    pub use fvm_ipld_blockstore::Blockstore;
+    pub use fvm_ipld_encoding::CborStore;
+}
+
+/// Generates the version-agnostic view of an actor state:
+///
+/// - the given normalized state struct, with a `changes` method listing the fields whose values
+///   differ in another state;
+/// - a `VersionedState` enum of the states of the given versions, with `load`, `load_code`,
+///   `load_best_effort`, `version` and a
+///   fallible `normalize` method evaluating the given closure body on the state of each version,
+///   in which `?` returns the error;
+/// - a `load_dual` function loading the states on both sides of a network upgrade from the code
+///   CIDs of the actor, which are resolved to actors versions with [`KnownCids`].
+///
+/// A version whose layout differs can override the closure after its state type.
+///
+/// ```ignore
+/// fil_actors_shared::versioned_state! {
+///     actor: "cron",
+///     type: Cron,
+///     /// The fields of the cron state.
+///     pub struct NormalizedState {
+///         pub entries: Vec<Entry>,
+///     }
+///     versions {
+///         V8 => 8, crate::v8::State;
+///         V16 => 16, crate::v16::State;
+///     }
+///     normalize |st| NormalizedState { entries: convert(&st.entries) }
+/// }
+/// ```
+#[macro_export]
+macro_rules! versioned_state {
+    (
+        actor: $actor:literal,
+        type: $actor_type:ident,
+        $(#[$meta:meta])*
+        pub struct $normalized:ident {
+            $($(#[$field_meta:meta])* pub $field:ident: $field_ty:ty,)*
+        }
+        versions {
+            $($variant:ident => $version:literal, $state:ty $(=> |$over_st:ident| $over:expr)?;)*
+        }
+        normalize |$st:ident| $body:expr $(,)?
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $normalized {
+            $($(#[$field_meta])* pub $field: $field_ty,)*
+        }
+
+        impl $normalized {
+            /// Returns the fields whose values differ in the given state after the upgrade.
+            pub fn changes(&self, post: &Self) -> Vec<$crate::versioned::FieldChange> {
+                [$($crate::versioned::FieldChange::of(
+                    stringify!($field),
+                    &self.$field,
+                    &post.$field,
+                ),)*]
+                .into_iter()
+                .flatten()
+                .collect()
+            }
+        }
+
+        #[doc = concat!("The state of the ", $actor, " actor decoded with the types of its actors version.")]
+        #[derive(Debug)]
+        pub enum VersionedState {
+            $($variant($state),)*
+        }
+
+        impl VersionedState {
+            #[doc = concat!("Loads the state of the ", $actor, " actor as the state of the given actors version.")]
+            pub fn load<BS: $crate::versioned::__private::Blockstore>(
+                store: &BS,
+                version: u64,
+                state: &$crate::versioned::__private::Cid,
+            ) -> $crate::versioned::__private::anyhow::Result<Self> {
+                use $crate::versioned::__private::anyhow::Context as _;
+                use $crate::versioned::__private::CborStore as _;
+                match version {
+                    $($version => Ok(Self::$variant(
+                        store
+                            .get_cbor(state)?
+                            .with_context(|| format!(concat!($actor, " state {} not found"), state))?,
+                    )),)*
+                    _ => $crate::versioned::__private::anyhow::bail!(
+                        concat!("unsupported ", $actor, " actors version {}"),
+                        version
+                    ),
+                }
+            }
+
+            #[doc = concat!(
+                "Loads the state of the ", $actor, " actor as the state of the given actors ",
+                "version in best-effort mode, see the `forward_compat` module of ",
+                "fil_actors_shared. The state of a version newer than the supported ones is ",
+                "decoded as the newest supported version, with a `DecodeWarning::UnknownVersion`. ",
+                "This is meant for read-only tooling which should keep working across unexpected ",
+                "upgrades."
+            )]
+            pub fn load_best_effort<BS: $crate::versioned::__private::Blockstore>(
+                store: &BS,
+                version: u64,
+                state: &$crate::versioned::__private::Cid,
+            ) -> $crate::versioned::__private::anyhow::Result<
+                $crate::forward_compat::BestEffort<Self>,
+            > {
+                use $crate::forward_compat::{get_cbor_best_effort, DecodeWarning};
+                let newest = [$($version),*].into_iter().max().unwrap_or_default();
+                if version > newest {
+                    let mut loaded = Self::load_best_effort(store, newest, state)?;
+                    let warning = DecodeWarning::UnknownVersion {
+                        version,
+                        decoded_as: newest,
+                    };
+                    loaded.warnings.insert(0, warning);
+                    return Ok(loaded);
+                }
+                match version {
+                    $($version => Ok(
+                        get_cbor_best_effort::<_, $state>(store, state)?.map(Self::$variant),
+                    ),)*
+                    _ => $crate::versioned::__private::anyhow::bail!(
+                        concat!("unsupported ", $actor, " actors version {}"),
+                        version
+                    ),
+                }
+            }
+
+            #[doc = concat!(
+                "Loads the state of the ", $actor, " actor with the given code CID, as the ",
+                "state of the actors version the known CIDs resolve it to."
+            )]
+            pub fn load_code<BS: $crate::versioned::__private::Blockstore>(
+                store: &BS,
+                known: &$crate::bundle::KnownCids,
+                code: &$crate::versioned::__private::Cid,
+                state: &$crate::versioned::__private::Cid,
+            ) -> $crate::versioned::__private::anyhow::Result<Self> {
+                let version = $crate::versioned::code_version(
+                    known,
+                    code,
+                    $crate::actor_type::ActorType::$actor_type,
+                )?;
+                Self::load(store, version, state)
+            }
+
+            pub fn version(&self) -> u64 {
+                match self {
+                    $(Self::$variant(_) => $version,)*
+                }
+            }
+
+            pub fn normalize(&self) -> $crate::versioned::__private::anyhow::Result<$normalized> {
+                Ok(match self {
+                    $(Self::$variant(st) => {
+                        $crate::versioned_state!(@normalize st, $st, $body $(, $over_st, $over)?)
+                    })*
+                })
+            }
+        }
+
+        #[doc = concat!(
+            "Loads the states of the ", $actor, " actor on both sides of a network upgrade, from ",
+            "the parent and the child state roots of the upgrade tipset, with the code CIDs of ",
+            "the actor in each. Compare their [`VersionedState::normalize`] forms to see what ",
+            "the migration changed."
+        )]
+        pub fn load_dual<BS: $crate::versioned::__private::Blockstore>(
+            store: &BS,
+            known: &$crate::bundle::KnownCids,
+            code_pre: &$crate::versioned::__private::Cid,
+            state_pre: &$crate::versioned::__private::Cid,
+            code_post: &$crate::versioned::__private::Cid,
+            state_post: &$crate::versioned::__private::Cid,
+        ) -> $crate::versioned::__private::anyhow::Result<(VersionedState, VersionedState)> {
+            use $crate::versioned::__private::anyhow::Context as _;
+            let pre = VersionedState::load_code(store, known, code_pre, state_pre).context(
+                concat!("failed to load the ", $actor, " state before the upgrade"),
+            )?;
+            let post = VersionedState::load_code(store, known, code_post, state_post).context(
+                concat!("failed to load the ", $actor, " state after the upgrade"),
+            )?;
+            Ok((pre, post))
+        }
+    };
+    (@normalize $value:ident, $st:ident, $body:expr) => {{
+        let $st = $value;
+        $body
+    }};
+    (@normalize $value:ident, $st:ident, $body:expr, $over_st:ident, $over:expr) => {{
+        let $over_st = $value;
+        $over
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::tuple::*;
+    use fvm_ipld_encoding::{CborStore, IPLD_RAW};
+    use multihash_codetable::{Code, MultihashDigest};
+
+    fn code(name: &str, version: u64) -> Cid {
+        Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(format!("{name}{version}").as_bytes()))
+    }
+
+    #[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
+    pub struct StateV1 {
+        pub next_id: u64,
+    }
+
+    #[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
+    pub struct StateV2 {
+        pub next_id: u64,
+        pub name: String,
+    }
+
+    versioned_state! {
+        actor: "test",
+        type: Cron,
+        /// The fields of the test state.
+        pub struct NormalizedState {
+            pub next_id: u64,
+            /// Since v2.
+            pub name: Option<String>,
+        }
+        versions {
+            V1 => 1, StateV1 => |st| NormalizedState {
+                next_id: st.next_id,
+                name: None,
+            };
+            V2 => 2, StateV2;
+        }
+        normalize |st| NormalizedState {
+            next_id: st.next_id,
+            name: Some(st.name.clone()),
+        }
+    }
+
+    #[test]
+    fn test_versioned_state() {
+        let store = MemoryBlockstore::new();
+        let pre_root = store
+            .put_cbor(&StateV1 { next_id: 3 }, Code::Blake2b256)
+            .unwrap();
+        let post = StateV2 {
+            next_id: 3,
+            name: "test".into(),
+        };
+        let post_root = store.put_cbor(&post, Code::Blake2b256).unwrap();
+
+        let mut known = KnownCids::default();
+        known.insert("testnet", 1, ActorType::Cron, code("cron", 1));
+        known.insert("testnet", 2, ActorType::Cron, code("cron", 2));
+        known.insert("testnet", 2, ActorType::Init, code("init", 2));
+        let (pre, post) = load_dual(
+            &store,
+            &known,
+            &code("cron", 1),
+            &pre_root,
+            &code("cron", 2),
+            &post_root,
+        )
+        .unwrap();
+        assert_eq!((pre.version(), post.version()), (1, 2));
+        assert_eq!(
+            pre.normalize().unwrap().changes(&post.normalize().unwrap()),
+            [FieldChange {
+                field: "name",
+                pre: "None".into(),
+                post: "Some(\"test\")".into(),
+            }]
+        );
+
+        let err = VersionedState::load(&store, 3, &pre_root).unwrap_err();
+        assert_eq!(err.to_string(), "unsupported test actors version 3");
+
+        // The code of another actor, or an unknown one, is rejected.
+        let err = VersionedState::load_code(&store, &known, &code("init", 2), &post_root)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "actor code {} is the init code of actors v2, not a cron code",
+                code("init", 2)
+            )
+        );
+        assert!(VersionedState::load_code(&store, &known, &code("cron", 3), &post_root).is_err());
+    }
+
+    #[test]
+    fn test_load_best_effort() {
+        use crate::forward_compat::DecodeWarning;
+
+        // A v3 state, unknown to this crate, which appended a field to the v2 layout.
+        let store = MemoryBlockstore::new();
+        let root = store.put_cbor(&(3u64, "test", true), Code::Blake2b256).unwrap();
+        assert!(VersionedState::load(&store, 3, &root).is_err());
+
+        let loaded = VersionedState::load_best_effort(&store, 3, &root).unwrap();
+        assert_eq!(loaded.value.version(), 2);
+        assert_eq!(loaded.value.normalize().unwrap().name.as_deref(), Some("test"));
+        assert_eq!(
+            loaded.warnings,
+            [
+                DecodeWarning::UnknownVersion {
+                    version: 3,
+                    decoded_as: 2,
+                },
+                DecodeWarning::UnknownTrailingFields { known: 2, found: 3 },
+            ]
+        );
+
+        // Supported versions decode exactly, and versions older than the supported ones fail.
+        let root = store.put_cbor(&StateV1 { next_id: 3 }, Code::Blake2b256).unwrap();
+        assert!(VersionedState::load_best_effort(&store, 1, &root).unwrap().is_exact());
+        assert!(VersionedState::load_best_effort(&store, 0, &root).is_err());
+    }
+
+    #[test]
+    fn test_upgrade_versions() {
+        assert_eq!(
+            UpgradeVersions::at(NetworkVersion::V17),
+            Some(UpgradeVersions { pre: 8, post: 9 })
+        );
+        assert_eq!(
+            UpgradeVersions::at(NetworkVersion::V19),
+            Some(UpgradeVersions { pre: 10, post: 11 })
+        );
+        assert_eq!(
+            UpgradeVersions::at(NetworkVersion::V20),
+            Some(UpgradeVersions { pre: 11, post: 11 })
+        );
+        assert_eq!(UpgradeVersions::at(NetworkVersion::V16), None);
+        assert_eq!(UpgradeVersions::at(NetworkVersion::V0), None);
+
+        assert_eq!(FieldChange::of("next_id", &1, &1), None);
+        assert_eq!(
+            FieldChange::of("next_id", &1, &2),
+            Some(FieldChange {
+                field: "next_id",
+                pre: "1".into(),
+                post: "2".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        let address = fvm_shared::address::Address::new_id(100);
+        assert_eq!(
+            normalize_address(&address.to_bytes()).unwrap(),
+            Address::new_id(100)
+        );
+        assert!(normalize_address(&[0xff, 0x01]).is_err());
+    }
+}